@@ -11,7 +11,6 @@
 // This file may not be copied, modified, or distributed except according to those terms.
 
 use criterion::{criterion_group, criterion_main, Criterion};
-use serde_json;
 use iperf3_statuspage::{clear_last_result_for_test, get_last_result, set_last_result_for_test, ConnectingTo, CpuUtilizationPercent, End, Iperf3Report, Start, SumReceived, SumSent, TestStart, Timestamp};
 
 fn dummy_result() -> Iperf3Report {
@@ -60,7 +59,11 @@ fn dummy_result() -> Iperf3Report {
                 bytes: 0,
                 bits_per_second: 0.0,
                 retransmits: 0,
-                sender: false
+                sender: false,
+                jitter_ms: None,
+                lost_packets: None,
+                packets: None,
+                lost_percent: None
             },
             sum_received: SumReceived {
                 start: 0.0,
@@ -68,7 +71,11 @@ fn dummy_result() -> Iperf3Report {
                 seconds: 0.0,
                 bytes: 0,
                 bits_per_second: 0.0,
-                sender: false
+                sender: false,
+                jitter_ms: None,
+                lost_packets: None,
+                packets: None,
+                lost_percent: None
             },
             cpu_utilization_percent: CpuUtilizationPercent {
                 host_total: 0.0,
@@ -78,9 +85,12 @@ fn dummy_result() -> Iperf3Report {
                 remote_user: 0.0,
                 remote_system: 0.0,
             },
+            sum_sent_bidir_reverse: None,
+            sum_received_bidir_reverse: None,
             sender_tcp_congestion: "".to_string(),
             receiver_tcp_congestion: "".to_string()
-        }
+        },
+        extra: Default::default(),
     }
 }
 
@@ -0,0 +1,41 @@
+//! # iperf3-statuspage
+//!
+//! Build script embedding the git SHA and build timestamp served by `/version`.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn main() {
+    // Rebuild when the checked-out commit changes
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs/heads");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+
+    // Builds outside a git checkout (e.g. from a crate tarball) simply have no SHA
+    let sha = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|sha| !sha.is_empty());
+    if let Some(sha) = sha {
+        println!("cargo:rustc-env=IPERF3_STATUSPAGE_GIT_SHA={}", sha);
+    }
+
+    // Honour SOURCE_DATE_EPOCH for reproducible builds
+    let timestamp = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs());
+    println!("cargo:rustc-env=IPERF3_STATUSPAGE_BUILD_TIMESTAMP={}", timestamp);
+}
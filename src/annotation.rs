@@ -0,0 +1,66 @@
+//! # iperf3-statuspage
+//!
+//! Free-form annotation attached to each cached result, e.g. a deploy's git SHA.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+use std::sync::Mutex;
+use once_cell::sync::Lazy;
+use tracing::{error, info};
+
+/// Annotation attached to results cached from now on, initially `RUN_ANNOTATION`.
+static RUN_ANNOTATION: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(env_annotation()));
+
+fn env_annotation() -> Option<String> {
+    env::var("RUN_ANNOTATION").ok().filter(|s| !s.trim().is_empty())
+}
+
+/// Returns the annotation attached to newly cached results.
+pub fn run_annotation() -> Option<String> {
+    RUN_ANNOTATION.lock().unwrap().clone()
+}
+
+/// Replaces (or with `None` clears) the annotation attached to newly cached results.
+pub fn set_run_annotation(annotation: Option<String>) {
+    *RUN_ANNOTATION.lock().unwrap() = annotation;
+}
+
+/// Re-reads `RUN_ANNOTATION`, preferring the value in `.env` (which may have been edited
+/// since startup) over the process environment, and returns the new annotation.
+pub fn reload_run_annotation() -> Option<String> {
+    let from_dotenv = dotenvy::dotenv_iter()
+        .ok()
+        .and_then(|mut vars| vars.find_map(|var| var.ok().filter(|(key, _)| key == "RUN_ANNOTATION")))
+        .map(|(_, value)| value)
+        .filter(|s| !s.trim().is_empty());
+    let annotation = from_dotenv.or_else(env_annotation);
+    set_run_annotation(annotation.clone());
+    annotation
+}
+
+/// Reloads the annotation on every SIGHUP. Never returns unless the handler cannot be installed.
+#[cfg(unix)]
+pub async fn reload_annotation_on_sighup() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(hangups) => hangups,
+        Err(e) => {
+            error!(error = %e, "Failed to install SIGHUP handler");
+            return;
+        }
+    };
+    while hangups.recv().await.is_some() {
+        match reload_run_annotation() {
+            Some(annotation) => info!("Run annotation reloaded: {}", annotation),
+            None => info!("Run annotation cleared"),
+        }
+    }
+}
@@ -0,0 +1,63 @@
+//! # iperf3-statuspage
+//!
+//! Bearer-token authentication against `API_TOKEN`.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+use std::future::{ready, Ready};
+use actix_web::dev::Payload;
+use actix_web::http::header;
+use actix_web::{FromRequest, HttpRequest};
+use crate::errors::Iperf3Error;
+
+/// Reads the environment variable `API_TOKEN`, if set and non-empty.
+pub fn api_token() -> Option<String> {
+    env::var("API_TOKEN").ok().filter(|token| !token.is_empty())
+}
+
+/// Compares two byte strings in time independent of where they differ.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Returns `true` if the request carries `Authorization: Bearer <API_TOKEN>`.
+///
+/// Always `false` while `API_TOKEN` is unset.
+pub fn is_authenticated(req: &HttpRequest) -> bool {
+    let Some(token) = api_token() else {
+        return false;
+    };
+    req.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|presented| constant_time_eq(presented.trim().as_bytes(), token.as_bytes()))
+}
+
+/// Extractor guarding write and trigger endpoints with `API_TOKEN`.
+///
+/// Adding a `RequireToken` argument to a handler rejects requests without
+/// `Authorization: Bearer <API_TOKEN>` with [`Iperf3Error::Unauthorized`] (HTTP 401)
+/// before the handler runs. While `API_TOKEN` is unset every request is let through.
+#[derive(Debug, Clone, Copy)]
+pub struct RequireToken;
+
+impl FromRequest for RequireToken {
+    type Error = Iperf3Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(if api_token().is_none() || is_authenticated(req) {
+            Ok(RequireToken)
+        } else {
+            Err(Iperf3Error::Unauthorized("A valid bearer token is required.".to_string()))
+        })
+    }
+}
@@ -0,0 +1,78 @@
+//! # iperf3-statuspage
+//!
+//! Capture of unparseable iperf3 output and the optional retry after a parse failure.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+use std::sync::Mutex;
+use actix_web::{get, HttpRequest, HttpResponse};
+use once_cell::sync::Lazy;
+use crate::errors::Iperf3Error;
+use crate::redact::ensure_raw_output_allowed;
+
+/// Raw output of the last run that failed to parse as an iperf3 JSON report.
+static LAST_BAD_OUTPUT: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
+/// Reads the environment variable `RETRY_ON_PARSE_FAILURE` (`true`/`1`), defaulting to
+/// disabled. When enabled a run whose output fails to parse is retried once.
+pub fn retry_on_parse_failure_enabled() -> bool {
+    env::var("RETRY_ON_PARSE_FAILURE")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+/// Stores `output` as the last unparseable output, replacing any earlier capture.
+pub fn capture_bad_output(output: &str) {
+    *LAST_BAD_OUTPUT.lock().unwrap() = Some(output.to_string());
+}
+
+/// Characters of context shown on each side of a parse error by [`parse_error_snippet`].
+const SNIPPET_CONTEXT: usize = 40;
+
+/// Returns the text of `output` around the 1-based `line` and `column` where parsing
+/// failed, for logging, with `»` marking the error position.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::parse_error_snippet;
+/// let output = "{\n  \"end\": {\"sum_sent\": oops}\n}";
+/// assert_eq!(parse_error_snippet(output, 2, 23), "  \"end\": {\"sum_sent\": »oops}");
+/// ```
+pub fn parse_error_snippet(output: &str, line: usize, column: usize) -> String {
+    let text: Vec<char> = output.lines().nth(line.saturating_sub(1)).unwrap_or_default().chars().collect();
+    let position = column.saturating_sub(1).min(text.len());
+    let before: String = text[position.saturating_sub(SNIPPET_CONTEXT)..position].iter().collect();
+    let after: String = text[position..(position + SNIPPET_CONTEXT).min(text.len())].iter().collect();
+    format!("{}»{}", before, after)
+}
+
+/// Returns the last unparseable output, if any.
+pub fn get_last_bad_output() -> Option<String> {
+    LAST_BAD_OUTPUT.lock().unwrap().clone()
+}
+
+/// Clears the captured unparseable output.
+pub fn clear_last_bad_output_for_test() {
+    *LAST_BAD_OUTPUT.lock().unwrap() = None;
+}
+
+/// HTTP GET endpoint `/debug/last-bad-output` returns the raw output of the last run that
+/// failed to parse, as plain text.
+///
+/// Returns HTTP 404 Not Found if no output has failed to parse since startup. With
+/// `REDACT_TARGET` enabled anonymous clients get HTTP 401 Unauthorized.
+#[get("/debug/last-bad-output")]
+pub async fn debug_last_bad_output(req: HttpRequest) -> Result<HttpResponse, Iperf3Error> {
+    ensure_raw_output_allowed(&req)?;
+    let output = get_last_bad_output()
+        .ok_or_else(|| Iperf3Error::NotFound("No unparseable output captured.".to_string()))?;
+    Ok(HttpResponse::Ok().content_type("text/plain; charset=utf-8").body(output))
+}
@@ -0,0 +1,158 @@
+//! # iperf3-statuspage
+//!
+//! shields.io endpoint badge of the latest throughput, served at `/badge`.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+use actix_web::{get, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use crate::{bits_per_second_to_mbps, get_last_result};
+use crate::maintenance::maintenance_enabled;
+
+/// Reads the environment variable `SLA_MIN_MBPS`, the received throughput a result must
+/// reach to meet the SLA. Returns `None` when unset or not a positive number.
+pub fn sla_min_mbps() -> Option<f64> {
+    positive_mbps("SLA_MIN_MBPS")
+}
+
+/// Reads the environment variable `SLA_MIN_SENT_MBPS`, the sent throughput a result must
+/// reach to meet the SLA. Returns `None` when unset or not a positive number.
+pub fn sla_min_sent_mbps() -> Option<f64> {
+    positive_mbps("SLA_MIN_SENT_MBPS")
+}
+
+/// Reads the environment variable `SLA_MIN_RECEIVED_MBPS`, the received throughput a
+/// result must reach to meet the SLA, falling back to `SLA_MIN_MBPS`. Returns `None` when
+/// neither is a positive number.
+pub fn sla_min_received_mbps() -> Option<f64> {
+    positive_mbps("SLA_MIN_RECEIVED_MBPS").or_else(sla_min_mbps)
+}
+
+/// Outcome of a result against the per-direction SLA thresholds.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SlaOutcome {
+    /// Whether the sent throughput met `SLA_MIN_SENT_MBPS`, `None` when unset.
+    pub sent_met: Option<bool>,
+    /// Whether the received throughput met `SLA_MIN_RECEIVED_MBPS`, `None` when unset.
+    pub received_met: Option<bool>,
+}
+
+impl SlaOutcome {
+    /// Evaluates throughputs in Mbit/s against optional thresholds, each independently.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use iperf3_statuspage::SlaOutcome;
+    /// let outcome = SlaOutcome::evaluate(40.0, 940.0, Some(50.0), Some(500.0));
+    /// assert_eq!(outcome.sent_met, Some(false));
+    /// assert_eq!(outcome.received_met, Some(true));
+    /// assert_eq!(outcome.met(), Some(false));
+    /// assert_eq!(SlaOutcome::evaluate(40.0, 940.0, None, None).met(), None);
+    /// ```
+    pub fn evaluate(sent_mbps: f64, received_mbps: f64, min_sent_mbps: Option<f64>, min_received_mbps: Option<f64>) -> Self {
+        SlaOutcome {
+            sent_met: min_sent_mbps.map(|min| sent_mbps >= min),
+            received_met: min_received_mbps.map(|min| received_mbps >= min),
+        }
+    }
+
+    /// Whether every configured threshold was met, `None` when none is configured.
+    pub fn met(&self) -> Option<bool> {
+        match (self.sent_met, self.received_met) {
+            (None, None) => None,
+            (sent, received) => Some(sent.unwrap_or(true) && received.unwrap_or(true)),
+        }
+    }
+}
+
+/// Reads the environment variable `SLA_WARN_MBPS`: results meeting the received SLA but
+/// below this are flagged as a warning. Returns `None` when unset or not a positive number.
+pub fn sla_warn_mbps() -> Option<f64> {
+    positive_mbps("SLA_WARN_MBPS")
+}
+
+fn positive_mbps(name: &str) -> Option<f64> {
+    env::var(name)
+        .ok()
+        .and_then(|s| s.trim().parse::<f64>().ok())
+        .filter(|n| n.is_finite() && *n > 0.0)
+}
+
+/// shields.io endpoint badge, see <https://shields.io/badges/endpoint-badge>.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Badge {
+    /// Always `1`.
+    pub schema_version: u8,
+    pub label: String,
+    pub message: String,
+    pub color: String,
+}
+
+impl Badge {
+    fn new(message: impl Into<String>, color: &str) -> Self {
+        Badge {
+            schema_version: 1,
+            label: "download".to_string(),
+            message: message.into(),
+            color: color.to_string(),
+        }
+    }
+}
+
+/// Returns the badge color for a received throughput of `mbps`.
+///
+/// Below `min_mbps` is `red`, below `warn_mbps` is `yellow` and anything else `green`.
+/// Without thresholds the color is a neutral `blue`.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::badge_color;
+/// assert_eq!(badge_color(942.0, Some(500.0), Some(900.0)), "green");
+/// assert_eq!(badge_color(700.0, Some(500.0), Some(900.0)), "yellow");
+/// assert_eq!(badge_color(100.0, Some(500.0), Some(900.0)), "red");
+/// assert_eq!(badge_color(100.0, None, None), "blue");
+/// ```
+pub fn badge_color(mbps: f64, min_mbps: Option<f64>, warn_mbps: Option<f64>) -> &'static str {
+    match (min_mbps, warn_mbps) {
+        (Some(min), _) if mbps < min => "red",
+        (_, Some(warn)) if mbps < warn => "yellow",
+        (None, None) => "blue",
+        _ => "green",
+    }
+}
+
+/// Builds the badge of a received throughput in bits per second, `None` meaning no data.
+pub fn build_badge(received_bits_per_second: Option<f64>) -> Badge {
+    match received_bits_per_second {
+        Some(bits_per_second) => {
+            let mbps = bits_per_second_to_mbps(bits_per_second);
+            Badge::new(format!("{:.0} Mbps", mbps), badge_color(mbps, sla_min_received_mbps(), sla_warn_mbps()))
+        }
+        None => Badge::new("no data", "lightgrey"),
+    }
+}
+
+/// HTTP GET endpoint `/badge` returns a shields.io endpoint badge of the latest received
+/// throughput, colored by `SLA_MIN_RECEIVED_MBPS` (or `SLA_MIN_MBPS`) and `SLA_WARN_MBPS`.
+///
+/// Always returns HTTP 200 so embedded badges keep rendering: a "no data" badge while no
+/// result is cached and a "maintenance" badge while maintenance mode is enabled.
+#[get("/badge")]
+pub async fn iperf3_badge() -> impl Responder {
+    let badge = if maintenance_enabled() {
+        Badge::new("maintenance", "lightgrey")
+    } else {
+        build_badge(get_last_result().map(|report| report.end.sum_received.bits_per_second))
+    };
+    HttpResponse::Ok().json(badge)
+}
@@ -0,0 +1,160 @@
+//! # iperf3-statuspage
+//!
+//! Known-good baseline result that the latest result is compared against.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use actix_web::{get, post, HttpRequest, HttpResponse};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+use crate::auth::RequireToken;
+use crate::errors::Iperf3Error;
+use crate::{bits_per_second_to_mbps, get_last_result};
+use crate::maintenance::ensure_not_in_maintenance;
+use crate::models::Iperf3Report;
+use crate::oneshot::{read_state_file, write_state_file};
+use crate::redact::public_json;
+
+/// Baseline report, loaded from `BASELINE_FILE` at startup or promoted through
+/// `/admin/set-baseline`.
+static BASELINE: Lazy<Mutex<Option<Iperf3Report>>> = Lazy::new(|| Mutex::new(None));
+
+/// Reads the environment variable `BASELINE_FILE`, the path of the baseline report.
+pub fn baseline_file_path() -> Option<PathBuf> {
+    env::var("BASELINE_FILE")
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+        .map(PathBuf::from)
+}
+
+/// Reads a baseline report from a JSON file, as written by iperf3 or `STATE_FILE`.
+///
+/// A checksum written alongside is verified, see [`read_state_file`].
+pub fn load_baseline_file(path: &Path) -> Result<Iperf3Report, String> {
+    read_state_file(path)
+}
+
+/// Loads the baseline from `BASELINE_FILE`, if set. Failures are logged and leave no baseline.
+pub fn load_baseline_from_env() {
+    let Some(path) = baseline_file_path() else {
+        return;
+    };
+    match load_baseline_file(&path) {
+        Ok(report) => set_baseline(Some(report)),
+        Err(e) => warn!("{}; no baseline loaded", e),
+    }
+}
+
+/// Reads the environment variable `AUTO_BASELINE` (`true`/`1`), defaulting to disabled.
+pub fn auto_baseline_enabled() -> bool {
+    env::var("AUTO_BASELINE")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+/// With `AUTO_BASELINE` enabled and no baseline set yet (e.g. none loaded from
+/// `BASELINE_FILE`), makes `report` the baseline and writes it to `BASELINE_FILE` if set.
+///
+/// A later `/admin/set-baseline` replaces it as usual. A failed write is logged.
+pub fn capture_auto_baseline(report: &Iperf3Report) {
+    if !auto_baseline_enabled() {
+        return;
+    }
+    {
+        let mut baseline = BASELINE.lock().unwrap();
+        if baseline.is_some() {
+            return;
+        }
+        *baseline = Some(report.clone());
+    }
+    info!("Baseline automatically set to the result of {}", report.start.timestamp.time);
+    if let Some(path) = baseline_file_path()
+        && let Err(e) = write_state_file(&path, report)
+    {
+        error!("{}", e);
+    }
+}
+
+/// Returns the baseline report, if any.
+pub fn get_baseline() -> Option<Iperf3Report> {
+    BASELINE.lock().unwrap().clone()
+}
+
+/// Replaces (or with `None` clears) the baseline report.
+pub fn set_baseline(report: Option<Iperf3Report>) {
+    *BASELINE.lock().unwrap() = report;
+}
+
+/// Difference between a result and the baseline, positive when the result is higher.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct BaselineDelta {
+    pub sent_mbps: f64,
+    pub received_mbps: f64,
+    pub retransmits: i64,
+}
+
+/// Computes `report - baseline` for the headline figures.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::{baseline_delta, Iperf3Report};
+/// let mut baseline = Iperf3Report::default();
+/// baseline.end.sum_received.bits_per_second = 940_000_000.0;
+/// let mut report = Iperf3Report::default();
+/// report.end.sum_received.bits_per_second = 900_000_000.0;
+/// assert_eq!(baseline_delta(&report, &baseline).received_mbps, -40.0);
+/// ```
+pub fn baseline_delta(report: &Iperf3Report, baseline: &Iperf3Report) -> BaselineDelta {
+    BaselineDelta {
+        sent_mbps: bits_per_second_to_mbps(report.end.sum_sent.bits_per_second - baseline.end.sum_sent.bits_per_second),
+        received_mbps: bits_per_second_to_mbps(
+            report.end.sum_received.bits_per_second - baseline.end.sum_received.bits_per_second,
+        ),
+        retransmits: i64::from(report.end.sum_sent.retransmits) - i64::from(baseline.end.sum_sent.retransmits),
+    }
+}
+
+/// HTTP GET endpoint `/baseline` returns the baseline report as JSON.
+///
+/// Returns HTTP 503 Service Unavailable if no baseline is set.
+///
+/// Returns HTTP 503 with `Retry-After` while maintenance mode is enabled.
+#[get("/baseline")]
+pub async fn iperf3_baseline(req: HttpRequest) -> Result<HttpResponse, Iperf3Error> {
+    ensure_not_in_maintenance()?;
+    let baseline = get_baseline().ok_or_else(|| Iperf3Error::NotAvailable("No baseline has been set.".to_string()))?;
+    Ok(HttpResponse::Ok().json(public_json(&req, &baseline)?))
+}
+
+/// HTTP POST endpoint `/admin/set-baseline` promotes the latest result to the baseline
+/// and returns it as JSON.
+///
+/// When `BASELINE_FILE` is set the new baseline is also written there so it survives
+/// restarts; a failed write is logged but the in-memory baseline is still replaced.
+///
+/// Returns HTTP 503 Service Unavailable if no result is cached yet. When `API_TOKEN` is
+/// set, requests without `Authorization: Bearer <API_TOKEN>` get HTTP 401 Unauthorized,
+/// see [`RequireToken`].
+#[post("/admin/set-baseline")]
+pub async fn set_baseline_from_latest(req: HttpRequest, _: RequireToken) -> Result<HttpResponse, Iperf3Error> {
+    let report = get_last_result().ok_or_else(Iperf3Error::not_available)?;
+    if let Some(path) = baseline_file_path()
+        && let Err(e) = write_state_file(&path, &report)
+    {
+        error!("{}", e);
+    }
+    set_baseline(Some(report.clone()));
+    info!("Baseline set to the result of {}", report.start.timestamp.time);
+    Ok(HttpResponse::Ok().json(public_json(&req, &report)?))
+}
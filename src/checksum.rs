@@ -0,0 +1,29 @@
+//! # iperf3-statuspage
+//!
+//! CRC-32 checksums guarding persisted results against corruption.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+/// Computes the CRC-32 (IEEE 802.3, as used by zlib and PNG) of `data`.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::crc32;
+/// assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+/// assert_eq!(crc32(b""), 0);
+/// ```
+pub fn crc32(data: &[u8]) -> u32 {
+    crc32fast::hash(data)
+}
+
+/// Formats a checksum as stored next to a persisted file: `crc32:` and eight hex digits.
+pub fn format_checksum(data: &[u8]) -> String {
+    format!("crc32:{:08x}", crc32(data))
+}
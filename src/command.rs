@@ -0,0 +1,373 @@
+//! # iperf3-statuspage
+//!
+//! Construction of the iperf3 command line.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+use serde::{Deserialize, Deserializer};
+use tracing::warn;
+use crate::errors::Iperf3Error;
+
+/// Options used to build a single iperf3 client invocation.
+///
+/// Deserializable so per-target options can be given in a `TARGETS_FILE`.
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct Iperf3Options {
+    /// Host of the iperf3 server, passed to `-c`, or the path of its UNIX socket.
+    pub host: String,
+    /// Port of the iperf3 server, passed to `-p`. Ignored for UNIX socket targets.
+    #[serde(deserialize_with = "string_or_number")]
+    pub port: String,
+    /// Number of parallel client streams, passed to `-P`.
+    #[serde(default)]
+    pub parallel: Option<u32>,
+    /// Run in reverse mode (server sends), passed as `-R`.
+    #[serde(default)]
+    pub reverse: bool,
+    /// Use UDP rather than TCP, passed as `-u`.
+    #[serde(default)]
+    pub udp: bool,
+    /// Target bitrate in bits per second per stream, passed to `-b`.
+    #[serde(default)]
+    pub bitrate: Option<u64>,
+    /// Test duration in seconds, passed to `-t`.
+    #[serde(default)]
+    pub duration: Option<u32>,
+    /// Local address to bind, passed to `-B`, selecting the interface tested.
+    #[serde(default)]
+    pub bind: Option<String>,
+}
+
+/// Accepts a port written either as a JSON string or a number.
+fn string_or_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrNumber {
+        String(String),
+        Number(u64),
+    }
+
+    Ok(match StringOrNumber::deserialize(deserializer)? {
+        StringOrNumber::String(s) => s,
+        StringOrNumber::Number(n) => n.to_string(),
+    })
+}
+
+impl Iperf3Options {
+    /// Creates options targeting the given iperf3 server.
+    pub fn new(host: impl Into<String>, port: impl Into<String>) -> Self {
+        Iperf3Options {
+            host: host.into(),
+            port: port.into(),
+            parallel: None,
+            reverse: false,
+            udp: false,
+            bitrate: None,
+            duration: None,
+            bind: None,
+        }
+    }
+
+    /// Creates options targeting the given iperf3 server, with tuning read from the environment.
+    ///
+    /// `IPERF3_PARALLEL` sets the number of parallel streams, `IPERF3_BITRATE` the target
+    /// bitrate, `IPERF3_DURATION` the test duration and `PROTOCOL` whether to test UDP.
+    pub fn from_env(host: impl Into<String>, port: impl Into<String>) -> Self {
+        Iperf3Options {
+            parallel: configured_parallel_streams(),
+            udp: configured_udp(),
+            bitrate: configured_bitrate(),
+            duration: configured_duration(),
+            ..Iperf3Options::new(host, port)
+        }
+    }
+
+    /// Returns the socket path if the target is a UNIX socket rather than a host.
+    pub fn unix_socket_path(&self) -> Option<&str> {
+        is_unix_socket_path(&self.host).then_some(self.host.as_str())
+    }
+}
+
+/// Returns whether `target` names a filesystem socket path (it starts with `/`)
+/// rather than a host.
+pub fn is_unix_socket_path(target: &str) -> bool {
+    target.starts_with('/')
+}
+
+/// Reads the environment variable `IPERF3_PARALLEL`, the number of parallel streams to run.
+///
+/// Returns `None` when unset or not a positive integer, leaving iperf3's default of one stream.
+pub fn configured_parallel_streams() -> Option<u32> {
+    env::var("IPERF3_PARALLEL")
+        .ok()
+        .and_then(|s| s.parse::<u32>().ok())
+        .filter(|n| *n > 0)
+}
+
+/// Reads the environment variable `IPERF3_BITRATE`, the target bitrate in bits per second.
+///
+/// Returns `None` when unset or not a positive integer, leaving iperf3's default.
+pub fn configured_bitrate() -> Option<u64> {
+    env::var("IPERF3_BITRATE")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .filter(|n| *n > 0)
+}
+
+/// Reads the environment variable `PROTOCOL`, `tcp` (the default) or `udp`.
+///
+/// Returns `true` for `udp`, which runs iperf3 with `-u`.
+pub fn configured_udp() -> bool {
+    env::var("PROTOCOL").is_ok_and(|v| v.trim().eq_ignore_ascii_case("udp"))
+}
+
+/// Reads the environment variable `IPERF3_DURATION`, the test duration in seconds.
+///
+/// Returns `None` when unset or not a positive integer, leaving iperf3's default of 10 seconds.
+pub fn configured_duration() -> Option<u32> {
+    env::var("IPERF3_DURATION")
+        .ok()
+        .and_then(|s| s.parse::<u32>().ok())
+        .filter(|n| *n > 0)
+}
+
+/// iperf3's default test duration in seconds.
+const DEFAULT_DURATION_SECONDS: u64 = 10;
+
+/// iperf3's default UDP bitrate in bits per second.
+const DEFAULT_UDP_BITRATE: u64 = 1_000_000;
+
+/// Projects the bytes a run with `opts` transfers from its bitrate, duration and streams.
+///
+/// Returns `None` for TCP tests without a bitrate, which run as fast as the link allows.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::{projected_test_bytes, Iperf3Options};
+/// let mut opts = Iperf3Options::new("10.0.0.1", "5201");
+/// assert_eq!(projected_test_bytes(&opts), None);
+/// opts.bitrate = Some(100_000_000);
+/// opts.duration = Some(60);
+/// assert_eq!(projected_test_bytes(&opts), Some(750_000_000));
+/// ```
+pub fn projected_test_bytes(opts: &Iperf3Options) -> Option<u64> {
+    let bitrate = opts.bitrate.or(opts.udp.then_some(DEFAULT_UDP_BITRATE))?;
+    let duration = opts.duration.map_or(DEFAULT_DURATION_SECONDS, u64::from);
+    let streams = u64::from(opts.parallel.unwrap_or(1));
+    Some(bitrate.saturating_mul(duration).saturating_mul(streams) / 8)
+}
+
+/// Reads the environment variable `MAX_TEST_BYTES`, defaulting to no ceiling.
+pub fn max_test_bytes() -> Option<u64> {
+    env::var("MAX_TEST_BYTES")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .filter(|n| *n > 0)
+}
+
+/// Refuses runs whose projected transfer exceeds `max_bytes`.
+///
+/// The projection takes `-t`, `-b`, `-P` and `-u` from `IPERF3_EXTRA_ARGS` into account,
+/// see [`effective_options`]. TCP tests without a bitrate cannot be projected; they are
+/// allowed with a warning.
+pub fn check_test_bytes(opts: &Iperf3Options, max_bytes: u64) -> Result<(), Iperf3Error> {
+    match projected_test_bytes(&effective_options(opts)) {
+        Some(projected_bytes) if projected_bytes > max_bytes => {
+            Err(Iperf3Error::OverByteBudget { projected_bytes, max_bytes })
+        }
+        Some(_) => Ok(()),
+        None => {
+            warn!("MAX_TEST_BYTES is set but the TCP test has no bitrate, so its transfer cannot be projected");
+            Ok(())
+        }
+    }
+}
+
+/// Reads the environment variable `IPERF3_BINARY`, the iperf3 executable to run, or
+/// returns a default of `iperf3` looked up on `PATH`.
+pub fn iperf3_binary() -> String {
+    env::var("IPERF3_BINARY")
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+        .unwrap_or_else(|| "iperf3".to_string())
+}
+
+/// Long options of iperf3 that change or redirect its JSON output, with the length of
+/// their shortest unambiguous abbreviation: iperf3 accepts any longer prefix.
+const OUTPUT_OPTIONS: &[(&str, usize)] =
+    &[("logfile", 2), ("json", 1), ("json-stream", 1), ("json-stream-full-output", 1)];
+
+/// Returns whether `arg` is `-J`, or one of [`OUTPUT_OPTIONS`] spelt out or abbreviated,
+/// with or without `=value`.
+fn changes_output(arg: &str) -> bool {
+    if arg == "-J" {
+        return true;
+    }
+    let Some(option) = arg.strip_prefix("--") else {
+        return false;
+    };
+    let name = option.split('=').next().unwrap_or(option);
+    name.starts_with("json")
+        || OUTPUT_OPTIONS.iter().any(|(long, shortest)| name.len() >= *shortest && long.starts_with(name))
+}
+
+/// Reads the environment variable `IPERF3_EXTRA_ARGS`, split on whitespace into extra
+/// arguments appended after those of [`build_iperf3_args`], e.g. `-t 30 -P 4`.
+///
+/// Returns an error if an argument would change the output format or redirect it
+/// (`-J`, `--json*`, `--logfile`, including abbreviations such as `--log`), since the
+/// crate relies on parsing iperf3's JSON on stdout, or if the value of a `-t`, `-b` or `-P` cannot be read (see
+/// [`apply_extra_args`]).
+pub fn iperf3_extra_args() -> Result<Vec<String>, String> {
+    let args: Vec<String> = env::var("IPERF3_EXTRA_ARGS")
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(str::to_string)
+        .collect();
+    if let Some(arg) = args.iter().find(|arg| changes_output(arg)) {
+        return Err(format!("IPERF3_EXTRA_ARGS must not contain {}, the output format is set by the crate", arg));
+    }
+    apply_extra_args(&Iperf3Options::new("", ""), &args)?;
+    Ok(args)
+}
+
+/// Parses an iperf3 bitrate such as `100M`, `2.5G` or `500K/10` (a burst) into bits per
+/// second. The suffixes are decimal, as in iperf3.
+fn parse_bitrate(value: &str) -> Option<u64> {
+    let rate = value.split('/').next().unwrap_or(value);
+    let (number, multiplier) = match rate.chars().last()?.to_ascii_uppercase() {
+        'K' => (&rate[..rate.len() - 1], 1e3),
+        'M' => (&rate[..rate.len() - 1], 1e6),
+        'G' => (&rate[..rate.len() - 1], 1e9),
+        'T' => (&rate[..rate.len() - 1], 1e12),
+        _ => (rate, 1.0),
+    };
+    number.parse::<f64>().ok().filter(|n| n.is_finite() && *n >= 0.0).map(|n| (n * multiplier) as u64)
+}
+
+/// Returns `opts` with the test duration (`-t`/`--time`), bitrate (`-b`/`--bitrate`),
+/// streams (`-P`/`--parallel`) and protocol (`-u`/`--udp`) given in the iperf3 arguments
+/// `extra` applied. They come after the crate's own arguments, so iperf3 uses them.
+///
+/// Returns an error naming the flag if its value is missing or cannot be read.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::{apply_extra_args, Iperf3Options};
+/// let extra: Vec<String> = ["-t", "120", "--bitrate=100M", "-P4"].iter().map(|s| s.to_string()).collect();
+/// let opts = apply_extra_args(&Iperf3Options::new("10.0.0.1", "5201"), &extra).unwrap();
+/// assert_eq!((opts.duration, opts.bitrate, opts.parallel), (Some(120), Some(100_000_000), Some(4)));
+/// assert!(apply_extra_args(&opts, &["-t".to_string(), "long".to_string()]).is_err());
+/// ```
+pub fn apply_extra_args(opts: &Iperf3Options, extra: &[String]) -> Result<Iperf3Options, String> {
+    let mut opts = opts.clone();
+    let mut args = extra.iter();
+    while let Some(arg) = args.next() {
+        let (flag, attached) = match arg.split_once('=') {
+            Some((flag, value)) if arg.starts_with("--") => (flag, Some(value.to_string())),
+            _ if !arg.starts_with("--") && arg.len() > 2 && ["-t", "-b", "-P"].contains(&&arg[..2]) => {
+                (&arg[..2], Some(arg[2..].to_string()))
+            }
+            _ => (arg.as_str(), None),
+        };
+        let mut value = |flag: &str| {
+            attached.clone().or_else(|| args.next().cloned()).ok_or_else(|| format!("IPERF3_EXTRA_ARGS: {} needs a value", flag))
+        };
+        let invalid = |flag: &str, value: &str| format!("IPERF3_EXTRA_ARGS: invalid value {:?} for {}", value, flag);
+        match flag {
+            "-t" | "--time" => {
+                let v = value(flag)?;
+                opts.duration = Some(v.parse().map_err(|_| invalid(flag, &v))?);
+            }
+            "-b" | "--bitrate" => {
+                let v = value(flag)?;
+                opts.bitrate = Some(parse_bitrate(&v).ok_or_else(|| invalid(flag, &v))?);
+            }
+            "-P" | "--parallel" => {
+                let v = value(flag)?;
+                opts.parallel = Some(v.parse().map_err(|_| invalid(flag, &v))?);
+            }
+            "-u" | "--udp" => opts.udp = true,
+            _ => {}
+        }
+    }
+    Ok(opts)
+}
+
+/// Returns `opts` as iperf3 will actually run it, with any `-t`, `-b`, `-P` or `-u` from
+/// `IPERF3_EXTRA_ARGS` applied (see [`apply_extra_args`]). Invalid extra arguments are
+/// left to fail the run itself.
+pub fn effective_options(opts: &Iperf3Options) -> Iperf3Options {
+    iperf3_extra_args()
+        .and_then(|extra| apply_extra_args(opts, &extra))
+        .unwrap_or_else(|_| opts.clone())
+}
+
+/// Builds the argument vector passed to the iperf3 binary.
+///
+/// This is the only place iperf3 arguments are assembled. The arguments are handed
+/// straight to [`tokio::process::Command::args`], never to a shell, and every option
+/// value is emitted as its own argv entry directly after its flag so that spaces,
+/// quotes or shell metacharacters in a value cannot turn into additional flags.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::{build_iperf3_args, Iperf3Options};
+/// let args = build_iperf3_args(&Iperf3Options::new("10.0.0.1", "5201"));
+/// assert_eq!(args, vec!["-c", "10.0.0.1", "-p", "5201", "--json"]);
+/// ```
+///
+/// A target whose host is a socket path (see [`is_unix_socket_path`]) is passed to
+/// `-c` followed by `--unix-domain` instead of a port, for iperf3 builds or wrappers
+/// that test over a shared UNIX socket:
+///
+/// ```
+/// # use iperf3_statuspage::{build_iperf3_args, Iperf3Options};
+/// let args = build_iperf3_args(&Iperf3Options::new("/run/iperf3.sock", ""));
+/// assert_eq!(args, vec!["-c", "/run/iperf3.sock", "--unix-domain", "--json"]);
+/// ```
+pub fn build_iperf3_args(opts: &Iperf3Options) -> Vec<String> {
+    let mut args = match opts.unix_socket_path() {
+        Some(path) => vec!["-c".to_string(), path.to_string(), "--unix-domain".to_string()],
+        None => vec![
+            "-c".to_string(),
+            opts.host.clone(),
+            "-p".to_string(),
+            opts.port.clone(),
+        ],
+    };
+    if let Some(parallel) = opts.parallel {
+        args.push("-P".to_string());
+        args.push(parallel.to_string());
+    }
+    if let Some(bitrate) = opts.bitrate {
+        args.push("-b".to_string());
+        args.push(bitrate.to_string());
+    }
+    if let Some(duration) = opts.duration {
+        args.push("-t".to_string());
+        args.push(duration.to_string());
+    }
+    if opts.reverse {
+        args.push("-R".to_string());
+    }
+    if opts.udp {
+        args.push("-u".to_string());
+    }
+    if let Some(bind) = &opts.bind {
+        args.push("-B".to_string());
+        args.push(bind.clone());
+    }
+    args.push("--json".to_string());
+    args
+}
@@ -0,0 +1,348 @@
+//! # iperf3-statuspage
+//!
+//! Resolved application configuration, printable via `--print-config` and `/debug/config`.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+use std::fmt;
+use std::io::Write;
+use std::path::PathBuf;
+use actix_web::{get, HttpRequest, HttpResponse};
+use serde::{Serialize, Serializer};
+use tracing::error;
+use crate::badge::{sla_min_mbps, sla_min_received_mbps, sla_min_sent_mbps, sla_warn_mbps};
+use crate::baseline::{auto_baseline_enabled, baseline_file_path};
+use crate::command::{
+    configured_bitrate, configured_duration, configured_parallel_streams, configured_udp, iperf3_binary,
+    iperf3_extra_args, is_unix_socket_path, max_test_bytes,
+};
+use crate::deep::{deep_interval, deep_options};
+use crate::diagnostics::diagnostics_on_failure_enabled;
+use crate::direction::rotate_direction_enabled;
+use crate::endpoints::{enabled_endpoints, metrics_bind};
+use crate::errors::{error_format, ErrorFormat, Iperf3Error};
+use crate::annotation::run_annotation;
+use crate::auth::api_token;
+use crate::interfaces::bind_addresses;
+use crate::history::{file_history_enabled, history_max_bytes, history_size};
+use crate::history_file::history_file_path;
+use crate::inflight::max_inflight_requests;
+use crate::maintenance::{maintenance_enabled, maintenance_retry_after_seconds};
+use crate::metrics::{
+    metrics_created_enabled, metrics_float_format, metrics_per_stream_enabled, metrics_smoothing_alpha, MetricsFloatFormat,
+};
+use crate::oneshot::{one_shot_enabled, state_file_path};
+use crate::redact::redact_target_enabled;
+use crate::bad_output::retry_on_parse_failure_enabled;
+use crate::nice::iperf3_nice;
+use crate::link_speed::link_interface;
+use crate::logging::rust_log;
+use crate::live::json_stream_enabled;
+use crate::redact::public_json;
+use crate::shutdown::shutdown_timeout;
+use crate::serialize::response_timeout;
+use crate::slo::slo_window;
+use crate::stale::stale_after;
+use crate::status::{
+    expected_protocol, max_clock_skew, max_host_cpu_percent, min_valid_bytes, reject_clock_skew_enabled,
+    reject_cpu_saturated_enabled,
+};
+use crate::summary::{link_capacity_mbps, resolve_remote_host_enabled};
+use crate::targets::{max_concurrent_runs, multi_server_stagger, server_busy_retry_delay, server_list};
+use crate::timing::phase_timing_enabled;
+use crate::whoami::instance_label;
+use crate::{
+    discard_first_run_enabled, initial_delay, interval_minutes, iperf3_max_retries, iperf3_timeout, prewarm_enabled,
+};
+
+/// Command-line flag printing the resolved configuration and exiting.
+pub const PRINT_CONFIG_FLAG: &str = "--print-config";
+
+/// A configuration value that must never be printed.
+///
+/// Serializes and debug-formats as `"[redacted]"`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+    /// Wraps a secret value.
+    pub fn new(value: impl Into<String>) -> Self {
+        Secret(value.into())
+    }
+
+    /// Returns the underlying secret value.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(\"[redacted]\")")
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str("[redacted]")
+    }
+}
+
+/// Fully resolved application configuration.
+#[derive(Serialize, Debug, Clone)]
+pub struct Config {
+    pub bind_address: String,
+    pub bind_port: u16,
+    pub iperf3_server_ip: String,
+    pub iperf3_server_port: String,
+    pub interval_minutes: u64,
+    pub parallel: Option<u32>,
+    pub max_concurrent_runs: usize,
+    pub multi_server_stagger_ms: u64,
+    pub rotate_direction: bool,
+    pub redact_target: bool,
+    pub instance_label: Option<String>,
+    pub retry_on_parse_failure: bool,
+    pub iperf3_binary: String,
+    pub iperf3_extra_args: Vec<String>,
+    pub iperf3_nice: Option<i32>,
+    pub link_interface: Option<String>,
+    pub shutdown_timeout_seconds: u64,
+    pub history_max_bytes: Option<usize>,
+    pub metrics_bind_address: Option<String>,
+    pub metrics_bind_port: Option<u16>,
+    pub auto_baseline: bool,
+    pub iperf3_json_stream: bool,
+    pub one_shot: bool,
+    pub state_file: Option<PathBuf>,
+    pub targets_file: Option<PathBuf>,
+    pub error_format: ErrorFormat,
+    pub history_size: usize,
+    pub resolve_remote_host: bool,
+    pub min_valid_bytes: Option<u64>,
+    pub discard_first_run: bool,
+    pub prewarm: bool,
+    pub phase_timing: bool,
+    pub maintenance_mode: bool,
+    pub maintenance_retry_after_seconds: u64,
+    pub stale_after_seconds: Option<u64>,
+    /// Milliseconds a response body may take to serialize, from `RESPONSE_TIMEOUT_MS`.
+    pub response_timeout_ms: u64,
+    pub max_inflight_requests: Option<usize>,
+    /// Log filter, from `RUST_LOG`.
+    pub rust_log: String,
+    /// Endpoints registered, from `ENABLED_ENDPOINTS`; `None` means all.
+    pub enabled_endpoints: Option<Vec<String>>,
+    pub bitrate: Option<u64>,
+    pub duration: Option<u32>,
+    pub max_test_bytes: Option<u64>,
+    pub baseline_file: Option<PathBuf>,
+    pub metrics_float_format: MetricsFloatFormat,
+    pub metrics_per_stream: bool,
+    pub metrics_created: bool,
+    pub metrics_smoothing_alpha: Option<f64>,
+    pub link_capacity_mbps: Option<f64>,
+    pub sla_min_mbps: Option<f64>,
+    pub sla_min_sent_mbps: Option<f64>,
+    pub sla_min_received_mbps: Option<f64>,
+    pub sla_warn_mbps: Option<f64>,
+    pub initial_delay_seconds: u64,
+    pub iperf3_timeout_seconds: u64,
+    pub iperf3_max_retries: u32,
+    pub deep_interval_minutes: Option<u64>,
+    pub deep_parallel: Option<u32>,
+    pub deep_bitrate: Option<u64>,
+    pub deep_duration: Option<u32>,
+    pub max_clock_skew_seconds: Option<u64>,
+    pub reject_clock_skew: bool,
+    pub max_host_cpu_percent: Option<f64>,
+    pub reject_cpu_saturated: bool,
+    pub diagnostics_on_failure: bool,
+    /// `HISTORY_FILE` when `HISTORY_BACKEND=file`, `None` for the in-memory history.
+    pub history_file: Option<PathBuf>,
+    /// SQLite database results are exported to (`sqlite` feature).
+    pub db_path: Option<PathBuf>,
+    pub slo_window_hours: u64,
+    /// Protocol tested, `tcp` or `udp`, from `PROTOCOL`.
+    pub protocol: String,
+    pub bind_addresses: Vec<String>,
+    pub expected_protocol: Option<String>,
+    pub run_annotation: Option<String>,
+    pub server_busy_retry_seconds: u64,
+    pub api_token: Option<Secret>,
+    /// NATS server URL; may embed credentials.
+    pub nats_url: Option<Secret>,
+    pub nats_subject: Option<String>,
+    pub otel_exporter_otlp_endpoint: Option<String>,
+    /// Password iperf3 reads from `IPERF3_PASSWORD` for authenticated tests.
+    pub iperf3_password: Option<Secret>,
+}
+
+/// Reads and resolves the configuration from the environment.
+///
+/// Returns an error if `IPERF3_SERVER_IP` is unset, `IPERF3_SERVER_PORT` is unset for a
+/// non-socket target, the servers and ports listed do not match up (see [`server_list`]),
+/// `BIND_PORT` is not a valid `u16`, `INTERVAL_MINUTES` is out of range (see
+/// [`interval_minutes`]), `IPERF3_EXTRA_ARGS` is rejected or, with the `hardening`
+/// feature, a child limit is not a whole number (see `ChildLimits::try_from_env`).
+pub fn load_config() -> Result<Config, String> {
+    let bind_address = env::var("BIND_ADDRESS").unwrap_or_else(|_| "127.0.0.1".to_string());
+    let bind_port_str = env::var("BIND_PORT").unwrap_or_else(|_| "8080".to_string());
+    let bind_port = bind_port_str
+        .parse::<u16>()
+        .map_err(|_| format!("BIND_PORT must be a valid u16, got {:?}", bind_port_str))?;
+
+    let iperf3_server_ip = env::var("IPERF3_SERVER_IP").map_err(|_| "IPERF3_SERVER_IP must be set".to_string())?;
+    let iperf3_server_port = match env::var("IPERF3_SERVER_PORT") {
+        Ok(port) => port,
+        Err(_) if is_unix_socket_path(&iperf3_server_ip) => String::new(),
+        Err(_) => return Err("IPERF3_SERVER_PORT must be set".to_string()),
+    };
+    let (primary_ip, primary_port) = server_list(&iperf3_server_ip, &iperf3_server_port)?.swap_remove(0);
+    let interval_minutes = interval_minutes()?;
+    let iperf3_extra_args = iperf3_extra_args()?;
+    #[cfg(all(target_os = "linux", feature = "hardening"))]
+    crate::hardening::ChildLimits::try_from_env()?;
+    let deep = deep_options(primary_ip, primary_port);
+    let metrics_bind = metrics_bind();
+
+    Ok(Config {
+        bind_address,
+        bind_port,
+        iperf3_server_ip,
+        iperf3_server_port,
+        interval_minutes,
+        parallel: configured_parallel_streams(),
+        max_concurrent_runs: max_concurrent_runs(),
+        multi_server_stagger_ms: multi_server_stagger().as_millis() as u64,
+        rotate_direction: rotate_direction_enabled(),
+        redact_target: redact_target_enabled(),
+        instance_label: instance_label(),
+        retry_on_parse_failure: retry_on_parse_failure_enabled(),
+        iperf3_binary: iperf3_binary(),
+        iperf3_extra_args,
+        iperf3_nice: iperf3_nice(),
+        link_interface: link_interface(),
+        shutdown_timeout_seconds: shutdown_timeout().as_secs(),
+        history_max_bytes: history_max_bytes(),
+        metrics_bind_address: metrics_bind.as_ref().map(|(address, _)| address.clone()),
+        metrics_bind_port: metrics_bind.map(|(_, port)| port),
+        auto_baseline: auto_baseline_enabled(),
+        iperf3_json_stream: json_stream_enabled(),
+        one_shot: one_shot_enabled(),
+        state_file: state_file_path(),
+        targets_file: env::var("TARGETS_FILE").ok().map(PathBuf::from),
+        error_format: error_format(),
+        history_size: history_size(),
+        resolve_remote_host: resolve_remote_host_enabled(),
+        min_valid_bytes: min_valid_bytes(),
+        discard_first_run: discard_first_run_enabled(),
+        prewarm: prewarm_enabled(),
+        phase_timing: phase_timing_enabled(),
+        maintenance_mode: maintenance_enabled(),
+        maintenance_retry_after_seconds: maintenance_retry_after_seconds(),
+        stale_after_seconds: stale_after().map(|d| d.as_secs()),
+        response_timeout_ms: response_timeout().as_millis() as u64,
+        max_inflight_requests: max_inflight_requests(),
+        rust_log: rust_log(),
+        enabled_endpoints: enabled_endpoints(),
+        bitrate: configured_bitrate(),
+        duration: configured_duration(),
+        max_test_bytes: max_test_bytes(),
+        baseline_file: baseline_file_path(),
+        metrics_float_format: metrics_float_format(),
+        metrics_per_stream: metrics_per_stream_enabled(),
+        metrics_created: metrics_created_enabled(),
+        metrics_smoothing_alpha: metrics_smoothing_alpha(),
+        link_capacity_mbps: link_capacity_mbps(),
+        sla_min_mbps: sla_min_mbps(),
+        sla_min_sent_mbps: sla_min_sent_mbps(),
+        sla_min_received_mbps: sla_min_received_mbps(),
+        sla_warn_mbps: sla_warn_mbps(),
+        initial_delay_seconds: initial_delay().as_secs(),
+        iperf3_timeout_seconds: iperf3_timeout().as_secs(),
+        iperf3_max_retries: iperf3_max_retries(),
+        deep_interval_minutes: deep_interval().map(|d| d.as_secs() / 60),
+        deep_parallel: deep.parallel,
+        deep_bitrate: deep.bitrate,
+        deep_duration: deep.duration,
+        max_clock_skew_seconds: max_clock_skew().map(|d| d.as_secs()),
+        reject_clock_skew: reject_clock_skew_enabled(),
+        max_host_cpu_percent: max_host_cpu_percent(),
+        reject_cpu_saturated: reject_cpu_saturated_enabled(),
+        diagnostics_on_failure: diagnostics_on_failure_enabled(),
+        history_file: file_history_enabled().then(history_file_path),
+        db_path: env::var("DB_PATH").ok().map(PathBuf::from),
+        slo_window_hours: slo_window().as_secs() / 3600,
+        protocol: if configured_udp() { "udp" } else { "tcp" }.to_string(),
+        bind_addresses: bind_addresses(),
+        expected_protocol: expected_protocol(),
+        run_annotation: run_annotation(),
+        server_busy_retry_seconds: server_busy_retry_delay().as_secs(),
+        api_token: api_token().map(Secret::new),
+        nats_url: env::var("NATS_URL").ok().map(Secret::new),
+        nats_subject: env::var("NATS_SUBJECT").ok(),
+        otel_exporter_otlp_endpoint: env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok(),
+        iperf3_password: env::var("IPERF3_PASSWORD").ok().map(Secret::new),
+    })
+}
+
+impl Config {
+    /// Returns the primary (first listed) iperf3 server and its port.
+    pub fn primary_server(&self) -> (String, String) {
+        server_list(&self.iperf3_server_ip, &self.iperf3_server_port)
+            .map(|mut servers| servers.swap_remove(0))
+            .unwrap_or_else(|_| (self.iperf3_server_ip.clone(), self.iperf3_server_port.clone()))
+    }
+}
+
+/// Returns `true` if the command-line arguments request `--print-config`.
+pub fn print_config_requested<I: IntoIterator<Item = String>>(args: I) -> bool {
+    args.into_iter().any(|arg| arg == PRINT_CONFIG_FLAG)
+}
+
+/// Prints the resolved configuration as pretty JSON to `out` and returns the process exit code.
+///
+/// Secrets are redacted. Configuration errors are logged to stderr and yield exit code `1`.
+pub fn print_config(out: &mut dyn Write) -> i32 {
+    let config = match load_config() {
+        Ok(config) => config,
+        Err(e) => {
+            error!("Invalid configuration: {}", e);
+            return 1;
+        }
+    };
+
+    match serde_json::to_string_pretty(&config) {
+        Ok(json) => match writeln!(out, "{}", json) {
+            Ok(()) => 0,
+            Err(e) => {
+                error!(error = %e, "Failed to write configuration");
+                1
+            }
+        },
+        Err(e) => {
+            error!(error = %e, "Failed to serialize configuration");
+            1
+        }
+    }
+}
+
+/// HTTP GET endpoint `/debug/config` returns the resolved configuration as JSON with secrets redacted.
+///
+/// With `REDACT_TARGET` enabled the servers are redacted for anonymous clients, see
+/// [`Redaction`](crate::redact::Redaction).
+///
+/// Returns HTTP 500 if the configuration is invalid.
+#[get("/debug/config")]
+pub async fn debug_config(req: HttpRequest) -> Result<HttpResponse, Iperf3Error> {
+    let config = load_config().map_err(Iperf3Error::InvalidConfig)?;
+    Ok(HttpResponse::Ok().json(public_json(&req, &config)?))
+}
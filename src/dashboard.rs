@@ -0,0 +1,186 @@
+//! # iperf3-statuspage
+//!
+//! Self-contained HTML pages: the status page of the latest result at `/` and the
+//! dashboard charting the result history at `/dashboard`.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::time::Duration;
+use actix_web::{get, HttpResponse, Responder};
+use crate::models::Iperf3Report;
+use crate::{bits_per_second_to_mbps, get_last_result, min_frequency_duration};
+
+/// Dashboard page; `{refresh_ms}` is replaced with the refresh period in milliseconds.
+///
+/// The chart is drawn as inline SVG by the embedded script, so the page loads nothing but
+/// `/history`.
+const DASHBOARD_TEMPLATE: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>iperf3 dashboard</title>
+<style>
+body { font-family: sans-serif; margin: 2em; color: #222; }
+#chart { width: 100%; max-width: 960px; height: 320px; }
+#chart .received { stroke: #2b7bb9; }
+#chart .sent { stroke: #e07b39; }
+#status { color: #666; }
+</style>
+</head>
+<body>
+<h1>iperf3 throughput</h1>
+<p id="status">Waiting for data&hellip;</p>
+<svg id="chart" viewBox="0 0 960 320" preserveAspectRatio="none"></svg>
+<script>
+const REFRESH_MS = {refresh_ms};
+const W = 960, H = 320, PAD = 40;
+
+function line(values, max, cls) {
+  const step = values.length > 1 ? (W - 2 * PAD) / (values.length - 1) : 0;
+  const points = values.map((v, i) =>
+    (PAD + i * step).toFixed(1) + "," + (H - PAD - (v / max) * (H - 2 * PAD)).toFixed(1));
+  return '<polyline fill="none" stroke-width="2" class="' + cls + '" points="' + points.join(" ") + '"/>';
+}
+
+function render(history) {
+  const chart = document.getElementById("chart");
+  const status = document.getElementById("status");
+  if (!history.length) {
+    chart.innerHTML = "";
+    status.textContent = "Waiting for data…";
+    return;
+  }
+  const received = history.map(r => r.end.sum_received.bits_per_second / 1e6);
+  const sent = history.map(r => r.end.sum_sent.bits_per_second / 1e6);
+  const max = Math.max(1, ...received, ...sent);
+  chart.innerHTML =
+    '<line x1="' + PAD + '" y1="' + (H - PAD) + '" x2="' + (W - PAD) + '" y2="' + (H - PAD) + '" stroke="#ccc"/>' +
+    '<text x="' + PAD + '" y="' + (PAD - 10) + '" font-size="12">' + max.toFixed(0) + ' Mbps</text>' +
+    line(received, max, "received") + line(sent, max, "sent");
+  const latest = history[history.length - 1];
+  status.textContent = history.length + " results; latest " + received[received.length - 1].toFixed(1) +
+    " Mbps received, " + sent[sent.length - 1].toFixed(1) + " Mbps sent at " + latest.start.timestamp.time;
+}
+
+async function refresh() {
+  try {
+    const response = await fetch("/history", { headers: { Accept: "application/json" } });
+    if (!response.ok) throw new Error(response.status + " " + response.statusText);
+    render(await response.json());
+  } catch (e) {
+    document.getElementById("status").textContent = "Waiting for data (" + e.message + ")";
+  }
+}
+
+refresh();
+setInterval(refresh, REFRESH_MS);
+</script>
+</body>
+</html>
+"##;
+
+/// Renders the dashboard page, refreshing its chart every `refresh`.
+pub fn render_dashboard(refresh: Duration) -> String {
+    DASHBOARD_TEMPLATE.replace("{refresh_ms}", &refresh.as_millis().to_string())
+}
+
+/// HTTP GET endpoint `/dashboard` returns an HTML page charting the received and sent
+/// throughput from `/history`, refreshed every `INTERVAL_MINUTES`.
+///
+/// The page needs no external assets and shows "waiting for data" while the history is
+/// empty or unavailable.
+#[get("/dashboard")]
+pub async fn iperf3_dashboard() -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(render_dashboard(min_frequency_duration()))
+}
+
+/// Escapes the characters with a special meaning in HTML text and attributes.
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Wraps `body` in the status page's HTML document.
+fn status_page(body: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>iperf3 status</title>
+<style>
+body {{ font-family: sans-serif; margin: 2em; color: #222; }}
+th {{ text-align: left; padding-right: 2em; font-weight: normal; color: #666; }}
+</style>
+</head>
+<body>
+<h1>iperf3 status</h1>
+{}
+</body>
+</html>
+"#,
+        body
+    )
+}
+
+/// Renders the status page of `report`: sent and received throughput in Mbps,
+/// retransmits, CPU utilization and the test timestamp.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::{render_status_html, Iperf3Report};
+/// let mut report = Iperf3Report::default();
+/// report.end.sum_received.bits_per_second = 941_500_000.0;
+/// assert!(render_status_html(&report).contains("941.50 Mbps"));
+/// ```
+pub fn render_status_html(report: &Iperf3Report) -> String {
+    let end = &report.end;
+    status_page(&format!(
+        r#"<table>
+<tr><th>Sent</th><td>{:.2} Mbps</td></tr>
+<tr><th>Received</th><td>{:.2} Mbps</td></tr>
+<tr><th>Retransmits</th><td>{}</td></tr>
+<tr><th>CPU (host / remote)</th><td>{:.1}% / {:.1}%</td></tr>
+<tr><th>Tested at</th><td>{}</td></tr>
+</table>"#,
+        bits_per_second_to_mbps(end.sum_sent.bits_per_second),
+        bits_per_second_to_mbps(end.sum_received.bits_per_second),
+        end.sum_sent.retransmits,
+        end.cpu_utilization_percent.host_total,
+        end.cpu_utilization_percent.remote_total,
+        escape_html(&report.start.timestamp.time),
+    ))
+}
+
+/// Renders the status page shown before the first result is cached.
+pub fn render_no_results_html() -> String {
+    status_page("<p>No test results yet. The first test is on its way; check back shortly.</p>")
+}
+
+/// HTTP GET endpoint `/` returns an HTML status page of the last cached result, see
+/// [`render_status_html`].
+///
+/// Before the first result it answers HTTP 200 with a "No test results yet" page rather
+/// than 503, so browsers show something friendly.
+#[get("/")]
+pub async fn iperf3_status_page() -> impl Responder {
+    let body = match get_last_result() {
+        Some(report) => render_status_html(&report),
+        None => render_no_results_html(),
+    };
+    HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body)
+}
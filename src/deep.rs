@@ -0,0 +1,127 @@
+//! # iperf3-statuspage
+//!
+//! Secondary, lower-frequency "deep" test profile served at `/iperf3/deep`.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use actix_web::{get, HttpRequest, HttpResponse};
+use once_cell::sync::Lazy;
+use tokio::time;
+use tracing::{error, info};
+use crate::command::{check_test_bytes, max_test_bytes, Iperf3Options};
+use crate::errors::Iperf3Error;
+use crate::maintenance::ensure_not_in_maintenance;
+use crate::models::Iperf3Report;
+use crate::redact::public_json;
+use crate::shutdown::track_run;
+use crate::targets::{target_lock, Target};
+use crate::{run_iperf3_with_timeout, Iperf3Runner, RealIperf3Runner};
+
+/// Last deep result and when it was cached, kept apart from the regular result.
+static DEEP_RESULT: Lazy<Mutex<Option<(Iperf3Report, Instant)>>> = Lazy::new(|| Mutex::new(None));
+
+/// Reads the environment variable `DEEP_INTERVAL_MINUTES`.
+///
+/// Returns `None` when unset or zero, which disables the deep schedule.
+pub fn deep_interval() -> Option<Duration> {
+    env::var("DEEP_INTERVAL_MINUTES")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .filter(|n| *n > 0)
+        .map(|minutes| Duration::from_secs(minutes * 60))
+}
+
+/// Reads a positive `DEEP_*` override.
+fn deep_override<T: FromStr + Default + PartialOrd>(name: &str) -> Option<T> {
+    env::var(name)
+        .ok()
+        .and_then(|s| s.parse::<T>().ok())
+        .filter(|n| *n > T::default())
+}
+
+/// Builds the options of a deep run against `host:port`.
+///
+/// Starts from the regular options ([`Iperf3Options::from_env`]) and overrides them with
+/// `DEEP_PARALLEL`, `DEEP_BITRATE` and `DEEP_DURATION` where set.
+pub fn deep_options(host: impl Into<String>, port: impl Into<String>) -> Iperf3Options {
+    let opts = Iperf3Options::from_env(host, port);
+    Iperf3Options {
+        parallel: deep_override("DEEP_PARALLEL").or(opts.parallel),
+        bitrate: deep_override("DEEP_BITRATE").or(opts.bitrate),
+        duration: deep_override("DEEP_DURATION").or(opts.duration),
+        ..opts
+    }
+}
+
+/// Returns the last cached deep result, if any.
+pub fn get_deep_result() -> Option<Iperf3Report> {
+    DEEP_RESULT.lock().unwrap().as_ref().map(|(report, _)| report.clone())
+}
+
+/// Clears the cached deep result.
+pub fn clear_deep_result_for_test() {
+    *DEEP_RESULT.lock().unwrap() = None;
+}
+
+/// Runs one deep test with the provided runner and caches its result on success.
+///
+/// Holds the target's lock (see [`target_lock`]) for the whole run, so a deep test never
+/// overlaps a regular test against the same server. `MAX_TEST_BYTES` and the run timeout
+/// apply as usual.
+pub async fn run_deep_with_runner(runner: &dyn Iperf3Runner, opts: &Iperf3Options) -> Result<Iperf3Report, Iperf3Error> {
+    if let Some(max_bytes) = max_test_bytes() {
+        check_test_bytes(opts, max_bytes)?;
+    }
+
+    let lock = target_lock(&Target::with_options(opts.clone()));
+    let _guard = lock.lock().await;
+    let _in_flight = track_run();
+    let stdout = run_iperf3_with_timeout(runner, opts).await?;
+    let data = serde_json::from_str::<Iperf3Report>(&stdout).map_err(|e| Iperf3Error::Parse(e.to_string()))?;
+    *DEEP_RESULT.lock().unwrap() = Some((data.clone(), Instant::now()));
+    info!("Deep iperf3 result updated at {}", data.start.timestamp.time);
+    Ok(data)
+}
+
+/// Background async task running the deep profile every `DEEP_INTERVAL_MINUTES`.
+pub async fn spawn_deep_scheduler(iperf3_ip: String, iperf3_port: String, interval: Duration) {
+    run_deep_scheduler_with_runner(&RealIperf3Runner, &deep_options(iperf3_ip, iperf3_port), interval).await
+}
+
+/// Runs a deep test every `interval` with the provided runner. Never returns.
+///
+/// The first deep test runs one `interval` after startup, leaving the startup slot to the
+/// regular schedule.
+pub async fn run_deep_scheduler_with_runner(runner: &dyn Iperf3Runner, opts: &Iperf3Options, interval: Duration) -> ! {
+    let mut ticker = time::interval_at(time::Instant::now() + interval, interval);
+    loop {
+        ticker.tick().await;
+        if let Err(e) = run_deep_with_runner(runner, opts).await {
+            error!(error = %e, code = e.code(), "Deep run failed");
+        }
+    }
+}
+
+/// HTTP GET endpoint `/iperf3/deep` returns the last cached deep result as JSON.
+///
+/// Returns HTTP 503 Service Unavailable if no deep result is cached yet, e.g. because
+/// `DEEP_INTERVAL_MINUTES` is unset.
+///
+/// Returns HTTP 503 with `Retry-After` while maintenance mode is enabled.
+#[get("/iperf3/deep")]
+pub async fn iperf3_deep(req: HttpRequest) -> Result<HttpResponse, Iperf3Error> {
+    ensure_not_in_maintenance()?;
+    let report = get_deep_result()
+        .ok_or_else(|| Iperf3Error::NotAvailable("Deep iperf3 result not available yet.".to_string()))?;
+    Ok(HttpResponse::Ok().json(public_json(&req, &report)?))
+}
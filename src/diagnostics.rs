@@ -0,0 +1,171 @@
+//! # iperf3-statuspage
+//!
+//! `ping` and `traceroute` diagnostics captured when a test fails, served at
+//! `/debug/last-diagnostics`.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use actix_web::{get, HttpRequest, HttpResponse};
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tokio::process::Command;
+use tokio::time;
+use tracing::info;
+use crate::command::{is_unix_socket_path, Iperf3Options};
+use crate::errors::Iperf3Error;
+use crate::redact::public_json;
+
+/// Longest a single diagnostic command may run before it is killed.
+pub const DIAGNOSTIC_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Reads the environment variable `DIAGNOSTICS_ON_FAILURE` (`true`/`1`), defaulting to
+/// disabled. When enabled a failed run is followed by a `ping` and a `traceroute` to the
+/// target, see [`capture_failure_diagnostics`].
+pub fn diagnostics_on_failure_enabled() -> bool {
+    env::var("DIAGNOSTICS_ON_FAILURE")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+/// External network diagnostics run against a failing target.
+#[async_trait]
+pub trait DiagnosticsRunner: Send + Sync {
+    /// Pings `host`, returning the command's output or why it could not be run.
+    async fn ping(&self, host: &str) -> Result<String, String>;
+    /// Traces the route to `host`, returning the command's output or why it could not be run.
+    async fn traceroute(&self, host: &str) -> Result<String, String>;
+}
+
+/// Diagnostics runner invoking the system's `ping` and `traceroute`.
+pub struct SystemDiagnosticsRunner;
+
+#[async_trait]
+impl DiagnosticsRunner for SystemDiagnosticsRunner {
+    async fn ping(&self, host: &str) -> Result<String, String> {
+        run_diagnostic_command("ping", &["-c", "4", "-W", "2", host]).await
+    }
+
+    async fn traceroute(&self, host: &str) -> Result<String, String> {
+        run_diagnostic_command("traceroute", &["-w", "2", "-m", "20", host]).await
+    }
+}
+
+/// Runs `program` with `args` for at most [`DIAGNOSTIC_TIMEOUT`], returning its stdout
+/// followed by its stderr.
+///
+/// A non-zero exit is not an error: a failing `ping` is exactly what diagnostics are for.
+async fn run_diagnostic_command(program: &str, args: &[&str]) -> Result<String, String> {
+    let child = Command::new(program)
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    let output = time::timeout(DIAGNOSTIC_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| format!("{} timed out after {}s", program, DIAGNOSTIC_TIMEOUT.as_secs()))?
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    Ok(format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    ))
+}
+
+/// Runner used for diagnostics, the system's commands unless replaced.
+static DIAGNOSTICS_RUNNER: Lazy<Mutex<Arc<dyn DiagnosticsRunner>>> =
+    Lazy::new(|| Mutex::new(Arc::new(SystemDiagnosticsRunner)));
+
+/// Installs the runner used for diagnostics, e.g. a mock in tests.
+pub fn set_diagnostics_runner(runner: Arc<dyn DiagnosticsRunner>) {
+    *DIAGNOSTICS_RUNNER.lock().unwrap() = runner;
+}
+
+/// Diagnostics captured after a failed run.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Diagnostics {
+    /// Host the failed run targeted.
+    pub host: String,
+    /// Error the run failed with.
+    pub error: String,
+    /// Unix time the diagnostics were captured, in seconds.
+    pub captured_at: u64,
+    /// Output of `ping`, or why it could not be run.
+    pub ping: String,
+    /// Output of `traceroute`, or why it could not be run.
+    pub traceroute: String,
+}
+
+/// Last captured diagnostics.
+static LAST_DIAGNOSTICS: Lazy<Mutex<Option<Diagnostics>>> = Lazy::new(|| Mutex::new(None));
+
+/// Returns the last captured diagnostics, if any.
+pub fn get_last_diagnostics() -> Option<Diagnostics> {
+    LAST_DIAGNOSTICS.lock().unwrap().clone()
+}
+
+/// Clears the captured diagnostics.
+pub fn clear_last_diagnostics_for_test() {
+    *LAST_DIAGNOSTICS.lock().unwrap() = None;
+}
+
+/// Returns whether `error` suggests a network problem worth diagnosing: iperf3 failed or
+/// stalled, or the server could not be reached. Runs refused or rejected locally are not.
+pub fn warrants_diagnostics(error: &Iperf3Error) -> bool {
+    matches!(error, Iperf3Error::NonZeroExit { .. } | Iperf3Error::Unreachable(_) | Iperf3Error::TimedOut { .. })
+}
+
+/// Runs `ping` and `traceroute` against `host` with `runner` and stores the outcome for
+/// `/debug/last-diagnostics`, replacing any earlier capture.
+pub async fn capture_diagnostics(runner: &dyn DiagnosticsRunner, host: &str, error: &Iperf3Error) -> Diagnostics {
+    let ping = runner.ping(host).await.unwrap_or_else(|e| e);
+    let traceroute = runner.traceroute(host).await.unwrap_or_else(|e| e);
+    let diagnostics = Diagnostics {
+        host: host.to_string(),
+        error: error.to_string(),
+        captured_at: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+        ping,
+        traceroute,
+    };
+    *LAST_DIAGNOSTICS.lock().unwrap() = Some(diagnostics.clone());
+    diagnostics
+}
+
+/// Captures diagnostics for a run with `opts` that failed with `error`, using the
+/// installed runner (see [`set_diagnostics_runner`]).
+///
+/// Does nothing unless `DIAGNOSTICS_ON_FAILURE` is enabled, the error
+/// [warrants diagnostics](warrants_diagnostics) and the target is a network host rather
+/// than a socket path.
+pub async fn capture_failure_diagnostics(opts: &Iperf3Options, error: &Iperf3Error) {
+    if !diagnostics_on_failure_enabled() || !warrants_diagnostics(error) || is_unix_socket_path(&opts.host) {
+        return;
+    }
+    let runner = DIAGNOSTICS_RUNNER.lock().unwrap().clone();
+    info!("Capturing diagnostics for {}", opts.host);
+    capture_diagnostics(runner.as_ref(), &opts.host, error).await;
+}
+
+/// HTTP GET endpoint `/debug/last-diagnostics` returns the diagnostics captured after the
+/// last failed run as JSON.
+///
+/// Returns HTTP 404 Not Found if none were captured since startup, e.g. because
+/// `DIAGNOSTICS_ON_FAILURE` is disabled.
+#[get("/debug/last-diagnostics")]
+pub async fn debug_last_diagnostics(req: HttpRequest) -> Result<HttpResponse, Iperf3Error> {
+    let diagnostics =
+        get_last_diagnostics().ok_or_else(|| Iperf3Error::NotFound("No diagnostics captured.".to_string()))?;
+    Ok(HttpResponse::Ok().json(public_json(&req, &diagnostics)?))
+}
@@ -0,0 +1,98 @@
+//! # iperf3-statuspage
+//!
+//! Per-direction caching and the `ROTATE_DIRECTION` schedule alternating upload and
+//! download tests.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+use std::sync::Mutex;
+use std::time::Instant;
+use once_cell::sync::Lazy;
+use crate::errors::Iperf3Error;
+use crate::models::Iperf3Report;
+use crate::targets::Target;
+
+/// Direction of a test as seen from this client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// The client sends, i.e. a normal run.
+    Up,
+    /// The server sends, i.e. a reverse (`-R`) run.
+    Down,
+}
+
+impl Direction {
+    /// Returns the direction of a run with the given `reverse` option.
+    pub fn of_run(reverse: bool) -> Self {
+        if reverse { Direction::Down } else { Direction::Up }
+    }
+
+    /// Parses the `direction` query parameter, `up` or `down`.
+    pub fn parse(value: &str) -> Result<Self, Iperf3Error> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "up" => Ok(Direction::Up),
+            "down" => Ok(Direction::Down),
+            _ => Err(Iperf3Error::BadRequest("direction must be up or down.".to_string())),
+        }
+    }
+}
+
+/// A cached result and when it was cached.
+type CachedResult = Option<(Iperf3Report, Instant)>;
+
+/// Latest result of each direction, indexed up then down.
+static DIRECTION_RESULTS: Lazy<Mutex<[CachedResult; 2]>> = Lazy::new(|| Mutex::new([None, None]));
+
+fn slot(direction: Direction) -> usize {
+    match direction {
+        Direction::Up => 0,
+        Direction::Down => 1,
+    }
+}
+
+/// Caches `report` as the latest result of `direction`.
+pub fn cache_direction_result(direction: Direction, report: Iperf3Report) {
+    DIRECTION_RESULTS.lock().unwrap()[slot(direction)] = Some((report, Instant::now()));
+}
+
+/// Returns the latest result of `direction` and when it was cached.
+pub fn get_direction_result(direction: Direction) -> CachedResult {
+    DIRECTION_RESULTS.lock().unwrap()[slot(direction)].clone()
+}
+
+/// Clears the per-direction results.
+pub fn clear_direction_results_for_test() {
+    *DIRECTION_RESULTS.lock().unwrap() = [None, None];
+}
+
+/// Reads the environment variable `ROTATE_DIRECTION` (`true`/`1`), defaulting to disabled.
+pub fn rotate_direction_enabled() -> bool {
+    env::var("ROTATE_DIRECTION")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+/// Returns the targets to run in the 1-based `cycle`.
+///
+/// With `ROTATE_DIRECTION` enabled every even cycle flips each target's `reverse` option,
+/// so odd cycles run as configured and even cycles in the opposite direction.
+pub fn rotated_targets(targets: &[Target], cycle: u64) -> Vec<Target> {
+    if !rotate_direction_enabled() || cycle % 2 == 1 {
+        return targets.to_vec();
+    }
+    targets
+        .iter()
+        .map(|target| {
+            let mut options = target.options.clone();
+            options.reverse = !options.reverse;
+            Target::with_options(options)
+        })
+        .collect()
+}
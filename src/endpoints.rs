@@ -0,0 +1,183 @@
+//! # iperf3-statuspage
+//!
+//! Registration of the HTTP endpoints, limited to those enabled by `ENABLED_ENDPOINTS`.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+use actix_web::web;
+use tracing::warn;
+use crate::bad_output::debug_last_bad_output;
+use crate::badge::iperf3_badge;
+use crate::baseline::{iperf3_baseline, set_baseline_from_latest};
+use crate::config::debug_config;
+use crate::dashboard::{iperf3_dashboard, iperf3_status_page};
+use crate::deep::iperf3_deep;
+use crate::diagnostics::debug_last_diagnostics;
+use crate::events::{iperf3_events, iperf3_result_events};
+use crate::interfaces::iperf3_summary_interfaces;
+use crate::history::{iperf3_history, iperf3_history_entry, iperf3_history_points, iperf3_stats};
+use crate::intervals::{iperf3_intervals, iperf3_intervals_tcp};
+use crate::live::iperf3_live;
+use crate::maintenance::{favicon, get_maintenance, healthz, set_maintenance_mode};
+use crate::metrics::iperf3_metrics;
+use crate::on_demand::iperf3_run;
+use crate::sparkline::sparkline;
+use crate::status::{iperf3_health, iperf3_status};
+use crate::summary::iperf3_summary;
+use crate::targets::iperf3_target;
+use crate::timing::debug_timing;
+use crate::version::iperf3_version;
+use crate::whoami::iperf3_whoami;
+use crate::{iperf3, iperf3_download, iperf3_head};
+
+/// Names accepted in `ENABLED_ENDPOINTS` and the routes they cover:
+///
+/// | Name        | Routes                                                                                                                                  |
+/// |-------------|-----------------------------------------------------------------------------------------------------------------------------------------|
+/// | `iperf3`    | `/iperf3` (GET and HEAD), `/iperf3/{host}/{port}`                                                                                       |
+/// | `download`  | `/iperf3/download`                                                                                                                      |
+/// | `intervals` | `/intervals`, `/intervals/tcp`                                                                                                          |
+/// | `status`    | `/status`, `/iperf3/status`                                                                                                             |
+/// | `sparkline` | `/sparkline`                                                                                                                            |
+/// | `summary`   | `/summary`, `/summary/interfaces`                                                                                                       |
+/// | `metrics`   | `/metrics`                                                                                                                              |
+/// | `debug`     | `/debug/timing`, `/debug/config`, `/debug/last-bad-output`, `/debug/last-diagnostics`                                                   |
+/// | `baseline`  | `/baseline`                                                                                                                             |
+/// | `admin`     | `/admin/maintenance`, `/admin/set-baseline`                                                                                             |
+/// | `badge`     | `/badge`                                                                                                                                |
+/// | `deep`      | `/iperf3/deep`                                                                                                                          |
+/// | `version`   | `/version`                                                                                                                              |
+/// | `history`   | `/history`, `/history/{index}`, `/iperf3/history`, `/iperf3/stats`, `/history.parquet` (`parquet` feature), `/query` (`sqlite` feature) |
+/// | `dashboard` | `/dashboard`, `/`                                                                                                                       |
+/// | `live`      | `/iperf3/live`                                                                                                                          |
+/// | `run`       | `/iperf3/run` (POST)                                                                                                                    |
+/// | `events`    | `/events`, `/iperf3/events`                                                                                                             |
+/// | `whoami`    | `/whoami`                                                                                                                               |
+pub const ENDPOINT_NAMES: &[&str] = &[
+    "iperf3", "download", "intervals", "status", "sparkline", "summary", "metrics", "debug", "baseline", "admin",
+    "badge", "deep", "version", "history", "dashboard", "live", "run", "events", "whoami",
+];
+
+/// Reads the environment variable `ENABLED_ENDPOINTS`, a comma-separated list of
+/// [`ENDPOINT_NAMES`]. Returns `None` (all endpoints enabled) when unset.
+///
+/// Unknown names are logged and ignored.
+pub fn enabled_endpoints() -> Option<Vec<String>> {
+    let list = env::var("ENABLED_ENDPOINTS").ok()?;
+    Some(
+        list.split(',')
+            .map(|name| name.trim().to_ascii_lowercase())
+            .filter(|name| !name.is_empty())
+            .filter(|name| {
+                let known = ENDPOINT_NAMES.contains(&name.as_str());
+                if !known {
+                    warn!("Ignoring unknown endpoint {:?} in ENABLED_ENDPOINTS", name);
+                }
+                known
+            })
+            .collect(),
+    )
+}
+
+/// Reads the environment variables `METRICS_BIND_PORT` and `METRICS_BIND_ADDRESS`
+/// (defaulting to `127.0.0.1`), a dedicated listener for `/metrics`.
+///
+/// Returns `None` when `METRICS_BIND_PORT` is unset or not a valid `u16`, in which case
+/// `/metrics` is served on the main port.
+pub fn metrics_bind() -> Option<(String, u16)> {
+    let port = env::var("METRICS_BIND_PORT").ok()?.trim().parse::<u16>().ok()?;
+    let address = env::var("METRICS_BIND_ADDRESS").unwrap_or_else(|_| "127.0.0.1".to_string());
+    Some((address, port))
+}
+
+/// Registers the enabled endpoints. `/healthz` and `/favicon.ico` are always registered;
+/// disabled routes 404.
+///
+/// With a dedicated metrics listener (see [`metrics_bind`]) `/metrics` is left to
+/// [`configure_metrics_services`] instead.
+pub fn configure_services(cfg: &mut web::ServiceConfig) {
+    let enabled = enabled_endpoints();
+    let dedicated_metrics = metrics_bind().is_some();
+    let is_enabled = |name: &str| {
+        !(name == "metrics" && dedicated_metrics)
+            && enabled.as_ref().is_none_or(|names| names.iter().any(|n| n == name))
+    };
+
+    cfg.service(healthz).service(favicon);
+    if is_enabled("iperf3") {
+        cfg.service(iperf3).service(iperf3_head).service(iperf3_target);
+    }
+    if is_enabled("download") {
+        cfg.service(iperf3_download);
+    }
+    if is_enabled("intervals") {
+        cfg.service(iperf3_intervals).service(iperf3_intervals_tcp);
+    }
+    if is_enabled("status") {
+        cfg.service(iperf3_status).service(iperf3_health);
+    }
+    if is_enabled("sparkline") {
+        cfg.service(sparkline);
+    }
+    if is_enabled("summary") {
+        cfg.service(iperf3_summary).service(iperf3_summary_interfaces);
+    }
+    if is_enabled("metrics") {
+        cfg.service(iperf3_metrics);
+    }
+    if is_enabled("debug") {
+        cfg.service(debug_timing)
+            .service(debug_config)
+            .service(debug_last_bad_output)
+            .service(debug_last_diagnostics);
+    }
+    if is_enabled("baseline") {
+        cfg.service(iperf3_baseline);
+    }
+    if is_enabled("admin") {
+        cfg.service(get_maintenance).service(set_maintenance_mode).service(set_baseline_from_latest);
+    }
+    if is_enabled("badge") {
+        cfg.service(iperf3_badge);
+    }
+    if is_enabled("deep") {
+        cfg.service(iperf3_deep);
+    }
+    if is_enabled("version") {
+        cfg.service(iperf3_version);
+    }
+    if is_enabled("history") {
+        cfg.service(iperf3_history).service(iperf3_history_entry).service(iperf3_history_points).service(iperf3_stats);
+        #[cfg(feature = "parquet")]
+        cfg.service(crate::parquet::history_parquet);
+        #[cfg(feature = "sqlite")]
+        cfg.service(crate::sqlite::query_results);
+    }
+    if is_enabled("dashboard") {
+        cfg.service(iperf3_dashboard).service(iperf3_status_page);
+    }
+    if is_enabled("live") {
+        cfg.service(iperf3_live);
+    }
+    if is_enabled("run") {
+        cfg.service(iperf3_run);
+    }
+    if is_enabled("events") {
+        cfg.service(iperf3_events).service(iperf3_result_events);
+    }
+    if is_enabled("whoami") {
+        cfg.service(iperf3_whoami);
+    }
+}
+
+/// Registers the endpoints of the dedicated metrics listener: `/metrics` and `/healthz`.
+pub fn configure_metrics_services(cfg: &mut web::ServiceConfig) {
+    cfg.service(healthz).service(iperf3_metrics);
+}
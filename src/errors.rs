@@ -0,0 +1,331 @@
+//! # iperf3-statuspage
+//!
+//! Error response rendering shared by the HTTP endpoints.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+use std::fmt;
+use std::io;
+use actix_web::http::header::{self, HeaderValue};
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use serde::Serialize;
+use serde_json::json;
+
+/// Body format used for error responses.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorFormat {
+    /// Plain-text message body.
+    Text,
+    /// JSON body of the form `{"error": "<code>", "message": "<message>"}`.
+    Json,
+}
+
+/// Reads the environment variable `ERROR_FORMAT` (`text` or `json`) or returns a default of `text`.
+pub fn error_format() -> ErrorFormat {
+    match env::var("ERROR_FORMAT").map(|s| s.trim().to_ascii_lowercase()) {
+        Ok(format) if format == "json" => ErrorFormat::Json,
+        _ => ErrorFormat::Text,
+    }
+}
+
+/// Builds an error response with the given status in the configured `ERROR_FORMAT`.
+///
+/// `code` is a short machine-readable identifier only included in JSON bodies.
+pub fn error_response(status: StatusCode, code: &str, message: &str) -> HttpResponse {
+    match error_format() {
+        ErrorFormat::Text => HttpResponse::build(status)
+            .content_type("text/plain; charset=utf-8")
+            .body(message.to_string()),
+        ErrorFormat::Json => HttpResponse::build(status).json(json!({
+            "error": code,
+            "message": message,
+        })),
+    }
+}
+
+/// Builds the HTTP 503 response served while no iperf3 result is cached yet.
+pub fn not_available_response() -> HttpResponse {
+    Iperf3Error::not_available().error_response()
+}
+
+/// Errors raised while running iperf3 or serving the HTTP endpoints.
+///
+/// Implements [`ResponseError`], so handlers can `?`-propagate it and every error renders
+/// through [`error_response`] with a consistent status, code and message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Iperf3Error {
+    /// No iperf3 result (or cycle) is available yet.
+    NotAvailable(String),
+    /// A query parameter was invalid.
+    BadRequest(String),
+    /// The request lacks a valid `Authorization: Bearer <API_TOKEN>` header.
+    Unauthorized(String),
+    /// The requested resource, e.g. a history entry, does not exist.
+    NotFound(String),
+    /// The request conflicts with work already in progress, e.g. an on-demand run.
+    Conflict(String),
+    /// `fields` named paths that do not exist in the report.
+    UnknownFields(Vec<String>),
+    /// The configuration could not be resolved.
+    InvalidConfig(String),
+    /// An unexpected internal failure, e.g. serialization.
+    Internal(String),
+    /// The iperf3 process could not be spawned or awaited.
+    Spawn(String),
+    /// This process lacks permission to execute iperf3.
+    PermissionDenied(String),
+    /// iperf3 exited unsuccessfully. `code` is `None` if it was killed by a signal.
+    NonZeroExit { code: Option<i32>, stderr: String },
+    /// The iperf3 server is busy running a test for another client.
+    ServerBusy(String),
+    /// The iperf3 server could not be reached by a probe.
+    Unreachable(String),
+    /// iperf3 did not finish within the run timeout and was killed.
+    TimedOut { timeout_seconds: u64 },
+    /// iperf3's output was not a valid JSON report.
+    Parse(String),
+    /// The report parsed but failed a sanity check, so it was not cached.
+    Rejected(String),
+    /// No result is cached because the last run failed with this error.
+    LastRunFailed(String),
+    /// Maintenance mode is enabled; clients should retry after the given number of seconds.
+    Maintenance { retry_after_seconds: u64 },
+    /// `MAX_INFLIGHT_REQUESTS` requests are already being handled; clients should retry
+    /// after the given number of seconds.
+    Overloaded { retry_after_seconds: u64 },
+    /// The cached result is older than `STALE_AFTER_SECONDS`.
+    Stale { age_seconds: u64 },
+    /// Serializing the response took longer than `RESPONSE_TIMEOUT_MS`.
+    ResponseTimeout { timeout_ms: u64 },
+    /// The projected transfer of a run exceeds `MAX_TEST_BYTES`, so it was not started.
+    OverByteBudget { projected_bytes: u64, max_bytes: u64 },
+}
+
+/// Lowercase fragments of iperf3's stderr marking a failure to reach or stay connected to
+/// the server, which [`Iperf3Error::is_transient`] retries.
+pub const CONNECTION_FAILURES: &[&str] = &[
+    "unable to connect",
+    "connection refused",
+    "connection reset",
+    "no route to host",
+    "network is unreachable",
+    "control socket has closed unexpectedly",
+];
+
+impl Iperf3Error {
+    /// The error served while no iperf3 result is cached yet.
+    pub fn not_available() -> Self {
+        Iperf3Error::NotAvailable("Iperf3 result not available yet.".to_string())
+    }
+
+    /// Classifies a failed iperf3 run from its exit code and output.
+    ///
+    /// With `--json` iperf3 reports errors as `{"error": "..."}` on stdout, so that message
+    /// is used when stderr is empty. A "server is busy" message yields
+    /// [`Iperf3Error::ServerBusy`], anything else [`Iperf3Error::NonZeroExit`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use iperf3_statuspage::Iperf3Error;
+    /// let busy = Iperf3Error::from_failed_run(
+    ///     Some(1),
+    ///     r#"{"error": "error - the server is busy running a test. try again later"}"#,
+    ///     "",
+    /// );
+    /// assert!(matches!(busy, Iperf3Error::ServerBusy(_)));
+    /// ```
+    pub fn from_failed_run(code: Option<i32>, stdout: &str, stderr: &str) -> Self {
+        let message = if stderr.trim().is_empty() {
+            serde_json::from_str::<serde_json::Value>(stdout)
+                .ok()
+                .and_then(|json| json.get("error").and_then(|e| e.as_str()).map(str::to_string))
+                .unwrap_or_default()
+        } else {
+            stderr.to_string()
+        };
+        if message.to_ascii_lowercase().contains("the server is busy") {
+            Iperf3Error::ServerBusy(message.trim().to_string())
+        } else {
+            Iperf3Error::NonZeroExit { code, stderr: message }
+        }
+    }
+
+    /// Classifies a failure to spawn or await iperf3.
+    ///
+    /// A permission error yields [`Iperf3Error::PermissionDenied`], anything else
+    /// [`Iperf3Error::Spawn`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::io;
+    /// # use iperf3_statuspage::Iperf3Error;
+    /// let denied = Iperf3Error::from_spawn_error(&io::Error::from(io::ErrorKind::PermissionDenied));
+    /// assert!(matches!(denied, Iperf3Error::PermissionDenied(_)));
+    /// ```
+    pub fn from_spawn_error(e: &io::Error) -> Self {
+        if e.kind() == io::ErrorKind::PermissionDenied {
+            Iperf3Error::PermissionDenied(e.to_string())
+        } else {
+            Iperf3Error::Spawn(e.to_string())
+        }
+    }
+
+    /// Returns whether the error is a transient connection failure worth retrying right
+    /// away: iperf3 timed out, the server could not be reached, or iperf3 exited
+    /// unsuccessfully with a connection error on stderr (see [`CONNECTION_FAILURES`]).
+    /// Other unsuccessful exits, e.g. a bad option, fail the same way every time and are
+    /// not retried. A busy server is retried separately after a longer delay.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use iperf3_statuspage::Iperf3Error;
+    /// let refused = Iperf3Error::from_failed_run(Some(1), "", "iperf3: error - unable to connect to server: Connection refused");
+    /// assert!(refused.is_transient());
+    /// let bad_option = Iperf3Error::from_failed_run(Some(1), "", "iperf3: option requires an argument -- 't'");
+    /// assert!(!bad_option.is_transient());
+    /// ```
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Iperf3Error::NonZeroExit { stderr, .. } => {
+                let stderr = stderr.to_ascii_lowercase();
+                CONNECTION_FAILURES.iter().any(|failure| stderr.contains(failure))
+            }
+            Iperf3Error::Unreachable(_) | Iperf3Error::TimedOut { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the exit code carried by a [`Iperf3Error::NonZeroExit`].
+    pub fn exit_code(&self) -> Option<i32> {
+        match self {
+            Iperf3Error::NonZeroExit { code, .. } => *code,
+            _ => None,
+        }
+    }
+
+    /// Short machine-readable identifier included in JSON bodies.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Iperf3Error::NotAvailable(_) => "not_available",
+            Iperf3Error::BadRequest(_) => "bad_request",
+            Iperf3Error::Unauthorized(_) => "unauthorized",
+            Iperf3Error::NotFound(_) => "not_found",
+            Iperf3Error::Conflict(_) => "conflict",
+            Iperf3Error::UnknownFields(_) => "unknown_fields",
+            Iperf3Error::InvalidConfig(_) => "invalid_config",
+            Iperf3Error::Internal(_) => "internal_error",
+            Iperf3Error::Spawn(_) | Iperf3Error::NonZeroExit { .. } => "iperf3_failed",
+            Iperf3Error::PermissionDenied(_) => "permission_denied",
+            Iperf3Error::ServerBusy(_) => "server_busy",
+            Iperf3Error::Unreachable(_) => "unreachable",
+            Iperf3Error::TimedOut { .. } => "timed_out",
+            Iperf3Error::Parse(_) => "parse_failed",
+            Iperf3Error::Rejected(_) => "rejected",
+            Iperf3Error::LastRunFailed(_) => "last_run_failed",
+            Iperf3Error::Maintenance { .. } => "maintenance",
+            Iperf3Error::Overloaded { .. } => "overloaded",
+            Iperf3Error::Stale { .. } => "stale",
+            Iperf3Error::ResponseTimeout { .. } => "response_timeout",
+            Iperf3Error::OverByteBudget { .. } => "over_byte_budget",
+        }
+    }
+}
+
+impl fmt::Display for Iperf3Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Iperf3Error::NotAvailable(message)
+            | Iperf3Error::BadRequest(message)
+            | Iperf3Error::Unauthorized(message)
+            | Iperf3Error::NotFound(message)
+            | Iperf3Error::Conflict(message)
+            | Iperf3Error::InvalidConfig(message)
+            | Iperf3Error::Internal(message)
+            | Iperf3Error::Rejected(message) => f.write_str(message),
+            Iperf3Error::UnknownFields(paths) => write!(f, "Unknown field paths: {}", paths.join(", ")),
+            Iperf3Error::Spawn(e) => write!(f, "Failed to run iperf3: {}", e),
+            Iperf3Error::PermissionDenied(e) => write!(
+                f,
+                "Permission denied running iperf3: {}; check that the binary is executable by this user and not \
+                 blocked by AppArmor or SELinux",
+                e
+            ),
+            Iperf3Error::NonZeroExit { code: Some(code), stderr } => {
+                write!(f, "iperf3 failed with exit code {}: {}", code, stderr)
+            }
+            Iperf3Error::NonZeroExit { code: None, stderr } => write!(f, "iperf3 failed: {}", stderr),
+            Iperf3Error::ServerBusy(message) => write!(f, "iperf3 server is busy: {}", message),
+            Iperf3Error::Unreachable(e) => write!(f, "iperf3 server is unreachable: {}", e),
+            Iperf3Error::TimedOut { timeout_seconds } => write!(f, "iperf3 timed out after {}s", timeout_seconds),
+            Iperf3Error::Parse(e) => write!(f, "Failed to parse iperf3 JSON: {}", e),
+            Iperf3Error::LastRunFailed(e) => write!(f, "No iperf3 result yet; the last run failed: {}", e),
+            Iperf3Error::Maintenance { .. } => f.write_str("Service is under maintenance."),
+            Iperf3Error::Overloaded { .. } => f.write_str("Too many requests in flight; try again shortly."),
+            Iperf3Error::Stale { age_seconds } => write!(f, "Iperf3 result is stale ({} seconds old).", age_seconds),
+            Iperf3Error::ResponseTimeout { timeout_ms } => {
+                write!(f, "Timed out serializing the response after {} ms.", timeout_ms)
+            }
+            Iperf3Error::OverByteBudget { projected_bytes, max_bytes } => write!(
+                f,
+                "Refusing to run iperf3: projected transfer of {} bytes exceeds MAX_TEST_BYTES of {}",
+                projected_bytes, max_bytes
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Iperf3Error {}
+
+impl ResponseError for Iperf3Error {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Iperf3Error::NotAvailable(_)
+            | Iperf3Error::Maintenance { .. }
+            | Iperf3Error::Overloaded { .. }
+            | Iperf3Error::Stale { .. }
+            | Iperf3Error::ResponseTimeout { .. }
+            | Iperf3Error::ServerBusy(_) => StatusCode::SERVICE_UNAVAILABLE,
+            Iperf3Error::BadRequest(_) | Iperf3Error::UnknownFields(_) => StatusCode::BAD_REQUEST,
+            Iperf3Error::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Iperf3Error::NotFound(_) => StatusCode::NOT_FOUND,
+            Iperf3Error::Conflict(_) => StatusCode::CONFLICT,
+            Iperf3Error::TimedOut { .. } => StatusCode::GATEWAY_TIMEOUT,
+            Iperf3Error::InvalidConfig(_)
+            | Iperf3Error::Internal(_)
+            | Iperf3Error::OverByteBudget { .. }
+            | Iperf3Error::PermissionDenied(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Iperf3Error::Spawn(_)
+            | Iperf3Error::NonZeroExit { .. }
+            | Iperf3Error::Unreachable(_)
+            | Iperf3Error::Parse(_)
+            | Iperf3Error::Rejected(_)
+            | Iperf3Error::LastRunFailed(_) => StatusCode::BAD_GATEWAY,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut response = error_response(self.status_code(), self.code(), &self.to_string());
+        match self {
+            Iperf3Error::Maintenance { retry_after_seconds } | Iperf3Error::Overloaded { retry_after_seconds } => {
+                response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(*retry_after_seconds));
+            }
+            Iperf3Error::Unauthorized(_) => {
+                response.headers_mut().insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+            }
+            _ => {}
+        }
+        response
+    }
+}
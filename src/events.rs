@@ -0,0 +1,139 @@
+//! # iperf3-statuspage
+//!
+//! Server-Sent Events at `/events`: a countdown to the next scheduled run and a
+//! notification whenever a new result is cached, so dashboards need not poll.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::time::{Duration, Instant};
+use actix_web::http::header;
+use actix_web::web::Bytes;
+use actix_web::{get, HttpResponse};
+use futures::{stream, StreamExt};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tokio::time::{self, MissedTickBehavior};
+use crate::{bits_per_second_to_mbps, get_last_result, min_frequency_duration};
+use crate::models::Iperf3Report;
+use crate::status::last_cycle_start;
+
+/// How often `/events` pushes a `countdown` event.
+pub const COUNTDOWN_PERIOD: Duration = Duration::from_secs(1);
+
+/// Notification pushed as an `event: result` when a new result is cached.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ResultEvent {
+    /// Unix time the cached test started.
+    pub timestamp: u64,
+    pub sent_mbps: f64,
+    pub received_mbps: f64,
+}
+
+/// Payload of an `event: countdown`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CountdownEvent {
+    /// Seconds until the next scheduled run is due, `None` before the first cycle.
+    pub next_run_in_seconds: Option<u64>,
+}
+
+impl From<&Iperf3Report> for ResultEvent {
+    fn from(report: &Iperf3Report) -> Self {
+        ResultEvent {
+            timestamp: report.start.timestamp.timesecs,
+            sent_mbps: bits_per_second_to_mbps(report.end.sum_sent.bits_per_second),
+            received_mbps: bits_per_second_to_mbps(report.end.sum_received.bits_per_second),
+        }
+    }
+}
+
+/// Channel fanning result notifications out to every `/events` and `/iperf3/events`
+/// subscriber.
+static RESULT_EVENTS: Lazy<broadcast::Sender<ResultEvent>> = Lazy::new(|| broadcast::channel(16).0);
+
+/// Notifies event stream subscribers that `report` was cached.
+///
+/// Sending never waits: it does nothing without subscribers, and slow or dropped
+/// subscribers cannot hold up the caller.
+pub fn notify_result_cached(report: &Iperf3Report) {
+    let _ = RESULT_EVENTS.send(ResultEvent::from(report));
+}
+
+/// Returns the countdown to the next run, due `INTERVAL_MINUTES` after the last cycle started.
+pub fn countdown(now: Instant) -> CountdownEvent {
+    CountdownEvent {
+        next_run_in_seconds: last_cycle_start()
+            .map(|start| (start + min_frequency_duration()).saturating_duration_since(now).as_secs()),
+    }
+}
+
+/// Formats one Server-Sent Event.
+fn sse_frame(event: &str, data: &impl Serialize) -> Bytes {
+    let data = serde_json::to_string(data).unwrap_or_else(|_| "null".to_string());
+    Bytes::from(format!("event: {}\ndata: {}\n\n", event, data))
+}
+
+/// HTTP GET endpoint `/events` streams Server-Sent Events: `event: countdown` every
+/// second with the seconds until the next scheduled run, and `event: result` with the
+/// headline figures whenever a new result is cached.
+///
+/// A subscriber too slow to keep up skips the result notifications it missed.
+#[get("/events")]
+pub async fn iperf3_events() -> HttpResponse {
+    let results = RESULT_EVENTS.subscribe();
+    let mut ticks = time::interval(COUNTDOWN_PERIOD);
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    let events = stream::unfold((results, ticks), |(mut results, mut ticks)| async move {
+        loop {
+            tokio::select! {
+                received = results.recv() => match received {
+                    Ok(event) => return Some((Ok::<_, actix_web::Error>(sse_frame("result", &event)), (results, ticks))),
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                },
+                _ = ticks.tick() => {
+                    let frame = sse_frame("countdown", &countdown(Instant::now()));
+                    return Some((Ok(frame), (results, ticks)));
+                }
+            }
+        }
+    });
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        .streaming(events)
+}
+
+/// HTTP GET endpoint `/iperf3/events` streams Server-Sent Events of the cached results:
+/// an `event: result` with the headline figures of the current result right away (if
+/// one is cached), then one whenever a new result of the primary target is cached.
+///
+/// A subscriber too slow to keep up skips the result notifications it missed.
+#[get("/iperf3/events")]
+pub async fn iperf3_result_events() -> HttpResponse {
+    // Subscribe before reading the cache so no result cached in between is missed
+    let results = RESULT_EVENTS.subscribe();
+    let current = get_last_result().map(|report| sse_frame("result", &ResultEvent::from(&report)));
+
+    let updates = stream::unfold(results, |mut results| async move {
+        loop {
+            match results.recv().await {
+                Ok(event) => return Some((sse_frame("result", &event), results)),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+    let events = stream::iter(current).chain(updates).map(Ok::<_, actix_web::Error>);
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        .streaming(events)
+}
@@ -0,0 +1,88 @@
+//! # iperf3-statuspage
+//!
+//! Field path selection over serialized reports, used by `/iperf3?fields=`.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use serde_json::{Map, Value};
+
+/// Parses a comma-separated `fields` query value into individual dotted paths.
+///
+/// Surrounding whitespace and empty entries are ignored.
+pub fn parse_field_paths(fields: &str) -> Vec<String> {
+    fields
+        .split(',')
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Looks up a dotted path such as `end.sum_received.bytes` in a JSON object tree.
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |current, key| current.as_object()?.get(key))
+}
+
+/// Inserts `selected` at the dotted `path` in `target`, creating intermediate objects.
+fn insert_at(target: &mut Map<String, Value>, path: &str, selected: Value) {
+    let mut keys = path.split('.').peekable();
+    let mut current = target;
+    while let Some(key) = keys.next() {
+        if keys.peek().is_none() {
+            current.insert(key.to_string(), selected);
+            return;
+        }
+        let entry = current
+            .entry(key.to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+        if !entry.is_object() {
+            // A shorter path already selected the whole parent.
+            return;
+        }
+        current = entry.as_object_mut().unwrap();
+    }
+}
+
+/// Prunes `value` down to the requested dotted `paths`, keeping their nesting.
+///
+/// Returns the list of paths that do not exist in `value` as the error.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::select_fields;
+/// # use serde_json::json;
+/// let report = json!({"start": {"cookie": "abc", "version": "3.16"}, "end": {}});
+///
+/// let pruned = select_fields(&report, &["start.cookie".to_string()]).unwrap();
+/// assert_eq!(pruned, json!({"start": {"cookie": "abc"}}));
+///
+/// let bad = select_fields(&report, &["start.nope".to_string()]).unwrap_err();
+/// assert_eq!(bad, vec!["start.nope".to_string()]);
+/// ```
+pub fn select_fields(value: &Value, paths: &[String]) -> Result<Value, Vec<String>> {
+    let invalid: Vec<String> = paths
+        .iter()
+        .filter(|path| lookup(value, path).is_none())
+        .cloned()
+        .collect();
+    if !invalid.is_empty() {
+        return Err(invalid);
+    }
+
+    // Insert shorter paths last so a whole parent wins over its individually selected children.
+    let mut ordered: Vec<&String> = paths.iter().collect();
+    ordered.sort_by_key(|path| std::cmp::Reverse(path.matches('.').count()));
+
+    let mut pruned = Map::new();
+    for path in ordered {
+        insert_at(&mut pruned, path, lookup(value, path).unwrap().clone());
+    }
+    Ok(Value::Object(pruned))
+}
@@ -0,0 +1,128 @@
+//! # iperf3-statuspage
+//!
+//! Resource limits and privilege dropping for the spawned iperf3 child (Linux, `hardening` feature).
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+use std::io;
+use tokio::process::Command;
+use tracing::warn;
+
+/// Limits applied to the iperf3 child between `fork` and `exec`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ChildLimits {
+    /// `RLIMIT_CPU` in seconds of CPU time.
+    pub cpu_seconds: Option<u64>,
+    /// `RLIMIT_AS` in bytes of virtual address space.
+    pub address_space_bytes: Option<u64>,
+    /// Group id to switch to before exec.
+    pub gid: Option<u32>,
+    /// User id to switch to before exec.
+    pub uid: Option<u32>,
+}
+
+/// Reads the environment variable `name` as a whole number, `None` when unset or empty.
+fn env_number<T: std::str::FromStr>(name: &str) -> Result<Option<T>, String> {
+    match env::var(name).ok().filter(|s| !s.trim().is_empty()) {
+        Some(s) => {
+            s.trim().parse::<T>().map(Some).map_err(|_| format!("{} must be a whole number, got {:?}", name, s))
+        }
+        None => Ok(None),
+    }
+}
+
+impl ChildLimits {
+    /// Reads the limits from the environment.
+    ///
+    /// `IPERF3_RLIMIT_CPU_SECONDS`, `IPERF3_RLIMIT_AS_BYTES`, `IPERF3_RUN_AS_GID` and
+    /// `IPERF3_RUN_AS_UID` are each optional; unset values apply no limit. Returns an error
+    /// naming the variable if one is not a whole number, which [`load_config`] reports at
+    /// startup.
+    ///
+    /// [`load_config`]: crate::load_config
+    pub fn try_from_env() -> Result<Self, String> {
+        Ok(ChildLimits {
+            cpu_seconds: env_number("IPERF3_RLIMIT_CPU_SECONDS")?,
+            address_space_bytes: env_number("IPERF3_RLIMIT_AS_BYTES")?,
+            gid: env_number("IPERF3_RUN_AS_GID")?,
+            uid: env_number("IPERF3_RUN_AS_UID")?,
+        })
+    }
+
+    /// Reads the limits from the environment like [`ChildLimits::try_from_env`], logging a
+    /// warning and applying no limit if a value is invalid.
+    pub fn from_env() -> Self {
+        Self::try_from_env().unwrap_or_else(|e| {
+            warn!("{}; applying no limits to the iperf3 child", e);
+            ChildLimits::default()
+        })
+    }
+
+    /// Returns `true` when no limit is configured.
+    pub fn is_empty(&self) -> bool {
+        *self == ChildLimits::default()
+    }
+
+    /// Installs a `pre_exec` hook on `command` applying these limits to the child.
+    ///
+    /// Resource limits are set first, then the group and user are dropped, so the child
+    /// cannot raise its limits back. A failure aborts the spawn with the OS error.
+    pub fn apply(&self, command: &mut Command) {
+        if self.is_empty() {
+            return;
+        }
+        let limits = self.clone();
+        // SAFETY: the hook only calls async-signal-safe libc functions and does not allocate.
+        unsafe {
+            command.pre_exec(move || limits.apply_in_child());
+        }
+    }
+
+    fn apply_in_child(&self) -> io::Result<()> {
+        if let Some(seconds) = self.cpu_seconds {
+            set_rlimit(libc::RLIMIT_CPU, seconds)?;
+        }
+        if let Some(bytes) = self.address_space_bytes {
+            set_rlimit(libc::RLIMIT_AS, bytes)?;
+        }
+        if let Some(gid) = self.gid {
+            // SAFETY: plain syscalls with valid arguments.
+            unsafe {
+                if libc::setgroups(0, std::ptr::null()) != 0 || libc::setgid(gid) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+        }
+        if let Some(uid) = self.uid {
+            // SAFETY: plain syscall with a valid argument.
+            if unsafe { libc::setuid(uid) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_env = "gnu")]
+type RlimitResource = libc::__rlimit_resource_t;
+#[cfg(not(target_env = "gnu"))]
+type RlimitResource = libc::c_int;
+
+fn set_rlimit(resource: RlimitResource, value: u64) -> io::Result<()> {
+    let limit = libc::rlimit {
+        rlim_cur: value as libc::rlim_t,
+        rlim_max: value as libc::rlim_t,
+    };
+    // SAFETY: `limit` is a valid, initialized rlimit.
+    if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
@@ -0,0 +1,464 @@
+//! # iperf3-statuspage
+//!
+//! Bounded history of recent iperf3 results for trends.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::collections::VecDeque;
+use std::env;
+use std::fmt::Write;
+use std::sync::Mutex;
+use actix_web::http::header::Accept;
+use actix_web::{get, web, HttpMessage, HttpRequest, HttpResponse};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tracing::{error, info, warn};
+use crate::bits_per_second_to_mbps;
+use crate::errors::Iperf3Error;
+use crate::history_file::{history_file_path, FileHistory};
+use crate::maintenance::ensure_not_in_maintenance;
+use crate::models::Iperf3Report;
+use crate::msgpack::to_msgpack;
+use crate::redact::{public_json, Redaction};
+
+/// Ring buffer of results, oldest first, tracking the estimated serialized size of each.
+#[derive(Debug, Default)]
+pub struct History {
+    entries: VecDeque<(Iperf3Report, usize)>,
+    total_bytes: usize,
+}
+
+impl History {
+    /// Appends `report`, then evicts the oldest entries while more than `max_len` are held
+    /// or, with `max_bytes`, while their estimated size exceeds it. The newest entry is
+    /// always kept.
+    pub fn push(&mut self, report: Iperf3Report, max_len: usize, max_bytes: Option<usize>) {
+        let size = estimated_size(&report);
+        self.entries.push_back((report, size));
+        self.total_bytes += size;
+        while self.entries.len() > max_len
+            || (self.entries.len() > 1 && max_bytes.is_some_and(|max| self.total_bytes > max))
+        {
+            if let Some((_, evicted)) = self.entries.pop_front() {
+                self.total_bytes -= evicted;
+            }
+        }
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the history holds no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the estimated serialized size of all entries in bytes.
+    pub fn total_bytes(&self) -> usize {
+        self.total_bytes
+    }
+
+    /// Iterates over the entries, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &Iperf3Report> {
+        self.entries.iter().map(|(report, _)| report)
+    }
+
+    /// Returns the entry at `index`, oldest first.
+    pub fn get(&self, index: usize) -> Option<&Iperf3Report> {
+        self.entries.get(index).map(|(report, _)| report)
+    }
+
+    /// Removes every entry.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.total_bytes = 0;
+    }
+}
+
+/// Storage of the result history, see `HISTORY_BACKEND`.
+pub trait HistoryBackend: Send {
+    /// Appends `report`, then evicts the oldest entries while more than `max_len` are held
+    /// or, with `max_bytes`, while their estimated size exceeds it. The newest entry is
+    /// always kept.
+    fn push(&mut self, report: Iperf3Report, max_len: usize, max_bytes: Option<usize>);
+    /// Returns the number of entries.
+    fn len(&self) -> usize;
+    /// Returns `true` if the history holds no entries.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Returns the estimated serialized size of all entries in bytes.
+    fn total_bytes(&self) -> usize;
+    /// Returns every entry, oldest first.
+    fn snapshot(&self) -> Vec<Iperf3Report>;
+    /// Returns the entry at `index`, oldest first.
+    fn entry(&self, index: usize) -> Option<Iperf3Report>;
+    /// Removes every entry.
+    fn clear(&mut self);
+}
+
+impl HistoryBackend for History {
+    fn push(&mut self, report: Iperf3Report, max_len: usize, max_bytes: Option<usize>) {
+        History::push(self, report, max_len, max_bytes)
+    }
+
+    fn len(&self) -> usize {
+        History::len(self)
+    }
+
+    fn total_bytes(&self) -> usize {
+        History::total_bytes(self)
+    }
+
+    fn snapshot(&self) -> Vec<Iperf3Report> {
+        self.iter().cloned().collect()
+    }
+
+    fn entry(&self, index: usize) -> Option<Iperf3Report> {
+        self.get(index).cloned()
+    }
+
+    fn clear(&mut self) {
+        History::clear(self)
+    }
+}
+
+/// Estimates the size of `report` as its length in compact JSON.
+fn estimated_size(report: &Iperf3Report) -> usize {
+    serde_json::to_vec(report).map(|json| json.len()).unwrap_or(0)
+}
+
+/// Global history of the most recent successful results, oldest first. In memory unless
+/// replaced by [`load_history_from_env`].
+pub static HISTORY: Lazy<Mutex<Box<dyn HistoryBackend>>> = Lazy::new(|| Mutex::new(Box::new(History::default())));
+
+/// Installs `backend` as the global history, dropping the current one.
+pub fn set_history_backend(backend: Box<dyn HistoryBackend>) {
+    *HISTORY.lock().unwrap() = backend;
+}
+
+/// Reads the environment variable `HISTORY_BACKEND`, `memory` (the default) or `file`.
+///
+/// Returns `true` for `file`, which keeps the history in `HISTORY_FILE` across restarts.
+pub fn file_history_enabled() -> bool {
+    env::var("HISTORY_BACKEND").is_ok_and(|v| v.trim().eq_ignore_ascii_case("file"))
+}
+
+/// Switches the global history to the file backend when `HISTORY_BACKEND=file`, restoring
+/// the entries already in `HISTORY_FILE`.
+///
+/// A file that cannot be opened is logged as a warning and the history stays in memory.
+pub fn load_history_from_env() {
+    if !file_history_enabled() {
+        return;
+    }
+    let path = history_file_path();
+    match FileHistory::open(&path, history_size(), history_max_bytes()) {
+        Ok(history) => {
+            info!("Restored {} history entries from {}", history.len(), path.display());
+            set_history_backend(Box::new(history));
+        }
+        Err(e) => warn!("Failed to open {}: {}; keeping the history in memory", path.display(), e),
+    }
+}
+
+/// Reads the environment variable `HISTORY_SIZE` or returns a default of 100 results.
+pub fn history_size() -> usize {
+    env::var("HISTORY_SIZE")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(100)
+}
+
+/// Reads the environment variable `HISTORY_MAX_BYTES`, the largest estimated JSON size of
+/// the history. Returns `None` when unset or zero, leaving only `HISTORY_SIZE` in effect.
+pub fn history_max_bytes() -> Option<usize> {
+    env::var("HISTORY_MAX_BYTES")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .filter(|n| *n > 0)
+}
+
+/// Appends a result to the history, evicting the oldest entries beyond `HISTORY_SIZE` or
+/// `HISTORY_MAX_BYTES`.
+pub fn push_history(report: Iperf3Report) {
+    HISTORY.lock().unwrap().push(report, history_size(), history_max_bytes());
+}
+
+/// Appends a result to the history like [`push_history`], on a blocking thread so a file
+/// backend's disk write does not stall the async runtime.
+pub async fn push_history_blocking(report: Iperf3Report) {
+    if let Err(e) = tokio::task::spawn_blocking(move || push_history(report)).await {
+        error!(error = %e, "Failed to append to the history");
+    }
+}
+
+/// Returns a snapshot of the history, oldest first.
+pub fn get_history() -> Vec<Iperf3Report> {
+    HISTORY.lock().unwrap().snapshot()
+}
+
+/// Returns the estimated serialized size of the history in bytes.
+pub fn history_bytes() -> usize {
+    HISTORY.lock().unwrap().total_bytes()
+}
+
+/// Returns the `index`th most recent result (0 = latest), if the history holds that many.
+pub fn get_history_entry(index: usize) -> Option<Iperf3Report> {
+    let history = HISTORY.lock().unwrap();
+    history.len().checked_sub(index + 1).and_then(|i| history.entry(i))
+}
+
+/// Appends a result to the history. Used for testing purposes.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::{push_history_for_test, clear_history_for_test, get_history, Iperf3Report};
+/// clear_history_for_test();
+/// push_history_for_test(Iperf3Report::default());
+/// assert_eq!(get_history().len(), 1);
+/// ```
+pub fn push_history_for_test(report: Iperf3Report) {
+    push_history(report);
+}
+
+/// Clears the history.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::{push_history_for_test, clear_history_for_test, get_history, Iperf3Report};
+/// push_history_for_test(Iperf3Report::default());
+/// clear_history_for_test();
+/// assert!(get_history().is_empty());
+/// ```
+pub fn clear_history_for_test() {
+    HISTORY.lock().unwrap().clear();
+}
+
+/// Serializations of the history offered by `/history`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryFormat {
+    /// A JSON array of reports.
+    Json,
+    /// One CSV row of headline figures per report.
+    Csv,
+    /// One JSON report per line.
+    Ndjson,
+    /// A MessagePack array of reports.
+    MsgPack,
+}
+
+impl HistoryFormat {
+    /// Returns the format for a media type, e.g. `text/csv`.
+    pub fn from_media_type(media_type: &str) -> Option<Self> {
+        match media_type {
+            "application/json" => Some(HistoryFormat::Json),
+            "text/csv" => Some(HistoryFormat::Csv),
+            "application/x-ndjson" => Some(HistoryFormat::Ndjson),
+            "application/msgpack" => Some(HistoryFormat::MsgPack),
+            _ => None,
+        }
+    }
+
+    /// Picks the highest-ranked supported format from an `Accept` header, defaulting to JSON.
+    pub fn negotiate(accept: Option<&Accept>) -> Self {
+        accept
+            .into_iter()
+            .flat_map(|accept| accept.ranked())
+            .find_map(|mime| HistoryFormat::from_media_type(mime.essence_str()))
+            .unwrap_or(HistoryFormat::Json)
+    }
+
+    /// Content type of responses in this format.
+    pub fn content_type(self) -> &'static str {
+        match self {
+            HistoryFormat::Json => "application/json",
+            HistoryFormat::Csv => "text/csv; charset=utf-8",
+            HistoryFormat::Ndjson => "application/x-ndjson",
+            HistoryFormat::MsgPack => "application/msgpack",
+        }
+    }
+}
+
+/// Header row of [`history_to_csv`].
+pub const HISTORY_CSV_HEADER: &str = "timestamp,sent_bps,received_bps,retransmits,host_cpu_percent";
+
+/// Renders reports as CSV with the columns of [`HISTORY_CSV_HEADER`], one row per report.
+pub fn history_to_csv(reports: &[Iperf3Report]) -> String {
+    let mut out = format!("{}\n", HISTORY_CSV_HEADER);
+    for report in reports {
+        let _ = writeln!(
+            out,
+            "{},{},{},{},{}",
+            report.start.timestamp.timesecs,
+            report.end.sum_sent.bits_per_second,
+            report.end.sum_received.bits_per_second,
+            report.end.sum_sent.retransmits,
+            report.end.cpu_utilization_percent.host_total
+        );
+    }
+    out
+}
+
+/// Headline throughput of one history entry, as served by `/iperf3/history` for trend graphs.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct HistoryPoint {
+    pub timesecs: u64,
+    pub bits_per_second_sent: f64,
+    pub bits_per_second_received: f64,
+}
+
+impl From<&Iperf3Report> for HistoryPoint {
+    fn from(report: &Iperf3Report) -> Self {
+        HistoryPoint {
+            timesecs: report.start.timestamp.timesecs,
+            bits_per_second_sent: report.end.sum_sent.bits_per_second,
+            bits_per_second_received: report.end.sum_received.bits_per_second,
+        }
+    }
+}
+
+/// Minimum, maximum and mean of one throughput direction, in Mbps.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ThroughputStats {
+    pub min_mbps: f64,
+    pub max_mbps: f64,
+    pub mean_mbps: f64,
+}
+
+/// Aggregate of a set of results, as served by `/iperf3/stats`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct AggregateStats {
+    /// Number of results aggregated.
+    pub count: usize,
+    /// `None` when no result was aggregated, as are `received`.
+    pub sent: Option<ThroughputStats>,
+    pub received: Option<ThroughputStats>,
+    pub total_retransmits: u64,
+}
+
+/// Returns the minimum, maximum and mean of `values`, or `None` if there are none.
+///
+/// The mean is summed in `f64`, so it cannot overflow however large the counts are.
+fn throughput_stats(values: impl Iterator<Item = f64>) -> Option<ThroughputStats> {
+    let (min, max, sum, n) = values.fold((f64::INFINITY, f64::NEG_INFINITY, 0.0, 0usize), |(min, max, sum, n), v| {
+        (min.min(v), max.max(v), sum + v, n + 1)
+    });
+    (n > 0).then(|| ThroughputStats { min_mbps: min, max_mbps: max, mean_mbps: sum / n as f64 })
+}
+
+/// Aggregates the throughput and retransmits of `reports`.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::{aggregate, Iperf3Report};
+/// let run = |sent_mbps: f64, retransmits: u32| {
+///     let mut report = Iperf3Report::default();
+///     report.end.sum_sent.bits_per_second = sent_mbps * 1_000_000.0;
+///     report.end.sum_sent.retransmits = retransmits;
+///     report
+/// };
+/// let stats = aggregate(&[run(100.0, 3), run(300.0, 4)]);
+/// assert_eq!(stats.sent.unwrap().mean_mbps, 200.0);
+/// assert_eq!(stats.total_retransmits, 7);
+/// assert_eq!(aggregate(&[]).sent, None);
+/// ```
+pub fn aggregate(reports: &[Iperf3Report]) -> AggregateStats {
+    AggregateStats {
+        count: reports.len(),
+        sent: throughput_stats(reports.iter().map(|r| bits_per_second_to_mbps(r.end.sum_sent.bits_per_second))),
+        received: throughput_stats(
+            reports.iter().map(|r| bits_per_second_to_mbps(r.end.sum_received.bits_per_second)),
+        ),
+        total_retransmits: reports.iter().map(|r| u64::from(r.end.sum_sent.retransmits)).sum(),
+    }
+}
+
+/// Serializes reports in the given format.
+pub fn serialize_history(reports: &[Iperf3Report], format: HistoryFormat) -> Result<Vec<u8>, Iperf3Error> {
+    let serialization_error = |e: serde_json::Error| Iperf3Error::Internal(format!("Failed to serialize history: {}", e));
+    match format {
+        HistoryFormat::Json => serde_json::to_vec(reports).map_err(serialization_error),
+        HistoryFormat::Csv => Ok(history_to_csv(reports).into_bytes()),
+        HistoryFormat::Ndjson => {
+            let mut out = Vec::new();
+            for report in reports {
+                out.extend(serde_json::to_vec(report).map_err(serialization_error)?);
+                out.push(b'\n');
+            }
+            Ok(out)
+        }
+        HistoryFormat::MsgPack => Ok(to_msgpack(&serde_json::to_value(reports).map_err(serialization_error)?)),
+    }
+}
+
+/// HTTP GET endpoint `/history` returns the result history, oldest first.
+///
+/// The format follows the `Accept` header: `application/json` (the default),
+/// `text/csv`, `application/x-ndjson` or `application/msgpack`.
+///
+/// With `REDACT_TARGET` enabled the reports are redacted for anonymous clients, see
+/// [`Redaction`].
+///
+/// Returns HTTP 503 with `Retry-After` while maintenance mode is enabled.
+#[get("/history")]
+pub async fn iperf3_history(req: HttpRequest) -> Result<HttpResponse, Iperf3Error> {
+    ensure_not_in_maintenance()?;
+    let format = HistoryFormat::negotiate(req.get_header::<Accept>().as_ref());
+    let mut reports = get_history();
+    if let Some(redaction) = Redaction::for_request(&req) {
+        reports.iter_mut().for_each(|report| redaction.report(report));
+    }
+    let body = serialize_history(&reports, format)?;
+    Ok(HttpResponse::Ok().content_type(format.content_type()).body(body))
+}
+
+/// HTTP GET endpoint `/iperf3/history` returns the throughput of each result in the
+/// history, oldest first, as a JSON array of [`HistoryPoint`]s.
+///
+/// Returns HTTP 503 with `Retry-After` while maintenance mode is enabled.
+#[get("/iperf3/history")]
+pub async fn iperf3_history_points(req: HttpRequest) -> Result<HttpResponse, Iperf3Error> {
+    ensure_not_in_maintenance()?;
+    let points: Vec<HistoryPoint> = get_history().iter().map(HistoryPoint::from).collect();
+    Ok(HttpResponse::Ok().json(public_json(&req, &points)?))
+}
+
+/// HTTP GET endpoint `/iperf3/stats` returns the [`aggregate`] of the history as JSON: the
+/// minimum, maximum and mean throughput in each direction and the total retransmits.
+///
+/// An empty history yields a `count` of 0 and `null` throughput.
+///
+/// Returns HTTP 503 with `Retry-After` while maintenance mode is enabled.
+#[get("/iperf3/stats")]
+pub async fn iperf3_stats() -> Result<HttpResponse, Iperf3Error> {
+    ensure_not_in_maintenance()?;
+    Ok(HttpResponse::Ok().json(aggregate(&get_history())))
+}
+
+/// HTTP GET endpoint `/history/{index}` returns the `index`th most recent full report as
+/// JSON, `0` being the latest.
+///
+/// Returns HTTP 404 Not Found if the history holds fewer results.
+///
+/// Returns HTTP 503 with `Retry-After` while maintenance mode is enabled.
+#[get("/history/{index}")]
+pub async fn iperf3_history_entry(req: HttpRequest, index: web::Path<usize>) -> Result<HttpResponse, Iperf3Error> {
+    ensure_not_in_maintenance()?;
+    let index = index.into_inner();
+    let report = get_history_entry(index)
+        .ok_or_else(|| Iperf3Error::NotFound(format!("No history entry at index {}.", index)))?;
+    Ok(HttpResponse::Ok().json(public_json(&req, &report)?))
+}
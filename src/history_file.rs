@@ -0,0 +1,181 @@
+//! # iperf3-statuspage
+//!
+//! File-backed result history selected with `HISTORY_BACKEND=file`: an append-only log of
+//! one JSON report per line, indexed in memory by offset.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::collections::VecDeque;
+use std::env;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use tracing::{error, warn};
+use crate::history::HistoryBackend;
+use crate::models::Iperf3Report;
+use crate::oneshot::write_atomically;
+
+/// Reads the environment variable `HISTORY_FILE`, the log of the file history backend.
+/// Defaults to `history.ndjson` in the working directory.
+pub fn history_file_path() -> PathBuf {
+    env::var("HISTORY_FILE")
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("history.ndjson"))
+}
+
+/// History kept in an append-only line-delimited JSON file.
+///
+/// Only the offset and length of each live line are held in memory, so memory stays
+/// bounded however large the reports are, and the entries survive restarts. Evicted lines
+/// stay in the file until they outweigh the live ones, when the file is compacted.
+#[derive(Debug)]
+pub struct FileHistory {
+    path: PathBuf,
+    file: File,
+    /// Offset and length (without the newline) of each live line, oldest first.
+    index: VecDeque<(u64, usize)>,
+    /// Length of the file.
+    end: u64,
+    total_bytes: usize,
+}
+
+impl FileHistory {
+    /// Opens (creating if needed) the log at `path` and indexes the reports it holds, then
+    /// evicts the oldest beyond `max_len` or `max_bytes` as [`HistoryBackend::push`] does.
+    ///
+    /// Lines that are not valid reports are skipped, and a trailing line cut short by a
+    /// crash is truncated away so later appends start on a fresh line. Evictions are not
+    /// recorded in the file, so reopening with larger bounds may bring back evicted lines
+    /// not yet compacted away.
+    pub fn open(path: impl Into<PathBuf>, max_len: usize, max_bytes: Option<usize>) -> io::Result<Self> {
+        let path = path.into();
+        let file = OpenOptions::new().read(true).append(true).create(true).open(&path)?;
+
+        let mut index = VecDeque::new();
+        let mut total_bytes = 0;
+        let mut end = 0;
+        let mut reader = BufReader::new(&file);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            let read = reader.read_until(b'\n', &mut line)?;
+            if read == 0 || line.last() != Some(&b'\n') {
+                break;
+            }
+            let len = read - 1;
+            if serde_json::from_slice::<Iperf3Report>(&line[..len]).is_ok() {
+                index.push_back((end, len));
+                total_bytes += len;
+            }
+            end += read as u64;
+        }
+        if file.metadata()?.len() > end {
+            warn!("Truncating an incomplete last entry of {}", path.display());
+            file.set_len(end)?;
+        }
+
+        let mut history = FileHistory { path, file, index, end, total_bytes };
+        history.evict(max_len, max_bytes)?;
+        Ok(history)
+    }
+
+    /// Returns the path of the log.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends `report` as a line and evicts the oldest entries beyond the bounds.
+    fn try_push(&mut self, report: &Iperf3Report, max_len: usize, max_bytes: Option<usize>) -> io::Result<()> {
+        let mut line = serde_json::to_vec(report)?;
+        let len = line.len();
+        line.push(b'\n');
+        (&self.file).write_all(&line)?;
+        self.index.push_back((self.end, len));
+        self.end += line.len() as u64;
+        self.total_bytes += len;
+        self.evict(max_len, max_bytes)
+    }
+
+    /// Drops the oldest entries from the index while more than `max_len` are held or their
+    /// size exceeds `max_bytes`, keeping the newest, then compacts the file if needed.
+    fn evict(&mut self, max_len: usize, max_bytes: Option<usize>) -> io::Result<()> {
+        while self.index.len() > max_len
+            || (self.index.len() > 1 && max_bytes.is_some_and(|max| self.total_bytes > max))
+        {
+            if let Some((_, evicted)) = self.index.pop_front() {
+                self.total_bytes -= evicted;
+            }
+        }
+        let live_start = self.index.front().map_or(self.end, |(offset, _)| *offset);
+        if live_start > self.end - live_start {
+            self.compact(live_start)?;
+        }
+        Ok(())
+    }
+
+    /// Rewrites the log without the evicted lines before `live_start`.
+    fn compact(&mut self, live_start: u64) -> io::Result<()> {
+        let mut live = Vec::with_capacity((self.end - live_start) as usize);
+        (&self.file).seek(SeekFrom::Start(live_start))?;
+        (&self.file).take(self.end - live_start).read_to_end(&mut live)?;
+        write_atomically(&self.path, &live).map_err(io::Error::other)?;
+        self.file = OpenOptions::new().read(true).append(true).open(&self.path)?;
+        for (offset, _) in &mut self.index {
+            *offset -= live_start;
+        }
+        self.end -= live_start;
+        Ok(())
+    }
+
+    /// Reads the report of an indexed line.
+    fn read_entry(&self, (offset, len): (u64, usize)) -> io::Result<Iperf3Report> {
+        let mut line = vec![0; len];
+        (&self.file).seek(SeekFrom::Start(offset))?;
+        (&self.file).read_exact(&mut line)?;
+        Ok(serde_json::from_slice(&line)?)
+    }
+}
+
+impl HistoryBackend for FileHistory {
+    fn push(&mut self, report: Iperf3Report, max_len: usize, max_bytes: Option<usize>) {
+        if let Err(e) = self.try_push(&report, max_len, max_bytes) {
+            error!(error = %e, "Failed to append to {}", self.path.display());
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    fn total_bytes(&self) -> usize {
+        self.total_bytes
+    }
+
+    fn snapshot(&self) -> Vec<Iperf3Report> {
+        (0..self.index.len()).filter_map(|i| self.entry(i)).collect()
+    }
+
+    fn entry(&self, index: usize) -> Option<Iperf3Report> {
+        let position = *self.index.get(index)?;
+        self.read_entry(position)
+            .map_err(|e| error!(error = %e, "Failed to read {}", self.path.display()))
+            .ok()
+    }
+
+    fn clear(&mut self) {
+        if let Err(e) = self.file.set_len(0) {
+            error!(error = %e, "Failed to clear {}", self.path.display());
+        }
+        self.index.clear();
+        self.end = 0;
+        self.total_bytes = 0;
+    }
+}
@@ -0,0 +1,82 @@
+//! # iperf3-statuspage
+//!
+//! Cap on concurrently handled HTTP requests (`MAX_INFLIGHT_REQUESTS`), shedding the
+//! overflow with 503 so a burst of clients cannot overwhelm the host.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+use std::sync::Arc;
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{Error, ResponseError};
+use tokio::sync::Semaphore;
+use crate::errors::Iperf3Error;
+
+/// Paths never counted against nor refused by the limit, so health checks keep working
+/// under load.
+pub const INFLIGHT_EXEMPT_PATHS: &[&str] = &["/healthz", "/favicon.ico"];
+
+/// `Retry-After` sent with refused requests; requests are short, so the herd can come
+/// back almost at once.
+pub const INFLIGHT_RETRY_AFTER_SECONDS: u64 = 1;
+
+/// Reads the environment variable `MAX_INFLIGHT_REQUESTS`, the most requests handled at
+/// once. Returns `None` (no limit) when unset or zero.
+pub fn max_inflight_requests() -> Option<usize> {
+    env::var("MAX_INFLIGHT_REQUESTS")
+        .ok()
+        .and_then(|s| s.trim().parse::<usize>().ok())
+        .filter(|n| *n > 0)
+}
+
+/// Shared budget of in-flight requests.
+///
+/// Clones share the same budget, so one limit created at startup caps the requests of
+/// every worker together.
+#[derive(Debug, Clone, Default)]
+pub struct InflightLimit {
+    semaphore: Option<Arc<Semaphore>>,
+}
+
+impl InflightLimit {
+    /// Creates a limit of `max` requests, or no limit for `None`.
+    pub fn new(max: Option<usize>) -> Self {
+        InflightLimit {
+            semaphore: max.map(|max| Arc::new(Semaphore::new(max))),
+        }
+    }
+
+    /// Creates the limit configured by `MAX_INFLIGHT_REQUESTS`.
+    pub fn from_env() -> Self {
+        InflightLimit::new(max_inflight_requests())
+    }
+}
+
+/// Middleware enforcing `limit`, for use with [`actix_web::middleware::from_fn`].
+///
+/// A request beyond the limit is answered with [`Iperf3Error::Overloaded`] (HTTP 503 with
+/// `Retry-After`) without reaching its handler. The slot is held until the handler has
+/// produced its response; streamed bodies (e.g. `/events`) do not keep holding it.
+/// [`INFLIGHT_EXEMPT_PATHS`] bypass the limit.
+pub async fn limit_inflight<B: MessageBody>(
+    limit: InflightLimit,
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<EitherBody<B>>, Error> {
+    let Some(semaphore) = limit.semaphore.filter(|_| !INFLIGHT_EXEMPT_PATHS.contains(&req.path())) else {
+        return Ok(next.call(req).await?.map_into_left_body());
+    };
+    let Ok(_permit) = semaphore.try_acquire_owned() else {
+        let error = Iperf3Error::Overloaded { retry_after_seconds: INFLIGHT_RETRY_AFTER_SECONDS };
+        return Ok(req.into_response(error.error_response()).map_into_right_body());
+    };
+    Ok(next.call(req).await?.map_into_left_body())
+}
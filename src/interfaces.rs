@@ -0,0 +1,129 @@
+//! # iperf3-statuspage
+//!
+//! Per-interface testing with `IPERF3_BIND_ADDRESSES`: every target is run once per local
+//! bind address and the results are cached per address.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::sync::Mutex;
+use std::time::Instant;
+use actix_web::{get, HttpResponse};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use crate::bits_per_second_to_mbps;
+use crate::errors::Iperf3Error;
+use crate::maintenance::ensure_not_in_maintenance;
+use crate::models::Iperf3Report;
+use crate::targets::Target;
+
+/// Reads the environment variable `IPERF3_BIND_ADDRESSES`, a comma-separated list of
+/// local addresses to bind (`-B`), one per interface to test.
+///
+/// Returns an empty list when unset, leaving the choice of interface to the OS.
+pub fn bind_addresses() -> Vec<String> {
+    env::var("IPERF3_BIND_ADDRESSES")
+        .map(|v| v.split(',').map(str::trim).filter(|a| !a.is_empty()).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Returns one target per pair of `targets` and `binds`, each binding its address.
+///
+/// Without bind addresses the targets are returned unchanged.
+pub fn bound_targets(targets: Vec<Target>, binds: &[String]) -> Vec<Target> {
+    if binds.is_empty() {
+        return targets;
+    }
+    targets
+        .iter()
+        .flat_map(|target| {
+            binds.iter().map(|bind| {
+                let mut options = target.options.clone();
+                options.bind = Some(bind.clone());
+                Target::with_options(options)
+            })
+        })
+        .collect()
+}
+
+/// Latest result of each bind address and when it was cached.
+static INTERFACE_RESULTS: Lazy<Mutex<BTreeMap<String, (Iperf3Report, Instant)>>> =
+    Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+/// Caches `report` as the latest result of the interface bound to `bind`.
+pub fn cache_interface_result(bind: &str, report: Iperf3Report) {
+    INTERFACE_RESULTS.lock().unwrap().insert(bind.to_string(), (report, Instant::now()));
+}
+
+/// Returns the latest result of the interface bound to `bind` and when it was cached.
+pub fn get_interface_result(bind: &str) -> Option<(Iperf3Report, Instant)> {
+    INTERFACE_RESULTS.lock().unwrap().get(bind).cloned()
+}
+
+/// Clears the per-interface results.
+pub fn clear_interface_results_for_test() {
+    INTERFACE_RESULTS.lock().unwrap().clear();
+}
+
+/// Headline figures of one interface's latest result.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct InterfaceSummary {
+    pub bind_address: String,
+    pub timestamp: u64,
+    pub sent_mbps: f64,
+    pub received_mbps: f64,
+    pub retransmits: u32,
+}
+
+/// Latest results of every interface and their combined throughput.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct InterfacesSummary {
+    /// One entry per bind address with a cached result, ordered by address.
+    pub interfaces: Vec<InterfaceSummary>,
+    pub total_sent_mbps: f64,
+    pub total_received_mbps: f64,
+}
+
+/// Builds the aggregated view of the cached per-interface results.
+pub fn build_interfaces_summary() -> InterfacesSummary {
+    let interfaces: Vec<InterfaceSummary> = INTERFACE_RESULTS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(bind, (report, _))| InterfaceSummary {
+            bind_address: bind.clone(),
+            timestamp: report.start.timestamp.timesecs,
+            sent_mbps: bits_per_second_to_mbps(report.end.sum_sent.bits_per_second),
+            received_mbps: bits_per_second_to_mbps(report.end.sum_received.bits_per_second),
+            retransmits: report.end.sum_sent.retransmits,
+        })
+        .collect();
+    InterfacesSummary {
+        total_sent_mbps: interfaces.iter().map(|i| i.sent_mbps).sum(),
+        total_received_mbps: interfaces.iter().map(|i| i.received_mbps).sum(),
+        interfaces,
+    }
+}
+
+/// HTTP GET endpoint `/summary/interfaces` returns the latest result of each interface in
+/// `IPERF3_BIND_ADDRESSES` and their combined throughput as JSON.
+///
+/// Returns HTTP 503 Service Unavailable if no interface has a cached result yet.
+///
+/// Returns HTTP 503 with `Retry-After` while maintenance mode is enabled.
+#[get("/summary/interfaces")]
+pub async fn iperf3_summary_interfaces() -> Result<HttpResponse, Iperf3Error> {
+    ensure_not_in_maintenance()?;
+    let summary = build_interfaces_summary();
+    if summary.interfaces.is_empty() {
+        return Err(Iperf3Error::not_available());
+    }
+    Ok(HttpResponse::Ok().json(summary))
+}
@@ -0,0 +1,278 @@
+//! # iperf3-statuspage
+//!
+//! Interval series helpers backing the `/intervals` endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use actix_web::{get, web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use crate::models::{Interval, Sum};
+use crate::errors::Iperf3Error;
+use crate::maintenance::ensure_not_in_maintenance;
+use crate::get_last_result;
+
+/// Query parameters accepted by `/intervals`.
+#[derive(Deserialize, Debug, Default)]
+pub struct IntervalsQuery {
+    /// Step in seconds to round interval boundaries to.
+    pub round: Option<f64>,
+    /// Number of buckets to downsample the intervals to.
+    pub points: Option<usize>,
+    /// How each bucket aggregates its intervals: `avg` (the default), `max`, `min` or `last`.
+    pub agg: Option<String>,
+}
+
+/// How many intervals `/iperf3?intervals=` keeps in the response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntervalsLimit {
+    /// Every interval (the default, also `intervals=true`).
+    All,
+    /// No `intervals` key at all (`intervals=false`).
+    Omit,
+    /// At most the first N intervals (`intervals=N`).
+    Cap(usize),
+}
+
+impl IntervalsLimit {
+    /// Parses `true`, `false` or a non-negative count.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use iperf3_statuspage::IntervalsLimit;
+    /// assert_eq!(IntervalsLimit::parse("false").unwrap(), IntervalsLimit::Omit);
+    /// assert_eq!(IntervalsLimit::parse("5").unwrap(), IntervalsLimit::Cap(5));
+    /// assert!(IntervalsLimit::parse("some").is_err());
+    /// ```
+    pub fn parse(value: &str) -> Result<Self, Iperf3Error> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "true" => Ok(IntervalsLimit::All),
+            "false" => Ok(IntervalsLimit::Omit),
+            count => count.parse().map(IntervalsLimit::Cap).map_err(|_| {
+                Iperf3Error::BadRequest("intervals must be true, false or a non-negative count.".to_string())
+            }),
+        }
+    }
+}
+
+/// Rounds `value` to the nearest multiple of `step`.
+fn round_to_step(value: f64, step: f64) -> f64 {
+    (value / step).round() * step
+}
+
+/// Rounds the `start`/`end` boundaries of every interval, its sum and its streams
+/// to the nearest multiple of `step` seconds.
+///
+/// Only the boundaries change: `seconds`, `bytes` and `bits_per_second` are kept
+/// as measured so throughput figures are unaffected.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::{round_interval_boundaries, Interval, Sum};
+/// let interval = Interval {
+///     streams: vec![],
+///     sum: Sum { start: 0.0, end: 1.001128, bytes: 42, ..Sum::default() },
+/// };
+///
+/// let rounded = round_interval_boundaries(&[interval], 0.5);
+/// assert_eq!(rounded[0].sum.end, 1.0);
+/// assert_eq!(rounded[0].sum.bytes, 42);
+/// ```
+pub fn round_interval_boundaries(intervals: &[Interval], step: f64) -> Vec<Interval> {
+    intervals
+        .iter()
+        .map(|interval| {
+            let mut interval = interval.clone();
+            interval.sum.start = round_to_step(interval.sum.start, step);
+            interval.sum.end = round_to_step(interval.sum.end, step);
+            for stream in &mut interval.streams {
+                stream.start = round_to_step(stream.start, step);
+                stream.end = round_to_step(stream.end, step);
+            }
+            interval
+        })
+        .collect()
+}
+
+/// How [`downsample_intervals`] combines the intervals of a bucket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Aggregation {
+    /// Mean of the bucket.
+    #[default]
+    Avg,
+    /// Largest value in the bucket.
+    Max,
+    /// Smallest value in the bucket.
+    Min,
+    /// Value of the bucket's last interval.
+    Last,
+}
+
+impl Aggregation {
+    /// Parses `avg`, `max`, `min` or `last`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use iperf3_statuspage::Aggregation;
+    /// assert_eq!(Aggregation::parse("MAX").unwrap(), Aggregation::Max);
+    /// assert!(Aggregation::parse("median").is_err());
+    /// ```
+    pub fn parse(value: &str) -> Result<Self, Iperf3Error> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "avg" => Ok(Aggregation::Avg),
+            "max" => Ok(Aggregation::Max),
+            "min" => Ok(Aggregation::Min),
+            "last" => Ok(Aggregation::Last),
+            _ => Err(Iperf3Error::BadRequest("agg must be avg, max, min or last.".to_string())),
+        }
+    }
+
+    fn apply(self, values: impl Iterator<Item = f64>) -> f64 {
+        let values: Vec<f64> = values.collect();
+        match self {
+            Aggregation::Avg => values.iter().sum::<f64>() / values.len().max(1) as f64,
+            Aggregation::Max => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            Aggregation::Min => values.iter().copied().fold(f64::INFINITY, f64::min),
+            Aggregation::Last => values.last().copied().unwrap_or_default(),
+        }
+    }
+}
+
+/// Downsamples `intervals` to at most `points` buckets of consecutive intervals.
+///
+/// Each bucket spans from its first interval's start to its last interval's end, with the
+/// total `seconds` and `bytes`, while `bits_per_second` and `retransmits` are combined with
+/// `agg`. Per-stream detail is dropped. Series with no more than `points` intervals are
+/// returned unchanged.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::{downsample_intervals, Aggregation, Interval, Sum};
+/// let intervals: Vec<Interval> = [1.0, 3.0, 2.0, 6.0]
+///     .iter()
+///     .map(|&bits_per_second| Interval { streams: vec![], sum: Sum { bits_per_second, ..Sum::default() } })
+///     .collect();
+///
+/// let peaks = downsample_intervals(&intervals, 2, Aggregation::Max);
+/// assert_eq!(peaks.iter().map(|i| i.sum.bits_per_second).collect::<Vec<_>>(), vec![3.0, 6.0]);
+/// ```
+pub fn downsample_intervals(intervals: &[Interval], points: usize, agg: Aggregation) -> Vec<Interval> {
+    if points == 0 || intervals.len() <= points {
+        return intervals.to_vec();
+    }
+    (0..points)
+        .map(|bucket| {
+            let bucket = &intervals[bucket * intervals.len() / points..(bucket + 1) * intervals.len() / points];
+            let (first, last) = (&bucket[0].sum, &bucket[bucket.len() - 1].sum);
+            let retransmits = agg.apply(bucket.iter().map(|i| f64::from(i.sum.retransmits)));
+            Interval {
+                streams: Vec::new(),
+                sum: Sum {
+                    start: first.start,
+                    end: last.end,
+                    seconds: bucket.iter().map(|i| i.sum.seconds).sum(),
+                    bytes: bucket.iter().map(|i| i.sum.bytes).sum(),
+                    bits_per_second: agg.apply(bucket.iter().map(|i| i.sum.bits_per_second)),
+                    retransmits: retransmits.round() as u32,
+                    ..last.clone()
+                },
+            }
+        })
+        .collect()
+}
+
+/// HTTP GET endpoint `/intervals` returns the intervals of the cached iperf3 result as JSON.
+///
+/// Accepts an optional `round` query parameter (seconds) which aligns every interval
+/// boundary to a common time axis. Returns HTTP 400 if `round` is not a positive number
+/// and HTTP 503 Service Unavailable if no result is cached yet.
+///
+/// `points` downsamples the (rounded) series to that many buckets, aggregated by `agg`
+/// (`avg`, `max`, `min` or `last`), see [`downsample_intervals`]. Returns HTTP 400 if
+/// `points` is zero or `agg` is unknown.
+///
+/// Returns HTTP 503 with `Retry-After` while maintenance mode is enabled.
+#[get("/intervals")]
+pub async fn iperf3_intervals(query: web::Query<IntervalsQuery>) -> Result<HttpResponse, Iperf3Error> {
+    ensure_not_in_maintenance()?;
+    let agg = query.agg.as_deref().map(Aggregation::parse).transpose()?.unwrap_or_default();
+    if query.points == Some(0) {
+        return Err(Iperf3Error::BadRequest("points must be a positive count.".to_string()));
+    }
+    let mut intervals = get_last_result().ok_or_else(Iperf3Error::not_available)?.intervals;
+
+    match query.round {
+        Some(step) if !(step.is_finite() && step > 0.0) => {
+            return Err(Iperf3Error::BadRequest("round must be a positive number of seconds.".to_string()));
+        }
+        Some(step) => intervals = round_interval_boundaries(&intervals, step),
+        None => {}
+    }
+    if let Some(points) = query.points {
+        intervals = downsample_intervals(&intervals, points, agg);
+    }
+    Ok(HttpResponse::Ok().json(intervals))
+}
+
+/// One interval of a stream's TCP state.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TcpSample {
+    /// End of the interval in seconds since the test started.
+    pub time: f64,
+    pub retransmits: u32,
+    /// Congestion window in bytes.
+    pub snd_cwnd: u64,
+    /// Smoothed round-trip time in microseconds.
+    pub rtt: u32,
+}
+
+/// TCP time series of a single stream, identified by its socket.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TcpStreamSeries {
+    pub socket: u32,
+    pub samples: Vec<TcpSample>,
+}
+
+/// Extracts each stream's per-interval `retransmits`, `snd_cwnd` and `rtt`, streams in
+/// order of first appearance and samples in interval order.
+pub fn tcp_series(intervals: &[Interval]) -> Vec<TcpStreamSeries> {
+    let mut series: Vec<TcpStreamSeries> = Vec::new();
+    for stream in intervals.iter().flat_map(|interval| &interval.streams) {
+        let sample = TcpSample {
+            time: stream.end,
+            retransmits: stream.retransmits,
+            snd_cwnd: stream.snd_cwnd,
+            rtt: stream.rtt,
+        };
+        match series.iter_mut().find(|s| s.socket == stream.socket) {
+            Some(existing) => existing.samples.push(sample),
+            None => series.push(TcpStreamSeries { socket: stream.socket, samples: vec![sample] }),
+        }
+    }
+    series
+}
+
+/// HTTP GET endpoint `/intervals/tcp` returns the per-stream TCP time series of the cached
+/// iperf3 result as JSON, see [`tcp_series`].
+///
+/// Returns HTTP 503 Service Unavailable if no result is cached yet or it has no intervals.
+///
+/// Returns HTTP 503 with `Retry-After` while maintenance mode is enabled.
+#[get("/intervals/tcp")]
+pub async fn iperf3_intervals_tcp() -> Result<HttpResponse, Iperf3Error> {
+    ensure_not_in_maintenance()?;
+    let series = tcp_series(&get_last_result().ok_or_else(Iperf3Error::not_available)?.intervals);
+    if series.is_empty() {
+        return Err(Iperf3Error::NotAvailable("No interval data in the cached result.".to_string()));
+    }
+    Ok(HttpResponse::Ok().json(series))
+}
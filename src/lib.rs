@@ -11,17 +11,126 @@
 // This file may not be copied, modified, or distributed except according to those terms.
 
 pub mod models;
+pub mod intervals;
+pub mod command;
+pub mod timing;
+pub mod status;
+pub mod fields;
+pub mod oneshot;
+pub mod targets;
+pub mod errors;
+pub mod config;
+pub mod history;
+pub mod msgpack;
+pub mod sparkline;
+pub mod summary;
+pub mod publish;
+pub mod maintenance;
+pub mod metrics;
+pub mod auth;
+pub mod stale;
+pub mod serialize;
+pub mod endpoints;
+pub mod baseline;
+pub mod annotation;
+pub mod version;
+pub mod deep;
+pub mod shutdown;
+pub mod badge;
+pub mod direction;
+pub mod redact;
+pub mod bad_output;
+pub mod dashboard;
+pub mod nice;
+pub mod link_speed;
+pub mod checksum;
+pub mod live;
+pub mod diagnostics;
+pub mod on_demand;
+pub mod history_file;
+pub mod slo;
+pub mod interfaces;
+pub mod events;
+pub mod whoami;
+pub mod inflight;
+pub mod logging;
+#[cfg(all(target_os = "linux", feature = "hardening"))]
+pub mod hardening;
+#[cfg(feature = "nats")]
+pub mod nats;
+#[cfg(feature = "otel")]
+pub mod otel;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
 
 use std::env;
+use std::fmt;
 use std::process::{Stdio};
 use std::sync::Mutex;
-use std::time::{Duration, Instant};
-use actix_web::{get, HttpResponse, Responder};
+use std::time::{Duration, Instant, SystemTime};
+use actix_web::{get, head, http::header, web, HttpRequest, HttpResponse, HttpResponseBuilder};
 use async_trait::async_trait;
 use once_cell::sync::Lazy;
+use serde::Deserialize;
 use tokio::process::Command;
 use tokio::time;
+use tracing::{error, info, info_span, warn, Instrument};
 pub use models::*;
+pub use intervals::*;
+pub use command::*;
+pub use timing::*;
+pub use status::*;
+pub use fields::*;
+pub use oneshot::*;
+pub use targets::*;
+pub use errors::*;
+pub use config::*;
+pub use history::*;
+pub use msgpack::*;
+pub use sparkline::*;
+pub use summary::*;
+pub use publish::*;
+pub use maintenance::*;
+pub use metrics::*;
+pub use auth::*;
+pub use stale::*;
+pub use serialize::*;
+pub use endpoints::*;
+pub use baseline::*;
+pub use annotation::*;
+pub use version::*;
+pub use deep::*;
+pub use shutdown::*;
+pub use badge::*;
+pub use direction::*;
+pub use redact::*;
+pub use bad_output::*;
+pub use dashboard::*;
+pub use nice::*;
+pub use link_speed::*;
+pub use checksum::*;
+pub use live::*;
+pub use diagnostics::*;
+pub use on_demand::*;
+pub use history_file::*;
+pub use slo::*;
+pub use interfaces::*;
+pub use events::*;
+pub use whoami::*;
+pub use inflight::*;
+pub use logging::*;
+#[cfg(all(target_os = "linux", feature = "hardening"))]
+pub use hardening::*;
+#[cfg(feature = "nats")]
+pub use nats::*;
+#[cfg(feature = "otel")]
+pub use otel::*;
+#[cfg(feature = "parquet")]
+pub use parquet::*;
+#[cfg(feature = "sqlite")]
+pub use sqlite::*;
 
 /// Global cached iperf3 result and the instant it was cached.
 ///
@@ -29,7 +138,58 @@ pub use models::*;
 /// Initially empty until the first iperf3 run.
 pub static LAST_RESULT: Lazy<Mutex<Option<(Iperf3Report, Instant)>>> = Lazy::new(|| Mutex::new(None));
 
-/// Retrieves the last cached iperf3 result, if available.
+/// Raw iperf3 JSON output the cached result was parsed from.
+///
+/// Only populated by real measurement runs; results set through the test helpers have no raw output.
+pub static LAST_RAW_OUTPUT: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
+/// Details of the cached entry kept alongside the iperf3 report itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheMetadata {
+    /// Wall-clock time the result was stored, used for `Last-Modified`.
+    pub cached_at: SystemTime,
+    /// `RUN_ANNOTATION` in effect when the result was stored.
+    pub annotation: Option<String>,
+    /// Link speed of `LINK_INTERFACE` in Mbps when the result was stored, if detected.
+    pub link_speed_mbps: Option<u64>,
+}
+
+impl CacheMetadata {
+    /// Metadata for a result stored now.
+    fn now() -> Self {
+        CacheMetadata {
+            cached_at: SystemTime::now(),
+            annotation: run_annotation(),
+            link_speed_mbps: detect_link_speed_mbps(),
+        }
+    }
+}
+
+/// Metadata of the cached result.
+static LAST_CACHE_METADATA: Lazy<Mutex<Option<CacheMetadata>>> = Lazy::new(|| Mutex::new(None));
+
+/// Returns the metadata of the cached result, if any.
+pub fn last_cache_metadata() -> Option<CacheMetadata> {
+    LAST_CACHE_METADATA.lock().unwrap().clone()
+}
+
+/// Returns the wall-clock time the cached result was stored, if any.
+pub fn last_cached_at() -> Option<SystemTime> {
+    last_cache_metadata().map(|metadata| metadata.cached_at)
+}
+
+/// Returns the result of the primary target (see [`set_primary_target`]) and when it was
+/// cached, falling back to the latest result of any target while the primary target has
+/// none or none is set.
+///
+/// Every endpoint serving "the" result reads it through here, so with several targets they
+/// all describe the primary one.
+pub fn primary_result() -> Option<(Iperf3Report, Instant)> {
+    get_primary_target_result().or_else(|| LAST_RESULT.lock().unwrap().clone())
+}
+
+/// Retrieves the cached iperf3 result of the primary target, if available, see
+/// [`primary_result`].
 ///
 /// # Examples
 ///
@@ -80,7 +240,11 @@ pub static LAST_RESULT: Lazy<Mutex<Option<(Iperf3Report, Instant)>>> = Lazy::new
 /// #             bytes: 0,
 /// #             bits_per_second: 0.0,
 /// #             retransmits: 0,
-/// #             sender: false
+/// #             sender: false,
+/// #             jitter_ms: None,
+/// #             lost_packets: None,
+/// #             packets: None,
+/// #             lost_percent: None
 /// #         },
 /// #         sum_received: SumReceived {
 /// #             start: 0.0,
@@ -88,7 +252,11 @@ pub static LAST_RESULT: Lazy<Mutex<Option<(Iperf3Report, Instant)>>> = Lazy::new
 /// #             seconds: 0.0,
 /// #             bytes: 0,
 /// #             bits_per_second: 0.0,
-/// #             sender: false
+/// #             sender: false,
+/// #             jitter_ms: None,
+/// #             lost_packets: None,
+/// #             packets: None,
+/// #             lost_percent: None
 /// #         },
 /// #         cpu_utilization_percent: CpuUtilizationPercent {
 /// #             host_total: 0.0,
@@ -98,9 +266,12 @@ pub static LAST_RESULT: Lazy<Mutex<Option<(Iperf3Report, Instant)>>> = Lazy::new
 /// #             remote_user: 0.0,
 /// #             remote_system: 0.0,
 /// #         },
+/// #         sum_sent_bidir_reverse: None,
+/// #         sum_received_bidir_reverse: None,
 /// #         sender_tcp_congestion: "".to_string(),
 /// #         receiver_tcp_congestion: "".to_string()
-/// #     }
+/// #     },
+/// #     extra: Default::default(),
 /// # };
 /// set_last_result_for_test(dummy_result.clone());
 ///
@@ -112,9 +283,28 @@ pub static LAST_RESULT: Lazy<Mutex<Option<(Iperf3Report, Instant)>>> = Lazy::new
 /// assert!(get_last_result().is_none());
 /// ```
 
+#[allow(clippy::empty_line_after_doc_comments)]
 pub fn get_last_result() -> Option<Iperf3Report> {
-    let cache = LAST_RESULT.lock().unwrap();
-    cache.as_ref().map(|(result, _)| result.clone())
+    primary_result().map(|(result, _)| result)
+}
+
+/// Returns the cached iperf3 result of the primary target, if any, with the time elapsed
+/// since it was cached.
+///
+/// # Examples
+///
+/// ```
+/// # use std::time::Duration;
+/// # use iperf3_statuspage::{get_last_result_with_age, set_last_result_for_test, clear_last_result_for_test, Iperf3Report};
+/// set_last_result_for_test(Iperf3Report::default());
+/// let (_, age) = get_last_result_with_age().unwrap();
+/// assert!(age < Duration::from_secs(60));
+///
+/// clear_last_result_for_test();
+/// assert!(get_last_result_with_age().is_none());
+/// ```
+pub fn get_last_result_with_age() -> Option<(Iperf3Report, Duration)> {
+    primary_result().map(|(result, cached_at)| (result, cached_at.elapsed()))
 }
 
 /// Sets the cached iperf3 result. Used for testing purposes.
@@ -168,7 +358,11 @@ pub fn get_last_result() -> Option<Iperf3Report> {
 /// #             bytes: 0,
 /// #             bits_per_second: 0.0,
 /// #             retransmits: 0,
-/// #             sender: false
+/// #             sender: false,
+/// #             jitter_ms: None,
+/// #             lost_packets: None,
+/// #             packets: None,
+/// #             lost_percent: None
 /// #         },
 /// #         sum_received: SumReceived {
 /// #             start: 0.0,
@@ -176,7 +370,11 @@ pub fn get_last_result() -> Option<Iperf3Report> {
 /// #             seconds: 0.0,
 /// #             bytes: 0,
 /// #             bits_per_second: 0.0,
-/// #             sender: false
+/// #             sender: false,
+/// #             jitter_ms: None,
+/// #             lost_packets: None,
+/// #             packets: None,
+/// #             lost_percent: None
 /// #         },
 /// #         cpu_utilization_percent: CpuUtilizationPercent {
 /// #             host_total: 0.0,
@@ -186,18 +384,33 @@ pub fn get_last_result() -> Option<Iperf3Report> {
 /// #             remote_user: 0.0,
 /// #             remote_system: 0.0,
 /// #         },
+/// #         sum_sent_bidir_reverse: None,
+/// #         sum_received_bidir_reverse: None,
 /// #         sender_tcp_congestion: "".to_string(),
 /// #         receiver_tcp_congestion: "".to_string()
-/// #     }
+/// #     },
+/// #     extra: Default::default(),
 /// # };
 /// set_last_result_for_test(dummy_result.clone());
 /// let cached = get_last_result().unwrap();
 /// assert_eq!(cached.start.timestamp.timesecs, 0);
 /// ```
 
+#[allow(clippy::empty_line_after_doc_comments)]
 pub fn set_last_result_for_test(result: Iperf3Report) {
+    restore_last_result(result);
+}
+
+/// Caches `result` as if it had just been measured, e.g. when restoring it from `STATE_FILE`.
+///
+/// Unlike a measurement run this does not touch the history, the metrics or the raw output.
+/// Subscribers of `/events` are notified as for a measurement.
+pub fn restore_last_result(result: Iperf3Report) {
     let mut cache = LAST_RESULT.lock().unwrap();
-    *cache = Some((result, Instant::now()));
+    let (cached, _) = cache.insert((result, Instant::now()));
+    *LAST_RAW_OUTPUT.lock().unwrap() = None;
+    *LAST_CACHE_METADATA.lock().unwrap() = Some(CacheMetadata::now());
+    notify_result_cached(cached);
 }
 
 /// Clears the cached iperf3 result.
@@ -212,33 +425,259 @@ pub fn set_last_result_for_test(result: Iperf3Report) {
 /// assert!(get_last_result().is_none());
 /// ```
 
+#[allow(clippy::empty_line_after_doc_comments)]
 pub fn clear_last_result_for_test() {
     let mut cache = LAST_RESULT.lock().unwrap();
     *cache = None;
+    clear_direction_results_for_test();
+    clear_target_results_for_test();
+    *LAST_RAW_OUTPUT.lock().unwrap() = None;
+    *LAST_CACHE_METADATA.lock().unwrap() = None;
+}
+
+/// Bits per megabit: Mbps is decimal, 1 Mbps = 1_000_000 bits per second.
+pub const BITS_PER_MEGABIT: f64 = 1_000_000.0;
+
+/// Bytes per mebibyte: MiB is binary, 1 MiB = 1024 * 1024 bytes.
+pub const BYTES_PER_MEBIBYTE: f64 = 1024.0 * 1024.0;
+
+/// Converts bits per second to decimal megabits per second, dividing by 1_000_000.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::bits_per_second_to_mbps;
+/// assert_eq!(bits_per_second_to_mbps(940_000_000.0), 940.0);
+/// ```
+pub fn bits_per_second_to_mbps(bits_per_second: f64) -> f64 {
+    bits_per_second / BITS_PER_MEGABIT
+}
+
+/// Converts bytes to binary mebibytes, dividing by 1024 * 1024.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::bytes_to_mib;
+/// assert_eq!(bytes_to_mib(3 * 1024 * 1024), 3.0);
+/// ```
+pub fn bytes_to_mib(bytes: u64) -> f64 {
+    bytes as f64 / BYTES_PER_MEBIBYTE
+}
+
+/// Adds the derived `mbps_sent`, `mbps_received` and `mbytes_transferred` (MiB received)
+/// fields of `report` to the top level of `value`, keeping the raw fields.
+fn add_unit_fields(value: &mut serde_json::Value, report: &Iperf3Report) {
+    if let Some(object) = value.as_object_mut() {
+        object.insert("mbps_sent".to_string(), bits_per_second_to_mbps(report.end.sum_sent.bits_per_second).into());
+        object.insert(
+            "mbps_received".to_string(),
+            bits_per_second_to_mbps(report.end.sum_received.bits_per_second).into(),
+        );
+        object.insert("mbytes_transferred".to_string(), bytes_to_mib(report.end.sum_received.bytes).into());
+    }
+}
+
+/// Parses the `units` query value of `/iperf3`; only `mbps` is accepted.
+fn parse_units(value: &str) -> Result<(), Iperf3Error> {
+    if value.trim().eq_ignore_ascii_case("mbps") {
+        Ok(())
+    } else {
+        Err(Iperf3Error::BadRequest("units must be mbps.".to_string()))
+    }
+}
+
+/// Query parameters accepted by `/iperf3`.
+#[derive(Deserialize, Debug, Default)]
+pub struct Iperf3Query {
+    /// Comma-separated dotted paths to include, e.g. `start.timestamp,end.sum_received`.
+    pub fields: Option<String>,
+    /// `false` to omit the intervals, or the maximum number of intervals to include.
+    pub intervals: Option<String>,
+    /// `up` or `down` to return the latest result of that direction.
+    pub direction: Option<String>,
+    /// A bind address of `IPERF3_BIND_ADDRESSES` to return the latest result of that interface.
+    pub iface: Option<String>,
+    /// `mbps` to add throughput in Mbps and the transfer in MiB alongside the raw fields.
+    pub units: Option<String>,
 }
 
 /// HTTP GET endpoint `/iperf3` returns the last cached iperf3 result as JSON.
 ///
-/// Returns HTTP 503 Service Unavailable if no result is cached yet.
+/// With several targets this is the result of the primary (first configured) target once
+/// it has one; other targets are served by `/iperf3/{host}/{port}`.
+///
+/// When the `fields` query parameter is given only the requested paths are returned,
+/// as a pruned JSON object. Returns HTTP 400 listing any unknown paths.
+///
+/// The `intervals` query parameter trims the report: `false` omits the `intervals` array
+/// and a count keeps only that many leading intervals. `start` and `end` are unaffected.
+/// Returns HTTP 400 for any other value.
+///
+/// `direction=up` or `direction=down` returns the latest normal or reverse (`-R`) result
+/// instead, see `ROTATE_DIRECTION`. `iface=<addr>` returns the latest result bound to that
+/// address of `IPERF3_BIND_ADDRESSES`. Combining both returns HTTP 400.
+///
+/// `units=mbps` adds `mbps_sent`, `mbps_received` (see [`bits_per_second_to_mbps`]) and
+/// `mbytes_transferred` (MiB received, see [`bytes_to_mib`]) at the top level, after any
+/// `fields` selection. Returns HTTP 400 for any other value.
+///
+/// With `REDACT_TARGET` enabled the server's address and system info are redacted for
+/// anonymous clients, see [`Redaction`].
+///
+/// Returns HTTP 503 Service Unavailable if no result is cached yet, or HTTP 502 Bad
+/// Gateway with the iperf3 error if the runs so far failed, with a text or JSON body
+/// depending on `ERROR_FORMAT`.
+///
+/// Returns HTTP 503 with `Retry-After` while maintenance mode is enabled.
+///
+/// Results older than `STALE_AFTER_SECONDS` yield HTTP 503 for anonymous clients, while
+/// authenticated clients still get them flagged with `X-Stale: true`. Every result
+/// carries its age in whole seconds in `X-Cache-Age-Seconds`.
+///
+/// Returns HTTP 503 if serializing the body takes longer than `RESPONSE_TIMEOUT_MS`.
 #[get("/iperf3")]
-pub async fn iperf3() -> impl Responder {
-    let cache = LAST_RESULT.lock().unwrap();
-    if let Some((cached_result, _timestamp)) = &*cache {
-        HttpResponse::Ok().json(cached_result)
-    } else {
-        HttpResponse::ServiceUnavailable().body("Iperf3 result not available yet.")
+pub async fn iperf3(req: HttpRequest, query: web::Query<Iperf3Query>) -> Result<HttpResponse, Iperf3Error> {
+    let (mut cached_result, mut response) = cached_result_response(&req, &query)?;
+    let Iperf3Query { fields, intervals, units, .. } = query.into_inner();
+
+    let intervals = intervals.as_deref().map(IntervalsLimit::parse).transpose()?.unwrap_or(IntervalsLimit::All);
+    let units = units.as_deref().map(parse_units).transpose()?.is_some();
+    if let IntervalsLimit::Cap(max) = intervals {
+        cached_result.intervals.truncate(max);
     }
+    if let Some(redaction) = Redaction::for_request(&req) {
+        redaction.report(&mut cached_result);
+    }
+
+    let body = serialize_with_timeout(response_timeout(), move || {
+        let serialized = if fields.is_none() && intervals != IntervalsLimit::Omit && !units {
+            serde_json::to_vec(&cached_result)
+        } else {
+            let mut value = serde_json::to_value(&cached_result)
+                .map_err(|e| Iperf3Error::Internal(format!("Failed to serialize iperf3 result: {}", e)))?;
+            if intervals == IntervalsLimit::Omit
+                && let Some(object) = value.as_object_mut()
+            {
+                object.remove("intervals");
+            }
+            if let Some(fields) = fields {
+                value = select_fields(&value, &parse_field_paths(&fields)).map_err(Iperf3Error::UnknownFields)?;
+            }
+            if units {
+                add_unit_fields(&mut value, &cached_result);
+            }
+            serde_json::to_vec(&value)
+        };
+        serialized.map_err(|e| Iperf3Error::Internal(format!("Failed to serialize iperf3 result: {}", e)))
+    })
+    .await?;
+    Ok(response.content_type("application/json").body(body))
 }
 
-/// Reads the environment variable `INTERVAL_MINUTES` or returns a default of 10 minutes.
+/// HTTP HEAD endpoint `/iperf3` answers with the status and headers a GET would return,
+/// without serializing the report.
 ///
-/// The duration represents how frequently iperf3 is run.
-pub fn min_frequency_duration() -> Duration {
-    let minutes = env::var("INTERVAL_MINUTES")
+/// The `direction` and `iface` query parameters select the cache as for GET; `fields` and
+/// `intervals` only shape the body and are ignored.
+#[head("/iperf3")]
+pub async fn iperf3_head(req: HttpRequest, query: web::Query<Iperf3Query>) -> Result<HttpResponse, Iperf3Error> {
+    let (_, mut response) = cached_result_response(&req, &query)?;
+    Ok(response.content_type("application/json").finish())
+}
+
+/// Looks up the cached result served by `/iperf3` for the optional `direction` or `iface`
+/// of `query` and starts its response, carrying [`X_CACHE_AGE_SECONDS`] and flagged with
+/// [`X_STALE`] where needed.
+///
+/// Fails while maintenance mode is enabled, if no result is cached or if the result is too
+/// stale for this client, see [`check_staleness`].
+fn cached_result_response(
+    req: &HttpRequest,
+    query: &Iperf3Query,
+) -> Result<(Iperf3Report, HttpResponseBuilder), Iperf3Error> {
+    ensure_not_in_maintenance()?;
+    let direction = query.direction.as_deref().map(Direction::parse).transpose()?;
+    let (cached_result, cached_at) = match (direction, query.iface.as_deref()) {
+        (Some(_), Some(_)) => {
+            return Err(Iperf3Error::BadRequest("direction and iface cannot be combined.".to_string()));
+        }
+        (Some(direction), None) => get_direction_result(direction).ok_or_else(Iperf3Error::not_available)?,
+        (None, Some(iface)) => get_interface_result(iface)
+            .ok_or_else(|| Iperf3Error::NotAvailable(format!("No result for interface {} yet.", iface)))?,
+        (None, None) => primary_result().ok_or_else(|| Iperf3Error::from(CacheError::current()))?,
+    };
+
+    let age = cached_at.elapsed();
+    let mut response = HttpResponse::Ok();
+    response.insert_header((X_CACHE_AGE_SECONDS, age.as_secs()));
+    if check_staleness(req, age)? {
+        response.insert_header((X_STALE, "true"));
+    }
+    Ok((cached_result, response))
+}
+
+/// HTTP GET endpoint `/iperf3/download` serves the last cached iperf3 result as a file attachment.
+///
+/// The body is the raw iperf3 JSON output when available, otherwise the serialized report.
+/// The filename is `iperf3-<timesecs>.json`, taken from the report's start timestamp.
+///
+/// Returns HTTP 503 Service Unavailable if no result is cached yet. With `REDACT_TARGET`
+/// enabled anonymous clients get HTTP 401 Unauthorized, as the raw output names the server.
+///
+/// Returns HTTP 503 with `Retry-After` while maintenance mode is enabled.
+#[get("/iperf3/download")]
+pub async fn iperf3_download(req: HttpRequest) -> Result<HttpResponse, Iperf3Error> {
+    ensure_not_in_maintenance()?;
+    ensure_raw_output_allowed(&req)?;
+    let cached_result = get_last_result().ok_or_else(Iperf3Error::not_available)?;
+
+    let body = match LAST_RAW_OUTPUT.lock().unwrap().clone() {
+        Some(raw) => raw,
+        None => serde_json::to_string_pretty(&cached_result)
+            .map_err(|e| Iperf3Error::Internal(format!("Failed to serialize iperf3 result: {}", e)))?,
+    };
+
+    let filename = format!("iperf3-{}.json", cached_result.start.timestamp.timesecs);
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", filename),
+        ))
+        .body(body))
+}
+
+/// Minutes between runs when `INTERVAL_MINUTES` is unset.
+pub const DEFAULT_INTERVAL_MINUTES: u64 = 10;
+
+/// Longest accepted `INTERVAL_MINUTES`: one week.
+pub const MAX_INTERVAL_MINUTES: u64 = 7 * 24 * 60;
+
+/// Reads the environment variable `INTERVAL_MINUTES`, the minutes between runs, or returns
+/// a default of [`DEFAULT_INTERVAL_MINUTES`].
+///
+/// Returns an error unless the value is a whole number from 1 to [`MAX_INTERVAL_MINUTES`].
+pub fn interval_minutes() -> Result<u64, String> {
+    let Ok(value) = env::var("INTERVAL_MINUTES") else {
+        return Ok(DEFAULT_INTERVAL_MINUTES);
+    };
+    value
+        .trim()
+        .parse::<u64>()
         .ok()
-        .and_then(|s| s.parse::<u64>().ok())
-        .unwrap_or(10); // default: 10 minutes
-    Duration::from_secs(minutes * 60)
+        .filter(|minutes| (1..=MAX_INTERVAL_MINUTES).contains(minutes))
+        .ok_or_else(|| {
+            format!("INTERVAL_MINUTES must be a whole number from 1 to {}, got {:?}", MAX_INTERVAL_MINUTES, value)
+        })
+}
+
+/// Returns how frequently iperf3 is run, from `INTERVAL_MINUTES`.
+///
+/// An invalid value is rejected by [`load_config`] at startup; should one be set later it
+/// falls back to [`DEFAULT_INTERVAL_MINUTES`].
+pub fn min_frequency_duration() -> Duration {
+    Duration::from_secs(interval_minutes().unwrap_or(DEFAULT_INTERVAL_MINUTES) * 60)
 }
 
 /// Trait to abstract running the iperf3 command.
@@ -247,82 +686,558 @@ pub fn min_frequency_duration() -> Duration {
 #[async_trait]
 pub trait Iperf3Runner: Send + Sync {
     /// Runs iperf3 and returns the raw JSON string output on success.
-    async fn run_iperf3(&self, iperf3_ip: String, iperf3_port: String) -> Result<String, String>;
+    async fn run_iperf3(&self, iperf3_ip: String, iperf3_port: String) -> Result<String, Iperf3Error>;
+
+    /// Runs iperf3 with explicit per-target options and returns the raw JSON string output on success.
+    ///
+    /// Defaults to [`Iperf3Runner::run_iperf3`] with the options' host and port.
+    async fn run_iperf3_with_options(&self, opts: &Iperf3Options) -> Result<String, Iperf3Error> {
+        self.run_iperf3(opts.host.clone(), opts.port.clone()).await
+    }
+
+    /// Checks that the iperf3 server can be reached before running a test.
+    ///
+    /// Defaults to `Ok(())`, so mock runners only override it to simulate an unreachable
+    /// server. [`RealIperf3Runner`] connects to the server, see [`probe_server`].
+    async fn probe(&self, _iperf3_ip: String, _iperf3_port: String) -> Result<(), Iperf3Error> {
+        Ok(())
+    }
+
+    /// Opens and closes a throwaway connection to the server right before a measured run,
+    /// see [`prewarm_enabled`].
+    ///
+    /// Defaults to [`Iperf3Runner::probe`] of the options' host and port.
+    async fn prewarm(&self, opts: &Iperf3Options) -> Result<(), Iperf3Error> {
+        self.probe(opts.host.clone(), opts.port.clone()).await
+    }
+}
+
+/// How long [`probe_server`] waits for the connection to be accepted.
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Connects to the iperf3 server at `host:port` (or the Unix socket `host`) and closes the
+/// connection again, failing with [`Iperf3Error::Unreachable`] after `limit`.
+pub async fn probe_server(host: &str, port: &str, limit: Duration) -> Result<(), Iperf3Error> {
+    let target = if is_unix_socket_path(host) {
+        host.to_string()
+    } else {
+        format!("{}:{}", host, port)
+    };
+    let connect = async {
+        #[cfg(unix)]
+        if is_unix_socket_path(host) {
+            return tokio::net::UnixStream::connect(host).await.map(drop);
+        }
+        tokio::net::TcpStream::connect(&target).await.map(drop)
+    };
+    match time::timeout(limit, connect).await {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(e)) => Err(Iperf3Error::Unreachable(format!("{}: {}", target, e))),
+        Err(_) => Err(Iperf3Error::Unreachable(format!("{}: timed out after {} ms", target, limit.as_millis()))),
+    }
+}
+
+/// Installs the `pre_exec` hooks preparing the iperf3 child: the niceness of `IPERF3_NICE`
+/// and, with the `hardening` feature, the [`ChildLimits`] from the environment.
+///
+/// Hooks run in the order they are installed. The niceness is set first, while the child
+/// still holds the service's privileges: once the hardening has dropped them, raising the
+/// priority fails and with it the spawn.
+pub fn prepare_child(command: &mut Command) {
+    #[cfg(unix)]
+    if let Some(nice) = iperf3_nice() {
+        apply_nice(command, nice);
+    }
+    #[cfg(all(target_os = "linux", feature = "hardening"))]
+    ChildLimits::from_env().apply(command);
 }
 
-/// Real iperf3 runner implementation using the `iperf3` binary.
+/// Real iperf3 runner implementation using the `iperf3` binary, or `IPERF3_BINARY`.
+///
+/// Arguments from `IPERF3_EXTRA_ARGS` are appended to the crate's own, see
+/// [`iperf3_extra_args`].
 pub struct RealIperf3Runner;
 
 #[async_trait]
 impl Iperf3Runner for RealIperf3Runner {
-    async fn run_iperf3(&self, iperf3_ip: String, iperf3_port: String) -> Result<String, String> {
-        let output = Command::new("iperf3")
-            .arg("-c")
-            .arg(iperf3_ip)
-            .arg("-p")
-            .arg(iperf3_port)
-            .arg("--json")
+    async fn run_iperf3(&self, iperf3_ip: String, iperf3_port: String) -> Result<String, Iperf3Error> {
+        self.run_iperf3_with_options(&Iperf3Options::from_env(iperf3_ip, iperf3_port)).await
+    }
+
+    async fn run_iperf3_with_options(&self, opts: &Iperf3Options) -> Result<String, Iperf3Error> {
+        let mut args = build_iperf3_args(opts);
+        args.extend(iperf3_extra_args().map_err(Iperf3Error::InvalidConfig)?);
+        let stream = json_stream_enabled();
+        if stream {
+            args.push("--json-stream".to_string());
+        }
+        let mut command = Command::new(iperf3_binary());
+        command
+            .args(&args)
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
-            .output()
-            .await
-            .map_err(|e| format!("Failed to run iperf3: {}", e))?;
+            // Cancelling the run (e.g. on shutdown) must not leave iperf3 behind
+            .kill_on_drop(true);
+        prepare_child(&mut command);
 
-        if output.status.success() {
-            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        let (status, stdout, stderr) = if stream {
+            run_json_stream_command(&mut command)
+                .instrument(info_span!("test_run"))
+                .await
+                .map_err(|e| Iperf3Error::from_spawn_error(&e))?
         } else {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            Err(format!("iperf3 failed: {}", stderr))
+            let child = info_span!("spawn")
+                .in_scope(|| command.spawn())
+                .map_err(|e| Iperf3Error::from_spawn_error(&e))?;
+            mark_child_spawned();
+            let output = child
+                .wait_with_output()
+                .instrument(info_span!("test_run"))
+                .await
+                .map_err(|e| Iperf3Error::from_spawn_error(&e))?;
+            (
+                output.status,
+                String::from_utf8_lossy(&output.stdout).to_string(),
+                String::from_utf8_lossy(&output.stderr).to_string(),
+            )
+        };
+
+        if status.success() {
+            Ok(stdout)
+        } else {
+            Err(Iperf3Error::from_failed_run(status.code(), &stdout, &stderr))
+        }
+    }
+
+    async fn probe(&self, iperf3_ip: String, iperf3_port: String) -> Result<(), Iperf3Error> {
+        probe_server(&iperf3_ip, &iperf3_port, PROBE_TIMEOUT).await
+    }
+}
+
+/// Reads the environment variable `IPERF3_TIMEOUT_SECONDS` or returns a default of 60
+/// seconds.
+pub fn iperf3_timeout() -> Duration {
+    let seconds = env::var("IPERF3_TIMEOUT_SECONDS")
+        .ok()
+        .and_then(|s| s.trim().parse::<u64>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(60);
+    Duration::from_secs(seconds)
+}
+
+/// Returns how long a run with `opts` may take: `IPERF3_TIMEOUT_SECONDS`, extended to the
+/// requested test duration plus ten seconds of setup so long tests are not cut short.
+///
+/// A `-t` in `IPERF3_EXTRA_ARGS` overrides the duration of `opts`, as it does for iperf3,
+/// see [`effective_options`].
+///
+/// # Examples
+///
+/// ```
+/// # use std::time::Duration;
+/// # use iperf3_statuspage::{run_timeout, Iperf3Options};
+/// let mut opts = Iperf3Options::new("10.0.0.1", "5201");
+/// assert_eq!(run_timeout(&opts), Duration::from_secs(60));
+/// opts.duration = Some(120);
+/// assert_eq!(run_timeout(&opts), Duration::from_secs(130));
+/// ```
+pub fn run_timeout(opts: &Iperf3Options) -> Duration {
+    let requested = effective_options(opts).duration.map_or(Duration::ZERO, |seconds| Duration::from_secs(u64::from(seconds) + 10));
+    iperf3_timeout().max(requested)
+}
+
+/// Runs iperf3 with `opts` through `runner`, giving up after [`run_timeout`].
+///
+/// On timeout the run's future is dropped, which kills the iperf3 child (it is spawned
+/// with `kill_on_drop`), and [`Iperf3Error::TimedOut`] is returned.
+pub async fn run_iperf3_with_timeout(runner: &dyn Iperf3Runner, opts: &Iperf3Options) -> Result<String, Iperf3Error> {
+    let limit = run_timeout(opts);
+    time::timeout(limit, runner.run_iperf3_with_options(opts))
+        .await
+        .unwrap_or(Err(Iperf3Error::TimedOut { timeout_seconds: limit.as_secs() }))
+}
+
+/// Reads the environment variable `IPERF3_MAX_RETRIES`, how often a run failing
+/// [transiently](Iperf3Error::is_transient) is retried, or returns a default of 2.
+pub fn iperf3_max_retries() -> u32 {
+    env::var("IPERF3_MAX_RETRIES")
+        .ok()
+        .and_then(|s| s.trim().parse::<u32>().ok())
+        .unwrap_or(2)
+}
+
+/// Delay before the first retry of a failed run; each further retry waits twice as long.
+pub const RETRY_BACKOFF_BASE: Duration = Duration::from_secs(1);
+
+/// Runs iperf3 with `opts` through `runner` (see [`run_iperf3_with_timeout`]), retrying
+/// transient failures up to `max_retries` times with exponential backoff from
+/// [`RETRY_BACKOFF_BASE`]. Returns the output of the first successful attempt or the
+/// error of the last one.
+///
+/// Every attempt is recorded on `timer` (see [`PhaseTimer::record_run`]) and every wait
+/// before a retry as `retry_backoff`.
+pub async fn run_iperf3_retrying(
+    runner: &dyn Iperf3Runner,
+    opts: &Iperf3Options,
+    max_retries: u32,
+    timer: &mut PhaseTimer,
+) -> Result<String, Iperf3Error> {
+    let mut backoff = RETRY_BACKOFF_BASE;
+    let mut attempt = 0;
+    loop {
+        let (result, spawned) = track_spawn(run_iperf3_with_timeout(runner, opts)).await;
+        timer.record_run(spawned);
+        match result {
+            Err(e) if e.is_transient() && attempt < max_retries => {
+                attempt += 1;
+                warn!(error = %e, attempt, max_retries, backoff_ms = backoff.as_millis() as u64, "Retrying iperf3 run");
+                time::sleep(backoff).await;
+                timer.record("retry_backoff");
+                backoff *= 2;
+            }
+            result => return result,
         }
     }
 }
 
 /// Runs the iperf3 test using the provided runner, parses the JSON output, and caches the result.
 ///
-/// Logs errors to stderr if the command or parsing fails.
-pub async fn run_iperf3_and_cache_with_runner(runner: &dyn Iperf3Runner, iperf3_ip: String, iperf3_port: String) {
-    match runner.run_iperf3(iperf3_ip, iperf3_port).await {
-        Ok(stdout) => match serde_json::from_str::<Iperf3Report>(&stdout) {
-            Ok(data) => {
-                let result = Iperf3Report {
-                    start: data.start,
-                    intervals: data.intervals,
-                    end: data.end,
-                };
-
-                let mut cache = LAST_RESULT.lock().unwrap();
-                *cache = Some((result.clone(), Instant::now()));
-                println!("Iperf3 result updated at {}", result.start.timestamp.time);
+/// Tuning options are read from the environment, see [`Iperf3Options::from_env`].
+/// Returns the freshly cached report, or the error (also logged at `error`) if
+/// the command or parsing fails.
+pub async fn run_iperf3_and_cache_with_runner(
+    runner: &dyn Iperf3Runner,
+    iperf3_ip: String,
+    iperf3_port: String,
+) -> Result<Iperf3Report, Iperf3Error> {
+    run_iperf3_and_cache_with_options(runner, &Iperf3Options::from_env(iperf3_ip, iperf3_port)).await
+}
+
+/// Runs the iperf3 test with explicit options using the provided runner, parses the JSON
+/// output, and caches the result.
+///
+/// Each phase (run, parse, cache) is wrapped in a `tracing` span and, with `PHASE_TIMING`
+/// enabled, its duration is recorded for `/debug/timing`, the run split into `spawn`,
+/// `test_run` and `retry_backoff`. Successful reports are then handed to the installed
+/// [`ResultPublisher`], whose failures are only logged. With the `otel` feature the
+/// cycle and its phases are also exported as OpenTelemetry spans, and with the `sqlite`
+/// feature successful reports are written to `DB_PATH` in the background.
+///
+/// Runs projected to exceed `MAX_TEST_BYTES` are refused without starting iperf3.
+/// Transient failures are retried up to `IPERF3_MAX_RETRIES` times with backoff, see
+/// [`run_iperf3_retrying`]. Output that fails to parse is captured for `/debug/last-bad-output` and, with
+/// `RETRY_ON_PARSE_FAILURE` enabled, the whole run is retried once. With `AUTO_BASELINE`
+/// the first successful report becomes the baseline, see [`capture_auto_baseline`]. With
+/// `DIAGNOSTICS_ON_FAILURE` a failed run is followed by network diagnostics, see
+/// [`capture_failure_diagnostics`].
+/// Successful runs are logged at `info` with the host, the received Mbps and the cycle
+/// duration. Returns the freshly cached report, or the error (also logged at `error`,
+/// including iperf3's stderr) if the run is refused or the command or parsing fails.
+pub async fn run_iperf3_and_cache_with_options(
+    runner: &dyn Iperf3Runner,
+    opts: &Iperf3Options,
+) -> Result<Iperf3Report, Iperf3Error> {
+    let cycle_span = info_span!("iperf3_cycle", host = %opts.host, port = %opts.port);
+    #[cfg(feature = "otel")]
+    let started_at = std::time::SystemTime::now();
+    let mut timer = PhaseTimer::start();
+    let started = Instant::now();
+    let _in_flight = track_run();
+
+    let mut result = run_cycle_phases(runner, opts, &cycle_span, &mut timer).await;
+    if let Err(Iperf3Error::Parse(e)) = &result
+        && retry_on_parse_failure_enabled()
+    {
+        warn!(error = %e, "Failed to parse iperf3 output; retrying once");
+        timer.next_attempt();
+        result = run_cycle_phases(runner, opts, &cycle_span, &mut timer).await;
+    }
+    record_run(result.is_ok());
+    record_last_error(&result);
+    match &result {
+        Ok(report) => {
+            cycle_span.in_scope(|| {
+                info!(
+                    host = %opts.host,
+                    mbps = bits_per_second_to_mbps(report.end.sum_received.bits_per_second),
+                    duration_ms = started.elapsed().as_millis() as u64,
+                    "Iperf3 result updated at {}",
+                    report.start.timestamp.time
+                )
+            });
+            capture_auto_baseline(report);
+            #[cfg(feature = "sqlite")]
+            sqlite::export_result(report);
+            publish_result(report).instrument(info_span!(parent: &cycle_span, "publish")).await
+        }
+        Err(e) => {
+            cycle_span.in_scope(|| error!(host = %opts.host, code = e.code(), "{}", e));
+            capture_failure_diagnostics(opts, e).await;
+        }
+    }
+
+    #[cfg(feature = "otel")]
+    export_cycle(opts, started_at, &timer.finish(), &result).await;
+    #[cfg(not(feature = "otel"))]
+    timer.finish();
+    result
+}
+
+/// Runs, parses and caches a single measurement, recording each phase on `timer`.
+async fn run_cycle_phases(
+    runner: &dyn Iperf3Runner,
+    opts: &Iperf3Options,
+    cycle_span: &tracing::Span,
+    timer: &mut PhaseTimer,
+) -> Result<Iperf3Report, Iperf3Error> {
+    if let Some(max_bytes) = max_test_bytes() {
+        check_test_bytes(opts, max_bytes)?;
+    }
+
+    if prewarm_enabled()
+        && let Err(e) = runner.prewarm(opts).await
+    {
+        warn!(error = %e, "Prewarm failed");
+    }
+    let output = run_iperf3_retrying(runner, opts, iperf3_max_retries(), timer)
+        .instrument(info_span!(parent: cycle_span, "run"))
+        .await;
+    {
+        let mut status = RUN_STATUS.lock().unwrap();
+        status.last_exit_code = match &output {
+            Ok(_) => Some(0),
+            Err(e) => e.exit_code(),
+        };
+        status.server_busy = matches!(output, Err(Iperf3Error::ServerBusy(_)));
+        status.permission_denied = matches!(output, Err(Iperf3Error::PermissionDenied(_)));
+    }
+    let stdout = output?;
+
+    let parsed = info_span!(parent: cycle_span, "parse")
+        .in_scope(|| serde_json::from_str::<Iperf3Report>(&stdout));
+    timer.record("parse");
+    let data = parsed.map_err(|e| {
+        error!(
+            line = e.line(),
+            column = e.column(),
+            "Failed to parse iperf3 output: {}",
+            parse_error_snippet(&stdout, e.line(), e.column())
+        );
+        capture_bad_output(&stdout);
+        Iperf3Error::Parse(e.to_string())
+    })?;
+
+    if let Some(min_bytes) = min_valid_bytes()
+        && let Some(reason) = check_min_bytes(&data, min_bytes)
+    {
+        warn!("{}; keeping the previous result", reason);
+        return Err(Iperf3Error::Rejected(reason));
+    }
+
+    let skew = max_clock_skew().and_then(|max_skew| check_clock_skew(&data, SystemTime::now(), max_skew));
+    if let Some(reason) = &skew
+        && reject_clock_skew_enabled()
+    {
+        warn!("{}; keeping the previous result", reason);
+        return Err(Iperf3Error::Rejected(reason.clone()));
+    }
+
+    let saturation = max_host_cpu_percent().and_then(|max_percent| check_cpu_saturation(&data, max_percent));
+    if let Some(reason) = &saturation
+        && reject_cpu_saturated_enabled()
+    {
+        warn!("{}; keeping the previous result", reason);
+        return Err(Iperf3Error::Rejected(reason.clone()));
+    }
+
+    // Everything but the per-target result and the fallback result describes the primary
+    // target only, like the endpoints serving it: the `/status` warnings, the cache
+    // metadata, the raw output, the smoothed throughput, the result events, the direction
+    // and interface results and the history
+    let key = Target::with_options(opts.clone()).key();
+    let primary = is_primary_target(&key);
+
+    let protocol_mismatch = expected_protocol().and_then(|expected| check_expected_protocol(&data, &expected));
+    let mut warnings = report_warnings(&data, opts);
+    warnings.extend(skew);
+    warnings.extend(saturation);
+    warnings.extend(protocol_mismatch.clone());
+    for warning in &warnings {
+        warn!("{}", warning);
+    }
+    if primary {
+        let mut status = RUN_STATUS.lock().unwrap();
+        status.protocol_mismatch = protocol_mismatch.is_some();
+        status.warnings = warnings;
+    }
+
+    let cache_span = info_span!(parent: cycle_span, "cache");
+    cache_span.in_scope(|| {
+        *LAST_RESULT.lock().unwrap() = Some((data.clone(), Instant::now()));
+        if primary {
+            *LAST_CACHE_METADATA.lock().unwrap() = Some(CacheMetadata::now());
+            *LAST_RAW_OUTPUT.lock().unwrap() = Some(stdout);
+            record_smoothed_throughput(&data);
+            notify_result_cached(&data);
+            cache_direction_result(Direction::of_run(opts.reverse), data.clone());
+            if let Some(bind) = &opts.bind {
+                cache_interface_result(bind, data.clone());
             }
-            Err(e) => eprintln!("Failed to parse iperf3 JSON: {}", e),
-        },
-        Err(e) => eprintln!("{}", e),
+        }
+        cache_target_result(&key, data.clone());
+    });
+    if primary {
+        push_history_blocking(data.clone()).instrument(cache_span).await;
+    }
+    timer.record("cache");
+
+    Ok(data)
+}
+
+/// Reads the environment variable `PREWARM` (`true`/`1`), defaulting to disabled.
+///
+/// When enabled every measured run is preceded by [`Iperf3Runner::prewarm`], so the path
+/// to the server (ARP/neighbour entries, NAT and firewall state) is set up before timing
+/// starts. A failed prewarm is only logged; the measured run reports any real problem.
+pub fn prewarm_enabled() -> bool {
+    env::var("PREWARM")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+/// Reads the environment variable `DISCARD_FIRST_RUN` (`true`/`1`), defaulting to disabled.
+pub fn discard_first_run_enabled() -> bool {
+    env::var("DISCARD_FIRST_RUN")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+/// Runs the startup measurement cycle against `targets`.
+///
+/// With `DISCARD_FIRST_RUN` enabled every target is run once purely as a warm-up: the
+/// output is dropped without being parsed, cached, published or counted as a cycle.
+/// Otherwise this is a regular cycle.
+pub async fn run_startup_cycle_with_runner(runner: &dyn Iperf3Runner, targets: &[Target], max_concurrent: usize) {
+    if !discard_first_run_enabled() {
+        record_cycle_start(Instant::now());
+        run_targets_retrying_busy(runner, targets, max_concurrent, server_busy_retry_delay()).await;
+        return;
+    }
+
+    for target in targets {
+        match run_iperf3_with_timeout(runner, &target.options).await {
+            Ok(_) => info!(%target, "Discarded warm-up run"),
+            Err(e) => warn!(%target, error = %e, "Warm-up run failed"),
+        }
     }
 }
 
+/// Reads the environment variable `INITIAL_DELAY_SECONDS` or returns a default of 0.
+///
+/// The scheduler waits this long before its first run, so the service serves 503 while
+/// networking settles.
+pub fn initial_delay() -> Duration {
+    let seconds = env::var("INITIAL_DELAY_SECONDS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(0);
+    Duration::from_secs(seconds)
+}
+
 /// Background async task which schedules periodic iperf3 runs.
 ///
-/// The interval between runs is configured by the `INTERVAL_MINUTES` env variable.
+/// The interval between runs is configured by the `INTERVAL_MINUTES` env variable and the
+/// first run is delayed by `INITIAL_DELAY_SECONDS`.
+/// Targets come from `TARGETS_FILE` when set, otherwise from the comma-separated servers
+/// given, see [`configured_targets`]; the first one becomes the primary target. They are run through [`run_targets_with_runner`], bounded by `MAX_CONCURRENT_RUNS`,
+/// and targets whose server is busy are retried sooner, see [`run_targets_retrying_busy`].
+/// The startup run may be discarded as a warm-up, see [`run_startup_cycle_with_runner`].
 pub async fn spawn_iperf3_scheduler(iperf3_ip: String, iperf3_port: String) {
-    let interval = min_frequency_duration();
-    let runner = RealIperf3Runner;
+    let targets = configured_targets(iperf3_ip, iperf3_port);
+    set_primary_target(targets.first().map(Target::key));
+    run_scheduler_with_runner(
+        &RealIperf3Runner,
+        &targets,
+        max_concurrent_runs(),
+        initial_delay(),
+        min_frequency_duration(),
+    )
+    .await
+}
+
+/// Runs the scheduler loop with the provided runner: waits `initial_delay`, runs the
+/// startup cycle, then runs every target each `interval`, alternating directions when
+/// `ROTATE_DIRECTION` is enabled (see [`rotated_targets`]). Never returns.
+pub async fn run_scheduler_with_runner(
+    runner: &dyn Iperf3Runner,
+    targets: &[Target],
+    max_concurrent: usize,
+    initial_delay: Duration,
+    interval: Duration,
+) -> ! {
+    if !initial_delay.is_zero() {
+        info!(delay_seconds = initial_delay.as_secs(), "Delaying the first iperf3 run");
+        time::sleep(initial_delay).await;
+    }
 
-    // Run one immediately on startup
-    run_iperf3_and_cache_with_runner(&runner, iperf3_ip.clone(), iperf3_port.clone()).await;
+    // Run one immediately on startup; a discarded warm-up is not counted as a cycle, so the
+    // first measured cycle follows it right away instead of an interval later
+    let warm_up = discard_first_run_enabled();
+    run_startup_cycle_with_runner(runner, targets, max_concurrent).await;
+    let mut cycle: u64 = if warm_up { 0 } else { 1 };
 
-    let mut ticker = time::interval(interval);
+    let first_tick = if warm_up { time::Instant::now() } else { time::Instant::now() + interval };
+    let mut ticker = time::interval_at(first_tick, interval);
     loop {
         ticker.tick().await;
-        run_iperf3_and_cache_with_runner(&runner, iperf3_ip.clone(), iperf3_port.clone()).await;
+        cycle += 1;
+        record_cycle_start(Instant::now());
+        let targets = rotated_targets(targets, cycle);
+        run_targets_retrying_busy(runner, &targets, max_concurrent, server_busy_retry_delay()).await;
     }
 }
 
-/// Async function to get the cached iperf3 result or return an error if not available.
-pub async fn get_cached_iperf3_result() -> Result<Iperf3Report, String> {
-    let cache = LAST_RESULT.lock().unwrap();
-    if let Some((cached_result, _)) = &*cache {
-        Ok(cached_result.clone())
-    } else {
-        Err("Iperf3 result not available yet.".to_string())
+/// Why no iperf3 result is cached.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CacheError {
+    /// No run has finished yet, e.g. right after startup.
+    NeverRun,
+    /// The last run failed with this error and no run has succeeded since startup.
+    Failing(String),
+}
+
+impl CacheError {
+    /// Classifies the missing result from the last run's error, see [`get_last_error`].
+    pub fn current() -> Self {
+        match get_last_error() {
+            Some((message, _)) => CacheError::Failing(message),
+            None => CacheError::NeverRun,
+        }
     }
 }
+
+impl fmt::Display for CacheError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CacheError::NeverRun => f.write_str("Iperf3 result not available yet."),
+            CacheError::Failing(message) => write!(f, "No iperf3 result yet; the last run failed: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for CacheError {}
+
+impl From<CacheError> for Iperf3Error {
+    fn from(e: CacheError) -> Self {
+        match e {
+            CacheError::NeverRun => Iperf3Error::not_available(),
+            CacheError::Failing(message) => Iperf3Error::LastRunFailed(message),
+        }
+    }
+}
+
+/// Async function to get the cached iperf3 result of the primary target, or why there is
+/// none: no run has finished yet, or the runs so far failed.
+pub async fn get_cached_iperf3_result() -> Result<Iperf3Report, CacheError> {
+    get_last_result().ok_or_else(CacheError::current)
+}
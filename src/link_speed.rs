@@ -0,0 +1,48 @@
+//! # iperf3-statuspage
+//!
+//! Detection of the source interface's link speed from sysfs (Linux).
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// Directory listing the network interfaces on Linux.
+pub const SYSFS_NET_DIR: &str = "/sys/class/net";
+
+/// Reads the environment variable `LINK_INTERFACE`, the network interface tests leave
+/// through, e.g. `eth0`. Returns `None` when unset or empty.
+pub fn link_interface() -> Option<String> {
+    env::var("LINK_INTERFACE")
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty() && !s.contains('/'))
+}
+
+/// Reads the link speed of `interface` in Mbps from `<net_dir>/<interface>/speed`.
+///
+/// Returns `None` if the file is missing or unreadable, or the speed is not positive, as
+/// reported by virtual interfaces and links that are down.
+pub fn read_link_speed_mbps(net_dir: &Path, interface: &str) -> Option<u64> {
+    fs::read_to_string(net_dir.join(interface).join("speed"))
+        .ok()
+        .and_then(|s| s.trim().parse::<i64>().ok())
+        .filter(|n| *n > 0)
+        .map(|n| n as u64)
+}
+
+/// Returns the link speed of `LINK_INTERFACE` in Mbps, `None` when unset, undetectable or
+/// not on Linux.
+pub fn detect_link_speed_mbps() -> Option<u64> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    read_link_speed_mbps(Path::new(SYSFS_NET_DIR), &link_interface()?)
+}
@@ -0,0 +1,142 @@
+//! # iperf3-statuspage
+//!
+//! Streaming runs with iperf3's `--json-stream` output, whose intervals are served live at
+//! `/iperf3/live` while the test is still running.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+use std::io;
+use std::process::ExitStatus;
+use std::sync::Mutex;
+use actix_web::{get, HttpRequest, HttpResponse};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::process::Command;
+use crate::errors::Iperf3Error;
+use crate::models::Interval;
+use crate::redact::public_json;
+use crate::timing::mark_child_spawned;
+
+/// Intervals of the current (or, once finished, the last) streamed run.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct LiveRun {
+    /// Whether the run is still going.
+    pub in_progress: bool,
+    /// Intervals received so far, in order.
+    pub intervals: Vec<Interval>,
+}
+
+/// Live buffer of the streamed run, `None` until one starts.
+static LIVE_RUN: Lazy<Mutex<Option<LiveRun>>> = Lazy::new(|| Mutex::new(None));
+
+/// Reads the environment variable `IPERF3_JSON_STREAM` (`true`/`1`), defaulting to
+/// disabled. When enabled iperf3 runs with `--json-stream` and its intervals are served
+/// at `/iperf3/live` as they arrive.
+pub fn json_stream_enabled() -> bool {
+    env::var("IPERF3_JSON_STREAM")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+/// Returns the live buffer of the current or last streamed run, if any.
+pub fn get_live_run() -> Option<LiveRun> {
+    LIVE_RUN.lock().unwrap().clone()
+}
+
+/// Clears the live buffer.
+pub fn clear_live_run_for_test() {
+    *LIVE_RUN.lock().unwrap() = None;
+}
+
+/// Reads `--json-stream` output line by line, updating the live buffer as events arrive,
+/// and returns it assembled into the single JSON document `--json` would have printed.
+///
+/// Each line is an `{"event": ..., "data": ...}` object. `start`, `interval` and `end`
+/// events become the report's sections and an `error` event becomes `{"error": ...}`.
+/// If any line is not such an object the raw output is returned unchanged, so it fails to
+/// parse and is captured like any other bad output.
+pub async fn read_json_stream(reader: impl AsyncBufRead + Unpin) -> io::Result<String> {
+    let mut lines = reader.lines();
+    let mut raw = String::new();
+    let (mut start, mut intervals, mut end, mut error) = (None, Vec::new(), None, None);
+    let mut malformed = false;
+    *LIVE_RUN.lock().unwrap() = Some(LiveRun { in_progress: true, intervals: Vec::new() });
+
+    let result = async {
+        while let Some(line) = lines.next_line().await? {
+            raw.push_str(&line);
+            raw.push('\n');
+            if line.trim().is_empty() || malformed {
+                continue;
+            }
+            let Ok(Value::Object(mut event)) = serde_json::from_str::<Value>(&line) else {
+                malformed = true;
+                continue;
+            };
+            let data = event.remove("data").unwrap_or(Value::Null);
+            match event.get("event").and_then(Value::as_str) {
+                Some("start") => start = Some(data),
+                Some("interval") => {
+                    if let Ok(interval) = serde_json::from_value::<Interval>(data.clone())
+                        && let Some(live) = LIVE_RUN.lock().unwrap().as_mut()
+                    {
+                        live.intervals.push(interval);
+                    }
+                    intervals.push(data);
+                }
+                Some("end") => end = Some(data),
+                Some("error") => error = Some(data),
+                _ => {}
+            }
+        }
+        Ok::<(), io::Error>(())
+    }
+    .await;
+    if let Some(live) = LIVE_RUN.lock().unwrap().as_mut() {
+        live.in_progress = false;
+    }
+    result?;
+
+    Ok(match (malformed, error) {
+        (true, _) => raw,
+        (false, Some(error)) => json!({ "error": error }).to_string(),
+        (false, None) => json!({ "start": start, "intervals": intervals, "end": end }).to_string(),
+    })
+}
+
+/// Spawns `command` (which must pipe stdout and stderr) and streams its stdout through
+/// [`read_json_stream`], returning the exit status, the assembled stdout and stderr.
+pub async fn run_json_stream_command(command: &mut Command) -> io::Result<(ExitStatus, String, String)> {
+    let mut child = command.spawn()?;
+    mark_child_spawned();
+    let stdout = child.stdout.take().ok_or_else(|| io::Error::other("iperf3 stdout is not piped"))?;
+    let mut stderr = child.stderr.take().ok_or_else(|| io::Error::other("iperf3 stderr is not piped"))?;
+
+    // Drain stderr alongside stdout so a chatty child cannot block on a full pipe
+    let mut stderr_text = String::new();
+    let (stdout_text, stderr_read) =
+        tokio::join!(read_json_stream(BufReader::new(stdout)), stderr.read_to_string(&mut stderr_text));
+    let stdout_text = stdout_text?;
+    stderr_read?;
+    Ok((child.wait().await?, stdout_text, stderr_text))
+}
+
+/// HTTP GET endpoint `/iperf3/live` returns the intervals of the running (or last)
+/// streamed run as JSON, see `IPERF3_JSON_STREAM`.
+///
+/// Returns HTTP 503 Service Unavailable if no streamed run has started yet.
+#[get("/iperf3/live")]
+pub async fn iperf3_live(req: HttpRequest) -> Result<HttpResponse, Iperf3Error> {
+    let live = get_live_run()
+        .ok_or_else(|| Iperf3Error::NotAvailable("No streamed iperf3 run has started yet.".to_string()))?;
+    Ok(HttpResponse::Ok().json(public_json(&req, &live)?))
+}
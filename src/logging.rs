@@ -0,0 +1,293 @@
+//! # iperf3-statuspage
+//!
+//! Structured logging: a minimal `tracing` subscriber writing timestamped, levelled lines
+//! to stderr, filtered by `RUST_LOG`.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::env;
+use std::fmt::{self, Write as _};
+use std::io::Write as _;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+
+/// Level logged when `RUST_LOG` is unset or names no level for a target.
+pub const DEFAULT_LOG_LEVEL: LevelFilter = LevelFilter::INFO;
+
+/// Reads the environment variable `RUST_LOG`, the log filter, defaulting to `info`.
+pub fn rust_log() -> String {
+    env::var("RUST_LOG").ok().filter(|s| !s.trim().is_empty()).unwrap_or_else(|| "info".to_string())
+}
+
+/// Log filter in the `RUST_LOG` syntax: comma-separated directives, each either a level
+/// (`warn`) applying to every target or `target=level` applying to that target and its
+/// submodules. The most specific matching target wins.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogFilter {
+    default: LevelFilter,
+    /// `(target, level)` directives, longest target first.
+    targets: Vec<(String, LevelFilter)>,
+}
+
+impl LogFilter {
+    /// Parses a `RUST_LOG` value. Directives that are not valid are skipped with a warning
+    /// on stderr, since no subscriber is installed yet to log it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use iperf3_statuspage::LogFilter;
+    /// # use tracing::Level;
+    /// let filter = LogFilter::parse("warn,iperf3_statuspage::history=debug");
+    /// assert!(filter.enabled("iperf3_statuspage::history", &Level::DEBUG));
+    /// assert!(!filter.enabled("iperf3_statuspage", &Level::INFO));
+    /// assert!(filter.enabled("actix_server", &Level::WARN));
+    /// ```
+    pub fn parse(spec: &str) -> Self {
+        let mut filter = LogFilter { default: DEFAULT_LOG_LEVEL, targets: Vec::new() };
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            let parsed = match directive.split_once('=') {
+                Some((target, level)) => level.trim().parse().map(|level| filter.targets.push((target.trim().to_string(), level))),
+                None => directive.parse().map(|level| filter.default = level),
+            };
+            if parsed.is_err() {
+                eprintln!("Warning: ignoring invalid RUST_LOG directive {:?}", directive);
+            }
+        }
+        filter.targets.sort_by_key(|(target, _)| std::cmp::Reverse(target.len()));
+        filter
+    }
+
+    /// Returns the filter of `RUST_LOG`.
+    pub fn from_env() -> Self {
+        Self::parse(&rust_log())
+    }
+
+    /// Returns whether events of `level` from `target` pass the filter.
+    pub fn enabled(&self, target: &str, level: &Level) -> bool {
+        let max = self
+            .targets
+            .iter()
+            .find(|(prefix, _)| {
+                target.strip_prefix(prefix.as_str()).is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .map_or(self.default, |(_, level)| *level);
+        max >= *level
+    }
+
+    /// Returns the most verbose level any directive lets through.
+    fn max_level(&self) -> LevelFilter {
+        self.targets.iter().map(|(_, level)| *level).fold(self.default, LevelFilter::max)
+    }
+}
+
+/// Formats `time` as an RFC 3339 UTC timestamp with millisecond precision.
+///
+/// # Examples
+///
+/// ```
+/// # use std::time::{Duration, UNIX_EPOCH};
+/// # use iperf3_statuspage::format_utc_timestamp;
+/// let time = UNIX_EPOCH + Duration::from_millis(1_754_995_182_250);
+/// assert_eq!(format_utc_timestamp(time), "2025-08-12T10:39:42.250Z");
+/// ```
+pub fn format_utc_timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+    // Civil date from days since the epoch, after Howard Hinnant's `civil_from_days`
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+/// Collects the message and the `key=value` fields of an event or span.
+#[derive(Default)]
+struct FieldWriter {
+    message: String,
+    fields: String,
+}
+
+impl Visit for FieldWriter {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+/// An open span: its `name{fields}` context, prefixed by its parents', and how many
+/// handles refer to it.
+struct SpanState {
+    context: String,
+    refs: usize,
+}
+
+thread_local! {
+    /// Spans entered on this thread, innermost last.
+    static ENTERED: RefCell<Vec<Id>> = const { RefCell::new(Vec::new()) };
+}
+
+/// `tracing` subscriber writing one line per event to stderr:
+/// `<timestamp> <LEVEL> <span context>: <target>: <message> <key=value ...>`.
+pub struct StderrSubscriber {
+    filter: LogFilter,
+    next_id: AtomicU64,
+    spans: Mutex<HashMap<u64, SpanState>>,
+}
+
+impl StderrSubscriber {
+    /// Creates a subscriber logging what passes `filter`.
+    pub fn new(filter: LogFilter) -> Self {
+        StderrSubscriber { filter, next_id: AtomicU64::new(1), spans: Mutex::new(HashMap::new()) }
+    }
+
+    /// Returns the context of the span `id`, or an empty string if it is closed.
+    fn context_of(&self, id: &Id) -> String {
+        self.spans.lock().unwrap().get(&id.into_u64()).map(|s| s.context.clone()).unwrap_or_default()
+    }
+
+    /// Returns the innermost span entered on this thread.
+    fn current(&self) -> Option<Id> {
+        ENTERED.with(|entered| entered.borrow().last().cloned())
+    }
+
+    /// Renders one log line for `event`.
+    fn format_event(&self, event: &Event<'_>, now: SystemTime) -> String {
+        let parent = if event.is_root() {
+            None
+        } else {
+            event.parent().cloned().or_else(|| self.current())
+        };
+        let context = parent.map(|id| self.context_of(&id)).unwrap_or_default();
+        let mut writer = FieldWriter::default();
+        event.record(&mut writer);
+        let metadata = event.metadata();
+        let mut line = format!("{} {:>5} ", format_utc_timestamp(now), metadata.level());
+        if !context.is_empty() {
+            let _ = write!(line, "{}: ", context);
+        }
+        let _ = write!(line, "{}: {}{}", metadata.target(), writer.message, writer.fields);
+        line
+    }
+}
+
+impl Subscriber for StderrSubscriber {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.filter.enabled(metadata.target(), metadata.level())
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(self.filter.max_level())
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let parent = if span.is_root() {
+            None
+        } else {
+            span.parent().cloned().or_else(|| self.current())
+        };
+        let mut writer = FieldWriter::default();
+        span.record(&mut writer);
+        let own = match writer.fields.trim_start() {
+            "" => span.metadata().name().to_string(),
+            fields => format!("{}{{{}}}", span.metadata().name(), fields),
+        };
+        let context = match parent.map(|id| self.context_of(&id)).filter(|c| !c.is_empty()) {
+            Some(parent) => format!("{}:{}", parent, own),
+            None => own,
+        };
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.spans.lock().unwrap().insert(id, SpanState { context, refs: 1 });
+        Id::from_u64(id)
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let line = self.format_event(event, SystemTime::now());
+        let _ = writeln!(std::io::stderr().lock(), "{}", line);
+    }
+
+    fn enter(&self, span: &Id) {
+        ENTERED.with(|entered| entered.borrow_mut().push(span.clone()));
+    }
+
+    fn exit(&self, span: &Id) {
+        ENTERED.with(|entered| {
+            let mut entered = entered.borrow_mut();
+            if let Some(position) = entered.iter().rposition(|id| id == span) {
+                entered.remove(position);
+            }
+        });
+    }
+
+    fn clone_span(&self, id: &Id) -> Id {
+        if let Some(state) = self.spans.lock().unwrap().get_mut(&id.into_u64()) {
+            state.refs += 1;
+        }
+        id.clone()
+    }
+
+    fn try_close(&self, id: Id) -> bool {
+        let mut spans = self.spans.lock().unwrap();
+        let Some(state) = spans.get_mut(&id.into_u64()) else {
+            return false;
+        };
+        state.refs -= 1;
+        if state.refs == 0 {
+            spans.remove(&id.into_u64());
+            return true;
+        }
+        false
+    }
+}
+
+/// Installs a [`StderrSubscriber`] filtered by `RUST_LOG` as the global default.
+///
+/// Returns `false`, leaving the existing one in place, if a global subscriber is already
+/// installed, e.g. by a test.
+pub fn init_logging() -> bool {
+    tracing::subscriber::set_global_default(StderrSubscriber::new(LogFilter::from_env())).is_ok()
+}
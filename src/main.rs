@@ -10,39 +10,141 @@
 // or the MIT license <http://opensource.org/licenses/MIT>, at your option.
 // This file may not be copied, modified, or distributed except according to those terms.
 
-mod models;
-
+use actix_web::middleware::from_fn;
 use actix_web::{App, HttpServer};
-use dotenvy;
 use std::env;
-use iperf3_statuspage::{spawn_iperf3_scheduler, iperf3};
+use tracing::{error, info};
+use iperf3_statuspage::{
+    spawn_iperf3_scheduler, one_shot_enabled, run_one_shot_with_runner, state_file_path, RealIperf3Runner,
+    print_config, print_config_requested, configure_services, load_baseline_from_env, process_start_time,
+    deep_interval, spawn_deep_scheduler, load_state_from_env, metrics_bind, configure_metrics_services, drain_until_shutdown, shutdown_signal, shutdown_timeout,
+    load_history_from_env, limit_inflight, InflightLimit, init_logging, load_config,
+};
 
 /// Main entrypoint starts the Actix-web server and the periodic iperf3 runner.
 ///
 /// Binds to `BIND_ADDRESS` and `BIND_PORT` environment variables or defaults.
+/// When `ONE_SHOT` is enabled a single test is run and the process exits instead.
+/// With `--print-config` the resolved configuration is printed as JSON and the process exits.
 ///
-/// # Panics
-///
-/// Panics if `BIND_PORT` cannot be parsed as a valid `u16`.
+/// An invalid configuration (see [`load_config`]) is reported on a single line and the
+/// process exits with code 1.
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     dotenvy::dotenv().ok();
 
-    let bind_address = env::var("BIND_ADDRESS").unwrap_or_else(|_| "127.0.0.1".to_string());
-    let bind_port_str = env::var("BIND_PORT").unwrap_or_else(|_| "8080".to_string());
-    let bind_port: u16 = bind_port_str.parse().expect("BIND_PORT must be a valid u16");
+    // Log to stderr filtered by RUST_LOG (which may come from .env)
+    init_logging();
+
+    // Pin the `_created` timestamp of the metrics counters to the process start
+    process_start_time();
+
+    // Print the resolved configuration and exit without binding or running anything
+    if print_config_requested(env::args().skip(1)) {
+        std::process::exit(print_config(&mut std::io::stdout()));
+    }
+
+    // Validate the whole configuration up front, failing with a single line instead of a panic
+    let config = match load_config() {
+        Ok(config) => config,
+        Err(e) => {
+            error!("Invalid configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let bind_address = config.bind_address.clone();
+    let bind_port = config.bind_port;
+    // IPERF3_SERVER_IP/IPERF3_SERVER_PORT may list several servers; the first is the
+    // primary one, used by one-shot runs and the deep profile
+    let (primary_ip, primary_port) = config.primary_server();
+
+    // Pick up RUN_ANNOTATION changes from .env on SIGHUP
+    #[cfg(unix)]
+    tokio::spawn(iperf3_statuspage::reload_annotation_on_sighup());
+
+    // Load the known-good baseline to compare results against
+    load_baseline_from_env();
 
-    let iperf3_ip = env::var("IPERF3_SERVER_IP").expect("IPERF3_SERVER_IP must be set");
-    let iperf3_port = env::var("IPERF3_SERVER_PORT").expect("IPERF3_SERVER_PORT must be set");
+    // Publish every successful result to NATS when configured
+    #[cfg(feature = "nats")]
+    if let Some(publisher) = iperf3_statuspage::NatsPublisher::from_env() {
+        iperf3_statuspage::set_publisher(Some(std::sync::Arc::new(publisher)));
+    }
 
+    // Export measurement cycles to an OTLP collector when configured
+    #[cfg(feature = "otel")]
+    if let Some(exporter) = iperf3_statuspage::OtlpHttpExporter::from_env() {
+        iperf3_statuspage::set_span_exporter(Some(std::sync::Arc::new(exporter)));
+    }
+
+    // In one-shot mode run a single test and exit without starting the server
+    if one_shot_enabled() {
+        let state_file = state_file_path();
+        let code = run_one_shot_with_runner(
+            &RealIperf3Runner,
+            primary_ip,
+            primary_port,
+            state_file.as_deref(),
+            &mut std::io::stdout(),
+        )
+        .await;
+        std::process::exit(code);
+    }
+
+    // Serve the result left in STATE_FILE (e.g. by a one-shot run) until the first run
+    load_state_from_env();
+
+    // Keep the history in HISTORY_FILE across restarts when HISTORY_BACKEND=file
+    load_history_from_env();
+
+    // Spawn the lower-frequency deep profile when DEEP_INTERVAL_MINUTES is set.
+    // On SIGINT or SIGTERM both schedulers let an in-flight run finish for up to
+    // SHUTDOWN_TIMEOUT_SECONDS, then stop (killing any running iperf3).
+    let drain_timeout = shutdown_timeout();
+    if let Some(interval) = deep_interval() {
+        let deep = spawn_deep_scheduler(primary_ip, primary_port, interval);
+        tokio::spawn(drain_until_shutdown(deep, shutdown_signal(), drain_timeout));
+    }
 
     // Spawn the periodic speedtest updater
-    tokio::spawn(spawn_iperf3_scheduler(iperf3_ip, iperf3_port));
+    let scheduler = tokio::spawn(drain_until_shutdown(
+        spawn_iperf3_scheduler(config.iperf3_server_ip, config.iperf3_server_port),
+        shutdown_signal(),
+        drain_timeout,
+    ));
 
-    println!("Starting server at http://{}:{}/iperf3", bind_address, bind_port);
+    info!("Starting server at http://{}:{}/iperf3", bind_address, bind_port);
 
-    HttpServer::new(|| App::new().service(iperf3))
+    // actix-web stops the server gracefully on the same signals, serving in-flight
+    // requests for at most SHUTDOWN_TIMEOUT_SECONDS
+    // At most MAX_INFLIGHT_REQUESTS requests are handled at once across all workers
+    let inflight = InflightLimit::from_env();
+    let server = HttpServer::new(move || {
+        let inflight = inflight.clone();
+        App::new()
+            .wrap(from_fn(move |req, next| limit_inflight(inflight.clone(), req, next)))
+            .configure(configure_services)
+    })
+        .shutdown_timeout(drain_timeout.as_secs())
         .bind((bind_address.as_str(), bind_port))?
-        .run()
-        .await
-}
\ No newline at end of file
+        .run();
+
+    // Serve /metrics on its own listener when METRICS_BIND_PORT is set
+    match metrics_bind() {
+        Some((metrics_address, metrics_port)) => {
+            info!("Serving metrics at http://{}:{}/metrics", metrics_address, metrics_port);
+            let metrics_server = HttpServer::new(|| App::new().configure(configure_metrics_services))
+                .workers(1)
+                .shutdown_timeout(drain_timeout.as_secs())
+                .bind((metrics_address.as_str(), metrics_port))?
+                .run();
+            futures::future::try_join(server, metrics_server).await?;
+        }
+        None => server.await?,
+    }
+
+    // Let the scheduler finish draining its in-flight run, bounded by the same timeout
+    let _ = tokio::time::timeout(drain_timeout, scheduler).await;
+    Ok(())
+}
+
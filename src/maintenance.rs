@@ -0,0 +1,121 @@
+//! # iperf3-statuspage
+//!
+//! Maintenance mode, toggled via `MAINTENANCE_MODE` or `/admin/maintenance`.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+use std::sync::Mutex;
+use actix_web::{get, http::header, post, web, HttpResponse, Responder};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use crate::auth::RequireToken;
+use crate::errors::Iperf3Error;
+
+/// Runtime override of `MAINTENANCE_MODE`, set through `/admin/maintenance`.
+static MAINTENANCE_OVERRIDE: Lazy<Mutex<Option<bool>>> = Lazy::new(|| Mutex::new(None));
+
+/// Returns whether maintenance mode is enabled.
+///
+/// A toggle through `/admin/maintenance` takes precedence over the environment variable
+/// `MAINTENANCE_MODE` (`true`/`1`), which defaults to disabled.
+pub fn maintenance_enabled() -> bool {
+    if let Some(enabled) = *MAINTENANCE_OVERRIDE.lock().unwrap() {
+        return enabled;
+    }
+    env::var("MAINTENANCE_MODE")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+/// Enables or disables maintenance mode at runtime, overriding `MAINTENANCE_MODE`.
+pub fn set_maintenance(enabled: bool) {
+    *MAINTENANCE_OVERRIDE.lock().unwrap() = Some(enabled);
+}
+
+/// Drops the runtime override so `MAINTENANCE_MODE` applies again.
+pub fn clear_maintenance_for_test() {
+    *MAINTENANCE_OVERRIDE.lock().unwrap() = None;
+}
+
+/// Reads the environment variable `MAINTENANCE_RETRY_AFTER_SECONDS` or returns a default of 300 seconds.
+pub fn maintenance_retry_after_seconds() -> u64 {
+    env::var("MAINTENANCE_RETRY_AFTER_SECONDS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(300)
+}
+
+/// Fails with [`Iperf3Error::Maintenance`] while maintenance mode is enabled.
+///
+/// Data endpoints call this first so they serve a 503 with `Retry-After` instead of stale data.
+pub fn ensure_not_in_maintenance() -> Result<(), Iperf3Error> {
+    if maintenance_enabled() {
+        Err(Iperf3Error::Maintenance { retry_after_seconds: maintenance_retry_after_seconds() })
+    } else {
+        Ok(())
+    }
+}
+
+/// Maintenance state as reported by `/admin/maintenance`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MaintenanceState {
+    pub enabled: bool,
+    pub retry_after_seconds: u64,
+}
+
+/// Query parameters for `POST /admin/maintenance`.
+#[derive(Deserialize, Debug)]
+pub struct MaintenanceQuery {
+    pub enabled: bool,
+}
+
+fn maintenance_state() -> MaintenanceState {
+    MaintenanceState {
+        enabled: maintenance_enabled(),
+        retry_after_seconds: maintenance_retry_after_seconds(),
+    }
+}
+
+/// HTTP GET endpoint `/admin/maintenance` returns the maintenance state as JSON.
+#[get("/admin/maintenance")]
+pub async fn get_maintenance() -> impl Responder {
+    HttpResponse::Ok().json(maintenance_state())
+}
+
+/// HTTP POST endpoint `/admin/maintenance?enabled=true|false` toggles maintenance mode
+/// and returns the new state as JSON.
+///
+/// When `API_TOKEN` is set, requests without `Authorization: Bearer <API_TOKEN>` get
+/// HTTP 401 Unauthorized, see [`RequireToken`].
+#[post("/admin/maintenance")]
+pub async fn set_maintenance_mode(_: RequireToken, query: web::Query<MaintenanceQuery>) -> impl Responder {
+    set_maintenance(query.enabled);
+    info!("Maintenance mode {}", if query.enabled { "enabled" } else { "disabled" });
+    HttpResponse::Ok().json(maintenance_state())
+}
+
+/// HTTP GET endpoint `/healthz` reports that the process is up, even in maintenance mode.
+#[get("/healthz")]
+pub async fn healthz() -> impl Responder {
+    HttpResponse::Ok().content_type("text/plain; charset=utf-8").body("ok")
+}
+
+/// HTTP GET endpoint `/favicon.ico` answers browser probes with HTTP 204 No Content.
+///
+/// The response may be cached for a day so browsers stop asking. Requests are only
+/// logged at debug level.
+#[get("/favicon.ico")]
+pub async fn favicon() -> impl Responder {
+    tracing::debug!("Served empty /favicon.ico");
+    HttpResponse::NoContent()
+        .insert_header((header::CACHE_CONTROL, "public, max-age=86400"))
+        .finish()
+}
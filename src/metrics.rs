@@ -0,0 +1,319 @@
+//! # iperf3-statuspage
+//!
+//! Prometheus text-format metrics, served at `/metrics`.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use actix_web::{get, HttpResponse};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use crate::errors::Iperf3Error;
+use crate::get_last_result;
+use crate::models::Iperf3Report;
+use crate::slo::current_slo_compliance;
+use crate::status::{get_run_status, RunStatus};
+use crate::summary::{asymmetry_ratio, link_capacity_mbps, stream_retransmits, utilization_percent};
+
+/// Content type of the Prometheus text exposition format.
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Formatting of floating-point sample values.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MetricsFloatFormat {
+    /// Shortest decimal that round-trips, e.g. `950000000` or `0.25`.
+    Shortest,
+    /// Plain decimal with three fractional digits, e.g. `950000000.000`.
+    Fixed,
+    /// Scientific notation, e.g. `9.5e8`.
+    Scientific,
+}
+
+/// Reads the environment variable `METRICS_FLOAT_FORMAT` (`shortest`, `fixed` or
+/// `scientific`) or returns a default of `shortest`.
+pub fn metrics_float_format() -> MetricsFloatFormat {
+    match env::var("METRICS_FLOAT_FORMAT").map(|s| s.trim().to_ascii_lowercase()) {
+        Ok(format) if format == "fixed" => MetricsFloatFormat::Fixed,
+        Ok(format) if format == "scientific" => MetricsFloatFormat::Scientific,
+        _ => MetricsFloatFormat::Shortest,
+    }
+}
+
+/// Formats a sample value in the given format.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::{format_metric_float, MetricsFloatFormat};
+/// assert_eq!(format_metric_float(950_000_000.0, MetricsFloatFormat::Shortest), "950000000");
+/// assert_eq!(format_metric_float(950_000_000.0, MetricsFloatFormat::Fixed), "950000000.000");
+/// assert_eq!(format_metric_float(950_000_000.0, MetricsFloatFormat::Scientific), "9.5e8");
+/// ```
+pub fn format_metric_float(value: f64, format: MetricsFloatFormat) -> String {
+    match format {
+        MetricsFloatFormat::Shortest => value.to_string(),
+        MetricsFloatFormat::Fixed => format!("{:.3}", value),
+        MetricsFloatFormat::Scientific => format!("{:e}", value),
+    }
+}
+
+/// Reads the environment variable `METRICS_PER_STREAM`, defaulting to `false`.
+///
+/// Per-stream series add one label value per parallel stream, so they are off unless
+/// explicitly enabled to keep cardinality bounded.
+pub fn metrics_per_stream_enabled() -> bool {
+    env::var("METRICS_PER_STREAM")
+        .map(|s| matches!(s.trim().to_ascii_lowercase().as_str(), "true" | "1"))
+        .unwrap_or(false)
+}
+
+/// Reads the environment variable `METRICS_CREATED`, defaulting to `true`.
+///
+/// When enabled every counter is followed by a `<name>_created` sample holding the process
+/// start time, so `rate()` is accurate across restarts.
+pub fn metrics_created_enabled() -> bool {
+    env::var("METRICS_CREATED")
+        .map(|s| !matches!(s.trim().to_ascii_lowercase().as_str(), "false" | "0"))
+        .unwrap_or(true)
+}
+
+/// Reads the environment variable `METRICS_SMOOTHING_ALPHA`, the weight in (0, 1] of each
+/// new result in `iperf3_received_bits_per_second_smoothed`. Smaller values smooth more and
+/// lag more. Unset or invalid values disable the smoothed gauge.
+pub fn metrics_smoothing_alpha() -> Option<f64> {
+    env::var("METRICS_SMOOTHING_ALPHA")
+        .ok()
+        .and_then(|s| s.trim().parse::<f64>().ok())
+        .filter(|alpha| *alpha > 0.0 && *alpha <= 1.0)
+}
+
+/// Returns the exponentially weighted moving average after `sample`, weighted by `alpha`,
+/// starting from the first sample when there is no `previous` average.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::ewma;
+/// assert_eq!(ewma(None, 100.0, 0.25), 100.0);
+/// assert_eq!(ewma(Some(100.0), 500.0, 0.25), 200.0);
+/// ```
+pub fn ewma(previous: Option<f64>, sample: f64, alpha: f64) -> f64 {
+    match previous {
+        Some(previous) => alpha * sample + (1.0 - alpha) * previous,
+        None => sample,
+    }
+}
+
+/// Smoothed received throughput, see [`record_smoothed_throughput`].
+static SMOOTHED_RECEIVED: Lazy<Mutex<Option<f64>>> = Lazy::new(|| Mutex::new(None));
+
+/// Folds the received throughput of a new result into the smoothed gauge. Does nothing
+/// unless `METRICS_SMOOTHING_ALPHA` is set.
+pub fn record_smoothed_throughput(report: &Iperf3Report) {
+    if let Some(alpha) = metrics_smoothing_alpha() {
+        let mut smoothed = SMOOTHED_RECEIVED.lock().unwrap();
+        *smoothed = Some(ewma(*smoothed, report.end.sum_received.bits_per_second, alpha));
+    }
+}
+
+/// Returns the smoothed received throughput, if any result was recorded with smoothing enabled.
+pub fn smoothed_received_bits_per_second() -> Option<f64> {
+    *SMOOTHED_RECEIVED.lock().unwrap()
+}
+
+/// Resets the smoothed received throughput.
+pub fn clear_smoothed_throughput_for_test() {
+    *SMOOTHED_RECEIVED.lock().unwrap() = None;
+}
+
+/// Time the counters started counting, i.e. the process start.
+static PROCESS_START: Lazy<SystemTime> = Lazy::new(SystemTime::now);
+
+/// Number of measurement cycles run.
+static RUNS_TOTAL: AtomicU64 = AtomicU64::new(0);
+
+/// Number of measurement cycles that failed.
+static RUN_FAILURES_TOTAL: AtomicU64 = AtomicU64::new(0);
+
+/// Returns the process start time reported by the `_created` samples.
+///
+/// Call this early in `main` so the timestamp is taken at startup rather than on first use.
+pub fn process_start_time() -> SystemTime {
+    *PROCESS_START
+}
+
+/// Counts a finished measurement cycle in `iperf3_runs_total` and, unless it
+/// succeeded, `iperf3_run_failures_total`.
+pub fn record_run(success: bool) {
+    RUNS_TOTAL.fetch_add(1, Ordering::Relaxed);
+    if !success {
+        RUN_FAILURES_TOTAL.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Writes the `# HELP` and `# TYPE` header of a gauge.
+fn gauge_header(out: &mut String, name: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+}
+
+/// Renders the metrics derived from a report: its throughput, retransmits, host CPU and
+/// start time, followed by derived gauges.
+///
+/// With `METRICS_SMOOTHING_ALPHA` the smoothed received throughput of recent results
+/// follows the raw one, see [`record_smoothed_throughput`].
+///
+/// Floating-point values are formatted according to `METRICS_FLOAT_FORMAT`. Utilization
+/// gauges are only included when `LINK_CAPACITY_MBPS` is set. Per-stream
+/// series such as `iperf3_stream_retransmits` are only included when `METRICS_PER_STREAM`
+/// is enabled.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::{render_prometheus, Iperf3Report};
+/// let mut report = Iperf3Report::default();
+/// report.end.sum_sent.bits_per_second = 400_000_000.0;
+/// report.end.sum_received.bits_per_second = 100_000_000.0;
+/// assert!(render_prometheus(&report).contains("iperf3_asymmetry_ratio 0.25\n"));
+/// ```
+pub fn render_prometheus(report: &Iperf3Report) -> String {
+    let mut out = String::new();
+    let float_format = metrics_float_format();
+
+    for (name, help, value) in [
+        ("iperf3_sent_bits_per_second", "Sent throughput of the last test.", report.end.sum_sent.bits_per_second),
+        (
+            "iperf3_received_bits_per_second",
+            "Received throughput of the last test.",
+            report.end.sum_received.bits_per_second,
+        ),
+        ("iperf3_cpu_host_total", "Host CPU utilization of the last test in percent.", report.end.cpu_utilization_percent.host_total),
+    ] {
+        gauge_header(&mut out, name, help);
+        let _ = writeln!(out, "{} {}", name, format_metric_float(value, float_format));
+    }
+    if let Some(smoothed) = smoothed_received_bits_per_second().filter(|_| metrics_smoothing_alpha().is_some()) {
+        let name = "iperf3_received_bits_per_second_smoothed";
+        gauge_header(&mut out, name, "Received throughput averaged with weight METRICS_SMOOTHING_ALPHA per test.");
+        let _ = writeln!(out, "{} {}", name, format_metric_float(smoothed, float_format));
+    }
+    gauge_header(&mut out, "iperf3_retransmits", "Sender retransmits in the last test.");
+    let _ = writeln!(out, "iperf3_retransmits {}", report.end.sum_sent.retransmits);
+    gauge_header(&mut out, "iperf3_last_run_timestamp_seconds", "Unix time the last test started.");
+    let _ = writeln!(out, "iperf3_last_run_timestamp_seconds {}", report.start.timestamp.timesecs);
+
+    if let Some(ratio) = asymmetry_ratio(report) {
+        gauge_header(&mut out, "iperf3_asymmetry_ratio", "Received divided by sent throughput in the last test.");
+        let _ = writeln!(out, "iperf3_asymmetry_ratio {}", format_metric_float(ratio, float_format));
+    }
+
+    if let Some(capacity) = link_capacity_mbps() {
+        for (name, help, bits_per_second) in [
+            (
+                "iperf3_received_utilization_percent",
+                "Received throughput of the last test as a percentage of LINK_CAPACITY_MBPS.",
+                report.end.sum_received.bits_per_second,
+            ),
+            (
+                "iperf3_sent_utilization_percent",
+                "Sent throughput of the last test as a percentage of LINK_CAPACITY_MBPS.",
+                report.end.sum_sent.bits_per_second,
+            ),
+        ] {
+            gauge_header(&mut out, name, help);
+            let percent = format_metric_float(utilization_percent(bits_per_second, capacity), float_format);
+            let _ = writeln!(out, "{} {}", name, percent);
+        }
+    }
+
+    let streams = stream_retransmits(report);
+    if metrics_per_stream_enabled() && !streams.is_empty() {
+        gauge_header(&mut out, "iperf3_stream_retransmits", "Sender retransmits of each parallel stream in the last test.");
+        for stream in streams {
+            let _ = writeln!(out, "iperf3_stream_retransmits{{socket=\"{}\"}} {}", stream.socket, stream.retransmits);
+        }
+    }
+    out
+}
+
+/// Renders the `iperf3_up` gauge: 1 if a result is cached, 0 otherwise.
+pub fn render_up_metric(up: bool) -> String {
+    let mut out = String::new();
+    gauge_header(&mut out, "iperf3_up", "Whether a test result is cached.");
+    let _ = writeln!(out, "iperf3_up {}", u8::from(up));
+    out
+}
+
+/// Renders the metrics describing the scheduler, formatting floats per `METRICS_FLOAT_FORMAT`.
+pub fn render_status_metrics(status: &RunStatus) -> String {
+    let mut out = String::new();
+    if let Some(seconds) = status.actual_interval_seconds {
+        gauge_header(&mut out, "iperf3_actual_interval_seconds", "Time between the starts of the last two measurement cycles.");
+        let seconds = format_metric_float(seconds, metrics_float_format());
+        let _ = writeln!(out, "iperf3_actual_interval_seconds {}", seconds);
+    }
+    if let Some(percent) = status.slo_compliance_percent {
+        gauge_header(&mut out, "iperf3_slo_compliance_percent", "Percentage of runs within SLO_WINDOW_HOURS meeting the received SLA.");
+        let percent = format_metric_float(percent, metrics_float_format());
+        let _ = writeln!(out, "iperf3_slo_compliance_percent {}", percent);
+    }
+    out
+}
+
+/// Renders the run counters, each followed by a `_created` sample holding the process start
+/// time in Unix seconds unless `METRICS_CREATED` is disabled.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::render_counter_metrics;
+/// assert!(render_counter_metrics().contains("# TYPE iperf3_runs_total counter\n"));
+/// ```
+pub fn render_counter_metrics() -> String {
+    let mut out = String::new();
+    let created = process_start_time().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64();
+    let created = format_metric_float(created, metrics_float_format());
+    let counters = [
+        ("iperf3_runs", "Measurement cycles run.", &RUNS_TOTAL),
+        ("iperf3_run_failures", "Measurement cycles that failed.", &RUN_FAILURES_TOTAL),
+    ];
+    for (name, help, counter) in counters {
+        let _ = writeln!(out, "# HELP {}_total {}", name, help);
+        let _ = writeln!(out, "# TYPE {}_total counter", name);
+        let _ = writeln!(out, "{}_total {}", name, counter.load(Ordering::Relaxed));
+        if metrics_created_enabled() {
+            let _ = writeln!(out, "{}_created {}", name, created);
+        }
+    }
+    out
+}
+
+/// HTTP GET endpoint `/metrics` returns the metrics of the last cached result and the
+/// scheduler in the Prometheus text format.
+///
+/// Succeeds even if no result is cached yet, reporting `iperf3_up 0` without the result's
+/// metrics, as Prometheus would count an error status as a failed scrape.
+#[get("/metrics")]
+pub async fn iperf3_metrics() -> Result<HttpResponse, Iperf3Error> {
+    let report = get_last_result();
+    let status = RunStatus { slo_compliance_percent: current_slo_compliance(), ..get_run_status() };
+    let mut body = render_up_metric(report.is_some());
+    if let Some(report) = &report {
+        body += &render_prometheus(report);
+    }
+    body += &render_status_metrics(&status);
+    body += &render_counter_metrics();
+    Ok(HttpResponse::Ok().content_type(PROMETHEUS_CONTENT_TYPE).body(body))
+}
@@ -10,13 +10,88 @@
 // or the MIT license <http://opensource.org/licenses/MIT>, at your option.
 // This file may not be copied, modified, or distributed except according to those terms.
 
-use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+/// Deserializes an integer field from either a JSON integer or a float.
+///
+/// iperf3 versions disagree on how they print some counters and rates, e.g. `target_bitrate`
+/// or `bytes` as `1000000000` in one and `1e+09` or `1000000000.0` in another. Floats are
+/// rounded to the nearest integer; values out of range for the field are an error.
+fn integer_or_float<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: TryFrom<i128>,
+{
+    let number = serde_json::Number::deserialize(deserializer)?;
+    let value = if let Some(n) = number.as_i64() {
+        n as i128
+    } else if let Some(n) = number.as_u64() {
+        n as i128
+    } else {
+        let n = number.as_f64().unwrap_or(f64::NAN);
+        if !n.is_finite() {
+            return Err(serde::de::Error::custom(format!("invalid number {}", number)));
+        }
+        n.round() as i128
+    };
+    T::try_from(value).map_err(|_| serde::de::Error::custom(format!("number {} out of range", number)))
+}
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct Iperf3Report {
     pub start: Start,
     pub intervals: Vec<Interval>,
     pub end: End,
+    /// Top-level fields of iperf3's output not modelled above, e.g. `title` or
+    /// `server_output_json`, passed through as is.
+    ///
+    /// Kept sorted by key so serializing the same report always yields the same bytes.
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, Value>,
+}
+
+impl Iperf3Report {
+    /// Combines a normal (upload) run `up` and a reverse (download) run `down` into one view.
+    ///
+    /// `sum_sent` and the sender congestion control come from `up`, `sum_received` and the
+    /// receiver congestion control from `down`. `start`, the CPU utilization and any extra
+    /// fields come from the more recent of the two runs, so the timestamp reflects the
+    /// freshest data. Intervals and per-stream figures of separate runs do not line up, so
+    /// they are left empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use iperf3_statuspage::Iperf3Report;
+    /// let mut up = Iperf3Report::default();
+    /// up.end.sum_sent.bits_per_second = 40_000_000.0;
+    /// let mut down = Iperf3Report::default();
+    /// down.end.sum_received.bits_per_second = 940_000_000.0;
+    ///
+    /// let merged = Iperf3Report::merge_directions(&up, &down);
+    /// assert_eq!(merged.end.sum_sent.bits_per_second, 40_000_000.0);
+    /// assert_eq!(merged.end.sum_received.bits_per_second, 940_000_000.0);
+    /// ```
+    pub fn merge_directions(up: &Iperf3Report, down: &Iperf3Report) -> Iperf3Report {
+        let latest = if down.start.timestamp.timesecs > up.start.timestamp.timesecs { down } else { up };
+        Iperf3Report {
+            start: latest.start.clone(),
+            intervals: Vec::new(),
+            end: End {
+                streams: Vec::new(),
+                sum_sent: up.end.sum_sent.clone(),
+                sum_received: down.end.sum_received.clone(),
+                cpu_utilization_percent: latest.end.cpu_utilization_percent.clone(),
+                sum_sent_bidir_reverse: None,
+                sum_received_bidir_reverse: None,
+                sender_tcp_congestion: up.end.sender_tcp_congestion.clone(),
+                receiver_tcp_congestion: down.end.receiver_tcp_congestion.clone(),
+            },
+            extra: latest.extra.clone(),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
@@ -26,12 +101,19 @@ pub struct Start {
     pub system_info: String,
     pub timestamp: Timestamp,
     pub connecting_to: ConnectingTo,
+    #[serde(default)]
     pub cookie: String,
+    #[serde(default)]
     pub tcp_mss_default: u32,
+    #[serde(default, deserialize_with = "integer_or_float")]
     pub target_bitrate: u64,
+    #[serde(default, deserialize_with = "integer_or_float")]
     pub fq_rate: u64,
+    #[serde(default, deserialize_with = "integer_or_float")]
     pub sock_bufsize: u64,
+    #[serde(default, deserialize_with = "integer_or_float")]
     pub sndbuf_actual: u64,
+    #[serde(default, deserialize_with = "integer_or_float")]
     pub rcvbuf_actual: u64,
     pub test_start: TestStart,
 }
@@ -48,6 +130,7 @@ pub struct Connected {
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct Timestamp {
     pub time: String,
+    #[serde(deserialize_with = "integer_or_float")]
     pub timesecs: u64,
 }
 
@@ -61,15 +144,24 @@ pub struct ConnectingTo {
 pub struct TestStart {
     pub protocol: String,
     pub num_streams: u32,
+    #[serde(deserialize_with = "integer_or_float")]
     pub blksize: u64,
+    #[serde(deserialize_with = "integer_or_float")]
     pub omit: u64,
+    #[serde(deserialize_with = "integer_or_float")]
     pub duration: u64,
+    #[serde(deserialize_with = "integer_or_float")]
     pub bytes: u64,
+    #[serde(deserialize_with = "integer_or_float")]
     pub blocks: u64,
     pub reverse: u32,
+    #[serde(default)]
     pub tos: u32,
+    #[serde(default, deserialize_with = "integer_or_float")]
     pub target_bitrate: u64,
+    #[serde(default)]
     pub bidir: u32,
+    #[serde(default, deserialize_with = "integer_or_float")]
     pub fqrate: u64,
 }
 
@@ -85,15 +177,26 @@ pub struct Stream {
     pub start: f64,
     pub end: f64,
     pub seconds: f64,
+    #[serde(deserialize_with = "integer_or_float")]
     pub bytes: u64,
     pub bits_per_second: f64,
+    #[serde(default)]
     pub retransmits: u32,
+    #[serde(default, deserialize_with = "integer_or_float")]
     pub snd_cwnd: u64,
+    #[serde(default, deserialize_with = "integer_or_float")]
     pub snd_wnd: i64,
+    #[serde(default)]
     pub rtt: u32,
+    #[serde(default)]
     pub rttvar: u32,
+    #[serde(default)]
     pub pmtu: u32,
+    /// UDP only: datagrams sent or received.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub packets: Option<u64>,
     pub omitted: bool,
+    #[serde(default)]
     pub sender: bool,
 }
 
@@ -102,11 +205,26 @@ pub struct Sum {
     pub start: f64,
     pub end: f64,
     pub seconds: f64,
+    #[serde(deserialize_with = "integer_or_float")]
     pub bytes: u64,
     pub bits_per_second: f64,
+    #[serde(default)]
     pub retransmits: u32,
     pub omitted: bool,
+    #[serde(default)]
     pub sender: bool,
+    /// UDP only: jitter in milliseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jitter_ms: Option<f64>,
+    /// UDP only: datagrams lost.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lost_packets: Option<u64>,
+    /// UDP only: datagrams sent or received.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub packets: Option<u64>,
+    /// UDP only: percentage of datagrams lost.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lost_percent: Option<f64>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
@@ -115,13 +233,24 @@ pub struct End {
     pub sum_sent: SumSent,
     pub sum_received: SumReceived,
     pub cpu_utilization_percent: CpuUtilizationPercent,
+    /// Bidirectional (`--bidir`) tests only: what the server sent in the reverse direction.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sum_sent_bidir_reverse: Option<SumSent>,
+    /// Bidirectional (`--bidir`) tests only: what the client received in the reverse direction.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sum_received_bidir_reverse: Option<SumReceived>,
+    #[serde(default)]
     pub sender_tcp_congestion: String,
+    #[serde(default)]
     pub receiver_tcp_congestion: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct EndStream {
+    /// TCP only; UDP reports describe each stream under `udp` instead.
+    #[serde(default)]
     pub sender: Sender,
+    #[serde(default)]
     pub receiver: Receiver,
 }
 
@@ -131,14 +260,22 @@ pub struct Sender {
     pub start: f64,
     pub end: f64,
     pub seconds: f64,
+    #[serde(deserialize_with = "integer_or_float")]
     pub bytes: u64,
     pub bits_per_second: f64,
+    #[serde(default)]
     pub retransmits: u32,
+    #[serde(default, deserialize_with = "integer_or_float")]
     pub max_snd_cwnd: u64,
+    #[serde(default, deserialize_with = "integer_or_float")]
     pub max_snd_wnd: u64,
+    #[serde(default)]
     pub max_rtt: u32,
+    #[serde(default)]
     pub min_rtt: u32,
+    #[serde(default)]
     pub mean_rtt: u32,
+    #[serde(default)]
     pub sender: bool,
 }
 
@@ -148,8 +285,10 @@ pub struct Receiver {
     pub start: f64,
     pub end: f64,
     pub seconds: f64,
+    #[serde(deserialize_with = "integer_or_float")]
     pub bytes: u64,
     pub bits_per_second: f64,
+    #[serde(default)]
     pub sender: bool,
 }
 
@@ -158,10 +297,25 @@ pub struct SumSent {
     pub start: f64,
     pub end: f64,
     pub seconds: f64,
+    #[serde(deserialize_with = "integer_or_float")]
     pub bytes: u64,
     pub bits_per_second: f64,
+    #[serde(default)]
     pub retransmits: u32,
+    #[serde(default)]
     pub sender: bool,
+    /// UDP only: jitter in milliseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jitter_ms: Option<f64>,
+    /// UDP only: datagrams lost.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lost_packets: Option<u64>,
+    /// UDP only: datagrams sent or received.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub packets: Option<u64>,
+    /// UDP only: percentage of datagrams lost.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lost_percent: Option<f64>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
@@ -169,9 +323,23 @@ pub struct SumReceived {
     pub start: f64,
     pub end: f64,
     pub seconds: f64,
+    #[serde(deserialize_with = "integer_or_float")]
     pub bytes: u64,
     pub bits_per_second: f64,
+    #[serde(default)]
     pub sender: bool,
+    /// UDP only: jitter in milliseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jitter_ms: Option<f64>,
+    /// UDP only: datagrams lost.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lost_packets: Option<u64>,
+    /// UDP only: datagrams sent or received.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub packets: Option<u64>,
+    /// UDP only: percentage of datagrams lost.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lost_percent: Option<f64>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
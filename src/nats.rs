@@ -0,0 +1,101 @@
+//! # iperf3-statuspage
+//!
+//! NATS publisher for successful results, enabled by the `nats` feature.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+use std::time::Duration;
+use async_trait::async_trait;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use crate::publish::ResultPublisher;
+
+/// Default NATS client port.
+const DEFAULT_NATS_PORT: u16 = 4222;
+
+/// Publishes results to a NATS subject using the NATS text protocol.
+///
+/// A connection is opened per publish; with measurements minutes apart this keeps
+/// the publisher stateless and resilient to server restarts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NatsPublisher {
+    /// `host:port` of the NATS server.
+    pub address: String,
+    pub subject: String,
+    pub timeout: Duration,
+}
+
+impl NatsPublisher {
+    /// Creates a publisher from a `nats://host[:port]` URL and a subject.
+    pub fn new(url: &str, subject: impl Into<String>) -> Self {
+        let address = url.trim_start_matches("nats://").trim_end_matches('/');
+        let address = if address.contains(':') {
+            address.to_string()
+        } else {
+            format!("{}:{}", address, DEFAULT_NATS_PORT)
+        };
+        NatsPublisher {
+            address,
+            subject: subject.into(),
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Reads `NATS_URL` and `NATS_SUBJECT` (default `iperf3.results`).
+    ///
+    /// Returns `None` if `NATS_URL` is unset.
+    pub fn from_env() -> Option<Self> {
+        let url = env::var("NATS_URL").ok()?;
+        let subject = env::var("NATS_SUBJECT").unwrap_or_else(|_| "iperf3.results".to_string());
+        Some(NatsPublisher::new(&url, subject))
+    }
+
+    async fn publish_once(&self, report_json: &str) -> Result<(), String> {
+        let stream = TcpStream::connect(&self.address).await.map_err(|e| e.to_string())?;
+        let (read, mut write) = stream.into_split();
+        let mut lines = BufReader::new(read).lines();
+
+        match lines.next_line().await.map_err(|e| e.to_string())? {
+            Some(info) if info.starts_with("INFO") => {}
+            other => return Err(format!("unexpected greeting from NATS: {:?}", other)),
+        }
+
+        let message = format!(
+            "CONNECT {{\"verbose\":false,\"pedantic\":false,\"name\":\"iperf3_statuspage\"}}\r\n\
+             PUB {} {}\r\n{}\r\nPING\r\n",
+            self.subject,
+            report_json.len(),
+            report_json
+        );
+        write.write_all(message.as_bytes()).await.map_err(|e| e.to_string())?;
+
+        // The server answers the PING only after processing the PUB, so PONG confirms delivery.
+        while let Some(line) = lines.next_line().await.map_err(|e| e.to_string())? {
+            if line.starts_with("PONG") {
+                return Ok(());
+            }
+            if line.starts_with("-ERR") {
+                return Err(line);
+            }
+        }
+        Err("NATS connection closed before PONG".to_string())
+    }
+}
+
+#[async_trait]
+impl ResultPublisher for NatsPublisher {
+    async fn publish(&self, report_json: &str) -> Result<(), String> {
+        timeout(self.timeout, self.publish_once(report_json))
+            .await
+            .map_err(|_| format!("timed out publishing to NATS at {}", self.address))?
+            .map_err(|e| format!("NATS {}: {}", self.address, e))
+    }
+}
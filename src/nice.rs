@@ -0,0 +1,48 @@
+//! # iperf3-statuspage
+//!
+//! Scheduling priority (`IPERF3_NICE`) of the spawned iperf3 child.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+#[cfg(unix)]
+use std::io;
+#[cfg(unix)]
+use tokio::process::Command;
+
+/// Reads the environment variable `IPERF3_NICE`, the niceness (`-20` to `19`) to run
+/// iperf3 at. Returns `None` when unset or out of range, in which case the child inherits
+/// this process's priority.
+pub fn iperf3_nice() -> Option<i32> {
+    env::var("IPERF3_NICE")
+        .ok()
+        .and_then(|s| s.trim().parse::<i32>().ok())
+        .filter(|n| (-20..=19).contains(n))
+}
+
+/// Installs a `pre_exec` hook on `command` setting the child's niceness to `nice`.
+///
+/// Raising the priority (a niceness below the current one) needs `CAP_SYS_NICE`; a failure
+/// aborts the spawn with the OS error.
+#[cfg(unix)]
+pub fn apply_nice(command: &mut Command, nice: i32) {
+    // SAFETY: the hook only calls the async-signal-safe `setpriority` and does not allocate.
+    unsafe {
+        command.pre_exec(move || set_own_priority(nice));
+    }
+}
+
+#[cfg(unix)]
+fn set_own_priority(nice: i32) -> io::Result<()> {
+    // SAFETY: plain syscall on the calling process (`who` 0) with valid arguments.
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS as _, 0, nice) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
@@ -0,0 +1,68 @@
+//! # iperf3-statuspage
+//!
+//! On-demand runs triggered with `POST /iperf3/run`, e.g. by a dashboard's "refresh now"
+//! button.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+use actix_web::{post, HttpRequest, HttpResponse};
+use crate::auth::RequireToken;
+use crate::command::{is_unix_socket_path, Iperf3Options};
+use crate::errors::Iperf3Error;
+use crate::models::Iperf3Report;
+use crate::redact::public_json;
+use crate::targets::{server_list, target_lock, Target};
+use crate::{run_iperf3_and_cache_with_options, Iperf3Runner, RealIperf3Runner};
+
+/// Reads the iperf3 server from `IPERF3_SERVER_IP` and `IPERF3_SERVER_PORT`, as `main`
+/// does; the port may be omitted for a socket path. When several servers are listed the
+/// primary (first) one is returned.
+pub fn server_from_env() -> Result<(String, String), Iperf3Error> {
+    let ip = env::var("IPERF3_SERVER_IP")
+        .map_err(|_| Iperf3Error::InvalidConfig("IPERF3_SERVER_IP must be set".to_string()))?;
+    let port = match env::var("IPERF3_SERVER_PORT") {
+        Ok(port) => port,
+        Err(_) if is_unix_socket_path(&ip) => String::new(),
+        Err(_) => return Err(Iperf3Error::InvalidConfig("IPERF3_SERVER_PORT must be set".to_string())),
+    };
+    let mut servers = server_list(&ip, &port).map_err(Iperf3Error::InvalidConfig)?;
+    Ok(servers.swap_remove(0))
+}
+
+/// Runs one test with `opts` and caches its result, unless a run against the same target
+/// is already in flight.
+///
+/// Takes the target's lock (see [`target_lock`]) without waiting, so neither a concurrent
+/// trigger nor a scheduled run is ever overlapped: both yield [`Iperf3Error::Conflict`].
+/// A failed run is returned as [`Iperf3Error::Internal`] carrying the error message.
+pub async fn run_on_demand_with_runner(runner: &dyn Iperf3Runner, opts: &Iperf3Options) -> Result<Iperf3Report, Iperf3Error> {
+    let lock = target_lock(&Target::with_options(opts.clone()));
+    let Ok(_guard) = lock.try_lock() else {
+        return Err(Iperf3Error::Conflict("An iperf3 run is already in progress.".to_string()));
+    };
+    run_iperf3_and_cache_with_options(runner, opts)
+        .await
+        .map_err(|e| Iperf3Error::Internal(e.to_string()))
+}
+
+/// HTTP POST endpoint `/iperf3/run` runs a test against the configured server right away
+/// and returns the freshly cached report as JSON.
+///
+/// Returns HTTP 409 Conflict while a run against the server is already in flight and
+/// HTTP 500 Internal Server Error with the error message if the run fails.
+///
+/// When `API_TOKEN` is set, requests without `Authorization: Bearer <API_TOKEN>` get
+/// HTTP 401 Unauthorized, see [`RequireToken`].
+#[post("/iperf3/run")]
+pub async fn iperf3_run(req: HttpRequest, _: RequireToken) -> Result<HttpResponse, Iperf3Error> {
+    let (ip, port) = server_from_env()?;
+    let report = run_on_demand_with_runner(&RealIperf3Runner, &Iperf3Options::from_env(ip, port)).await?;
+    Ok(HttpResponse::Ok().json(public_json(&req, &report)?))
+}
@@ -0,0 +1,141 @@
+//! # iperf3-statuspage
+//!
+//! One-shot mode: run a single measurement, write it out and exit.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{error, info, warn};
+use crate::checksum::format_checksum;
+use crate::{restore_last_result, run_iperf3_and_cache_with_runner, Iperf3Report, Iperf3Runner};
+
+/// Reads the environment variable `ONE_SHOT`.
+///
+/// When `true` (or `1`) the binary runs a single test and exits instead of serving HTTP.
+pub fn one_shot_enabled() -> bool {
+    env::var("ONE_SHOT")
+        .map(|s| matches!(s.trim().to_ascii_lowercase().as_str(), "true" | "1"))
+        .unwrap_or(false)
+}
+
+/// Reads the environment variable `STATE_FILE`, the path results are written to.
+pub fn state_file_path() -> Option<PathBuf> {
+    env::var("STATE_FILE")
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+        .map(PathBuf::from)
+}
+
+/// Returns the path of the checksum file kept next to the state file at `path`.
+pub fn checksum_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".crc32");
+    PathBuf::from(name)
+}
+
+/// Writes `contents` to `path` through a temporary sibling file renamed into place.
+pub(crate) fn write_atomically(path: &Path, contents: &[u8]) -> Result<(), String> {
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_name);
+    fs::write(&tmp_path, contents).map_err(|e| format!("Failed to write {}: {}", tmp_path.display(), e))?;
+    fs::rename(&tmp_path, path).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Writes `report` as JSON to `path`, with its checksum (see [`format_checksum`]) in the
+/// file at [`checksum_path`].
+///
+/// Each file is written to a temporary sibling first and renamed into place, so readers
+/// never observe a partially written state file.
+pub fn write_state_file(path: &Path, report: &Iperf3Report) -> Result<(), String> {
+    let json = serde_json::to_string(report).map_err(|e| format!("Failed to serialize iperf3 result: {}", e))?;
+    write_atomically(path, json.as_bytes())?;
+    write_atomically(&checksum_path(path), format_checksum(json.as_bytes()).as_bytes())
+}
+
+/// Reads a report from a JSON file, as written by iperf3 or [`write_state_file`].
+///
+/// When a checksum file exists the contents must match it; files without one (e.g. raw
+/// iperf3 output) are read unverified.
+pub fn read_state_file(path: &Path) -> Result<Iperf3Report, String> {
+    let contents = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    match fs::read_to_string(checksum_path(path)) {
+        Ok(expected) if expected.trim() != format_checksum(&contents) => {
+            return Err(format!("Checksum mismatch for {}", path.display()));
+        }
+        _ => {}
+    }
+    serde_json::from_slice(&contents).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+/// Restores the cached result from `STATE_FILE`, if set and present.
+///
+/// A corrupt or unreadable file is logged as a warning and leaves the cache empty.
+pub fn load_state_from_env() {
+    let Some(path) = state_file_path().filter(|path| path.exists()) else {
+        return;
+    };
+    match read_state_file(&path) {
+        Ok(report) => {
+            info!("Restored iperf3 result of {} from {}", report.start.timestamp.time, path.display());
+            restore_last_result(report);
+        }
+        Err(e) => warn!("{}; starting without a cached result", e),
+    }
+}
+
+/// Runs a single iperf3 test using the provided runner and returns the process exit code.
+///
+/// The server is probed first (see [`Iperf3Runner::probe`]) so an unreachable server fails
+/// fast without waiting for iperf3 to time out.
+///
+/// On success the report is printed as JSON to `out` and, if `state_file` is given,
+/// written to that path; the exit code is `0`. Any failure, including failing to write
+/// the state file, is logged to stderr and yields exit code `1`.
+pub async fn run_one_shot_with_runner(
+    runner: &dyn Iperf3Runner,
+    iperf3_ip: String,
+    iperf3_port: String,
+    state_file: Option<&Path>,
+    out: &mut dyn Write,
+) -> i32 {
+    if let Err(e) = runner.probe(iperf3_ip.clone(), iperf3_port.clone()).await {
+        error!("{}", e);
+        return 1;
+    }
+
+    let report = match run_iperf3_and_cache_with_runner(runner, iperf3_ip, iperf3_port).await {
+        Ok(report) => report,
+        Err(_) => return 1,
+    };
+
+    if let Some(path) = state_file
+        && let Err(e) = write_state_file(path, &report)
+    {
+        error!("{}", e);
+        return 1;
+    }
+
+    match serde_json::to_string(&report) {
+        Ok(json) => match writeln!(out, "{}", json) {
+            Ok(()) => 0,
+            Err(e) => {
+                error!(error = %e, "Failed to write iperf3 result");
+                1
+            }
+        },
+        Err(e) => {
+            error!(error = %e, "Failed to serialize iperf3 result");
+            1
+        }
+    }
+}
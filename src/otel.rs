@@ -0,0 +1,287 @@
+//! # iperf3-statuspage
+//!
+//! OpenTelemetry span export for measurement cycles, enabled by the `otel` feature.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::collections::hash_map::RandomState;
+use std::env;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tracing::warn;
+use crate::command::Iperf3Options;
+use crate::errors::Iperf3Error;
+use crate::models::Iperf3Report;
+use crate::timing::CycleTiming;
+
+/// Name reported as `service.name` and instrumentation scope.
+const SERVICE_NAME: &str = "iperf3_statuspage";
+
+/// A finished span ready for export.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpanData {
+    /// 32 lowercase hex digits.
+    pub trace_id: String,
+    /// 16 lowercase hex digits.
+    pub span_id: String,
+    pub parent_span_id: Option<String>,
+    pub name: String,
+    pub start_time_unix_nano: u64,
+    pub end_time_unix_nano: u64,
+    pub attributes: Vec<(String, Value)>,
+    /// `None` if the span succeeded, otherwise the error message.
+    pub error: Option<String>,
+}
+
+impl SpanData {
+    /// Returns the value of the attribute `key`, if set.
+    pub fn attribute(&self, key: &str) -> Option<&Value> {
+        self.attributes.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+}
+
+/// Destination for finished spans.
+#[async_trait]
+pub trait SpanExporter: Send + Sync {
+    /// Exports one cycle's spans.
+    async fn export(&self, spans: &[SpanData]) -> Result<(), String>;
+}
+
+/// Exporter keeping spans in memory, for tests and debugging.
+#[derive(Default)]
+pub struct InMemoryExporter {
+    spans: Mutex<Vec<SpanData>>,
+}
+
+impl InMemoryExporter {
+    /// Returns all spans exported so far.
+    pub fn finished_spans(&self) -> Vec<SpanData> {
+        self.spans.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl SpanExporter for InMemoryExporter {
+    async fn export(&self, spans: &[SpanData]) -> Result<(), String> {
+        self.spans.lock().unwrap().extend_from_slice(spans);
+        Ok(())
+    }
+}
+
+/// Exporter posting spans to an OTLP/HTTP collector using the JSON encoding.
+///
+/// Only plain `http://` endpoints are supported.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtlpHttpExporter {
+    /// `host:port` of the collector.
+    pub address: String,
+    pub timeout: Duration,
+}
+
+impl OtlpHttpExporter {
+    /// Creates an exporter for an `http://host[:port]` endpoint.
+    pub fn new(endpoint: &str) -> Result<Self, String> {
+        let Some(rest) = endpoint.strip_prefix("http://") else {
+            return Err(format!("Only http:// OTLP endpoints are supported, got {:?}", endpoint));
+        };
+        let authority = rest.split('/').next().unwrap_or_default();
+        if authority.is_empty() {
+            return Err(format!("Missing host in OTLP endpoint {:?}", endpoint));
+        }
+        let address = if authority.contains(':') {
+            authority.to_string()
+        } else {
+            format!("{}:80", authority)
+        };
+        Ok(OtlpHttpExporter { address, timeout: Duration::from_secs(5) })
+    }
+
+    /// Reads `OTEL_EXPORTER_OTLP_ENDPOINT`.
+    ///
+    /// Returns `None` (logging why) if it is unset or unsupported.
+    pub fn from_env() -> Option<Self> {
+        let endpoint = env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok()?;
+        OtlpHttpExporter::new(&endpoint)
+            .map_err(|e| warn!("{}; OpenTelemetry export disabled", e))
+            .ok()
+    }
+
+    async fn post(&self, body: &str) -> Result<(), String> {
+        let mut stream = TcpStream::connect(&self.address).await.map_err(|e| e.to_string())?;
+        let request = format!(
+            "POST /v1/traces HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.address,
+            body.len(),
+            body
+        );
+        stream.write_all(request.as_bytes()).await.map_err(|e| e.to_string())?;
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.map_err(|e| e.to_string())?;
+        let response = String::from_utf8_lossy(&response);
+        let status_line = response.lines().next().unwrap_or_default();
+        match status_line.split(' ').nth(1) {
+            Some(code) if code.starts_with('2') => Ok(()),
+            _ => Err(format!("collector responded {:?}", status_line)),
+        }
+    }
+}
+
+#[async_trait]
+impl SpanExporter for OtlpHttpExporter {
+    async fn export(&self, spans: &[SpanData]) -> Result<(), String> {
+        let body = otlp_json(spans).to_string();
+        timeout(self.timeout, self.post(&body))
+            .await
+            .map_err(|_| format!("timed out exporting spans to {}", self.address))?
+            .map_err(|e| format!("OTLP {}: {}", self.address, e))
+    }
+}
+
+/// Encodes spans as an OTLP `ExportTraceServiceRequest` in the JSON encoding.
+pub fn otlp_json(spans: &[SpanData]) -> Value {
+    let spans: Vec<Value> = spans
+        .iter()
+        .map(|span| {
+            let mut encoded = json!({
+                "traceId": span.trace_id,
+                "spanId": span.span_id,
+                "name": span.name,
+                "kind": 1,
+                "startTimeUnixNano": span.start_time_unix_nano.to_string(),
+                "endTimeUnixNano": span.end_time_unix_nano.to_string(),
+                "attributes": span.attributes.iter().map(|(k, v)| otlp_attribute(k, v)).collect::<Vec<_>>(),
+                "status": match &span.error {
+                    None => json!({"code": 1}),
+                    Some(message) => json!({"code": 2, "message": message}),
+                },
+            });
+            if let Some(parent) = &span.parent_span_id {
+                encoded["parentSpanId"] = json!(parent);
+            }
+            encoded
+        })
+        .collect();
+
+    json!({
+        "resourceSpans": [{
+            "resource": {"attributes": [otlp_attribute("service.name", &json!(SERVICE_NAME))]},
+            "scopeSpans": [{"scope": {"name": SERVICE_NAME}, "spans": spans}],
+        }]
+    })
+}
+
+fn otlp_attribute(key: &str, value: &Value) -> Value {
+    let value = match value {
+        Value::Bool(b) => json!({"boolValue": b}),
+        Value::Number(n) if n.is_i64() || n.is_u64() => json!({"intValue": n.to_string()}),
+        Value::Number(n) => json!({"doubleValue": n.as_f64()}),
+        Value::String(s) => json!({"stringValue": s}),
+        other => json!({"stringValue": other.to_string()}),
+    };
+    json!({"key": key, "value": value})
+}
+
+/// Exporter invoked after every measurement cycle, if any.
+static SPAN_EXPORTER: Lazy<Mutex<Option<Arc<dyn SpanExporter>>>> = Lazy::new(|| Mutex::new(None));
+
+/// Installs (or with `None` removes) the exporter invoked after every measurement cycle.
+pub fn set_span_exporter(exporter: Option<Arc<dyn SpanExporter>>) {
+    *SPAN_EXPORTER.lock().unwrap() = exporter;
+}
+
+/// Returns `digits` random lowercase hex digits (a multiple of 16).
+fn random_hex(digits: usize) -> String {
+    (0..digits / 16)
+        .map(|_| {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u128(SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos());
+            format!("{:016x}", hasher.finish())
+        })
+        .collect()
+}
+
+/// Builds the spans of one cycle: an `iperf3_cycle` root carrying the target, throughput
+/// and outcome attributes, with one child per timed phase.
+///
+/// Phases are laid out back to back from `started`, mirroring the `tracing` spans the
+/// cycle is instrumented with.
+pub fn cycle_spans(
+    opts: &Iperf3Options,
+    started: SystemTime,
+    timing: &CycleTiming,
+    result: &Result<Iperf3Report, Iperf3Error>,
+) -> Vec<SpanData> {
+    let start_nanos = started.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos() as u64;
+    let trace_id = random_hex(32);
+    let root_id = random_hex(16);
+
+    let mut attributes = vec![
+        ("iperf3.target.host".to_string(), json!(opts.host)),
+        ("iperf3.target.port".to_string(), json!(opts.port)),
+        ("iperf3.outcome".to_string(), json!(if result.is_ok() { "success" } else { "error" })),
+    ];
+    if let Ok(report) = result {
+        attributes.push(("iperf3.sent_bps".to_string(), json!(report.end.sum_sent.bits_per_second)));
+        attributes.push(("iperf3.received_bps".to_string(), json!(report.end.sum_received.bits_per_second)));
+    }
+
+    let mut spans = vec![SpanData {
+        trace_id: trace_id.clone(),
+        span_id: root_id.clone(),
+        parent_span_id: None,
+        name: "iperf3_cycle".to_string(),
+        start_time_unix_nano: start_nanos,
+        end_time_unix_nano: start_nanos + (timing.total_millis * 1_000_000.0) as u64,
+        attributes,
+        error: result.as_ref().err().map(|e| e.to_string()),
+    }];
+
+    let mut phase_start = start_nanos;
+    for phase in &timing.phases {
+        let phase_end = phase_start + (phase.millis * 1_000_000.0) as u64;
+        spans.push(SpanData {
+            trace_id: trace_id.clone(),
+            span_id: random_hex(16),
+            parent_span_id: Some(root_id.clone()),
+            name: phase.phase.clone(),
+            start_time_unix_nano: phase_start,
+            end_time_unix_nano: phase_end,
+            attributes: vec![("iperf3.attempt".to_string(), json!(phase.attempt))],
+            error: None,
+        });
+        phase_start = phase_end;
+    }
+    spans
+}
+
+/// Exports the spans of one cycle through the installed exporter.
+///
+/// Failures are logged to stderr and otherwise ignored.
+pub async fn export_cycle(
+    opts: &Iperf3Options,
+    started: SystemTime,
+    timing: &CycleTiming,
+    result: &Result<Iperf3Report, Iperf3Error>,
+) {
+    let Some(exporter) = SPAN_EXPORTER.lock().unwrap().clone() else {
+        return;
+    };
+    if let Err(e) = exporter.export(&cycle_spans(opts, started, timing, result)).await {
+        warn!(error = %e, "Failed to export spans");
+    }
+}
@@ -0,0 +1,250 @@
+//! # iperf3-statuspage
+//!
+//! Parquet export of the result history, enabled by the `parquet` feature.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use actix_web::{get, HttpResponse};
+use crate::errors::Iperf3Error;
+use crate::history::get_history;
+use crate::maintenance::ensure_not_in_maintenance;
+use crate::models::Iperf3Report;
+
+/// Content type of Parquet files.
+pub const PARQUET_CONTENT_TYPE: &str = "application/vnd.apache.parquet";
+
+/// Magic bytes starting and ending every Parquet file.
+const MAGIC: &[u8; 4] = b"PAR1";
+
+/// Writer identification stored in the file metadata.
+const CREATED_BY: &str = concat!("iperf3_statuspage ", env!("CARGO_PKG_VERSION"));
+
+/// Physical column types used by the export.
+#[derive(Clone, Copy)]
+enum PhysicalType {
+    Int64 = 2,
+    Double = 5,
+}
+
+/// Values of one required column.
+enum Column {
+    Int64(Vec<i64>),
+    Double(Vec<f64>),
+}
+
+impl Column {
+    fn physical_type(&self) -> PhysicalType {
+        match self {
+            Column::Int64(_) => PhysicalType::Int64,
+            Column::Double(_) => PhysicalType::Double,
+        }
+    }
+
+    /// PLAIN encoding: little-endian fixed-width values back to back.
+    fn plain_encoded(&self) -> Vec<u8> {
+        match self {
+            Column::Int64(values) => values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            Column::Double(values) => values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+        }
+    }
+}
+
+/// Minimal Thrift compact protocol writer, enough for Parquet's page headers and footer.
+#[derive(Default)]
+struct CompactWriter {
+    buf: Vec<u8>,
+    last_field: Vec<i16>,
+}
+
+const T_I32: u8 = 5;
+const T_I64: u8 = 6;
+const T_BINARY: u8 = 8;
+const T_LIST: u8 = 9;
+const T_STRUCT: u8 = 12;
+
+impl CompactWriter {
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.buf.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        self.buf.push(value as u8);
+    }
+
+    fn zigzag(&mut self, value: i64) {
+        self.varint(((value << 1) ^ (value >> 63)) as u64);
+    }
+
+    fn begin_struct(&mut self) {
+        self.last_field.push(0);
+    }
+
+    fn end_struct(&mut self) {
+        self.buf.push(0);
+        self.last_field.pop();
+    }
+
+    fn field(&mut self, id: i16, kind: u8) {
+        let last = self.last_field.last_mut().expect("field outside of a struct");
+        let delta = id - std::mem::replace(last, id);
+        if (1..=15).contains(&delta) {
+            self.buf.push(((delta as u8) << 4) | kind);
+        } else {
+            self.buf.push(kind);
+            self.zigzag(id as i64);
+        }
+    }
+
+    fn i32_field(&mut self, id: i16, value: i32) {
+        self.field(id, T_I32);
+        self.zigzag(value as i64);
+    }
+
+    fn i64_field(&mut self, id: i16, value: i64) {
+        self.field(id, T_I64);
+        self.zigzag(value);
+    }
+
+    fn string_field(&mut self, id: i16, value: &str) {
+        self.field(id, T_BINARY);
+        self.varint(value.len() as u64);
+        self.buf.extend_from_slice(value.as_bytes());
+    }
+
+    fn list_header(&mut self, id: i16, len: usize, kind: u8) {
+        self.field(id, T_LIST);
+        if len < 15 {
+            self.buf.push(((len as u8) << 4) | kind);
+        } else {
+            self.buf.push(0xf0 | kind);
+            self.varint(len as u64);
+        }
+    }
+
+    fn struct_field(&mut self, id: i16) {
+        self.field(id, T_STRUCT);
+        self.begin_struct();
+    }
+}
+
+/// Encodes named required columns of equal length as an uncompressed Parquet file with a
+/// single row group (none when empty).
+fn write_parquet(columns: &[(&str, Column)], num_rows: usize) -> Vec<u8> {
+    let mut out = MAGIC.to_vec();
+    let mut chunks = Vec::new();
+
+    // Readers expect no row group rather than an empty one
+    if num_rows > 0 {
+        for (_, column) in columns {
+            let data = column.plain_encoded();
+            let mut header = CompactWriter::default();
+            header.begin_struct();
+            header.i32_field(1, 0); // DATA_PAGE
+            header.i32_field(2, data.len() as i32);
+            header.i32_field(3, data.len() as i32);
+            header.struct_field(5); // DataPageHeader
+            header.i32_field(1, num_rows as i32);
+            header.i32_field(2, 0); // PLAIN
+            header.i32_field(3, 3); // RLE definition levels (none for required columns)
+            header.i32_field(4, 3); // RLE repetition levels
+            header.end_struct();
+            header.end_struct();
+
+            let offset = out.len() as i64;
+            let size = (header.buf.len() + data.len()) as i64;
+            out.extend_from_slice(&header.buf);
+            out.extend_from_slice(&data);
+            chunks.push((offset, size));
+        }
+    }
+
+    let mut meta = CompactWriter::default();
+    meta.begin_struct();
+    meta.i32_field(1, 1); // version
+    meta.list_header(2, columns.len() + 1, T_STRUCT);
+    meta.begin_struct(); // root schema element
+    meta.string_field(4, "schema");
+    meta.i32_field(5, columns.len() as i32);
+    meta.end_struct();
+    for (name, column) in columns {
+        meta.begin_struct();
+        meta.i32_field(1, column.physical_type() as i32);
+        meta.i32_field(3, 0); // REQUIRED
+        meta.string_field(4, name);
+        meta.end_struct();
+    }
+    meta.i64_field(3, num_rows as i64);
+    if num_rows == 0 {
+        meta.list_header(4, 0, T_STRUCT);
+        meta.string_field(6, CREATED_BY);
+        meta.end_struct();
+        return finish_file(out, meta.buf);
+    }
+    meta.list_header(4, 1, T_STRUCT);
+    meta.begin_struct(); // RowGroup
+    meta.list_header(1, columns.len(), T_STRUCT);
+    for ((name, column), (offset, size)) in columns.iter().zip(&chunks) {
+        meta.begin_struct(); // ColumnChunk
+        meta.i64_field(2, *offset);
+        meta.struct_field(3); // ColumnMetaData
+        meta.i32_field(1, column.physical_type() as i32);
+        meta.list_header(2, 1, T_I32);
+        meta.zigzag(0); // PLAIN
+        meta.list_header(3, 1, T_BINARY);
+        meta.varint(name.len() as u64);
+        meta.buf.extend_from_slice(name.as_bytes());
+        meta.i32_field(4, 0); // UNCOMPRESSED
+        meta.i64_field(5, num_rows as i64);
+        meta.i64_field(6, *size);
+        meta.i64_field(7, *size);
+        meta.i64_field(9, *offset);
+        meta.end_struct();
+        meta.end_struct();
+    }
+    meta.i64_field(2, chunks.iter().map(|(_, size)| size).sum());
+    meta.i64_field(3, num_rows as i64);
+    meta.end_struct();
+    meta.string_field(6, CREATED_BY);
+    meta.end_struct();
+    finish_file(out, meta.buf)
+}
+
+/// Appends the footer (metadata, its length and the closing magic) to `out`.
+fn finish_file(mut out: Vec<u8>, metadata: Vec<u8>) -> Vec<u8> {
+    out.extend_from_slice(&metadata);
+    out.extend_from_slice(&(metadata.len() as u32).to_le_bytes());
+    out.extend_from_slice(MAGIC);
+    out
+}
+
+/// Serializes reports as Parquet, one row per report.
+///
+/// Columns: `timestamp` (Unix seconds, INT64), `sent_bps` and `received_bps` (DOUBLE),
+/// `retransmits` (INT64) and `host_cpu_percent` (DOUBLE).
+pub fn history_to_parquet(reports: &[Iperf3Report]) -> Vec<u8> {
+    let columns = [
+        ("timestamp", Column::Int64(reports.iter().map(|r| r.start.timestamp.timesecs as i64).collect())),
+        ("sent_bps", Column::Double(reports.iter().map(|r| r.end.sum_sent.bits_per_second).collect())),
+        ("received_bps", Column::Double(reports.iter().map(|r| r.end.sum_received.bits_per_second).collect())),
+        ("retransmits", Column::Int64(reports.iter().map(|r| r.end.sum_sent.retransmits as i64).collect())),
+        ("host_cpu_percent", Column::Double(reports.iter().map(|r| r.end.cpu_utilization_percent.host_total).collect())),
+    ];
+    write_parquet(&columns, reports.len())
+}
+
+/// HTTP GET endpoint `/history.parquet` returns the result history as a Parquet file.
+///
+/// Returns HTTP 503 with `Retry-After` while maintenance mode is enabled.
+#[get("/history.parquet")]
+pub async fn history_parquet() -> Result<HttpResponse, Iperf3Error> {
+    ensure_not_in_maintenance()?;
+    Ok(HttpResponse::Ok()
+        .content_type(PARQUET_CONTENT_TYPE)
+        .body(history_to_parquet(&get_history())))
+}
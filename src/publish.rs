@@ -0,0 +1,49 @@
+//! # iperf3-statuspage
+//!
+//! Publishing of successful results to external consumers such as NATS.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::sync::{Arc, Mutex};
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use tracing::warn;
+use crate::models::Iperf3Report;
+
+/// Destination for successful iperf3 results.
+#[async_trait]
+pub trait ResultPublisher: Send + Sync {
+    /// Publishes the JSON-serialized report.
+    async fn publish(&self, report_json: &str) -> Result<(), String>;
+}
+
+/// Publisher invoked after every successful measurement, if any.
+static PUBLISHER: Lazy<Mutex<Option<Arc<dyn ResultPublisher>>>> = Lazy::new(|| Mutex::new(None));
+
+/// Installs (or with `None` removes) the publisher invoked after every successful measurement.
+pub fn set_publisher(publisher: Option<Arc<dyn ResultPublisher>>) {
+    *PUBLISHER.lock().unwrap() = publisher;
+}
+
+/// Publishes `report` through the installed publisher.
+///
+/// Failures are logged to stderr and otherwise ignored, so publishing never affects caching.
+pub async fn publish_result(report: &Iperf3Report) {
+    let Some(publisher) = PUBLISHER.lock().unwrap().clone() else {
+        return;
+    };
+
+    let result = match serde_json::to_string(report) {
+        Ok(json) => publisher.publish(&json).await,
+        Err(e) => Err(format!("Failed to serialize iperf3 result: {}", e)),
+    };
+    if let Err(e) = result {
+        warn!(error = %e, "Failed to publish iperf3 result");
+    }
+}
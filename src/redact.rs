@@ -0,0 +1,221 @@
+//! # iperf3-statuspage
+//!
+//! Redaction of the iperf3 server's identity from public responses.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+use std::path::Path;
+use actix_web::HttpRequest;
+use serde::Serialize;
+use serde_json::Value;
+use crate::auth::is_authenticated;
+use crate::errors::Iperf3Error;
+use crate::models::Iperf3Report;
+use crate::targets::load_targets_file;
+
+/// Placeholder replacing redacted values.
+pub const REDACTED: &str = "redacted";
+
+/// Keys whose string values identify the server and are always replaced.
+const REDACTED_KEYS: [&str; 3] = ["remote_host", "remote_hostname", "system_info"];
+
+/// Prefix of the top-level `server_output_json`/`server_output_text` passthrough fields,
+/// the server's own view of the test, which are replaced as a whole.
+const SERVER_OUTPUT_PREFIX: &str = "server_output_";
+
+/// Reads the environment variable `REDACT_TARGET` (`true`/`1`), defaulting to disabled.
+pub fn redact_target_enabled() -> bool {
+    env::var("REDACT_TARGET")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+/// Returns whether the response to `req` must be redacted: `REDACT_TARGET` is enabled and
+/// the request is not authenticated with `API_TOKEN`.
+pub fn should_redact(req: &HttpRequest) -> bool {
+    redact_target_enabled() && !is_authenticated(req)
+}
+
+/// Returns the configured target hosts: those listed in `IPERF3_SERVER_IP` and in
+/// `TARGETS_FILE`, if readable.
+pub fn configured_hosts() -> Vec<String> {
+    let mut hosts: Vec<String> = env::var("IPERF3_SERVER_IP")
+        .map(|ips| ips.split(',').map(str::trim).filter(|h| !h.is_empty()).map(str::to_string).collect())
+        .unwrap_or_default();
+    if let Some(targets) = env::var("TARGETS_FILE").ok().and_then(|path| load_targets_file(Path::new(&path)).ok()) {
+        hosts.extend(targets.into_iter().map(|target| target.host));
+    }
+    hosts
+}
+
+/// Redaction of the server's identity from a response body.
+///
+/// Known fields naming the server (`start.connecting_to.host`, every `remote_host`,
+/// `remote_hostname` and `system_info`) and the `server_output_*` passthrough fields are
+/// replaced with [`REDACTED`], and every other string mentioning a configured host or a
+/// host found in those fields is scrubbed too, so passthrough fields and error messages do
+/// not leak it either.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Redaction {
+    hosts: Vec<String>,
+}
+
+impl Redaction {
+    /// Creates a redaction scrubbing `hosts` besides the hosts found in the bodies.
+    pub fn new(hosts: Vec<String>) -> Self {
+        Redaction { hosts }
+    }
+
+    /// Returns the redaction of the configured hosts (see [`configured_hosts`]) if the
+    /// response to `req` must be redacted, see [`should_redact`].
+    pub fn for_request(req: &HttpRequest) -> Option<Self> {
+        should_redact(req).then(|| Redaction::new(configured_hosts()))
+    }
+
+    /// Redacts `value` in place.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use iperf3_statuspage::{Redaction, REDACTED};
+    /// # use serde_json::json;
+    /// let mut value = json!({
+    ///     "start": {"connecting_to": {"host": "iperf.internal", "port": 5201}},
+    ///     "title": "probe to iperf.internal",
+    ///     "error": "unable to connect to 10.0.0.5",
+    /// });
+    /// Redaction::new(vec!["10.0.0.5".to_string()]).value(&mut value);
+    /// assert_eq!(value["start"]["connecting_to"]["host"], REDACTED);
+    /// assert_eq!(value["title"], "probe to redacted");
+    /// assert_eq!(value["error"], "unable to connect to redacted");
+    /// ```
+    pub fn value(&self, value: &mut Value) {
+        let mut hosts = self.hosts.clone();
+        replace_server_fields(value, &mut hosts, false);
+        // Longest first, so a host containing another is scrubbed whole
+        hosts.retain(|host| !host.is_empty() && host != REDACTED);
+        hosts.sort_by_key(|host| std::cmp::Reverse(host.len()));
+        hosts.dedup();
+        scrub_strings(value, &hosts);
+    }
+
+    /// Returns `text` with every configured host replaced with [`REDACTED`].
+    pub fn text(&self, text: &str) -> String {
+        self.hosts
+            .iter()
+            .filter(|host| !host.is_empty())
+            .fold(text.to_string(), |text, host| text.replace(host.as_str(), REDACTED))
+    }
+
+    /// Redacts `report` in place, see [`redact_report`].
+    pub fn report(&self, report: &mut Iperf3Report) {
+        let mut hosts = self.hosts.clone();
+        hosts.push(std::mem::replace(&mut report.start.connecting_to.host, REDACTED.to_string()));
+        for connected in &mut report.start.connected {
+            hosts.push(std::mem::replace(&mut connected.remote_host, REDACTED.to_string()));
+        }
+        report.start.system_info = REDACTED.to_string();
+
+        let mut extra = Value::Object(std::mem::take(&mut report.extra).into_iter().collect());
+        Redaction::new(hosts).value(&mut extra);
+        if let Value::Object(extra) = extra {
+            report.extra = extra.into_iter().collect();
+        }
+    }
+}
+
+/// Replaces the fields naming the server throughout `value`, collecting their values in
+/// `hosts`. `in_connecting_to` is set while inside a `connecting_to` object.
+fn replace_server_fields(value: &mut Value, hosts: &mut Vec<String>, in_connecting_to: bool) {
+    match value {
+        Value::Object(object) => {
+            for (key, field) in object.iter_mut() {
+                let names_server = REDACTED_KEYS.contains(&key.as_str()) || (in_connecting_to && key == "host");
+                if key.starts_with(SERVER_OUTPUT_PREFIX) {
+                    *field = Value::String(REDACTED.to_string());
+                } else if names_server && field.is_string() {
+                    if key != "system_info"
+                        && let Some(host) = field.as_str()
+                    {
+                        hosts.push(host.to_string());
+                    }
+                    *field = Value::String(REDACTED.to_string());
+                } else {
+                    replace_server_fields(field, hosts, key == "connecting_to");
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| replace_server_fields(item, hosts, false)),
+        _ => {}
+    }
+}
+
+/// Replaces every occurrence of `hosts` in the strings of `value`.
+fn scrub_strings(value: &mut Value, hosts: &[String]) {
+    match value {
+        Value::String(text) => {
+            for host in hosts {
+                if text.contains(host.as_str()) {
+                    *text = text.replace(host.as_str(), REDACTED);
+                }
+            }
+        }
+        Value::Object(object) => object.values_mut().for_each(|field| scrub_strings(field, hosts)),
+        Value::Array(items) => items.iter_mut().for_each(|item| scrub_strings(item, hosts)),
+        _ => {}
+    }
+}
+
+/// Serializes `body` as the JSON of the response to `req`, redacted for anonymous clients
+/// while `REDACT_TARGET` is enabled (see [`Redaction`]).
+///
+/// Every handler returning reports or server details serializes through this.
+pub fn public_json<T: Serialize>(req: &HttpRequest, body: &T) -> Result<Value, Iperf3Error> {
+    let mut value =
+        serde_json::to_value(body).map_err(|e| Iperf3Error::Internal(format!("Failed to serialize response: {}", e)))?;
+    if let Some(redaction) = Redaction::for_request(req) {
+        redaction.value(&mut value);
+    }
+    Ok(value)
+}
+
+/// Refuses raw output, which cannot be redacted reliably, to anonymous clients while
+/// `REDACT_TARGET` is enabled.
+pub fn ensure_raw_output_allowed(req: &HttpRequest) -> Result<(), Iperf3Error> {
+    if should_redact(req) {
+        return Err(Iperf3Error::Unauthorized(
+            "Raw iperf3 output requires the API token while REDACT_TARGET is enabled.".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Replaces `start.connecting_to.host`, every `start.connected[].remote_host` and
+/// `start.system_info` with [`REDACTED`], keeping all throughput figures.
+///
+/// The `server_output_*` passthrough fields are replaced as well, and the hosts are
+/// scrubbed from the other passthrough fields, see [`Redaction`].
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::{redact_report, Iperf3Report, REDACTED};
+/// let mut report = Iperf3Report::default();
+/// report.start.connecting_to.host = "10.0.0.5".to_string();
+/// report.end.sum_received.bits_per_second = 941_000_000.0;
+/// report.extra.insert("title".to_string(), "to 10.0.0.5".into());
+/// redact_report(&mut report);
+/// assert_eq!(report.start.connecting_to.host, REDACTED);
+/// assert_eq!(report.extra["title"], "to redacted");
+/// assert_eq!(report.end.sum_received.bits_per_second, 941_000_000.0);
+/// ```
+pub fn redact_report(report: &mut Iperf3Report) {
+    Redaction::default().report(report);
+}
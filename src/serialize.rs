@@ -0,0 +1,48 @@
+//! # iperf3-statuspage
+//!
+//! Response serialization guarded by a timeout.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+use std::time::Duration;
+use actix_web::web;
+use tracing::warn;
+use crate::errors::Iperf3Error;
+
+/// Reads the environment variable `RESPONSE_TIMEOUT_MS` or returns a default of 5000 ms.
+///
+/// This bounds how long serializing a response body may take.
+pub fn response_timeout() -> Duration {
+    let millis = env::var("RESPONSE_TIMEOUT_MS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(5000);
+    Duration::from_millis(millis)
+}
+
+/// Runs `serialize` on the blocking thread pool, giving up after `limit`.
+///
+/// A serializer that overruns yields [`Iperf3Error::ResponseTimeout`] (logged as a warning)
+/// so the client gets HTTP 503 instead of a hanging connection. Errors returned by
+/// `serialize` itself are passed through.
+pub async fn serialize_with_timeout<F>(limit: Duration, serialize: F) -> Result<Vec<u8>, Iperf3Error>
+where
+    F: FnOnce() -> Result<Vec<u8>, Iperf3Error> + Send + 'static,
+{
+    match tokio::time::timeout(limit, web::block(serialize)).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(e)) => Err(Iperf3Error::Internal(format!("Failed to serialize response: {}", e))),
+        Err(_) => {
+            warn!("Serializing the response took longer than {} ms", limit.as_millis());
+            Err(Iperf3Error::ResponseTimeout { timeout_ms: limit.as_millis() as u64 })
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! # iperf3-statuspage
+//!
+//! Graceful shutdown of the background schedulers on SIGINT or SIGTERM, draining an
+//! in-flight run for up to `SHUTDOWN_TIMEOUT_SECONDS`.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use once_cell::sync::Lazy;
+use tokio::sync::Notify;
+use tokio::time;
+use tracing::{error, info, warn};
+
+/// Number of iperf3 runs currently in progress.
+static RUNS_IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+
+/// Notified whenever the last in-flight run finishes.
+static RUNS_IDLE: Lazy<Notify> = Lazy::new(Notify::new);
+
+/// Marks a run as in flight until dropped, see [`track_run`].
+pub struct InFlightRun(());
+
+impl Drop for InFlightRun {
+    fn drop(&mut self) {
+        if RUNS_IN_FLIGHT.fetch_sub(1, Ordering::SeqCst) == 1 {
+            RUNS_IDLE.notify_waiters();
+        }
+    }
+}
+
+/// Marks a run as in flight for as long as the returned guard lives, so shutdown can wait
+/// for it to finish.
+pub fn track_run() -> InFlightRun {
+    RUNS_IN_FLIGHT.fetch_add(1, Ordering::SeqCst);
+    InFlightRun(())
+}
+
+/// Returns the number of runs currently in flight.
+pub fn runs_in_flight() -> usize {
+    RUNS_IN_FLIGHT.load(Ordering::SeqCst)
+}
+
+/// Reads the environment variable `SHUTDOWN_TIMEOUT_SECONDS`, the longest shutdown waits
+/// for in-flight HTTP requests and iperf3 runs. Defaults to 30 seconds, as actix-web does.
+pub fn shutdown_timeout() -> Duration {
+    let seconds = env::var("SHUTDOWN_TIMEOUT_SECONDS")
+        .ok()
+        .and_then(|s| s.trim().parse::<u64>().ok())
+        .unwrap_or(30);
+    Duration::from_secs(seconds)
+}
+
+/// Completes on the first SIGINT (Ctrl-C) or, on Unix, SIGTERM.
+///
+/// If a handler cannot be installed the error is logged and that signal is ignored.
+pub async fn shutdown_signal() {
+    let interrupt = async {
+        match tokio::signal::ctrl_c().await {
+            Ok(()) => info!("Received SIGINT, shutting down"),
+            Err(e) => {
+                error!(error = %e, "Failed to install SIGINT handler");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+                info!("Received SIGTERM, shutting down");
+            }
+            Err(e) => {
+                error!(error = %e, "Failed to install SIGTERM handler");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => {}
+        _ = terminate => {}
+    }
+}
+
+/// Drives `task` (e.g. a scheduler) until `shutdown` completes, then drops it.
+///
+/// Dropping the task cancels any in-flight run; the iperf3 child of
+/// [`RealIperf3Runner`](crate::RealIperf3Runner) is spawned with `kill_on_drop`, so it is
+/// terminated with it.
+pub async fn run_until_shutdown(task: impl Future, shutdown: impl Future) {
+    tokio::select! {
+        _ = task => {}
+        _ = shutdown => {}
+    }
+}
+
+/// Drives `task` (e.g. a scheduler) until `shutdown` completes, then keeps driving it while
+/// a run is in flight (see [`track_run`]) for at most `timeout` before dropping it.
+///
+/// A run still going after `timeout` is cancelled as in [`run_until_shutdown`].
+pub async fn drain_until_shutdown(task: impl Future, shutdown: impl Future, timeout: Duration) {
+    tokio::pin!(task);
+    tokio::select! {
+        _ = &mut task => return,
+        _ = shutdown => {}
+    }
+
+    // Registered before driving the task further, so a run finishing (and the scheduler
+    // moving on) within a single poll is not missed
+    let idle = RUNS_IDLE.notified();
+    tokio::pin!(idle);
+    idle.as_mut().enable();
+    if runs_in_flight() == 0 {
+        return;
+    }
+    info!("Waiting up to {}s for the in-flight iperf3 run to finish", timeout.as_secs_f64());
+    let drain = async {
+        tokio::select! {
+            _ = &mut task => {}
+            _ = idle => {}
+        }
+    };
+    if time::timeout(timeout, drain).await.is_err() {
+        warn!("Shutdown timeout elapsed, cancelling the in-flight iperf3 run");
+    }
+}
@@ -0,0 +1,71 @@
+//! # iperf3-statuspage
+//!
+//! Error-budget tracking: the share of recent runs meeting the received throughput
+//! objective (`SLA_MIN_RECEIVED_MBPS` or `SLA_MIN_MBPS`) over `SLO_WINDOW_HOURS`.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::badge::sla_min_received_mbps;
+use crate::bits_per_second_to_mbps;
+use crate::history::get_history;
+use crate::models::Iperf3Report;
+
+/// Reads the environment variable `SLO_WINDOW_HOURS` or returns a default of 24 hours.
+pub fn slo_window() -> Duration {
+    let hours = env::var("SLO_WINDOW_HOURS")
+        .ok()
+        .and_then(|s| s.trim().parse::<u64>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(24);
+    Duration::from_secs(hours * 3600)
+}
+
+/// Returns the percentage of `reports` started within `window` before `now` whose received
+/// throughput reached `min_mbps`.
+///
+/// Returns `None` if no report falls within the window.
+///
+/// # Examples
+///
+/// ```
+/// # use std::time::{Duration, UNIX_EPOCH};
+/// # use iperf3_statuspage::{slo_compliance_percent, Iperf3Report};
+/// let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+/// let run = |timesecs: u64, mbps: f64| {
+///     let mut report = Iperf3Report::default();
+///     report.start.timestamp.timesecs = timesecs;
+///     report.end.sum_received.bits_per_second = mbps * 1_000_000.0;
+///     report
+/// };
+/// let reports = [run(1_699_999_000, 950.0), run(1_699_999_500, 400.0), run(1_600_000_000, 100.0)];
+/// assert_eq!(slo_compliance_percent(&reports, 500.0, Duration::from_secs(3600), now), Some(50.0));
+/// ```
+pub fn slo_compliance_percent(reports: &[Iperf3Report], min_mbps: f64, window: Duration, now: SystemTime) -> Option<f64> {
+    let now_secs = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let since = now_secs.saturating_sub(window.as_secs());
+    let (met, total) = reports
+        .iter()
+        .filter(|report| report.start.timestamp.timesecs >= since)
+        .fold((0usize, 0usize), |(met, total), report| {
+            let mbps = bits_per_second_to_mbps(report.end.sum_received.bits_per_second);
+            (met + usize::from(mbps >= min_mbps), total + 1)
+        });
+    (total > 0).then(|| met as f64 / total as f64 * 100.0)
+}
+
+/// Returns the SLO compliance of the history over `SLO_WINDOW_HOURS` against
+/// `SLA_MIN_RECEIVED_MBPS` (or `SLA_MIN_MBPS`), or `None` when no objective is set or no run falls within the window.
+///
+/// Only successful runs are kept in the history, so failed runs do not count against it.
+pub fn current_slo_compliance() -> Option<f64> {
+    let min_mbps = sla_min_received_mbps()?;
+    slo_compliance_percent(&get_history(), min_mbps, slo_window(), SystemTime::now())
+}
@@ -0,0 +1,66 @@
+//! # iperf3-statuspage
+//!
+//! Unicode sparkline of recent throughput, served at `/sparkline`.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use actix_web::{get, HttpResponse};
+use crate::bits_per_second_to_mbps;
+use crate::errors::Iperf3Error;
+use crate::maintenance::ensure_not_in_maintenance;
+use crate::history::get_history;
+
+/// Block characters from lowest to highest.
+pub const SPARKLINE_GLYPHS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Renders one glyph per value, scaled between the series' minimum and maximum.
+///
+/// A flat series renders at the lowest level.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::render_sparkline;
+/// assert_eq!(render_sparkline(&[1.0, 5.0, 8.0]), "▁▅█");
+/// assert_eq!(render_sparkline(&[]), "");
+/// ```
+pub fn render_sparkline(values: &[f64]) -> String {
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let range = max - min;
+    let top = (SPARKLINE_GLYPHS.len() - 1) as f64;
+
+    values
+        .iter()
+        .map(|value| {
+            let level = if range > 0.0 { ((value - min) / range * top).round() as usize } else { 0 };
+            SPARKLINE_GLYPHS[level.min(SPARKLINE_GLYPHS.len() - 1)]
+        })
+        .collect()
+}
+
+/// HTTP GET endpoint `/sparkline` returns a plain-text sparkline of the received
+/// throughput across the history, followed by the latest value in Mbps.
+///
+/// Returns HTTP 503 Service Unavailable if the history is empty.
+///
+/// Returns HTTP 503 with `Retry-After` while maintenance mode is enabled.
+#[get("/sparkline")]
+pub async fn sparkline() -> Result<HttpResponse, Iperf3Error> {
+    ensure_not_in_maintenance()?;
+    let received: Vec<f64> = get_history()
+        .iter()
+        .map(|report| report.end.sum_received.bits_per_second)
+        .collect();
+    let latest = received.last().ok_or_else(Iperf3Error::not_available)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; charset=utf-8")
+        .body(format!("{} {:.1} Mbps\n", render_sparkline(&received), bits_per_second_to_mbps(*latest))))
+}
@@ -0,0 +1,182 @@
+//! # iperf3-statuspage
+//!
+//! SQLite export of successful results to `DB_PATH`, enabled by the `sqlite` feature.
+//!
+//! The database is driven through the `sqlite3` command-line shell, so no SQLite library
+//! has to be linked into the binary.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use actix_web::{get, web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::time::timeout;
+use tracing::error;
+use crate::errors::Iperf3Error;
+use crate::maintenance::ensure_not_in_maintenance;
+use crate::models::Iperf3Report;
+
+/// Longest a single `sqlite3` invocation may take, so a locked database cannot pile up
+/// inserts.
+const SQLITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Creates the results table on first use.
+const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS results (\
+    timestamp INTEGER NOT NULL, \
+    sent_bps REAL, \
+    received_bps REAL, \
+    retransmits INTEGER NOT NULL, \
+    host_cpu_percent REAL);";
+
+/// Reads the environment variable `DB_PATH`, the SQLite database successful results are
+/// exported to. Returns `None` when unset, disabling the export.
+pub fn db_path() -> Option<PathBuf> {
+    env::var("DB_PATH").ok().filter(|s| !s.trim().is_empty()).map(PathBuf::from)
+}
+
+/// One row of the results table.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StoredResult {
+    /// Start of the test in seconds since the UNIX epoch.
+    pub timestamp: u64,
+    pub sent_bps: Option<f64>,
+    pub received_bps: Option<f64>,
+    pub retransmits: u32,
+    pub host_cpu_percent: Option<f64>,
+}
+
+impl From<&Iperf3Report> for StoredResult {
+    fn from(report: &Iperf3Report) -> Self {
+        StoredResult {
+            timestamp: report.start.timestamp.timesecs,
+            sent_bps: Some(report.end.sum_sent.bits_per_second),
+            received_bps: Some(report.end.sum_received.bits_per_second),
+            retransmits: report.end.sum_sent.retransmits,
+            host_cpu_percent: Some(report.end.cpu_utilization_percent.host_total),
+        }
+    }
+}
+
+/// Formats a float as an SQL literal; non-finite values, which SQLite cannot store as
+/// `REAL`, become `NULL`.
+fn sql_real(value: Option<f64>) -> String {
+    match value {
+        Some(v) if v.is_finite() => format!("{:?}", v),
+        _ => "NULL".to_string(),
+    }
+}
+
+/// Runs `sql` against the database at `path` and returns the shell's output.
+///
+/// `-bail` stops at the first error, which is returned with the shell's message.
+async fn run_sqlite(path: &Path, sql: &str, json: bool) -> Result<String, String> {
+    let mut command = Command::new("sqlite3");
+    command.arg("-batch").arg("-bail");
+    if json {
+        command.arg("-json");
+    }
+    command
+        .arg(path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let run = async {
+        let mut child = command.spawn().map_err(|e| format!("failed to start sqlite3: {}", e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(sql.as_bytes()).await.map_err(|e| e.to_string())?;
+        }
+        let output = child.wait_with_output().await.map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    };
+    timeout(SQLITE_TIMEOUT, run)
+        .await
+        .map_err(|_| format!("sqlite3 timed out after {}s", SQLITE_TIMEOUT.as_secs()))?
+}
+
+/// Appends `report` to the results table of the database at `path`, creating the table
+/// if needed.
+pub async fn insert_result(path: &Path, report: &Iperf3Report) -> Result<(), String> {
+    let row = StoredResult::from(report);
+    let sql = format!(
+        "{}\nINSERT INTO results VALUES ({}, {}, {}, {}, {});\n",
+        CREATE_TABLE,
+        row.timestamp,
+        sql_real(row.sent_bps),
+        sql_real(row.received_bps),
+        row.retransmits,
+        sql_real(row.host_cpu_percent),
+    );
+    run_sqlite(path, &sql, false).await.map(|_| ())
+}
+
+/// Returns the stored results started at or after `since` (seconds since the UNIX epoch),
+/// oldest first.
+pub async fn query_results_since(path: &Path, since: u64) -> Result<Vec<StoredResult>, String> {
+    let sql = format!(
+        "{}\nSELECT timestamp, sent_bps, received_bps, retransmits, host_cpu_percent \
+         FROM results WHERE timestamp >= {} ORDER BY timestamp, rowid;\n",
+        CREATE_TABLE, since
+    );
+    let output = run_sqlite(path, &sql, true).await?;
+    // The shell prints nothing at all, not `[]`, when no row matches.
+    if output.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    serde_json::from_str(&output).map_err(|e| format!("unexpected sqlite3 output: {}", e))
+}
+
+/// Exports `report` to `DB_PATH` in the background, if set.
+///
+/// The insert runs on its own task so a slow or unavailable database never delays the
+/// measurement cycle; failures are only logged.
+pub fn export_result(report: &Iperf3Report) {
+    let Some(path) = db_path() else {
+        return;
+    };
+    let report = report.clone();
+    tokio::spawn(async move {
+        if let Err(e) = insert_result(&path, &report).await {
+            error!(error = %e, "Failed to export iperf3 result to {}", path.display());
+        }
+    });
+}
+
+/// Query parameters of `/query`.
+#[derive(Deserialize, Debug, Default)]
+pub struct QueryParams {
+    /// Only results started at or after this UNIX timestamp; all results by default.
+    pub since: Option<u64>,
+}
+
+/// HTTP GET endpoint `/query?since=<timestamp>` returns the results stored in `DB_PATH`
+/// since the given UNIX timestamp as a JSON array, oldest first.
+///
+/// Returns HTTP 503 Service Unavailable if `DB_PATH` is unset and HTTP 500 Internal
+/// Server Error if the database cannot be read.
+///
+/// Returns HTTP 503 with `Retry-After` while maintenance mode is enabled.
+#[get("/query")]
+pub async fn query_results(params: web::Query<QueryParams>) -> Result<HttpResponse, Iperf3Error> {
+    ensure_not_in_maintenance()?;
+    let path = db_path().ok_or_else(|| Iperf3Error::NotAvailable("DB_PATH is not configured.".to_string()))?;
+    let results = query_results_since(&path, params.since.unwrap_or(0))
+        .await
+        .map_err(Iperf3Error::Internal)?;
+    Ok(HttpResponse::Ok().json(results))
+}
@@ -0,0 +1,52 @@
+//! # iperf3-statuspage
+//!
+//! Staleness policy for cached results, configured by `STALE_AFTER_SECONDS`.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+use std::time::Duration;
+use actix_web::HttpRequest;
+use crate::auth::is_authenticated;
+use crate::errors::Iperf3Error;
+
+/// Header set to `true` on responses serving stale data to authenticated clients.
+pub const X_STALE: &str = "X-Stale";
+
+/// Header carrying the age of the served result in whole seconds.
+pub const X_CACHE_AGE_SECONDS: &str = "X-Cache-Age-Seconds";
+
+/// Reads the environment variable `STALE_AFTER_SECONDS`, defaulting to disabled.
+///
+/// Cached results older than this are considered stale.
+pub fn stale_after() -> Option<Duration> {
+    env::var("STALE_AFTER_SECONDS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .filter(|n| *n > 0)
+        .map(Duration::from_secs)
+}
+
+/// Applies the staleness policy to a cached result of the given `age`.
+///
+/// Returns `Ok(false)` for fresh data. Stale data fails with [`Iperf3Error::Stale`] for
+/// anonymous clients, while authenticated clients get `Ok(true)` and should be served
+/// the data flagged with [`X_STALE`].
+pub fn check_staleness(req: &HttpRequest, age: Duration) -> Result<bool, Iperf3Error> {
+    match stale_after() {
+        Some(limit) if age > limit => {
+            if is_authenticated(req) {
+                Ok(true)
+            } else {
+                Err(Iperf3Error::Stale { age_seconds: age.as_secs() })
+            }
+        }
+        _ => Ok(false),
+    }
+}
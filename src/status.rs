@@ -0,0 +1,382 @@
+//! # iperf3-statuspage
+//!
+//! Scheduler health and sanity warnings, exposed at `/status`.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use actix_web::{get, HttpRequest, HttpResponse};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use crate::command::{effective_options, Iperf3Options};
+use crate::errors::Iperf3Error;
+use crate::models::Iperf3Report;
+use crate::redact::public_json;
+use crate::slo::current_slo_compliance;
+use crate::summary::session_cookie;
+use crate::{get_last_result, get_last_result_with_age, last_cache_metadata};
+
+/// Status of the measurement scheduler as reported by `/status`.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct RunStatus {
+    /// Sanity warnings raised about the last parsed report.
+    pub warnings: Vec<String>,
+    /// Actual time between the starts of the last two measurement cycles.
+    pub actual_interval_seconds: Option<f64>,
+    /// Exit code of the last iperf3 process, `None` if it never ran to completion.
+    pub last_exit_code: Option<i32>,
+    /// Whether the last run found the iperf3 server busy with another client.
+    pub server_busy: bool,
+    /// Whether the last run failed because this process may not execute iperf3.
+    pub permission_denied: bool,
+    /// Whether the last report's protocol differed from `EXPECTED_PROTOCOL`.
+    pub protocol_mismatch: bool,
+    /// `RUN_ANNOTATION` attached to the cached result, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotation: Option<String>,
+    /// iperf3 session cookie (`start.cookie`) of the cached result, for correlating with
+    /// the server's logs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cookie: Option<String>,
+    /// Percentage of runs within `SLO_WINDOW_HOURS` meeting the received SLA, see
+    /// [`current_slo_compliance`](crate::current_slo_compliance).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slo_compliance_percent: Option<f64>,
+}
+
+/// Global scheduler status, updated after every measurement cycle.
+pub static RUN_STATUS: Lazy<Mutex<RunStatus>> = Lazy::new(|| Mutex::new(RunStatus::default()));
+
+/// Message of the last failed run and when it failed, cleared by the next successful run.
+static LAST_ERROR: Lazy<Mutex<Option<(String, Instant)>>> = Lazy::new(|| Mutex::new(None));
+
+/// Records the outcome of a run: a failure becomes the last error, a success clears it.
+pub fn record_last_error<T>(result: &Result<T, Iperf3Error>) {
+    *LAST_ERROR.lock().unwrap() = result.as_ref().err().map(|e| (e.to_string(), Instant::now()));
+}
+
+/// Returns the message of the last failed run and when it failed, unless a run succeeded since.
+pub fn get_last_error() -> Option<(String, Instant)> {
+    LAST_ERROR.lock().unwrap().clone()
+}
+
+/// Sets the last error. Used for testing purposes.
+pub fn set_last_error_for_test(message: impl Into<String>) {
+    *LAST_ERROR.lock().unwrap() = Some((message.into(), Instant::now()));
+}
+
+/// Clears the last error.
+pub fn clear_last_error_for_test() {
+    *LAST_ERROR.lock().unwrap() = None;
+}
+
+/// Instant the most recent measurement cycle started.
+static LAST_CYCLE_START: Lazy<Mutex<Option<Instant>>> = Lazy::new(|| Mutex::new(None));
+
+/// Records that a measurement cycle started at `at`.
+///
+/// Returns the time since the previous cycle started, which is also published as
+/// `actual_interval_seconds` in `/status`. Because run durations vary this drifts
+/// from the nominal `INTERVAL_MINUTES`.
+///
+/// # Examples
+///
+/// ```
+/// # use std::time::{Duration, Instant};
+/// # use iperf3_statuspage::{record_cycle_start, clear_run_status_for_test};
+/// # clear_run_status_for_test();
+/// let t0 = Instant::now();
+/// assert_eq!(record_cycle_start(t0), None);
+/// assert_eq!(record_cycle_start(t0 + Duration::from_secs(605)), Some(Duration::from_secs(605)));
+/// ```
+pub fn record_cycle_start(at: Instant) -> Option<Duration> {
+    let mut last = LAST_CYCLE_START.lock().unwrap();
+    let delta = last.map(|previous| at.saturating_duration_since(previous));
+    *last = Some(at);
+
+    if let Some(delta) = delta {
+        RUN_STATUS.lock().unwrap().actual_interval_seconds = Some(delta.as_secs_f64());
+    }
+    delta
+}
+
+/// Returns when the most recent measurement cycle started, if any did.
+pub fn last_cycle_start() -> Option<Instant> {
+    *LAST_CYCLE_START.lock().unwrap()
+}
+
+/// Retrieves a snapshot of the current scheduler status.
+pub fn get_run_status() -> RunStatus {
+    RUN_STATUS.lock().unwrap().clone()
+}
+
+/// Resets the scheduler status to its initial state.
+pub fn clear_run_status_for_test() {
+    *RUN_STATUS.lock().unwrap() = RunStatus::default();
+    *LAST_CYCLE_START.lock().unwrap() = None;
+    clear_last_error_for_test();
+}
+
+/// Compares the number of streams iperf3 established against the configured parallel count.
+///
+/// Both `start.test_start.num_streams` and `end.streams.len()` are checked, so a
+/// server-side limit that silently reduces the stream count is caught. A `--bidir` test
+/// reports each stream once per direction at the end, so twice as many end streams are
+/// expected there.
+/// Returns a warning message on mismatch.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::{check_expected_streams, Iperf3Report};
+/// let mut report = Iperf3Report::default();
+/// report.start.test_start.num_streams = 2;
+/// assert!(check_expected_streams(&report, 4).is_some());
+///
+/// report.start.test_start.num_streams = 4;
+/// report.end.streams = vec![Default::default(); 4];
+/// assert!(check_expected_streams(&report, 4).is_none());
+///
+/// report.start.test_start.bidir = 1;
+/// assert!(check_expected_streams(&report, 4).is_some());
+/// report.end.streams = vec![Default::default(); 8];
+/// assert!(check_expected_streams(&report, 4).is_none());
+/// ```
+pub fn check_expected_streams(report: &Iperf3Report, expected: u32) -> Option<String> {
+    let announced = report.start.test_start.num_streams;
+    let finished = report.end.streams.len();
+    let directions = if report.start.test_start.bidir != 0 { 2 } else { 1 };
+    if announced != expected || finished != expected as usize * directions {
+        Some(format!(
+            "Expected {} parallel streams but iperf3 reported {} (test_start) and {} (end)",
+            expected, announced, finished
+        ))
+    } else {
+        None
+    }
+}
+
+/// Reads the environment variable `MIN_VALID_BYTES`, defaulting to disabled.
+///
+/// Reports that received fewer bytes are rejected instead of cached.
+pub fn min_valid_bytes() -> Option<u64> {
+    env::var("MIN_VALID_BYTES")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .filter(|n| *n > 0)
+}
+
+/// Checks that the report received at least `min_bytes`.
+///
+/// Returns a rejection message for near-zero transfers from momentary failures.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::{check_min_bytes, Iperf3Report};
+/// let mut report = Iperf3Report::default();
+/// report.end.sum_received.bytes = 1_024;
+/// assert!(check_min_bytes(&report, 1_000_000).is_some());
+/// assert!(check_min_bytes(&report, 1_024).is_none());
+/// ```
+pub fn check_min_bytes(report: &Iperf3Report, min_bytes: u64) -> Option<String> {
+    let received = report.end.sum_received.bytes;
+    (received < min_bytes).then(|| {
+        format!("Received only {} bytes, below MIN_VALID_BYTES of {}", received, min_bytes)
+    })
+}
+
+/// Reads the environment variable `EXPECTED_PROTOCOL` (e.g. `TCP` or `UDP`), defaulting
+/// to disabled.
+pub fn expected_protocol() -> Option<String> {
+    env::var("EXPECTED_PROTOCOL")
+        .ok()
+        .map(|s| s.trim().to_ascii_uppercase())
+        .filter(|s| !s.is_empty())
+}
+
+/// Checks that the report's `test_start.protocol` matches `expected`, ignoring case.
+///
+/// Returns a message when a misconfiguration ran e.g. UDP instead of the intended TCP.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::{check_expected_protocol, Iperf3Report};
+/// let mut report = Iperf3Report::default();
+/// report.start.test_start.protocol = "UDP".to_string();
+/// assert!(check_expected_protocol(&report, "TCP").is_some());
+/// assert!(check_expected_protocol(&report, "udp").is_none());
+/// ```
+pub fn check_expected_protocol(report: &Iperf3Report, expected: &str) -> Option<String> {
+    let protocol = &report.start.test_start.protocol;
+    (!protocol.eq_ignore_ascii_case(expected)).then(|| {
+        format!("Expected protocol {} but iperf3 reported {:?}", expected.to_ascii_uppercase(), protocol)
+    })
+}
+
+/// Reads the environment variable `MAX_CLOCK_SKEW_SECONDS`, defaulting to disabled.
+///
+/// Reports whose timestamp differs from the local clock by more than this are flagged.
+pub fn max_clock_skew() -> Option<Duration> {
+    env::var("MAX_CLOCK_SKEW_SECONDS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .filter(|n| *n > 0)
+        .map(Duration::from_secs)
+}
+
+/// Reads the environment variable `REJECT_CLOCK_SKEW`, defaulting to `false`.
+///
+/// When enabled, reports beyond `MAX_CLOCK_SKEW_SECONDS` are rejected instead of only
+/// raising a warning.
+pub fn reject_clock_skew_enabled() -> bool {
+    env::var("REJECT_CLOCK_SKEW")
+        .map(|s| matches!(s.trim().to_ascii_lowercase().as_str(), "true" | "1"))
+        .unwrap_or(false)
+}
+
+/// Reads the environment variable `MAX_HOST_CPU_PERCENT`, defaulting to disabled.
+///
+/// Reports whose `end.cpu_utilization_percent.host_total` exceeds this are flagged as
+/// CPU-bound: their throughput reflects the client rather than the link.
+pub fn max_host_cpu_percent() -> Option<f64> {
+    env::var("MAX_HOST_CPU_PERCENT")
+        .ok()
+        .and_then(|s| s.trim().parse::<f64>().ok())
+        .filter(|n| n.is_finite() && *n > 0.0)
+}
+
+/// Reads the environment variable `REJECT_CPU_SATURATED`, defaulting to `false`.
+///
+/// When enabled, reports beyond `MAX_HOST_CPU_PERCENT` are rejected instead of only
+/// being flagged.
+pub fn reject_cpu_saturated_enabled() -> bool {
+    env::var("REJECT_CPU_SATURATED")
+        .map(|s| matches!(s.trim().to_ascii_lowercase().as_str(), "true" | "1"))
+        .unwrap_or(false)
+}
+
+/// Returns whether the report's host CPU utilization exceeds `max_percent`.
+pub fn cpu_saturated(report: &Iperf3Report, max_percent: f64) -> bool {
+    report.end.cpu_utilization_percent.host_total > max_percent
+}
+
+/// Checks the report's host CPU utilization against `max_percent`.
+///
+/// Returns a message if the client was CPU-bound, see [`cpu_saturated`].
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::{check_cpu_saturation, Iperf3Report};
+/// let mut report = Iperf3Report::default();
+/// report.end.cpu_utilization_percent.host_total = 42.0;
+/// assert!(check_cpu_saturation(&report, 90.0).is_none());
+/// report.end.cpu_utilization_percent.host_total = 98.5;
+/// assert!(check_cpu_saturation(&report, 90.0).is_some());
+/// ```
+pub fn check_cpu_saturation(report: &Iperf3Report, max_percent: f64) -> Option<String> {
+    cpu_saturated(report, max_percent).then(|| {
+        format!(
+            "Host CPU utilization of {:.1}% exceeds MAX_HOST_CPU_PERCENT of {}; throughput is CPU-bound",
+            report.end.cpu_utilization_percent.host_total, max_percent
+        )
+    })
+}
+
+/// Compares the report's `start.timestamp.timesecs` against `now`.
+///
+/// Returns a message if the two differ by more than `max_skew`.
+///
+/// # Examples
+///
+/// ```
+/// # use std::time::{Duration, SystemTime, UNIX_EPOCH};
+/// # use iperf3_statuspage::{check_clock_skew, Iperf3Report};
+/// let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+/// let mut report = Iperf3Report::default();
+/// report.start.timestamp.timesecs = 1_700_000_030;
+/// assert!(check_clock_skew(&report, now, Duration::from_secs(60)).is_none());
+/// report.start.timestamp.timesecs = 1_700_003_600;
+/// assert!(check_clock_skew(&report, now, Duration::from_secs(60)).is_some());
+/// ```
+pub fn check_clock_skew(report: &Iperf3Report, now: SystemTime, max_skew: Duration) -> Option<String> {
+    let local = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let reported = report.start.timestamp.timesecs;
+    let (skew, direction) = if reported >= local {
+        (reported - local, "ahead of")
+    } else {
+        (local - reported, "behind")
+    };
+    (skew > max_skew.as_secs()).then(|| {
+        format!(
+            "Report timestamp is {} seconds {} the local clock, beyond MAX_CLOCK_SKEW_SECONDS of {}",
+            skew,
+            direction,
+            max_skew.as_secs()
+        )
+    })
+}
+
+/// Collects all sanity warnings for a report freshly parsed from a run with `opts`.
+///
+/// The expected stream count honours a `-P` in `IPERF3_EXTRA_ARGS`, like the run itself.
+pub fn report_warnings(report: &Iperf3Report, opts: &Iperf3Options) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if let Some(expected) = effective_options(opts).parallel {
+        warnings.extend(check_expected_streams(report, expected));
+    }
+    warnings
+}
+
+/// Health of the measurements as reported by `/iperf3/status`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Iperf3Health {
+    /// Whether a result is cached and the last run did not fail.
+    pub healthy: bool,
+    /// Age of the cached result in seconds, `None` if no run succeeded yet.
+    pub last_success_secs_ago: Option<u64>,
+    /// Error of the last run if it failed.
+    pub last_error: Option<String>,
+}
+
+/// Returns the current health of the measurements.
+///
+/// Distinguishes never having run (no success, no error) from having run but now failing
+/// (an error, possibly alongside an older success).
+pub fn current_health() -> Iperf3Health {
+    let last_success_secs_ago = get_last_result_with_age().map(|(_, age)| age.as_secs());
+    let last_error = get_last_error().map(|(message, _)| message);
+    Iperf3Health { healthy: last_success_secs_ago.is_some() && last_error.is_none(), last_success_secs_ago, last_error }
+}
+
+/// HTTP GET endpoint `/iperf3/status` returns whether the measurements are healthy, the
+/// age of the last successful result and the error of the last run, if it failed, as JSON.
+///
+/// Always answers HTTP 200, so health checks should look at `healthy`.
+#[get("/iperf3/status")]
+pub async fn iperf3_health(req: HttpRequest) -> Result<HttpResponse, Iperf3Error> {
+    Ok(HttpResponse::Ok().json(public_json(&req, &current_health())?))
+}
+
+/// HTTP GET endpoint `/status` returns the scheduler status as JSON, including the
+/// annotation and session cookie of the cached result and the SLO compliance.
+#[get("/status")]
+pub async fn iperf3_status(req: HttpRequest) -> Result<HttpResponse, Iperf3Error> {
+    let status = RunStatus {
+        annotation: last_cache_metadata().and_then(|metadata| metadata.annotation),
+        cookie: get_last_result().and_then(|report| session_cookie(&report)),
+        slo_compliance_percent: current_slo_compliance(),
+        ..get_run_status()
+    };
+    Ok(HttpResponse::Ok().json(public_json(&req, &status)?))
+}
@@ -0,0 +1,504 @@
+//! # iperf3-statuspage
+//!
+//! Compact headline view of the latest result, served at `/summary`.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::collections::HashMap;
+use std::env;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use actix_web::http::header::{HttpDate, IfModifiedSince, LastModified};
+use actix_web::{get, web, HttpMessage, HttpRequest, HttpResponse};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use crate::badge::{sla_min_received_mbps, sla_min_sent_mbps, SlaOutcome};
+use crate::baseline::{baseline_delta, get_baseline, BaselineDelta};
+use crate::errors::Iperf3Error;
+use crate::history::get_history;
+use crate::maintenance::ensure_not_in_maintenance;
+use crate::redact::public_json;
+use crate::stale::{check_staleness, X_STALE};
+use crate::status::{cpu_saturated, max_host_cpu_percent};
+use crate::{bits_per_second_to_mbps, last_cache_metadata, last_cached_at, primary_result};
+use crate::models::Iperf3Report;
+
+/// Headline figures of a single iperf3 run as reported by `/summary`.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct Summary {
+    pub timestamp: u64,
+    pub sent_mbps: f64,
+    pub received_mbps: f64,
+    /// Received throughput less the estimated share of retransmitted data, see
+    /// [`goodput_mbps`]; `None` when nothing was sent.
+    pub goodput_mbps: Option<f64>,
+    pub retransmits: u32,
+    pub remote_host: String,
+    /// Local address of the first connection, `None` if nothing connected.
+    pub local_host: Option<String>,
+    /// Local (source) port of the first connection, `None` if nothing connected.
+    pub local_port: Option<u16>,
+    /// Reverse-resolved name of `remote_host`, present when `RESOLVE_REMOTE_HOST` is enabled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote_hostname: Option<String>,
+    /// Stream with the most retransmits, `None` if no stream retransmitted.
+    pub worst_stream: Option<StreamRetransmits>,
+    /// `received_mbps / sent_mbps`, `None` when nothing was sent.
+    pub asymmetry_ratio: Option<f64>,
+    /// Difference from the baseline result, present when a baseline is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vs_baseline: Option<BaselineDelta>,
+    /// Stability of received throughput over the history, see [`stability_score`].
+    pub stability_score: Option<f64>,
+    /// Received throughput as a percentage of `LINK_CAPACITY_MBPS`, present when it is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub received_utilization_percent: Option<f64>,
+    /// Sent throughput as a percentage of `LINK_CAPACITY_MBPS`, present when it is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sent_utilization_percent: Option<f64>,
+    /// `RUN_ANNOTATION` attached to the result when it was cached.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotation: Option<String>,
+    /// Link speed of `LINK_INTERFACE` detected when the result was cached.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link_speed_mbps: Option<u64>,
+    /// iperf3 session cookie (`start.cookie`), for correlating with the server's logs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cookie: Option<String>,
+    /// Whether host CPU utilization exceeded `MAX_HOST_CPU_PERCENT`, making the throughput
+    /// CPU-bound rather than a measure of the link.
+    pub cpu_saturated: bool,
+    /// Whether the sent throughput met `SLA_MIN_SENT_MBPS`, present when it is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sla_sent_met: Option<bool>,
+    /// Whether the received throughput met `SLA_MIN_RECEIVED_MBPS` (or `SLA_MIN_MBPS`),
+    /// present when it is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sla_received_met: Option<bool>,
+    /// Whether every configured SLA threshold was met, present when any is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sla_met: Option<bool>,
+    /// Throughput of both directions, present for bidirectional (`--bidir`) tests.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bidir: Option<BidirSummary>,
+}
+
+impl Summary {
+    /// Records the detected `link_speed_mbps` and, unless `LINK_CAPACITY_MBPS` already
+    /// set them, derives the utilization figures from it.
+    pub fn apply_link_speed(&mut self, link_speed_mbps: u64) {
+        self.link_speed_mbps = Some(link_speed_mbps);
+        if self.received_utilization_percent.is_none() {
+            self.received_utilization_percent = Some(self.received_mbps / link_speed_mbps as f64 * 100.0);
+        }
+        if self.sent_utilization_percent.is_none() {
+            self.sent_utilization_percent = Some(self.sent_mbps / link_speed_mbps as f64 * 100.0);
+        }
+    }
+}
+
+/// Throughput of each direction of a bidirectional test, in Mbit/s.
+///
+/// The forward direction is client to server, as in a normal test; the reverse direction
+/// is server to client, as with `-R`. `sent_mbps` and `received_mbps` of [`Summary`] only
+/// describe the forward direction.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct BidirSummary {
+    pub forward_sent_mbps: f64,
+    pub forward_received_mbps: f64,
+    pub reverse_sent_mbps: f64,
+    pub reverse_received_mbps: f64,
+}
+
+/// Returns the throughput of both directions if `report` is of a bidirectional test.
+pub fn bidir_summary(report: &Iperf3Report) -> Option<BidirSummary> {
+    let end = &report.end;
+    let (reverse_sent, reverse_received) = (end.sum_sent_bidir_reverse.as_ref()?, end.sum_received_bidir_reverse.as_ref()?);
+    Some(BidirSummary {
+        forward_sent_mbps: bits_per_second_to_mbps(end.sum_sent.bits_per_second),
+        forward_received_mbps: bits_per_second_to_mbps(end.sum_received.bits_per_second),
+        reverse_sent_mbps: bits_per_second_to_mbps(reverse_sent.bits_per_second),
+        reverse_received_mbps: bits_per_second_to_mbps(reverse_received.bits_per_second),
+    })
+}
+
+/// Retransmits of a single parallel stream.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct StreamRetransmits {
+    pub socket: u32,
+    pub retransmits: u32,
+}
+
+/// Returns the sender-side retransmits of every stream in `end.streams`.
+pub fn stream_retransmits(report: &Iperf3Report) -> Vec<StreamRetransmits> {
+    report
+        .end
+        .streams
+        .iter()
+        .map(|stream| StreamRetransmits {
+            socket: stream.sender.socket,
+            retransmits: stream.sender.retransmits,
+        })
+        .collect()
+}
+
+/// Returns the iperf3 session cookie of `report`, `None` when iperf3 reported none.
+pub fn session_cookie(report: &Iperf3Report) -> Option<String> {
+    Some(report.start.cookie.clone()).filter(|cookie| !cookie.is_empty())
+}
+
+/// Returns the ratio of received to sent throughput, `None` when the sent rate is zero.
+///
+/// Values far from 1 flag links where one direction underperforms.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::{asymmetry_ratio, Iperf3Report};
+/// let mut report = Iperf3Report::default();
+/// assert_eq!(asymmetry_ratio(&report), None);
+/// report.end.sum_sent.bits_per_second = 400_000_000.0;
+/// report.end.sum_received.bits_per_second = 100_000_000.0;
+/// assert_eq!(asymmetry_ratio(&report), Some(0.25));
+/// ```
+pub fn asymmetry_ratio(report: &Iperf3Report) -> Option<f64> {
+    let sent = report.end.sum_sent.bits_per_second;
+    (sent > 0.0).then(|| report.end.sum_received.bits_per_second / sent)
+}
+
+/// MSS assumed when iperf3 does not report `start.tcp_mss_default`: a 1500-byte MTU less
+/// IPv4, TCP and timestamp option headers.
+pub const FALLBACK_MSS_BYTES: u32 = 1448;
+
+/// Returns the estimated goodput of `report` in Mbit/s: the received throughput scaled
+/// by the share of sent bytes that were not retransmissions.
+///
+/// iperf3 counts retransmitted segments, not bytes, so each retransmit is taken to be a
+/// full segment of `start.tcp_mss_default` bytes (or [`FALLBACK_MSS_BYTES`]):
+///
+/// `goodput = received × (1 − min(1, retransmits × mss / sent_bytes))`
+///
+/// Returns `None` when no bytes were sent.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::{goodput_mbps, Iperf3Report};
+/// let mut report = Iperf3Report::default();
+/// report.start.tcp_mss_default = 1000;
+/// report.end.sum_sent.bytes = 1_000_000;
+/// report.end.sum_sent.retransmits = 100;
+/// report.end.sum_received.bits_per_second = 800_000_000.0;
+/// assert_eq!(goodput_mbps(&report), Some(720.0));
+/// ```
+pub fn goodput_mbps(report: &Iperf3Report) -> Option<f64> {
+    let sent_bytes = report.end.sum_sent.bytes;
+    if sent_bytes == 0 {
+        return None;
+    }
+    let mss = match report.start.tcp_mss_default {
+        0 => FALLBACK_MSS_BYTES,
+        mss => mss,
+    };
+    let retransmitted_bytes = report.end.sum_sent.retransmits as f64 * mss as f64;
+    let retransmit_fraction = (retransmitted_bytes / sent_bytes as f64).min(1.0);
+    Some(bits_per_second_to_mbps(report.end.sum_received.bits_per_second) * (1.0 - retransmit_fraction))
+}
+
+/// Reads the environment variable `LINK_CAPACITY_MBPS`, the rated capacity of the link.
+///
+/// Returns `None` when unset or not a positive number, which omits utilization figures.
+pub fn link_capacity_mbps() -> Option<f64> {
+    env::var("LINK_CAPACITY_MBPS")
+        .ok()
+        .and_then(|s| s.trim().parse::<f64>().ok())
+        .filter(|n| n.is_finite() && *n > 0.0)
+}
+
+/// Returns `bits_per_second` as a percentage of a link capacity of `capacity_mbps`.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::utilization_percent;
+/// assert_eq!(utilization_percent(250_000_000.0, 1000.0), 25.0);
+/// ```
+pub fn utilization_percent(bits_per_second: f64, capacity_mbps: f64) -> f64 {
+    bits_per_second_to_mbps(bits_per_second) / capacity_mbps * 100.0
+}
+
+/// Scores how stable a throughput series is, from 0 (erratic) to 100 (constant).
+///
+/// The score is `100 / (1 + CV)`, where CV is the coefficient of variation: the
+/// population standard deviation divided by the mean. A constant series scores 100 and
+/// one whose standard deviation equals its mean scores 50.
+///
+/// Returns `None` for fewer than two samples or a zero mean.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::stability_score;
+/// assert_eq!(stability_score(&[500.0, 500.0, 500.0]), Some(100.0));
+/// assert_eq!(stability_score(&[500.0]), None);
+/// ```
+pub fn stability_score(samples: &[f64]) -> Option<f64> {
+    if samples.len() < 2 {
+        return None;
+    }
+    let n = samples.len() as f64;
+    let mean = samples.iter().sum::<f64>() / n;
+    if mean == 0.0 {
+        return None;
+    }
+    let variance = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+    let cv = variance.sqrt() / mean.abs();
+    Some(100.0 / (1.0 + cv))
+}
+
+/// Identifies the stream with the most retransmits, the first one on ties.
+///
+/// Returns `None` if there are no streams or none retransmitted.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::{worst_stream, EndStream, Iperf3Report};
+/// let mut report = Iperf3Report::default();
+/// for (socket, retransmits) in [(5, 2), (7, 40), (9, 3)] {
+///     let mut stream = EndStream::default();
+///     stream.sender.socket = socket;
+///     stream.sender.retransmits = retransmits;
+///     report.end.streams.push(stream);
+/// }
+/// assert_eq!(worst_stream(&report).unwrap().socket, 7);
+/// ```
+pub fn worst_stream(report: &Iperf3Report) -> Option<StreamRetransmits> {
+    stream_retransmits(report)
+        .into_iter()
+        .filter(|stream| stream.retransmits > 0)
+        .rev()
+        .max_by_key(|stream| stream.retransmits)
+}
+
+/// Reverse DNS lookups for the remote iperf3 host.
+pub trait HostResolver: Send + Sync {
+    /// Returns the hostname for `ip`, or `None` if it cannot be resolved.
+    fn reverse_lookup(&self, ip: IpAddr) -> Option<String>;
+}
+
+/// Resolver using the system's `getnameinfo`.
+pub struct SystemResolver;
+
+impl HostResolver for SystemResolver {
+    #[cfg(unix)]
+    fn reverse_lookup(&self, ip: IpAddr) -> Option<String> {
+        use std::ffi::CStr;
+        use std::mem;
+
+        // SAFETY: the socket addresses are zero-initialised plain C structs of the length
+        // passed, and `host` is a writable buffer whose length is passed alongside it.
+        unsafe {
+            let mut storage: libc::sockaddr_storage = mem::zeroed();
+            let len = match ip {
+                IpAddr::V4(v4) => {
+                    let sin = &mut *(&mut storage as *mut _ as *mut libc::sockaddr_in);
+                    sin.sin_family = libc::AF_INET as libc::sa_family_t;
+                    sin.sin_addr.s_addr = u32::from_ne_bytes(v4.octets());
+                    mem::size_of::<libc::sockaddr_in>()
+                }
+                IpAddr::V6(v6) => {
+                    let sin6 = &mut *(&mut storage as *mut _ as *mut libc::sockaddr_in6);
+                    sin6.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+                    sin6.sin6_addr.s6_addr = v6.octets();
+                    mem::size_of::<libc::sockaddr_in6>()
+                }
+            };
+
+            let mut host = [0 as libc::c_char; 1025];
+            let rc = libc::getnameinfo(
+                &storage as *const _ as *const libc::sockaddr,
+                len as libc::socklen_t,
+                host.as_mut_ptr(),
+                host.len() as libc::socklen_t,
+                std::ptr::null_mut(),
+                0,
+                libc::NI_NAMEREQD,
+            );
+            if rc != 0 {
+                return None;
+            }
+            CStr::from_ptr(host.as_ptr()).to_str().ok().map(str::to_string)
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn reverse_lookup(&self, _ip: IpAddr) -> Option<String> {
+        None
+    }
+}
+
+/// Lookups already performed, keyed by IP. Failures are cached as `None`.
+static HOSTNAME_CACHE: Lazy<Mutex<HashMap<IpAddr, Option<String>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Clears the reverse DNS cache.
+pub fn clear_hostname_cache_for_test() {
+    HOSTNAME_CACHE.lock().unwrap().clear();
+}
+
+/// Reads the environment variable `RESOLVE_REMOTE_HOST` (`true`/`1`), defaulting to disabled.
+pub fn resolve_remote_host_enabled() -> bool {
+    env::var("RESOLVE_REMOTE_HOST")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+/// Resolves `host` to a hostname through `resolver`, caching the outcome per IP.
+///
+/// Falls back to `host` itself if it is not an IP address or cannot be resolved.
+pub fn resolve_hostname(resolver: &dyn HostResolver, host: &str) -> String {
+    let Ok(ip) = host.parse::<IpAddr>() else {
+        return host.to_string();
+    };
+
+    if let Some(cached) = HOSTNAME_CACHE.lock().unwrap().get(&ip) {
+        return cached.clone().unwrap_or_else(|| host.to_string());
+    }
+    let resolved = resolver.reverse_lookup(ip);
+    HOSTNAME_CACHE.lock().unwrap().insert(ip, resolved.clone());
+    resolved.unwrap_or_else(|| host.to_string())
+}
+
+/// Builds the summary of `report`, resolving the remote host through `resolver` if given.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::{build_summary, Iperf3Report};
+/// let mut report = Iperf3Report::default();
+/// report.end.sum_received.bits_per_second = 941_000_000.0;
+/// let summary = build_summary(&report, None);
+/// assert_eq!(summary.received_mbps, 941.0);
+/// assert_eq!(summary.remote_hostname, None);
+/// ```
+pub fn build_summary(report: &Iperf3Report, resolver: Option<&dyn HostResolver>) -> Summary {
+    let remote_host = report
+        .start
+        .connected
+        .first()
+        .map(|c| c.remote_host.clone())
+        .unwrap_or_default();
+    let remote_hostname = resolver.map(|resolver| resolve_hostname(resolver, &remote_host));
+    let local = report.start.connected.first();
+    let capacity = link_capacity_mbps();
+    let sent_mbps = bits_per_second_to_mbps(report.end.sum_sent.bits_per_second);
+    let received_mbps = bits_per_second_to_mbps(report.end.sum_received.bits_per_second);
+    let sla = SlaOutcome::evaluate(sent_mbps, received_mbps, sla_min_sent_mbps(), sla_min_received_mbps());
+
+    Summary {
+        timestamp: report.start.timestamp.timesecs,
+        sent_mbps,
+        received_mbps,
+        goodput_mbps: goodput_mbps(report),
+        retransmits: report.end.sum_sent.retransmits,
+        remote_host,
+        local_host: local.map(|c| c.local_host.clone()),
+        local_port: local.map(|c| c.local_port),
+        remote_hostname,
+        worst_stream: worst_stream(report),
+        asymmetry_ratio: asymmetry_ratio(report),
+        vs_baseline: None,
+        stability_score: None,
+        received_utilization_percent: capacity
+            .map(|capacity| utilization_percent(report.end.sum_received.bits_per_second, capacity)),
+        sent_utilization_percent: capacity.map(|capacity| utilization_percent(report.end.sum_sent.bits_per_second, capacity)),
+        annotation: None,
+        link_speed_mbps: None,
+        cookie: session_cookie(report),
+        cpu_saturated: max_host_cpu_percent().is_some_and(|max_percent| cpu_saturated(report, max_percent)),
+        sla_sent_met: sla.sent_met,
+        sla_received_met: sla.received_met,
+        sla_met: sla.met(),
+        bidir: bidir_summary(report),
+    }
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+/// HTTP GET endpoint `/summary` returns the headline figures of the latest result as JSON.
+///
+/// When `RESOLVE_REMOTE_HOST` is enabled the remote host is reverse-resolved (off the
+/// async runtime) and included as `remote_hostname`.
+///
+/// Returns HTTP 503 Service Unavailable if no result is cached yet.
+///
+/// Returns HTTP 503 with `Retry-After` while maintenance mode is enabled.
+///
+/// Results older than `STALE_AFTER_SECONDS` yield HTTP 503 for anonymous clients, while
+/// authenticated clients still get them flagged with `X-Stale: true`.
+///
+/// `stability_score` rates the received throughput over the history buffer. When
+/// `LINK_CAPACITY_MBPS` is set, `received_utilization_percent` and `sent_utilization_percent`
+/// give the throughput as a percentage of it. Otherwise they are derived from the link
+/// speed of `LINK_INTERFACE`, included as `link_speed_mbps` when detected.
+///
+/// With `REDACT_TARGET` enabled the remote host is redacted for anonymous clients.
+///
+/// When a baseline is set the difference from it is included as `vs_baseline`, and the
+/// `RUN_ANNOTATION` of the cached result as `annotation`.
+///
+/// Responses carry `Last-Modified` set to when the result was cached, and a request whose
+/// `If-Modified-Since` is not older than that gets HTTP 304 Not Modified.
+#[get("/summary")]
+pub async fn iperf3_summary(req: HttpRequest) -> Result<HttpResponse, Iperf3Error> {
+    ensure_not_in_maintenance()?;
+    let (report, cached_at) = primary_result().ok_or_else(Iperf3Error::not_available)?;
+
+    let mut response = HttpResponse::Ok();
+    let stale = check_staleness(&req, cached_at.elapsed())?;
+    if stale {
+        response.insert_header((X_STALE, "true"));
+    }
+
+    if let Some(cached) = last_cached_at() {
+        // HTTP dates have one-second resolution, so compare whole seconds
+        let modified = HttpDate::from(cached);
+        if let Some(IfModifiedSince(since)) = req.get_header::<IfModifiedSince>()
+            && unix_seconds(cached) <= unix_seconds(since.into())
+        {
+            let mut not_modified = HttpResponse::NotModified();
+            if stale {
+                not_modified.insert_header((X_STALE, "true"));
+            }
+            return Ok(not_modified.insert_header(LastModified(modified)).finish());
+        }
+        response.insert_header(LastModified(modified));
+    }
+
+    let vs_baseline = get_baseline().map(|baseline| baseline_delta(&report, &baseline));
+    let mut summary = if resolve_remote_host_enabled() {
+        web::block(move || build_summary(&report, Some(&SystemResolver)))
+            .await
+            .map_err(|e| Iperf3Error::Internal(e.to_string()))?
+    } else {
+        build_summary(&report, None)
+    };
+    summary.vs_baseline = vs_baseline;
+    let received: Vec<f64> = get_history().iter().map(|r| r.end.sum_received.bits_per_second).collect();
+    summary.stability_score = stability_score(&received);
+    let metadata = last_cache_metadata();
+    if let Some(speed) = metadata.as_ref().and_then(|metadata| metadata.link_speed_mbps) {
+        summary.apply_link_speed(speed);
+    }
+    summary.annotation = metadata.and_then(|metadata| metadata.annotation);
+    Ok(response.json(public_json(&req, &summary)?))
+}
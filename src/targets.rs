@@ -0,0 +1,350 @@
+//! # iperf3-statuspage
+//!
+//! iperf3 targets and bounded concurrent execution across them.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use actix_web::{get, web, HttpRequest, HttpResponse};
+use futures::future::join_all;
+use once_cell::sync::Lazy;
+use tokio::sync::Semaphore;
+use tracing::{error, warn};
+use crate::command::Iperf3Options;
+use crate::errors::Iperf3Error;
+use crate::interfaces::{bind_addresses, bound_targets};
+use crate::maintenance::ensure_not_in_maintenance;
+use crate::redact::public_json;
+use crate::stale::{check_staleness, X_CACHE_AGE_SECONDS, X_STALE};
+use crate::{run_iperf3_and_cache_with_options, Iperf3Report, Iperf3Runner};
+
+/// An iperf3 server to run tests against, with the options used for its runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    pub host: String,
+    pub port: String,
+    pub options: Iperf3Options,
+}
+
+impl Target {
+    /// Creates a target for the given iperf3 server using the global options from the environment.
+    pub fn new(host: impl Into<String>, port: impl Into<String>) -> Self {
+        Target::with_options(Iperf3Options::from_env(host, port))
+    }
+
+    /// Creates a target running with its own `options`.
+    pub fn with_options(options: Iperf3Options) -> Self {
+        Target {
+            host: options.host.clone(),
+            port: options.port.clone(),
+            options,
+        }
+    }
+
+    /// Returns the `host:port` key (or socket path) identifying this target.
+    pub fn key(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.options.unix_socket_path() {
+            Some(path) => f.write_str(path),
+            None => write!(f, "{}:{}", self.host, self.port),
+        }
+    }
+}
+
+/// Loads targets and their per-target options from a JSON file.
+///
+/// The file holds an array of objects with `host`, `port` and optional
+/// `parallel`, `reverse`, `udp`, `bitrate`, `duration` and `bind` fields, e.g.
+/// `[{"host": "10.0.0.1", "port": 5201, "udp": true}, {"host": "10.0.0.2", "port": 5201, "reverse": true}]`.
+/// Options not given for a target use iperf3's defaults rather than the global ones.
+pub fn load_targets_file(path: &Path) -> Result<Vec<Target>, String> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let options: Vec<Iperf3Options> =
+        serde_json::from_str(&contents).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+    if options.is_empty() {
+        return Err(format!("{} does not list any targets", path.display()));
+    }
+    Ok(options.into_iter().map(Target::with_options).collect())
+}
+
+/// Splits comma-separated `IPERF3_SERVER_IP` and `IPERF3_SERVER_PORT` values into
+/// `(host, port)` pairs, in the order given.
+///
+/// A single port applies to every host; otherwise there must be one port per host.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::server_list;
+/// let servers = server_list("10.0.0.1, 10.0.0.2", "5201").unwrap();
+/// assert_eq!(servers, [("10.0.0.1".to_string(), "5201".to_string()), ("10.0.0.2".to_string(), "5201".to_string())]);
+/// assert_eq!(server_list("10.0.0.1,10.0.0.2", "5201,5202").unwrap()[1].1, "5202");
+/// assert!(server_list("10.0.0.1,10.0.0.2", "5201,5202,5203").is_err());
+/// ```
+pub fn server_list(ips: &str, ports: &str) -> Result<Vec<(String, String)>, String> {
+    let hosts: Vec<&str> = ips.split(',').map(str::trim).filter(|h| !h.is_empty()).collect();
+    let ports: Vec<&str> = ports.split(',').map(str::trim).collect();
+    if hosts.is_empty() {
+        return Err("IPERF3_SERVER_IP does not list any server".to_string());
+    }
+    if ports.len() != 1 && ports.len() != hosts.len() {
+        return Err(format!(
+            "IPERF3_SERVER_PORT must list one port or one per server ({}), got {}",
+            hosts.len(),
+            ports.len()
+        ));
+    }
+    Ok(hosts
+        .iter()
+        .enumerate()
+        .map(|(i, host)| (host.to_string(), ports[i.min(ports.len() - 1)].to_string()))
+        .collect())
+}
+
+/// Resolves the targets the scheduler runs against.
+///
+/// Reads the environment variable `TARGETS_FILE`; if set and valid its targets are used,
+/// otherwise the servers listed in `iperf3_ip` and `iperf3_port` (see [`server_list`]) are
+/// the targets. A broken file is logged and ignored.
+/// With `IPERF3_BIND_ADDRESSES` each target is run once per address, see [`bound_targets`].
+pub fn configured_targets(iperf3_ip: String, iperf3_port: String) -> Vec<Target> {
+    let targets = env::var("TARGETS_FILE")
+        .ok()
+        .and_then(|path| match load_targets_file(Path::new(&path)) {
+            Ok(targets) => Some(targets),
+            Err(e) => {
+                warn!("{}; falling back to IPERF3_SERVER_IP/IPERF3_SERVER_PORT", e);
+                None
+            }
+        })
+        .unwrap_or_else(|| match server_list(&iperf3_ip, &iperf3_port) {
+            Ok(servers) => servers.into_iter().map(|(host, port)| Target::new(host, port)).collect(),
+            Err(e) => {
+                error!("{}; not running any target", e);
+                Vec::new()
+            }
+        });
+    bound_targets(targets, &bind_addresses())
+}
+
+/// Latest result of each target, keyed by [`Target::key`], and when it was cached.
+static TARGET_RESULTS: Lazy<Mutex<HashMap<String, (Iperf3Report, Instant)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Key of the primary target, whose result `/iperf3` serves.
+static PRIMARY_TARGET: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
+/// Caches `report` as the latest result of the target identified by `key`.
+pub fn cache_target_result(key: &str, report: Iperf3Report) {
+    TARGET_RESULTS.lock().unwrap().insert(key.to_string(), (report, Instant::now()));
+}
+
+/// Returns the latest result of the target identified by `key` and when it was cached.
+pub fn get_target_result(key: &str) -> Option<(Iperf3Report, Instant)> {
+    TARGET_RESULTS.lock().unwrap().get(key).cloned()
+}
+
+/// Sets (or with `None` clears) the primary target, the first configured one.
+pub fn set_primary_target(key: Option<String>) {
+    *PRIMARY_TARGET.lock().unwrap() = key;
+}
+
+/// Returns the latest result of the primary target, if one is set and has a result.
+pub fn get_primary_target_result() -> Option<(Iperf3Report, Instant)> {
+    let key = PRIMARY_TARGET.lock().unwrap().clone()?;
+    get_target_result(&key)
+}
+
+/// Returns whether `key` identifies the primary target, or no primary target is set.
+///
+/// The history and the smoothed metrics only record runs of the primary target.
+pub fn is_primary_target(key: &str) -> bool {
+    PRIMARY_TARGET.lock().unwrap().as_deref().is_none_or(|primary| primary == key)
+}
+
+/// Clears the per-target results and the primary target.
+pub fn clear_target_results_for_test() {
+    TARGET_RESULTS.lock().unwrap().clear();
+    set_primary_target(None);
+}
+
+/// HTTP GET endpoint `/iperf3/{host}/{port}` returns the latest result of that target as
+/// JSON, carrying its age in `X-Cache-Age-Seconds`.
+///
+/// Returns HTTP 503 Service Unavailable if the target has no cached result yet.
+///
+/// Returns HTTP 503 with `Retry-After` while maintenance mode is enabled, and stale
+/// results are handled as for `/iperf3`.
+#[get("/iperf3/{host}/{port}")]
+pub async fn iperf3_target(req: HttpRequest, path: web::Path<(String, String)>) -> Result<HttpResponse, Iperf3Error> {
+    ensure_not_in_maintenance()?;
+    let (host, port) = path.into_inner();
+    let key = Target::new(host, port).key();
+    let (report, cached_at) = get_target_result(&key)
+        .ok_or_else(|| Iperf3Error::NotAvailable(format!("No result for target {} yet.", key)))?;
+
+    let age = cached_at.elapsed();
+    let mut response = HttpResponse::Ok();
+    response.insert_header((X_CACHE_AGE_SECONDS, age.as_secs()));
+    if check_staleness(&req, age)? {
+        response.insert_header((X_STALE, "true"));
+    }
+    Ok(response.json(public_json(&req, &report)?))
+}
+
+/// Per-target locks guaranteeing a target never runs concurrently with itself.
+///
+/// Shared process-wide so overlapping cycles and on-demand runs are serialized too.
+static TARGET_LOCKS: Lazy<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Returns the lock serializing runs against `target`.
+pub fn target_lock(target: &Target) -> Arc<tokio::sync::Mutex<()>> {
+    TARGET_LOCKS
+        .lock()
+        .unwrap()
+        .entry(target.key())
+        .or_default()
+        .clone()
+}
+
+/// Reads the environment variable `MAX_CONCURRENT_RUNS` or returns a default of 2.
+///
+/// This bounds how many iperf3 processes run at once across all targets.
+pub fn max_concurrent_runs() -> usize {
+    env::var("MAX_CONCURRENT_RUNS")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(2)
+}
+
+/// Reads the environment variable `MULTI_SERVER_STAGGER_MS` or returns a default of 0.
+///
+/// Each target's run starts this much later than the previous one's, so a cycle does
+/// not start every iperf3 process at the same instant.
+pub fn multi_server_stagger() -> Duration {
+    let millis = env::var("MULTI_SERVER_STAGGER_MS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(0);
+    Duration::from_millis(millis)
+}
+
+/// Returns the start offset of each of `count` targets within a cycle: `i * stagger`.
+///
+/// # Examples
+///
+/// ```
+/// # use std::time::Duration;
+/// # use iperf3_statuspage::stagger_offsets;
+/// let offsets = stagger_offsets(3, Duration::from_millis(250));
+/// assert_eq!(offsets, [Duration::ZERO, Duration::from_millis(250), Duration::from_millis(500)]);
+/// ```
+pub fn stagger_offsets(count: usize, stagger: Duration) -> Vec<Duration> {
+    (0..count as u32).map(|i| stagger * i).collect()
+}
+
+/// Runs one measurement per target using the provided runner, at most `max_concurrent` at a time.
+///
+/// A target listed more than once (or already being measured elsewhere) waits for its
+/// previous run to finish. A failing target does not affect the others. Results are
+/// returned in the same order as `targets`. Starts are staggered by `MULTI_SERVER_STAGGER_MS`,
+/// see [`run_targets_staggered`].
+pub async fn run_targets_with_runner(
+    runner: &dyn Iperf3Runner,
+    targets: &[Target],
+    max_concurrent: usize,
+) -> Vec<Result<Iperf3Report, Iperf3Error>> {
+    run_targets_staggered(runner, targets, max_concurrent, multi_server_stagger()).await
+}
+
+/// Like [`run_targets_with_runner`], but the `i`th target waits `i * stagger` before
+/// queueing for its lock and a concurrency permit.
+pub async fn run_targets_staggered(
+    runner: &dyn Iperf3Runner,
+    targets: &[Target],
+    max_concurrent: usize,
+    stagger: Duration,
+) -> Vec<Result<Iperf3Report, Iperf3Error>> {
+    let semaphore = Semaphore::new(max_concurrent.max(1));
+    let offsets = stagger_offsets(targets.len(), stagger);
+    join_all(targets.iter().zip(offsets).map(|(target, offset)| {
+        let semaphore = &semaphore;
+        async move {
+            if !offset.is_zero() {
+                tokio::time::sleep(offset).await;
+            }
+            let lock = target_lock(target);
+            let _guard = lock.lock().await;
+            let _permit = semaphore.acquire().await.expect("semaphore is never closed");
+            run_iperf3_and_cache_with_options(runner, &target.options).await
+        }
+    }))
+    .await
+}
+
+/// Reads the environment variable `SERVER_BUSY_RETRY_SECONDS` or returns a default of 30 seconds.
+///
+/// This is how long the scheduler waits before retrying a target whose server was busy.
+pub fn server_busy_retry_delay() -> Duration {
+    let seconds = env::var("SERVER_BUSY_RETRY_SECONDS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(30);
+    Duration::from_secs(seconds)
+}
+
+/// How many times a busy target is retried within one cycle.
+pub const MAX_SERVER_BUSY_RETRIES: usize = 3;
+
+/// Runs one measurement per target like [`run_targets_with_runner`], then retries the
+/// targets that failed with [`Iperf3Error::ServerBusy`] after `retry_delay`, up to
+/// [`MAX_SERVER_BUSY_RETRIES`] times, instead of leaving them until the next interval.
+///
+/// Returns the final result per target, in order.
+pub async fn run_targets_retrying_busy(
+    runner: &dyn Iperf3Runner,
+    targets: &[Target],
+    max_concurrent: usize,
+    retry_delay: Duration,
+) -> Vec<Result<Iperf3Report, Iperf3Error>> {
+    let mut results = run_targets_with_runner(runner, targets, max_concurrent).await;
+    for _ in 0..MAX_SERVER_BUSY_RETRIES {
+        let busy: Vec<usize> = results
+            .iter()
+            .enumerate()
+            .filter(|(_, result)| matches!(result, Err(Iperf3Error::ServerBusy(_))))
+            .map(|(i, _)| i)
+            .collect();
+        if busy.is_empty() {
+            break;
+        }
+
+        warn!("{} target(s) busy, retrying in {} seconds", busy.len(), retry_delay.as_secs());
+        tokio::time::sleep(retry_delay).await;
+        let busy_targets: Vec<Target> = busy.iter().map(|&i| targets[i].clone()).collect();
+        let retried = run_targets_with_runner(runner, &busy_targets, max_concurrent).await;
+        for (i, result) in busy.into_iter().zip(retried) {
+            results[i] = result;
+        }
+    }
+    results
+}
@@ -0,0 +1,154 @@
+//! # iperf3-statuspage
+//!
+//! Per-phase timing of measurement cycles, exposed at `/debug/timing`.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::cell::Cell;
+use std::env;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Instant;
+use actix_web::{get, HttpResponse};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use crate::errors::Iperf3Error;
+
+/// Reads the environment variable `PHASE_TIMING` (`true`/`1`), defaulting to disabled.
+///
+/// When enabled the phase breakdown of every measurement cycle is recorded for
+/// `/debug/timing`.
+pub fn phase_timing_enabled() -> bool {
+    env::var("PHASE_TIMING")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+/// Duration of a single phase of a measurement cycle.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PhaseTiming {
+    pub phase: String,
+    /// Attempt of the cycle the phase belongs to: 1, or 2 for a retry after a parse failure.
+    pub attempt: u32,
+    pub millis: f64,
+}
+
+/// Phase breakdown of one measurement cycle, in the order the phases ran.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct CycleTiming {
+    pub phases: Vec<PhaseTiming>,
+    pub total_millis: f64,
+}
+
+/// Phase breakdown of the most recent measurement cycle.
+pub static LAST_TIMING: Lazy<Mutex<Option<CycleTiming>>> = Lazy::new(|| Mutex::new(None));
+
+tokio::task_local! {
+    /// When the iperf3 child of the run tracked by [`track_spawn`] started.
+    static CHILD_SPAWNED: Cell<Option<Instant>>;
+}
+
+/// Notes that the iperf3 child of the current run has started, splitting its timing into
+/// `spawn` and `test_run`. Does nothing outside [`track_spawn`].
+pub fn mark_child_spawned() {
+    let _ = CHILD_SPAWNED.try_with(|spawned| spawned.set(Some(Instant::now())));
+}
+
+/// Awaits `run`, returning its output and when it marked its child as started, if it did.
+pub async fn track_spawn<F: Future>(run: F) -> (F::Output, Option<Instant>) {
+    CHILD_SPAWNED
+        .scope(Cell::new(None), async {
+            let output = run.await;
+            (output, CHILD_SPAWNED.with(Cell::get))
+        })
+        .await
+}
+
+/// Records consecutive phases of a cycle, each measured from the end of the previous one.
+pub struct PhaseTimer {
+    started: Instant,
+    mark: Instant,
+    attempt: u32,
+    phases: Vec<PhaseTiming>,
+}
+
+impl PhaseTimer {
+    /// Starts timing a new cycle.
+    pub fn start() -> Self {
+        let now = Instant::now();
+        PhaseTimer { started: now, mark: now, attempt: 1, phases: Vec::new() }
+    }
+
+    /// Starts the next attempt of the cycle; later phases are recorded under it.
+    pub fn next_attempt(&mut self) {
+        self.attempt += 1;
+    }
+
+    /// Records the time elapsed since the previous phase under `phase`.
+    pub fn record(&mut self, phase: &str) {
+        self.record_until(phase, Instant::now());
+    }
+
+    /// Records a run of iperf3 as `spawn` until its child started at `spawned` and
+    /// `test_run` from then on. A run whose child never started is all `spawn`.
+    pub fn record_run(&mut self, spawned: Option<Instant>) {
+        if let Some(spawned) = spawned {
+            self.record_until("spawn", spawned);
+            self.record("test_run");
+        } else {
+            self.record("spawn");
+        }
+    }
+
+    fn record_until(&mut self, phase: &str, end: Instant) {
+        self.phases.push(PhaseTiming {
+            phase: phase.to_string(),
+            attempt: self.attempt,
+            millis: end.saturating_duration_since(self.mark).as_secs_f64() * 1000.0,
+        });
+        self.mark = end;
+    }
+
+    /// Returns the recorded phases, storing them as the last cycle's timing when
+    /// `PHASE_TIMING` is enabled.
+    pub fn finish(self) -> CycleTiming {
+        let timing = CycleTiming {
+            phases: self.phases,
+            total_millis: self.started.elapsed().as_secs_f64() * 1000.0,
+        };
+        if phase_timing_enabled() {
+            *LAST_TIMING.lock().unwrap() = Some(timing.clone());
+        }
+        timing
+    }
+}
+
+/// Retrieves the phase breakdown of the last measurement cycle, if any ran yet.
+pub fn get_last_timing() -> Option<CycleTiming> {
+    LAST_TIMING.lock().unwrap().clone()
+}
+
+/// Clears the recorded cycle timing.
+pub fn clear_last_timing_for_test() {
+    *LAST_TIMING.lock().unwrap() = None;
+}
+
+/// HTTP GET endpoint `/debug/timing` returns the last cycle's phase breakdown as JSON.
+///
+/// Returns HTTP 503 Service Unavailable if `PHASE_TIMING` is disabled or no cycle has run
+/// yet.
+#[get("/debug/timing")]
+pub async fn debug_timing() -> Result<HttpResponse, Iperf3Error> {
+    if !phase_timing_enabled() {
+        return Err(Iperf3Error::NotAvailable("Phase timing is disabled; set PHASE_TIMING=true to record it.".to_string()));
+    }
+    let timing = get_last_timing()
+        .ok_or_else(|| Iperf3Error::NotAvailable("No iperf3 cycle has run yet.".to_string()))?;
+    Ok(HttpResponse::Ok().json(timing))
+}
@@ -0,0 +1,57 @@
+//! # iperf3-statuspage
+//!
+//! Build metadata embedded by the build script.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use actix_web::{get, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+
+/// Metadata identifying the running build, as reported by `/version`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BuildInfo {
+    /// Crate version from `Cargo.toml`.
+    pub version: String,
+    /// Abbreviated git SHA of the build, `None` if built outside a git checkout.
+    pub git_sha: Option<String>,
+    /// Unix time the binary was built (or `SOURCE_DATE_EPOCH`).
+    pub build_timestamp: Option<u64>,
+    /// `version+sha`, or just the version without a SHA.
+    pub build_id: String,
+}
+
+/// Returns the metadata of the running build.
+///
+/// # Examples
+///
+/// ```
+/// # use iperf3_statuspage::build_info;
+/// let info = build_info();
+/// assert!(info.build_id.starts_with(env!("CARGO_PKG_VERSION")));
+/// ```
+pub fn build_info() -> BuildInfo {
+    let version = env!("CARGO_PKG_VERSION").to_string();
+    let git_sha = option_env!("IPERF3_STATUSPAGE_GIT_SHA").map(str::to_string);
+    let build_id = match &git_sha {
+        Some(sha) => format!("{}+{}", version, sha),
+        None => version.clone(),
+    };
+    BuildInfo {
+        version,
+        git_sha,
+        build_timestamp: option_env!("IPERF3_STATUSPAGE_BUILD_TIMESTAMP").and_then(|s| s.parse().ok()),
+        build_id,
+    }
+}
+
+/// HTTP GET endpoint `/version` returns the build metadata as JSON.
+#[get("/version")]
+pub async fn iperf3_version() -> impl Responder {
+    HttpResponse::Ok().json(build_info())
+}
@@ -0,0 +1,61 @@
+//! # iperf3-statuspage
+//!
+//! Deployment self-description at `/whoami`: the configured target, the local source
+//! address of the last run, the instance label and the version.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+use std::env;
+use actix_web::{get, HttpRequest, HttpResponse};
+use serde::{Deserialize, Serialize};
+use crate::get_last_result;
+use crate::on_demand::server_from_env;
+use crate::redact::{should_redact, REDACTED};
+use crate::targets::Target;
+
+/// Reads the environment variable `INSTANCE_LABEL`, a free-form name identifying this
+/// deployment. Returns `None` when unset.
+pub fn instance_label() -> Option<String> {
+    env::var("INSTANCE_LABEL").ok().filter(|s| !s.trim().is_empty())
+}
+
+/// Effective configuration of this instance as reported by `/whoami`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Whoami {
+    /// `host:port` (or socket path) of the primary target, `None` if none is configured.
+    pub target: Option<String>,
+    /// Local address the last cached run connected from, `None` before the first result.
+    pub source_host: Option<String>,
+    pub source_port: Option<u16>,
+    pub instance: Option<String>,
+    pub version: String,
+}
+
+/// Builds the `/whoami` view from the environment and the cached result, replacing the
+/// target with [`REDACTED`] if `redact` is set.
+pub fn whoami(redact: bool) -> Whoami {
+    let target = server_from_env().ok().map(|(ip, port)| Target::new(ip, port).key());
+    let source = get_last_result().and_then(|report| report.start.connected.into_iter().next());
+    Whoami {
+        target: target.map(|target| if redact { REDACTED.to_string() } else { target }),
+        source_host: source.as_ref().map(|c| c.local_host.clone()),
+        source_port: source.map(|c| c.local_port),
+        instance: instance_label(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+    }
+}
+
+/// HTTP GET endpoint `/whoami` returns the configured target, the local source address of
+/// the last run, `INSTANCE_LABEL` and the version as JSON.
+///
+/// With `REDACT_TARGET` enabled the target is redacted for anonymous clients.
+#[get("/whoami")]
+pub async fn iperf3_whoami(req: HttpRequest) -> HttpResponse {
+    HttpResponse::Ok().json(whoami(should_redact(&req)))
+}
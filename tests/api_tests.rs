@@ -73,7 +73,11 @@ fn dummy_result() -> Iperf3Report {
                 bytes: 0,
                 bits_per_second: 0.0,
                 retransmits: 0,
-                sender: false
+                sender: false,
+                jitter_ms: None,
+                lost_packets: None,
+                packets: None,
+                lost_percent: None
             },
             sum_received: SumReceived {
                 start: 0.0,
@@ -81,7 +85,11 @@ fn dummy_result() -> Iperf3Report {
                 seconds: 0.0,
                 bytes: 0,
                 bits_per_second: 0.0,
-                sender: false
+                sender: false,
+                jitter_ms: None,
+                lost_packets: None,
+                packets: None,
+                lost_percent: None
             },
             cpu_utilization_percent: CpuUtilizationPercent {
                 host_total: 0.0,
@@ -91,9 +99,12 @@ fn dummy_result() -> Iperf3Report {
                 remote_user: 0.0,
                 remote_system: 0.0,
             },
+            sum_sent_bidir_reverse: None,
+            sum_received_bidir_reverse: None,
             sender_tcp_congestion: "".to_string(),
             receiver_tcp_congestion: "".to_string()
-        }
+        },
+        extra: Default::default(),
     }
 }
 
@@ -110,6 +121,7 @@ fn dummy_result() -> Iperf3Report {
 #[serial]
 async fn iperf3_returns_service_unavailable_when_no_cache() {
     clear_last_result_for_test();
+    clear_last_error_for_test();
 
     let app = test::init_service(App::new().service(iperf3)).await;
     let req = test::TestRequest::get().uri("/iperf3").to_request();
@@ -121,6 +133,46 @@ async fn iperf3_returns_service_unavailable_when_no_cache() {
     assert_eq!(body, "Iperf3 result not available yet.");
 }
 
+/// Test that `/iperf3` tells a cold start (503) apart from failing runs (502 with the
+/// iperf3 error), and that `get_cached_iperf3_result` reports the same distinction.
+#[actix_web::test]
+#[serial]
+async fn iperf3_distinguishes_never_run_from_failing() {
+    struct FailingRunner;
+
+    #[async_trait::async_trait]
+    impl Iperf3Runner for FailingRunner {
+        async fn run_iperf3(&self, _iperf3_ip: String, _iperf3_port: String) -> Result<String, Iperf3Error> {
+            Err(Iperf3Error::NonZeroExit { code: Some(1), stderr: "unable to connect to server".to_string() })
+        }
+    }
+
+    clear_last_result_for_test();
+    clear_last_error_for_test();
+    assert_eq!(get_cached_iperf3_result().await.unwrap_err(), CacheError::NeverRun);
+
+    unsafe { std::env::set_var("IPERF3_MAX_RETRIES", "0") };
+    let result = run_iperf3_and_cache_with_runner(&FailingRunner, "127.0.0.1".into(), "5201".into()).await;
+    unsafe { std::env::remove_var("IPERF3_MAX_RETRIES") };
+    let message = result.unwrap_err().to_string();
+    assert_eq!(get_cached_iperf3_result().await.unwrap_err(), CacheError::Failing(message.clone()));
+
+    let app = test::init_service(App::new().service(iperf3)).await;
+    let req = test::TestRequest::get().uri("/iperf3").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::BAD_GATEWAY);
+    let body = test::read_body(resp).await;
+    assert_eq!(body, format!("No iperf3 result yet; the last run failed: {}", message));
+
+    // A cached result is still served while later runs fail
+    set_last_result_for_test(Iperf3Report::default());
+    let req = test::TestRequest::get().uri("/iperf3").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), http::StatusCode::OK);
+
+    clear_last_result_for_test();
+    clear_last_error_for_test();
+}
+
 /// Test that the `/iperf3` endpoint returns the cached iperf3 result
 /// as JSON with HTTP 200 OK status.
 ///
@@ -167,6 +219,7 @@ async fn iperf3_returns_cached_result() {
 /// assert!(get_last_result().is_none());
 /// ```
 #[tokio::test]
+#[serial]
 async fn test_set_get_clear_last_result_for_test() {
     clear_last_result_for_test();
     assert!(get_last_result().is_none());
@@ -178,4 +231,301 @@ async fn test_set_get_clear_last_result_for_test() {
 
     clear_last_result_for_test();
     assert!(get_last_result().is_none());
-}
\ No newline at end of file
+}
+/// Test that `/iperf3/download` serves the serialized report as an attachment
+/// named after the report timestamp when no raw output is stored.
+#[actix_web::test]
+#[serial]
+async fn iperf3_download_serves_attachment() {
+    let mut res = dummy_result();
+    res.start.timestamp.timesecs = 1754995182;
+    set_last_result_for_test(res);
+
+    let app = test::init_service(App::new().service(iperf3_download)).await;
+    let req = test::TestRequest::get().uri("/iperf3/download").to_request();
+    let resp = test::call_service(&app, req).await;
+
+    assert_eq!(resp.status(), http::StatusCode::OK);
+    let disposition = resp.headers().get(http::header::CONTENT_DISPOSITION).unwrap().to_str().unwrap();
+    assert_eq!(disposition, "attachment; filename=\"iperf3-1754995182.json\"");
+
+    let body = test::read_body(resp).await;
+    let result: Iperf3Report = serde_json::from_slice(&body).unwrap();
+    assert_eq!(result.start.timestamp.timesecs, 1754995182);
+
+    clear_last_result_for_test();
+}
+
+/// Test that `/iperf3/download` serves the raw iperf3 output verbatim after a run.
+#[actix_web::test]
+#[serial]
+async fn iperf3_download_prefers_raw_output() {
+    struct RawRunner(String);
+
+    #[async_trait::async_trait]
+    impl Iperf3Runner for RawRunner {
+        async fn run_iperf3(&self, _iperf3_ip: String, _iperf3_port: String) -> Result<String, Iperf3Error> {
+            Ok(self.0.clone())
+        }
+    }
+
+    let raw = serde_json::to_string(&dummy_result()).unwrap();
+    run_iperf3_and_cache_with_runner(&RawRunner(raw.clone()), "127.0.0.1".into(), "5201".into()).await.unwrap();
+
+    let app = test::init_service(App::new().service(iperf3_download)).await;
+    let req = test::TestRequest::get().uri("/iperf3/download").to_request();
+    let resp = test::call_service(&app, req).await;
+
+    assert_eq!(resp.status(), http::StatusCode::OK);
+    let disposition = resp.headers().get(http::header::CONTENT_DISPOSITION).unwrap().to_str().unwrap();
+    assert!(disposition.starts_with("attachment; filename=\"iperf3-"));
+    assert!(disposition.ends_with(".json\""));
+
+    let body = test::read_body(resp).await;
+    assert_eq!(body, raw.as_bytes());
+
+    clear_last_result_for_test();
+}
+
+/// Test that `/iperf3/download` returns 503 when there is no cached result.
+#[actix_web::test]
+#[serial]
+async fn iperf3_download_unavailable_without_cache() {
+    clear_last_result_for_test();
+
+    let app = test::init_service(App::new().service(iperf3_download)).await;
+    let req = test::TestRequest::get().uri("/iperf3/download").to_request();
+    let resp = test::call_service(&app, req).await;
+
+    assert_eq!(resp.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+}
+
+/// Test that `HEAD /iperf3` returns the status and headers of a GET with an empty body.
+#[actix_web::test]
+#[serial]
+async fn iperf3_head_returns_headers_without_body() {
+    let app = test::init_service(App::new().service(iperf3).service(iperf3_head)).await;
+
+    clear_last_result_for_test();
+    let req = test::TestRequest::default().method(http::Method::HEAD).uri("/iperf3").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+
+    unsafe {
+        std::env::set_var("STALE_AFTER_SECONDS", "300");
+        std::env::set_var("API_TOKEN", "s3cret");
+    }
+    *LAST_RESULT.lock().unwrap() =
+        Some((dummy_result(), std::time::Instant::now() - std::time::Duration::from_secs(600)));
+    let req = test::TestRequest::default()
+        .method(http::Method::HEAD)
+        .uri("/iperf3")
+        .insert_header((http::header::AUTHORIZATION, "Bearer s3cret"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+    assert_eq!(resp.headers().get(http::header::CONTENT_TYPE).unwrap(), "application/json");
+    assert_eq!(resp.headers().get(X_STALE).unwrap(), "true");
+    assert!(test::read_body(resp).await.is_empty());
+
+    unsafe {
+        std::env::remove_var("STALE_AFTER_SECONDS");
+        std::env::remove_var("API_TOKEN");
+    }
+    clear_last_result_for_test();
+}
+
+/// Test that `/iperf3` reports the age of the cached result in whole seconds in
+/// `X-Cache-Age-Seconds`, matching `get_last_result_with_age`.
+#[actix_web::test]
+#[serial]
+async fn iperf3_reports_cache_age() {
+    let app = test::init_service(App::new().service(iperf3)).await;
+    *LAST_RESULT.lock().unwrap() =
+        Some((dummy_result(), std::time::Instant::now() - std::time::Duration::from_millis(3_700_500)));
+
+    let (_, age) = get_last_result_with_age().unwrap();
+    assert_eq!(age.as_secs(), 3700);
+    let req = test::TestRequest::get().uri("/iperf3").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+    assert_eq!(resp.headers().get(X_CACHE_AGE_SECONDS).unwrap(), "3700");
+
+    clear_last_result_for_test();
+}
+
+/// Test that `/iperf3` serves the jitter and loss of a UDP result, and a TCP result
+/// without them.
+#[actix_web::test]
+#[serial]
+async fn iperf3_serves_udp_and_tcp_shapes() {
+    let app = test::init_service(App::new().service(iperf3)).await;
+    let mut udp = dummy_result();
+    udp.start.test_start.protocol = "UDP".to_string();
+    udp.end.sum_received.jitter_ms = Some(0.021);
+    udp.end.sum_received.lost_percent = Some(0.0139);
+    set_last_result_for_test(udp);
+    let req = test::TestRequest::get().uri("/iperf3").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["end"]["sum_received"]["jitter_ms"], 0.021);
+    assert_eq!(body["end"]["sum_received"]["lost_percent"], 0.0139);
+
+    set_last_result_for_test(dummy_result());
+    let req = test::TestRequest::get().uri("/iperf3").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert!(body["end"]["sum_received"].get("jitter_ms").is_none());
+
+    clear_last_result_for_test();
+}
+
+/// Test that a run outlasting `IPERF3_TIMEOUT_SECONDS` fails with a timeout and leaves the
+/// cached result unchanged.
+#[tokio::test(start_paused = true)]
+#[serial]
+async fn hung_run_times_out_without_touching_cache() {
+    struct HangingRunner;
+
+    #[async_trait::async_trait]
+    impl Iperf3Runner for HangingRunner {
+        async fn run_iperf3(&self, _iperf3_ip: String, _iperf3_port: String) -> Result<String, Iperf3Error> {
+            tokio::time::sleep(std::time::Duration::from_secs(3600)).await;
+            Ok(serde_json::to_string(&Iperf3Report::default()).unwrap())
+        }
+    }
+
+    unsafe { std::env::set_var("IPERF3_TIMEOUT_SECONDS", "5") };
+    set_last_result_for_test(dummy_result());
+
+    let err = run_iperf3_and_cache_with_runner(&HangingRunner, "127.0.0.1".into(), "5201".into())
+        .await
+        .unwrap_err();
+    assert_eq!(err, Iperf3Error::TimedOut { timeout_seconds: 5 });
+    assert_eq!(err.to_string(), "iperf3 timed out after 5s");
+    assert_eq!(get_last_result().unwrap().start.timestamp.timesecs, dummy_result().start.timestamp.timesecs);
+
+    unsafe { std::env::remove_var("IPERF3_TIMEOUT_SECONDS") };
+    clear_last_result_for_test();
+}
+
+/// Test that with `PREWARM` enabled the runner's prewarm precedes the measured run, and
+/// that it is skipped otherwise.
+#[tokio::test]
+#[serial]
+async fn prewarm_precedes_measured_run() {
+    struct RecordingRunner(std::sync::Mutex<Vec<&'static str>>);
+
+    #[async_trait::async_trait]
+    impl Iperf3Runner for RecordingRunner {
+        async fn run_iperf3(&self, _iperf3_ip: String, _iperf3_port: String) -> Result<String, Iperf3Error> {
+            self.0.lock().unwrap().push("run");
+            Ok(serde_json::to_string(&dummy_result()).unwrap())
+        }
+
+        async fn prewarm(&self, _opts: &Iperf3Options) -> Result<(), Iperf3Error> {
+            self.0.lock().unwrap().push("prewarm");
+            Ok(())
+        }
+    }
+
+    unsafe { std::env::set_var("PREWARM", "true") };
+    let runner = RecordingRunner(Default::default());
+    run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into()).await.unwrap();
+    assert_eq!(*runner.0.lock().unwrap(), ["prewarm", "run"]);
+
+    unsafe { std::env::remove_var("PREWARM") };
+    let runner = RecordingRunner(Default::default());
+    run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into()).await.unwrap();
+    assert_eq!(*runner.0.lock().unwrap(), ["run"]);
+
+    clear_last_result_for_test();
+}
+
+/// Test that connection failures are retried with backoff until a run succeeds, while
+/// other unsuccessful exits and a successful run's unparseable output are not retried.
+#[tokio::test(start_paused = true)]
+#[serial]
+async fn transient_failures_are_retried() {
+    struct FlakyRunner {
+        failures: std::sync::atomic::AtomicUsize,
+        stderr: &'static str,
+        output: String,
+    }
+
+    #[async_trait::async_trait]
+    impl Iperf3Runner for FlakyRunner {
+        async fn run_iperf3(&self, _iperf3_ip: String, _iperf3_port: String) -> Result<String, Iperf3Error> {
+            if self.failures.fetch_sub(1, std::sync::atomic::Ordering::SeqCst) > 0 {
+                return Err(Iperf3Error::from_failed_run(Some(1), "", self.stderr));
+            }
+            Ok(self.output.clone())
+        }
+    }
+
+    unsafe { std::env::remove_var("IPERF3_MAX_RETRIES") };
+    clear_last_result_for_test();
+    let runner = FlakyRunner {
+        failures: std::sync::atomic::AtomicUsize::new(2),
+        stderr: "iperf3: error - unable to connect to server: Connection refused",
+        output: serde_json::to_string(&dummy_result()).unwrap(),
+    };
+    let started = tokio::time::Instant::now();
+    run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into()).await.unwrap();
+    assert_eq!(started.elapsed(), RETRY_BACKOFF_BASE * 3);
+    assert_eq!(get_last_result().unwrap().start.timestamp.timesecs, dummy_result().start.timestamp.timesecs);
+
+    clear_last_result_for_test();
+    let runner = FlakyRunner {
+        failures: std::sync::atomic::AtomicUsize::new(2),
+        stderr: "iperf3: option requires an argument -- 't'",
+        output: serde_json::to_string(&dummy_result()).unwrap(),
+    };
+    let err = run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into()).await.unwrap_err();
+    assert!(matches!(err, Iperf3Error::NonZeroExit { .. }), "{:?}", err);
+    assert_eq!(runner.failures.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+    clear_last_result_for_test();
+    let runner = FlakyRunner {
+        failures: std::sync::atomic::AtomicUsize::new(0),
+        stderr: "",
+        output: "not json".to_string(),
+    };
+    let err = run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into()).await.unwrap_err();
+    assert!(matches!(err, Iperf3Error::Parse(_)), "{:?}", err);
+    assert_eq!(runner.failures.load(std::sync::atomic::Ordering::SeqCst), usize::MAX);
+    assert!(get_last_result().is_none());
+}
+
+/// Test that `units=mbps` adds the derived Mbps and MiB fields beside the raw ones, that
+/// the body without it is exactly the serialized report, and that other units are rejected.
+#[actix_web::test]
+#[serial]
+async fn iperf3_units_adds_derived_fields() {
+    let mut report = Iperf3Report::default();
+    report.end.sum_sent.bits_per_second = 950_000_000.0;
+    report.end.sum_received.bits_per_second = 940_000_000.0;
+    report.end.sum_received.bytes = 10 * 1024 * 1024;
+    set_last_result_for_test(report.clone());
+    let app = test::init_service(App::new().service(iperf3)).await;
+
+    let req = test::TestRequest::get().uri("/iperf3").to_request();
+    let body = test::call_and_read_body(&app, req).await;
+    assert_eq!(body, serde_json::to_vec(&report).unwrap());
+
+    let req = test::TestRequest::get().uri("/iperf3?units=mbps").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["mbps_sent"], 950.0);
+    assert_eq!(body["mbps_received"], 940.0);
+    assert_eq!(body["mbytes_transferred"], 10.0);
+    assert_eq!(body["end"]["sum_received"]["bits_per_second"], 940_000_000.0);
+
+    let req = test::TestRequest::get().uri("/iperf3?units=mbps&fields=start.timestamp").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body.as_object().unwrap().len(), 4);
+    assert_eq!(body["mbps_received"], 940.0);
+
+    let req = test::TestRequest::get().uri("/iperf3?units=gbps").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+    clear_last_result_for_test();
+}
@@ -0,0 +1,78 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for guarding write and trigger endpoints with `API_TOKEN`.
+//!
+//! These tests modify `API_TOKEN` and the maintenance state, and are annotated with `#[serial]`.
+
+use actix_web::{test, http, App};
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+/// Posts to `/admin/maintenance?enabled=false` with the given `Authorization` header.
+async fn post_maintenance(authorization: Option<&str>) -> http::StatusCode {
+    let app = test::init_service(App::new().service(set_maintenance_mode)).await;
+    let mut req = test::TestRequest::post().uri("/admin/maintenance?enabled=false");
+    if let Some(authorization) = authorization {
+        req = req.insert_header((http::header::AUTHORIZATION, authorization));
+    }
+    let resp = test::call_service(&app, req.to_request()).await;
+    if resp.status() == http::StatusCode::UNAUTHORIZED {
+        assert_eq!(resp.headers().get(http::header::WWW_AUTHENTICATE).unwrap(), "Bearer");
+    }
+    resp.status()
+}
+
+/// Test that protected endpoints require the bearer token while `API_TOKEN` is set.
+#[actix_web::test]
+#[serial]
+async fn protected_endpoint_requires_token() {
+    unsafe { std::env::set_var("API_TOKEN", "s3cret") };
+
+    assert_eq!(post_maintenance(None).await, http::StatusCode::UNAUTHORIZED);
+    assert_eq!(post_maintenance(Some("Bearer wrong")).await, http::StatusCode::UNAUTHORIZED);
+    assert_eq!(post_maintenance(Some("Basic s3cret")).await, http::StatusCode::UNAUTHORIZED);
+    assert_eq!(post_maintenance(Some("Bearer s3cret")).await, http::StatusCode::OK);
+
+    // Triggering a run is refused before anything is started
+    let app = test::init_service(App::new().service(iperf3_run).service(set_baseline_from_latest)).await;
+    for uri in ["/iperf3/run", "/admin/set-baseline"] {
+        let resp = test::call_service(&app, test::TestRequest::post().uri(uri).to_request()).await;
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED, "{}", uri);
+    }
+
+    unsafe { std::env::remove_var("API_TOKEN") };
+}
+
+/// Test that without `API_TOKEN` no authentication is enforced.
+#[actix_web::test]
+#[serial]
+async fn no_token_configured_leaves_endpoints_open() {
+    unsafe { std::env::remove_var("API_TOKEN") };
+    assert_eq!(post_maintenance(None).await, http::StatusCode::OK);
+    assert_eq!(post_maintenance(Some("Bearer anything")).await, http::StatusCode::OK);
+}
+
+/// Test that read-only `/iperf3` stays public while `API_TOKEN` is set.
+#[actix_web::test]
+#[serial]
+async fn read_only_endpoint_stays_public() {
+    unsafe { std::env::set_var("API_TOKEN", "s3cret") };
+    set_last_result_for_test(Iperf3Report::default());
+
+    let app = test::init_service(App::new().service(iperf3)).await;
+    let resp = test::call_service(&app, test::TestRequest::get().uri("/iperf3").to_request()).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+
+    clear_last_result_for_test();
+    unsafe { std::env::remove_var("API_TOKEN") };
+}
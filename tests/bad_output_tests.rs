@@ -0,0 +1,102 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for `RETRY_ON_PARSE_FAILURE` and `/debug/last-bad-output`.
+//!
+//! These tests modify `RETRY_ON_PARSE_FAILURE` and are annotated with `#[serial]`.
+
+use std::sync::Mutex;
+use actix_web::{test, http, App};
+use async_trait::async_trait;
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+const BAD_OUTPUT: &str = "{\"start\": {\"connected\": [";
+
+/// Mock runner returning each of its outputs in turn, the last one repeatedly.
+struct SequenceRunner {
+    outputs: Mutex<Vec<String>>,
+}
+
+impl SequenceRunner {
+    fn new(outputs: Vec<String>) -> Self {
+        SequenceRunner { outputs: Mutex::new(outputs) }
+    }
+}
+
+#[async_trait]
+impl Iperf3Runner for SequenceRunner {
+    async fn run_iperf3(&self, _iperf3_ip: String, _iperf3_port: String) -> Result<String, Iperf3Error> {
+        let mut outputs = self.outputs.lock().unwrap();
+        if outputs.len() > 1 { Ok(outputs.remove(0)) } else { Ok(outputs[0].clone()) }
+    }
+}
+
+fn good_output() -> String {
+    let mut report = Iperf3Report::default();
+    report.start.timestamp.timesecs = 42;
+    serde_json::to_string(&report).unwrap()
+}
+
+/// Test that a parse failure is retried once, caching the good result, and that the bad
+/// output is captured and served at `/debug/last-bad-output`.
+#[actix_web::test]
+#[serial]
+async fn parse_failure_is_retried_and_captured() {
+    unsafe { std::env::set_var("RETRY_ON_PARSE_FAILURE", "true") };
+    clear_last_result_for_test();
+    clear_last_bad_output_for_test();
+
+    let runner = SequenceRunner::new(vec![BAD_OUTPUT.to_string(), good_output()]);
+    let report = run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".to_string(), "5201".to_string())
+        .await
+        .unwrap();
+    assert_eq!(report.start.timestamp.timesecs, 42);
+    assert_eq!(get_last_result().unwrap().start.timestamp.timesecs, 42);
+    assert_eq!(get_last_bad_output().as_deref(), Some(BAD_OUTPUT));
+
+    let app = test::init_service(App::new().service(debug_last_bad_output)).await;
+    let req = test::TestRequest::get().uri("/debug/last-bad-output").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+    let body = test::read_body(resp).await;
+    assert_eq!(body, BAD_OUTPUT.as_bytes());
+
+    unsafe { std::env::remove_var("RETRY_ON_PARSE_FAILURE") };
+}
+
+/// Test that without `RETRY_ON_PARSE_FAILURE` the parse error is returned and the output
+/// still captured.
+#[tokio::test]
+#[serial]
+async fn parse_failure_without_retry_fails() {
+    unsafe { std::env::remove_var("RETRY_ON_PARSE_FAILURE") };
+    clear_last_result_for_test();
+    clear_last_bad_output_for_test();
+
+    let runner = SequenceRunner::new(vec![BAD_OUTPUT.to_string(), good_output()]);
+    let result = run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".to_string(), "5201".to_string()).await;
+    assert!(matches!(result, Err(Iperf3Error::Parse(_))));
+    assert!(get_last_result().is_none());
+    assert_eq!(get_last_bad_output().as_deref(), Some(BAD_OUTPUT));
+}
+
+/// Test that `/debug/last-bad-output` returns 404 while nothing has been captured.
+#[actix_web::test]
+#[serial]
+async fn last_bad_output_not_found_when_empty() {
+    clear_last_bad_output_for_test();
+    let app = test::init_service(App::new().service(debug_last_bad_output)).await;
+    let req = test::TestRequest::get().uri("/debug/last-bad-output").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+}
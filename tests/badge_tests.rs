@@ -0,0 +1,78 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for the `/badge` endpoint.
+//!
+//! These tests modify `SLA_*` environment variables and are annotated with `#[serial]`.
+
+use actix_web::{test, http, App};
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+/// Fetches `/badge` with `received_mbps` cached, or nothing cached for `None`.
+async fn badge_for(received_mbps: Option<f64>) -> Badge {
+    match received_mbps {
+        Some(mbps) => {
+            let mut report = Iperf3Report::default();
+            report.end.sum_received.bits_per_second = mbps * 1_000_000.0;
+            set_last_result_for_test(report);
+        }
+        None => clear_last_result_for_test(),
+    }
+    let app = test::init_service(App::new().service(iperf3_badge)).await;
+    let req = test::TestRequest::get().uri("/badge").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+    test::read_body_json(resp).await
+}
+
+/// Test the badge fields and the color at throughputs above, between and below the thresholds.
+#[actix_web::test]
+#[serial]
+async fn badge_colors_follow_sla_thresholds() {
+    unsafe {
+        std::env::set_var("SLA_MIN_MBPS", "500");
+        std::env::set_var("SLA_WARN_MBPS", "900");
+    }
+
+    let badge = badge_for(Some(942.4)).await;
+    assert_eq!(
+        badge,
+        Badge {
+            schema_version: 1,
+            label: "download".to_string(),
+            message: "942 Mbps".to_string(),
+            color: "green".to_string(),
+        }
+    );
+    assert_eq!(badge_for(Some(720.0)).await.color, "yellow");
+    assert_eq!(badge_for(Some(120.0)).await.color, "red");
+
+    unsafe {
+        std::env::remove_var("SLA_MIN_MBPS");
+        std::env::remove_var("SLA_WARN_MBPS");
+    }
+    assert_eq!(badge_for(Some(120.0)).await.color, "blue");
+    clear_last_result_for_test();
+}
+
+/// Test that an empty cache yields a "no data" badge rather than an error.
+#[actix_web::test]
+#[serial]
+async fn badge_without_data() {
+    let badge = badge_for(None).await;
+    assert_eq!(badge.message, "no data");
+    assert_eq!(badge.color, "lightgrey");
+
+    let json = serde_json::to_value(&badge).unwrap();
+    assert_eq!(json["schemaVersion"], 1);
+}
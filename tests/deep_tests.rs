@@ -0,0 +1,127 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for the deep test profile and `/iperf3/deep`.
+//!
+//! These tests modify `DEEP_*` environment variables and are annotated with `#[serial]`.
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use actix_web::{test, http, App};
+use async_trait::async_trait;
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+/// Mock runner recording the options of every run and how many runs overlap.
+#[derive(Default)]
+struct RecordingRunner {
+    runs: Mutex<Vec<Iperf3Options>>,
+    active: AtomicUsize,
+    max_active: AtomicUsize,
+}
+
+#[async_trait]
+impl Iperf3Runner for RecordingRunner {
+    async fn run_iperf3(&self, iperf3_ip: String, iperf3_port: String) -> Result<String, Iperf3Error> {
+        self.run_iperf3_with_options(&Iperf3Options::new(iperf3_ip, iperf3_port)).await
+    }
+
+    async fn run_iperf3_with_options(&self, opts: &Iperf3Options) -> Result<String, Iperf3Error> {
+        let now_active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_active.fetch_max(now_active, Ordering::SeqCst);
+        self.runs.lock().unwrap().push(opts.clone());
+
+        tokio::time::sleep(Duration::from_secs(opts.duration.unwrap_or(10) as u64)).await;
+
+        self.active.fetch_sub(1, Ordering::SeqCst);
+        let mut report = Iperf3Report::default();
+        report.start.test_start.duration = opts.duration.unwrap_or(10) as u64;
+        Ok(serde_json::to_string(&report).unwrap())
+    }
+}
+
+/// Test that both schedules fire with their own options, the deep result is cached
+/// separately and runs against the same server never overlap.
+#[tokio::test(start_paused = true)]
+#[serial]
+async fn regular_and_deep_schedules_fire_with_their_options() {
+    clear_last_result_for_test();
+    clear_deep_result_for_test();
+    unsafe {
+        std::env::set_var("DEEP_INTERVAL_MINUTES", "60");
+        std::env::set_var("DEEP_PARALLEL", "8");
+        std::env::set_var("DEEP_DURATION", "60");
+    }
+    assert_eq!(deep_interval(), Some(Duration::from_secs(3600)));
+    let deep = deep_options("127.0.0.1", "5201");
+    assert_eq!((deep.parallel, deep.duration), (Some(8), Some(60)));
+
+    let runner = std::sync::Arc::new(RecordingRunner::default());
+    let regular = tokio::spawn({
+        let runner = runner.clone();
+        async move {
+            let targets = vec![Target::with_options(Iperf3Options::new("127.0.0.1", "5201"))];
+            run_scheduler_with_runner(&*runner, &targets, 1, Duration::ZERO, Duration::from_secs(600)).await
+        }
+    });
+    let deep_scheduler = tokio::spawn({
+        let runner = runner.clone();
+        async move { run_deep_scheduler_with_runner(&*runner, &deep, Duration::from_secs(3600)).await }
+    });
+
+    tokio::time::sleep(Duration::from_secs(3700)).await;
+    regular.abort();
+    deep_scheduler.abort();
+
+    let runs = runner.runs.lock().unwrap().clone();
+    let deep_runs: Vec<_> = runs.iter().filter(|o| o.duration == Some(60)).collect();
+    assert_eq!(deep_runs.len(), 1);
+    assert_eq!(deep_runs[0].parallel, Some(8));
+    assert!(runs.iter().filter(|o| o.duration.is_none()).count() >= 6);
+    assert_eq!(runner.max_active.load(Ordering::SeqCst), 1);
+
+    assert_eq!(get_deep_result().unwrap().start.test_start.duration, 60);
+    assert_eq!(get_last_result().unwrap().start.test_start.duration, 10);
+
+    unsafe {
+        std::env::remove_var("DEEP_INTERVAL_MINUTES");
+        std::env::remove_var("DEEP_PARALLEL");
+        std::env::remove_var("DEEP_DURATION");
+    }
+    assert_eq!(deep_interval(), None);
+    clear_last_result_for_test();
+    clear_deep_result_for_test();
+    clear_run_status_for_test();
+}
+
+/// Test that `/iperf3/deep` returns 503 until a deep result is cached and then serves it.
+#[actix_web::test]
+#[serial]
+async fn deep_endpoint_serves_cached_deep_result() {
+    clear_deep_result_for_test();
+    let app = test::init_service(App::new().service(iperf3_deep)).await;
+
+    let req = test::TestRequest::get().uri("/iperf3/deep").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+
+    let runner = RecordingRunner::default();
+    let opts = Iperf3Options { duration: Some(1), ..Iperf3Options::new("127.0.0.1", "5201") };
+    run_deep_with_runner(&runner, &opts).await.unwrap();
+    assert!(get_last_result().is_none());
+
+    let req = test::TestRequest::get().uri("/iperf3/deep").to_request();
+    let body: Iperf3Report = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body.start.test_start.duration, 1);
+
+    clear_deep_result_for_test();
+}
@@ -0,0 +1,92 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for `DIAGNOSTICS_ON_FAILURE` and `/debug/last-diagnostics`.
+//!
+//! These tests modify `DIAGNOSTICS_ON_FAILURE` and are annotated with `#[serial]`.
+
+use std::sync::{Arc, Mutex};
+use actix_web::{test, http, App};
+use async_trait::async_trait;
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+/// Mock runner whose every run fails as if the server could not be reached.
+struct FailingRunner;
+
+#[async_trait]
+impl Iperf3Runner for FailingRunner {
+    async fn run_iperf3(&self, _iperf3_ip: String, _iperf3_port: String) -> Result<String, Iperf3Error> {
+        Err(Iperf3Error::NonZeroExit {
+            code: Some(1),
+            stderr: "iperf3: error - unable to connect to server: No route to host".to_string(),
+        })
+    }
+}
+
+/// Mock diagnostics runner recording the hosts it was asked about.
+#[derive(Default)]
+struct MockDiagnosticsRunner {
+    hosts: Mutex<Vec<String>>,
+}
+
+#[async_trait]
+impl DiagnosticsRunner for MockDiagnosticsRunner {
+    async fn ping(&self, host: &str) -> Result<String, String> {
+        self.hosts.lock().unwrap().push(host.to_string());
+        Ok(format!("4 packets transmitted, 0 received, 100% packet loss ({})", host))
+    }
+
+    async fn traceroute(&self, _host: &str) -> Result<String, String> {
+        Err("Failed to run traceroute: No such file or directory".to_string())
+    }
+}
+
+/// Test that a failed run captures diagnostics only with `DIAGNOSTICS_ON_FAILURE` enabled,
+/// and that they are served at `/debug/last-diagnostics`.
+#[actix_web::test]
+#[serial]
+async fn failed_run_captures_diagnostics() {
+    clear_last_diagnostics_for_test();
+    let diagnostics = Arc::new(MockDiagnosticsRunner::default());
+    set_diagnostics_runner(diagnostics.clone());
+    let app = test::init_service(App::new().service(debug_last_diagnostics)).await;
+
+    run_iperf3_and_cache_with_runner(&FailingRunner, "10.0.0.5".into(), "5201".into()).await.unwrap_err();
+    assert!(diagnostics.hosts.lock().unwrap().is_empty());
+    let req = test::TestRequest::get().uri("/debug/last-diagnostics").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), http::StatusCode::NOT_FOUND);
+
+    unsafe { std::env::set_var("DIAGNOSTICS_ON_FAILURE", "true") };
+    run_iperf3_and_cache_with_runner(&FailingRunner, "10.0.0.5".into(), "5201".into()).await.unwrap_err();
+    assert_eq!(*diagnostics.hosts.lock().unwrap(), ["10.0.0.5"]);
+
+    let req = test::TestRequest::get().uri("/debug/last-diagnostics").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["host"], "10.0.0.5");
+    assert!(body["error"].as_str().unwrap().contains("No route to host"));
+    assert!(body["ping"].as_str().unwrap().contains("100% packet loss"));
+    assert!(body["traceroute"].as_str().unwrap().starts_with("Failed to run traceroute"));
+
+    unsafe { std::env::remove_var("DIAGNOSTICS_ON_FAILURE") };
+    set_diagnostics_runner(Arc::new(SystemDiagnosticsRunner));
+    clear_last_diagnostics_for_test();
+}
+
+/// Test that only failures pointing at the network warrant diagnostics.
+#[tokio::test]
+async fn only_network_failures_warrant_diagnostics() {
+    assert!(warrants_diagnostics(&Iperf3Error::Unreachable("timed out".to_string())));
+    assert!(warrants_diagnostics(&Iperf3Error::NonZeroExit { code: Some(1), stderr: String::new() }));
+    assert!(!warrants_diagnostics(&Iperf3Error::Rejected("too few bytes".to_string())));
+    assert!(!warrants_diagnostics(&Iperf3Error::Parse("EOF".to_string())));
+}
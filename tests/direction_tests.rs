@@ -0,0 +1,101 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for `ROTATE_DIRECTION` and `/iperf3?direction=`.
+//!
+//! These tests modify `ROTATE_DIRECTION` and are annotated with `#[serial]`.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use actix_web::{test, http, App};
+use async_trait::async_trait;
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+/// Mock runner recording the `reverse` option of every run and echoing it in the report.
+#[derive(Default)]
+struct DirectionRunner {
+    reverses: Mutex<Vec<bool>>,
+}
+
+#[async_trait]
+impl Iperf3Runner for DirectionRunner {
+    async fn run_iperf3(&self, iperf3_ip: String, iperf3_port: String) -> Result<String, Iperf3Error> {
+        self.run_iperf3_with_options(&Iperf3Options::new(iperf3_ip, iperf3_port)).await
+    }
+
+    async fn run_iperf3_with_options(&self, opts: &Iperf3Options) -> Result<String, Iperf3Error> {
+        let mut reverses = self.reverses.lock().unwrap();
+        reverses.push(opts.reverse);
+        let mut report = Iperf3Report::default();
+        report.start.test_start.reverse = opts.reverse as u32;
+        report.start.timestamp.timesecs = reverses.len() as u64;
+        Ok(serde_json::to_string(&report).unwrap())
+    }
+}
+
+/// Test that cycles alternate between normal and reverse runs, each direction is cached
+/// separately and `/iperf3?direction=` serves them.
+#[actix_web::test]
+#[serial]
+async fn rotation_alternates_and_caches_each_direction() {
+    unsafe { std::env::set_var("ROTATE_DIRECTION", "true") };
+    clear_last_result_for_test();
+
+    let runner = Arc::new(DirectionRunner::default());
+    let scheduler = tokio::spawn({
+        let runner = runner.clone();
+        async move {
+            let targets = vec![Target::with_options(Iperf3Options::new("127.0.0.1", "5201"))];
+            run_scheduler_with_runner(&*runner, &targets, 1, Duration::ZERO, Duration::from_millis(20)).await
+        }
+    });
+    while runner.reverses.lock().unwrap().len() < 5 {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    scheduler.abort();
+
+    let reverses = runner.reverses.lock().unwrap().clone();
+    assert_eq!(reverses[..5], [false, true, false, true, false]);
+
+    let up = get_direction_result(Direction::Up).unwrap().0;
+    let down = get_direction_result(Direction::Down).unwrap().0;
+    assert_eq!(up.start.test_start.reverse, 0);
+    assert_eq!(down.start.test_start.reverse, 1);
+    assert!(up.start.timestamp.timesecs >= 5);
+    assert!(down.start.timestamp.timesecs >= 4);
+
+    let app = test::init_service(App::new().service(iperf3)).await;
+    let req = test::TestRequest::get().uri("/iperf3?direction=down").to_request();
+    let body: Iperf3Report = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body.start.test_start.reverse, 1);
+    let req = test::TestRequest::get().uri("/iperf3?direction=up").to_request();
+    let body: Iperf3Report = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body.start.test_start.reverse, 0);
+    let req = test::TestRequest::get().uri("/iperf3?direction=sideways").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), http::StatusCode::BAD_REQUEST);
+
+    unsafe { std::env::remove_var("ROTATE_DIRECTION") };
+    clear_last_result_for_test();
+    clear_run_status_for_test();
+}
+
+/// Test that without `ROTATE_DIRECTION` every cycle keeps the configured direction.
+#[tokio::test]
+#[serial]
+async fn no_rotation_by_default() {
+    unsafe { std::env::remove_var("ROTATE_DIRECTION") };
+    let targets = vec![Target::with_options(Iperf3Options::new("127.0.0.1", "5201"))];
+    for cycle in 1..=4 {
+        assert!(!rotated_targets(&targets, cycle)[0].options.reverse);
+    }
+}
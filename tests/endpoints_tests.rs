@@ -0,0 +1,101 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for `ENABLED_ENDPOINTS` route registration.
+//!
+//! These tests modify `ENABLED_ENDPOINTS` and are annotated with `#[serial]`.
+
+use actix_web::{test, http, App};
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+/// Returns the status of a GET request to `uri` against an app built with `configure_services`.
+async fn status_of(uri: &str) -> http::StatusCode {
+    let app = test::init_service(App::new().configure(configure_services)).await;
+    let req = test::TestRequest::get().uri(uri).to_request();
+    test::call_service(&app, req).await.status()
+}
+
+/// Test that only the configured endpoints (and `/healthz`) are registered.
+#[actix_web::test]
+#[serial]
+async fn subset_of_endpoints_is_registered() {
+    unsafe { std::env::set_var("ENABLED_ENDPOINTS", "summary, metrics,bogus") };
+    assert_eq!(enabled_endpoints(), Some(vec!["summary".to_string(), "metrics".to_string()]));
+
+    assert_eq!(status_of("/healthz").await, http::StatusCode::OK);
+    // No result is cached, so enabled data endpoints resolve to 503 rather than 404, except
+    // `/metrics` which reports `iperf3_up 0`
+    assert_eq!(status_of("/summary").await, http::StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(status_of("/metrics").await, http::StatusCode::OK);
+    assert_eq!(status_of("/iperf3").await, http::StatusCode::NOT_FOUND);
+    assert_eq!(status_of("/debug/config").await, http::StatusCode::NOT_FOUND);
+    assert_eq!(status_of("/admin/maintenance").await, http::StatusCode::NOT_FOUND);
+
+    unsafe { std::env::remove_var("ENABLED_ENDPOINTS") };
+}
+
+/// Test that every endpoint is registered when `ENABLED_ENDPOINTS` is unset.
+#[actix_web::test]
+#[serial]
+async fn all_endpoints_registered_by_default() {
+    unsafe { std::env::remove_var("ENABLED_ENDPOINTS") };
+    assert_eq!(enabled_endpoints(), None);
+
+    assert_eq!(status_of("/healthz").await, http::StatusCode::OK);
+    assert_eq!(status_of("/admin/maintenance").await, http::StatusCode::OK);
+    assert_ne!(status_of("/iperf3").await, http::StatusCode::NOT_FOUND);
+    assert_ne!(status_of("/debug/timing").await, http::StatusCode::NOT_FOUND);
+}
+
+/// Test that `/favicon.ico` is answered with a cacheable 204 even when every data endpoint is disabled.
+#[actix_web::test]
+#[serial]
+async fn favicon_is_always_served() {
+    unsafe { std::env::set_var("ENABLED_ENDPOINTS", "summary") };
+
+    let app = test::init_service(App::new().configure(configure_services)).await;
+    let req = test::TestRequest::get().uri("/favicon.ico").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::NO_CONTENT);
+    assert!(resp.headers().get(http::header::CACHE_CONTROL).is_some());
+
+    unsafe { std::env::remove_var("ENABLED_ENDPOINTS") };
+}
+
+/// Test that with `METRICS_BIND_PORT` set `/metrics` moves to the metrics listener.
+#[actix_web::test]
+#[serial]
+async fn metrics_served_on_dedicated_listener() {
+    unsafe {
+        std::env::remove_var("ENABLED_ENDPOINTS");
+        std::env::set_var("METRICS_BIND_PORT", "9090");
+    }
+    assert_eq!(metrics_bind(), Some(("127.0.0.1".to_string(), 9090)));
+
+    assert_eq!(status_of("/metrics").await, http::StatusCode::NOT_FOUND);
+    assert_eq!(status_of("/healthz").await, http::StatusCode::OK);
+
+    let metrics_app = test::init_service(App::new().configure(configure_metrics_services)).await;
+    for (uri, expected) in [
+        ("/metrics", http::StatusCode::OK),
+        ("/healthz", http::StatusCode::OK),
+        ("/summary", http::StatusCode::NOT_FOUND),
+    ] {
+        let req = test::TestRequest::get().uri(uri).to_request();
+        assert_eq!(test::call_service(&metrics_app, req).await.status(), expected, "{}", uri);
+    }
+
+    unsafe { std::env::remove_var("METRICS_BIND_PORT") };
+    assert_eq!(metrics_bind(), None);
+    assert_eq!(status_of("/metrics").await, http::StatusCode::OK);
+}
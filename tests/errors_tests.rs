@@ -0,0 +1,111 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for the configurable error response format.
+//!
+//! These tests modify `ERROR_FORMAT` and are annotated with `#[serial]`.
+
+use actix_web::{test, http, App};
+use serde_json::{json, Value};
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+/// Test that the empty-cache 503 is plain text by default.
+#[actix_web::test]
+#[serial]
+async fn empty_cache_returns_text_by_default() {
+    unsafe { std::env::remove_var("ERROR_FORMAT") };
+    clear_last_result_for_test();
+
+    let app = test::init_service(App::new().service(iperf3)).await;
+    let req = test::TestRequest::get().uri("/iperf3").to_request();
+    let resp = test::call_service(&app, req).await;
+
+    assert_eq!(resp.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+    let content_type = resp.headers().get(http::header::CONTENT_TYPE).unwrap().to_str().unwrap();
+    assert!(content_type.starts_with("text/plain"));
+    assert_eq!(test::read_body(resp).await, "Iperf3 result not available yet.");
+}
+
+/// Test that `ERROR_FORMAT=json` yields a JSON error body with a JSON content type.
+#[actix_web::test]
+#[serial]
+async fn empty_cache_returns_json_when_configured() {
+    unsafe { std::env::set_var("ERROR_FORMAT", "json") };
+    clear_last_result_for_test();
+
+    let app = test::init_service(App::new().service(iperf3).service(iperf3_intervals)).await;
+    for uri in ["/iperf3", "/intervals"] {
+        let req = test::TestRequest::get().uri(uri).to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+        let content_type = resp.headers().get(http::header::CONTENT_TYPE).unwrap().to_str().unwrap();
+        assert_eq!(content_type, "application/json");
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(
+            body,
+            json!({"error": "not_available", "message": "Iperf3 result not available yet."})
+        );
+    }
+
+    unsafe { std::env::remove_var("ERROR_FORMAT") };
+}
+
+/// Test that a `?`-propagated error renders the JSON shape and status of its variant.
+#[actix_web::test]
+#[serial]
+async fn propagated_errors_render_as_json() {
+    unsafe { std::env::set_var("ERROR_FORMAT", "json") };
+    set_last_result_for_test(Iperf3Report::default());
+
+    let app = test::init_service(App::new().service(iperf3).service(iperf3_intervals)).await;
+
+    let req = test::TestRequest::get().uri("/iperf3?fields=start.bogus").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(
+        body,
+        json!({"error": "unknown_fields", "message": "Unknown field paths: start.bogus"})
+    );
+
+    let req = test::TestRequest::get().uri("/intervals?round=-1").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(
+        body,
+        json!({"error": "bad_request", "message": "round must be a positive number of seconds."})
+    );
+
+    unsafe { std::env::remove_var("ERROR_FORMAT") };
+    clear_last_result_for_test();
+}
+
+/// Test that each error variant maps to its status code and code.
+#[tokio::test]
+async fn error_variants_map_to_status_codes() {
+    use actix_web::ResponseError;
+
+    let cases = [
+        (Iperf3Error::not_available(), http::StatusCode::SERVICE_UNAVAILABLE, "not_available"),
+        (Iperf3Error::BadRequest("bad".into()), http::StatusCode::BAD_REQUEST, "bad_request"),
+        (Iperf3Error::UnknownFields(vec!["x".into()]), http::StatusCode::BAD_REQUEST, "unknown_fields"),
+        (Iperf3Error::InvalidConfig("bad".into()), http::StatusCode::INTERNAL_SERVER_ERROR, "invalid_config"),
+        (Iperf3Error::Internal("boom".into()), http::StatusCode::INTERNAL_SERVER_ERROR, "internal_error"),
+    ];
+    for (error, status, code) in cases {
+        assert_eq!(error.status_code(), status);
+        assert_eq!(error.code(), code);
+    }
+}
@@ -0,0 +1,169 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for the Server-Sent Events streams at `/events` and `/iperf3/events`.
+//!
+//! The cached result is process-global, so these tests are annotated with `#[serial]`.
+
+use std::time::{Duration, Instant};
+use actix_web::body::MessageBody;
+use async_trait::async_trait;
+use actix_web::{test, http, App};
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+/// Reads the next chunk of a streaming response body as text.
+async fn next_chunk<B: MessageBody + Unpin>(body: &mut B) -> String {
+    let chunk = std::future::poll_fn(|cx| std::pin::Pin::new(&mut *body).poll_next(cx)).await;
+    let bytes = chunk.expect("stream ended").map_err(|_| "body error").unwrap();
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+/// Test that a subscriber receives a countdown right away and an `event: result` once a
+/// result is cached.
+#[actix_web::test]
+#[serial]
+async fn result_event_follows_cache_update() {
+    clear_run_status_for_test();
+    let app = test::init_service(App::new().service(iperf3_events)).await;
+    let req = test::TestRequest::get().uri("/events").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+    assert_eq!(resp.headers().get(http::header::CONTENT_TYPE).unwrap(), "text/event-stream");
+    let mut body = resp.into_body();
+
+    assert_eq!(next_chunk(&mut body).await, "event: countdown\ndata: {\"next_run_in_seconds\":null}\n\n");
+
+    let mut report = Iperf3Report::default();
+    report.start.timestamp.timesecs = 1_700_000_000;
+    report.end.sum_received.bits_per_second = 941_000_000.0;
+    set_last_result_for_test(report);
+
+    let event = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let chunk = next_chunk(&mut body).await;
+            if chunk.starts_with("event: result\n") {
+                return chunk;
+            }
+        }
+    })
+    .await
+    .expect("no result event");
+    assert_eq!(
+        event,
+        "event: result\ndata: {\"timestamp\":1700000000,\"sent_mbps\":0.0,\"received_mbps\":941.0}\n\n"
+    );
+
+    clear_last_result_for_test();
+}
+
+/// Test that the countdown runs to the next cycle, `INTERVAL_MINUTES` after the last one
+/// started.
+#[tokio::test]
+#[serial]
+async fn countdown_counts_to_next_cycle() {
+    unsafe { std::env::set_var("INTERVAL_MINUTES", "10") };
+    clear_run_status_for_test();
+    assert_eq!(countdown(Instant::now()).next_run_in_seconds, None);
+
+    let started = Instant::now();
+    record_cycle_start(started);
+    assert_eq!(countdown(started + Duration::from_secs(100)).next_run_in_seconds, Some(500));
+    assert_eq!(countdown(started + Duration::from_secs(700)).next_run_in_seconds, Some(0));
+
+    unsafe { std::env::remove_var("INTERVAL_MINUTES") };
+    clear_run_status_for_test();
+}
+
+/// Test that `/iperf3/events` starts with the current result and then follows each new
+/// one, and that dropping the subscriber does not hold up caching.
+#[actix_web::test]
+#[serial]
+async fn result_stream_starts_with_current_result() {
+    let mut report = Iperf3Report::default();
+    report.start.timestamp.timesecs = 1_700_000_000;
+    report.end.sum_received.bits_per_second = 941_000_000.0;
+    set_last_result_for_test(report.clone());
+
+    let app = test::init_service(App::new().service(iperf3_result_events)).await;
+    let req = test::TestRequest::get().uri("/iperf3/events").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+    assert_eq!(resp.headers().get(http::header::CONTENT_TYPE).unwrap(), "text/event-stream");
+    let mut body = resp.into_body();
+    assert_eq!(
+        next_chunk(&mut body).await,
+        "event: result\ndata: {\"timestamp\":1700000000,\"sent_mbps\":0.0,\"received_mbps\":941.0}\n\n"
+    );
+
+    report.start.timestamp.timesecs = 1_700_000_600;
+    report.end.sum_sent.bits_per_second = 950_000_000.0;
+    set_last_result_for_test(report.clone());
+    let event = tokio::time::timeout(Duration::from_secs(5), next_chunk(&mut body)).await.expect("no result event");
+    assert_eq!(
+        event,
+        "event: result\ndata: {\"timestamp\":1700000600,\"sent_mbps\":950.0,\"received_mbps\":941.0}\n\n"
+    );
+
+    drop(body);
+    set_last_result_for_test(report);
+    clear_last_result_for_test();
+}
+
+/// Test that `/iperf3/events` sends nothing until a result is cached.
+#[actix_web::test]
+#[serial]
+async fn result_stream_waits_without_cached_result() {
+    clear_last_result_for_test();
+    let app = test::init_service(App::new().service(iperf3_result_events)).await;
+    let req = test::TestRequest::get().uri("/iperf3/events").to_request();
+    let mut body = test::call_service(&app, req).await.into_body();
+    assert!(tokio::time::timeout(Duration::from_millis(100), next_chunk(&mut body)).await.is_err());
+}
+
+/// Mock runner whose reports carry the last octet of the target's address as timestamp.
+struct PerTargetRunner;
+
+#[async_trait]
+impl Iperf3Runner for PerTargetRunner {
+    async fn run_iperf3(&self, iperf3_ip: String, _iperf3_port: String) -> Result<String, Iperf3Error> {
+        let mut report = Iperf3Report::default();
+        report.start.timestamp.timesecs = iperf3_ip.rsplit('.').next().unwrap().parse().unwrap();
+        Ok(serde_json::to_string(&report).unwrap())
+    }
+}
+
+/// Test that with several targets `/iperf3/events` carries only the primary target's results.
+#[actix_web::test]
+#[serial]
+async fn result_stream_carries_only_the_primary_target() {
+    clear_last_result_for_test();
+    let targets = vec![Target::new("10.0.0.1", "5201"), Target::new("10.0.0.2", "5201")];
+    set_primary_target(Some(targets[0].key()));
+
+    let app = test::init_service(App::new().service(iperf3_result_events)).await;
+    let req = test::TestRequest::get().uri("/iperf3/events").to_request();
+    let mut body = test::call_service(&app, req).await.into_body();
+
+    run_targets_with_runner(&PerTargetRunner, &targets, 1).await;
+    let mut events = Vec::new();
+    while let Ok(event) = tokio::time::timeout(Duration::from_millis(100), next_chunk(&mut body)).await {
+        events.push(event);
+    }
+    assert_eq!(
+        events,
+        vec!["event: result\ndata: {\"timestamp\":1,\"sent_mbps\":0.0,\"received_mbps\":0.0}\n\n"]
+    );
+
+    clear_last_result_for_test();
+    clear_run_status_for_test();
+}
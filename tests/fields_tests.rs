@@ -0,0 +1,104 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for `/iperf3?fields=` field selection.
+
+use actix_web::{test, http, App};
+use serde_json::{json, Value};
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+fn sample_report() -> Iperf3Report {
+    let mut report = Iperf3Report::default();
+    report.start.timestamp.time = "Tue, 12 Aug 2025 10:39:42 GMT".to_string();
+    report.start.timestamp.timesecs = 1754995182;
+    report.end.sum_received.bytes = 86734274560;
+    report.end.sum_received.bits_per_second = 69381438967.961;
+    report
+}
+
+/// Test that top-level and nested paths are selected and nothing else is returned.
+#[actix_web::test]
+#[serial]
+async fn fields_selects_requested_paths() {
+    set_last_result_for_test(sample_report());
+
+    let app = test::init_service(App::new().service(iperf3)).await;
+    let req = test::TestRequest::get()
+        .uri("/iperf3?fields=start.timestamp,end.sum_received")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["start"]["timestamp"]["timesecs"], json!(1754995182));
+    assert_eq!(body["end"]["sum_received"]["bytes"], json!(86734274560u64));
+    assert_eq!(body.as_object().unwrap().len(), 2);
+    assert_eq!(body["start"].as_object().unwrap().len(), 1);
+    assert!(body.get("intervals").is_none());
+
+    clear_last_result_for_test();
+}
+
+/// Test that deeply nested leaf paths keep their nesting.
+#[actix_web::test]
+#[serial]
+async fn fields_selects_nested_leaf() {
+    set_last_result_for_test(sample_report());
+
+    let app = test::init_service(App::new().service(iperf3)).await;
+    let req = test::TestRequest::get()
+        .uri("/iperf3?fields=end.sum_received.bits_per_second,start.timestamp.time")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(
+        body,
+        json!({
+            "start": {"timestamp": {"time": "Tue, 12 Aug 2025 10:39:42 GMT"}},
+            "end": {"sum_received": {"bits_per_second": 69381438967.961}}
+        })
+    );
+
+    clear_last_result_for_test();
+}
+
+/// Test that unknown paths produce a 400 listing every bad path.
+#[actix_web::test]
+#[serial]
+async fn fields_rejects_invalid_paths() {
+    set_last_result_for_test(sample_report());
+
+    let app = test::init_service(App::new().service(iperf3)).await;
+    let req = test::TestRequest::get()
+        .uri("/iperf3?fields=start.timestamp,start.bogus,nope")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+    let body = test::read_body(resp).await;
+    assert_eq!(body, "Unknown field paths: start.bogus, nope");
+
+    clear_last_result_for_test();
+}
+
+/// Test that a parent path selected alongside one of its children returns the whole parent.
+#[tokio::test]
+async fn select_fields_parent_wins_over_child() {
+    let value = serde_json::to_value(sample_report()).unwrap();
+    let paths = parse_field_paths("start.timestamp.time, start.timestamp");
+
+    let pruned = select_fields(&value, &paths).unwrap();
+    assert_eq!(pruned["start"]["timestamp"]["timesecs"], json!(1754995182));
+}
@@ -0,0 +1,136 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for the iperf3 child hardening (Linux, `hardening` feature).
+//!
+//! Run with `cargo test --features hardening`.
+
+#![cfg(all(target_os = "linux", feature = "hardening"))]
+
+use std::os::unix::process::ExitStatusExt;
+use std::time::Duration;
+use serial_test::serial;
+use tokio::process::Command;
+use iperf3_statuspage::*;
+
+/// Test that `RLIMIT_CPU` kills a deliberately CPU-heavy stub.
+#[tokio::test]
+async fn cpu_limit_kills_busy_child() {
+    let limits = ChildLimits { cpu_seconds: Some(1), ..ChildLimits::default() };
+    let mut command = Command::new("sh");
+    command.arg("-c").arg("while :; do :; done");
+    limits.apply(&mut command);
+
+    let status = tokio::time::timeout(Duration::from_secs(20), command.status())
+        .await
+        .expect("CPU-limited child should be killed well before the timeout")
+        .unwrap();
+
+    assert!(!status.success());
+    assert!(matches!(status.signal(), Some(libc::SIGXCPU) | Some(libc::SIGKILL)));
+}
+
+/// Test that `RLIMIT_AS` is visible from inside the child.
+#[tokio::test]
+async fn address_space_limit_is_applied() {
+    let limit: u64 = 512 * 1024 * 1024;
+    let limits = ChildLimits { address_space_bytes: Some(limit), ..ChildLimits::default() };
+    let mut command = Command::new("cat");
+    command.arg("/proc/self/limits");
+    limits.apply(&mut command);
+
+    let output = command.output().await.unwrap();
+    let limits_table = String::from_utf8(output.stdout).unwrap();
+    let line = limits_table
+        .lines()
+        .find(|line| line.starts_with("Max address space"))
+        .unwrap();
+    assert!(line.contains(&limit.to_string()), "unexpected limits line: {line}");
+}
+
+/// Test that limits are read from the environment.
+#[tokio::test]
+#[serial]
+async fn child_limits_read_env() {
+    unsafe {
+        std::env::set_var("IPERF3_RLIMIT_CPU_SECONDS", "30");
+        std::env::set_var("IPERF3_RLIMIT_AS_BYTES", "1073741824");
+        std::env::remove_var("IPERF3_RUN_AS_UID");
+        std::env::remove_var("IPERF3_RUN_AS_GID");
+    }
+
+    let limits = ChildLimits::from_env();
+    assert_eq!(limits.cpu_seconds, Some(30));
+    assert_eq!(limits.address_space_bytes, Some(1073741824));
+    assert_eq!(limits.uid, None);
+
+    unsafe {
+        std::env::remove_var("IPERF3_RLIMIT_CPU_SECONDS");
+        std::env::remove_var("IPERF3_RLIMIT_AS_BYTES");
+    }
+    assert!(ChildLimits::from_env().is_empty());
+}
+
+/// Test that an unparseable limit is an error naming the variable, reported by
+/// `load_config`, rather than silently applying no limit.
+#[tokio::test]
+#[serial]
+async fn invalid_child_limit_is_rejected() {
+    unsafe {
+        std::env::set_var("IPERF3_SERVER_IP", "127.0.0.1");
+        std::env::set_var("IPERF3_SERVER_PORT", "5201");
+        std::env::set_var("IPERF3_RUN_AS_UID", "nobody");
+    }
+    let err = ChildLimits::try_from_env().unwrap_err();
+    assert!(err.contains("IPERF3_RUN_AS_UID"), "{}", err);
+    assert!(load_config().unwrap_err().contains("IPERF3_RUN_AS_UID"));
+    assert!(ChildLimits::from_env().is_empty());
+
+    unsafe { std::env::set_var("IPERF3_RUN_AS_UID", "65534") };
+    assert_eq!(ChildLimits::try_from_env().unwrap().uid, Some(65534));
+    assert!(load_config().is_ok());
+
+    unsafe {
+        std::env::remove_var("IPERF3_RUN_AS_UID");
+        std::env::remove_var("IPERF3_SERVER_IP");
+        std::env::remove_var("IPERF3_SERVER_PORT");
+    }
+}
+
+/// Test that the niceness is set before privileges are dropped: raising the priority only
+/// works while the child is still root, so the spawn fails if the hooks run the other way
+/// round. Needs root to drop privileges; skipped otherwise.
+#[tokio::test]
+#[serial]
+async fn nice_is_applied_before_privileges_are_dropped() {
+    // SAFETY: geteuid has no preconditions.
+    if unsafe { libc::geteuid() } != 0 {
+        return;
+    }
+    unsafe {
+        std::env::set_var("IPERF3_NICE", "-3");
+        std::env::set_var("IPERF3_RUN_AS_UID", "65534");
+        std::env::set_var("IPERF3_RUN_AS_GID", "65534");
+    }
+    let mut command = Command::new("sh");
+    command.arg("-c").arg("echo $(nice) $(id -u)");
+    prepare_child(&mut command);
+    let output = command.output().await;
+    unsafe {
+        std::env::remove_var("IPERF3_NICE");
+        std::env::remove_var("IPERF3_RUN_AS_UID");
+        std::env::remove_var("IPERF3_RUN_AS_GID");
+    }
+
+    let output = output.expect("spawn should succeed with the niceness set first");
+    assert_eq!(String::from_utf8(output.stdout).unwrap().trim(), "-3 65534");
+}
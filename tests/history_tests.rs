@@ -0,0 +1,472 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for the result history and the views built on it.
+//!
+//! The history is process-global, so tests touching it are annotated with `#[serial]`.
+
+use actix_web::{test, http, App};
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+/// Builds a report with the given received throughput.
+fn report_received(bits_per_second: f64) -> Iperf3Report {
+    let mut report = Iperf3Report::default();
+    report.end.sum_received.bits_per_second = bits_per_second;
+    report
+}
+
+/// Test that the history evicts the oldest entries beyond `HISTORY_SIZE`.
+#[tokio::test]
+#[serial]
+async fn history_evicts_oldest_when_full() {
+    unsafe { std::env::set_var("HISTORY_SIZE", "3") };
+    clear_history_for_test();
+
+    for i in 1..=5 {
+        push_history_for_test(report_received(i as f64));
+    }
+
+    let received: Vec<f64> = get_history().iter().map(|r| r.end.sum_received.bits_per_second).collect();
+    assert_eq!(received, vec![3.0, 4.0, 5.0]);
+
+    unsafe { std::env::remove_var("HISTORY_SIZE") };
+    clear_history_for_test();
+}
+
+/// Builds a report whose JSON grows with `interval_count`.
+fn report_with_intervals(received: f64, interval_count: usize) -> Iperf3Report {
+    let mut report = report_received(received);
+    report.intervals = vec![Interval::default(); interval_count];
+    report
+}
+
+/// Test that the history evicts the oldest entries beyond `HISTORY_MAX_BYTES`, whatever
+/// their count, and tracks the running byte total.
+#[tokio::test]
+#[serial]
+async fn history_evicts_oldest_beyond_max_bytes() {
+    let size_of = |report: &Iperf3Report| serde_json::to_vec(report).unwrap().len();
+    let small = report_with_intervals(1.0, 0);
+    let large = report_with_intervals(2.0, 20);
+    let limit = size_of(&large) + 2 * size_of(&small);
+    unsafe { std::env::set_var("HISTORY_MAX_BYTES", limit.to_string()) };
+    clear_history_for_test();
+
+    push_history_for_test(small.clone());
+    push_history_for_test(small.clone());
+    push_history_for_test(report_with_intervals(3.0, 0));
+    assert_eq!(history_bytes(), 3 * size_of(&small));
+
+    // The large report forces out the oldest small ones until the total fits again
+    push_history_for_test(large.clone());
+    let received: Vec<f64> = get_history().iter().map(|r| r.end.sum_received.bits_per_second).collect();
+    assert_eq!(received, vec![1.0, 3.0, 2.0]);
+    assert_eq!(history_bytes(), size_of(&large) + 2 * size_of(&small));
+    assert!(history_bytes() <= limit);
+
+    // A single report above the limit is still kept as the newest entry
+    unsafe { std::env::set_var("HISTORY_MAX_BYTES", "1") };
+    push_history_for_test(small.clone());
+    assert_eq!(get_history().len(), 1);
+    assert_eq!(history_bytes(), size_of(&small));
+
+    unsafe { std::env::remove_var("HISTORY_MAX_BYTES") };
+    clear_history_for_test();
+    assert_eq!(history_bytes(), 0);
+}
+
+/// Test that `/sparkline` renders one glyph per history point plus the latest value.
+#[actix_web::test]
+#[serial]
+async fn sparkline_has_one_glyph_per_point() {
+    clear_history_for_test();
+    let series = [900e6, 940e6, 120e6, 610e6, 941e6];
+    for bps in series {
+        push_history_for_test(report_received(bps));
+    }
+
+    let app = test::init_service(App::new().service(sparkline)).await;
+    let req = test::TestRequest::get().uri("/sparkline").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    let (line, latest) = body.trim_end().split_once(' ').unwrap();
+    assert_eq!(line.chars().count(), series.len());
+    assert!(line.chars().all(|c| SPARKLINE_GLYPHS.contains(&c)));
+    assert_eq!(line.chars().nth(2), Some('▁'));
+    assert_eq!(line.chars().nth(4), Some('█'));
+    assert_eq!(latest, "941.0 Mbps");
+
+    clear_history_for_test();
+}
+
+/// Test that `/sparkline` returns 503 when the history is empty.
+#[actix_web::test]
+#[serial]
+async fn sparkline_unavailable_without_history() {
+    clear_history_for_test();
+
+    let app = test::init_service(App::new().service(sparkline)).await;
+    let req = test::TestRequest::get().uri("/sparkline").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+}
+
+/// Decodes a MessagePack value into JSON, supporting the forms `to_msgpack` emits.
+fn decode_msgpack(bytes: &[u8], pos: &mut usize) -> serde_json::Value {
+    use serde_json::{json, Value};
+    fn take<'a>(bytes: &'a [u8], pos: &mut usize, n: usize) -> &'a [u8] {
+        *pos += n;
+        &bytes[*pos - n..*pos]
+    }
+    fn be(bytes: &[u8]) -> u64 {
+        bytes.iter().fold(0, |acc, b| (acc << 8) | u64::from(*b))
+    }
+    let marker = take(bytes, pos, 1)[0];
+    let (kind, len) = match marker {
+        0x00..=0x7f => return json!(marker),
+        0x80..=0x8f => ('m', (marker & 0x0f) as usize),
+        0x90..=0x9f => ('a', (marker & 0x0f) as usize),
+        0xa0..=0xbf => ('s', (marker & 0x1f) as usize),
+        0xc0 => return Value::Null,
+        0xc2 => return json!(false),
+        0xc3 => return json!(true),
+        0xcb => return json!(f64::from_bits(be(take(bytes, pos, 8)))),
+        0xcc => return json!(be(take(bytes, pos, 1))),
+        0xcd => return json!(be(take(bytes, pos, 2))),
+        0xce => return json!(be(take(bytes, pos, 4))),
+        0xcf => return json!(be(take(bytes, pos, 8))),
+        0xd9 => ('s', be(take(bytes, pos, 1)) as usize),
+        0xda => ('s', be(take(bytes, pos, 2)) as usize),
+        0xdc => ('a', be(take(bytes, pos, 2)) as usize),
+        0xde => ('m', be(take(bytes, pos, 2)) as usize),
+        0xe0..=0xff => return json!(marker as i8),
+        other => panic!("unexpected msgpack marker {:#x}", other),
+    };
+    match kind {
+        's' => json!(String::from_utf8(take(bytes, pos, len).to_vec()).unwrap()),
+        'a' => Value::Array((0..len).map(|_| decode_msgpack(bytes, pos)).collect()),
+        _ => Value::Object(
+            (0..len)
+                .map(|_| {
+                    let key = decode_msgpack(bytes, pos).as_str().unwrap().to_string();
+                    (key, decode_msgpack(bytes, pos))
+                })
+                .collect(),
+        ),
+    }
+}
+
+/// Requests `/history` with the given `Accept` header and returns the content type and body.
+async fn get_history_as(accept: Option<&str>) -> (String, Vec<u8>) {
+    let app = test::init_service(App::new().service(iperf3_history)).await;
+    let mut req = test::TestRequest::get().uri("/history");
+    if let Some(accept) = accept {
+        req = req.insert_header((http::header::ACCEPT, accept));
+    }
+    let resp = test::call_service(&app, req.to_request()).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+    let content_type = resp.headers().get(http::header::CONTENT_TYPE).unwrap().to_str().unwrap().to_string();
+    (content_type, test::read_body(resp).await.to_vec())
+}
+
+/// Test that `/history` defaults to a JSON array, also for `*/*` and unsupported types.
+#[actix_web::test]
+#[serial]
+async fn history_defaults_to_json() {
+    clear_history_for_test();
+    push_history_for_test(report_received(100_000_000.0));
+    push_history_for_test(report_received(200_000_000.0));
+
+    for accept in [None, Some("*/*"), Some("application/json"), Some("image/png")] {
+        let (content_type, body) = get_history_as(accept).await;
+        assert_eq!(content_type, "application/json");
+        let reports: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[1]["end"]["sum_received"]["bits_per_second"], 200_000_000.0);
+    }
+
+    clear_history_for_test();
+}
+
+/// Test that `Accept: text/csv` yields a header and one parseable row per report.
+#[actix_web::test]
+#[serial]
+async fn history_as_csv() {
+    clear_history_for_test();
+    let mut report = report_received(200_000_000.0);
+    report.start.timestamp.timesecs = 1_700_000_000;
+    report.end.sum_sent.retransmits = 4;
+    push_history_for_test(report);
+
+    let (content_type, body) = get_history_as(Some("text/csv")).await;
+    assert_eq!(content_type, "text/csv; charset=utf-8");
+    let body = String::from_utf8(body).unwrap();
+    let lines: Vec<&str> = body.lines().collect();
+    assert_eq!(lines[0], HISTORY_CSV_HEADER);
+    let fields: Vec<f64> = lines[1].split(',').map(|f| f.parse().unwrap()).collect();
+    assert_eq!(fields, vec![1_700_000_000.0, 0.0, 200_000_000.0, 4.0, 0.0]);
+    assert_eq!(lines.len(), 2);
+
+    clear_history_for_test();
+}
+
+/// Test that `Accept: application/x-ndjson` yields one parseable report per line.
+#[actix_web::test]
+#[serial]
+async fn history_as_ndjson() {
+    clear_history_for_test();
+    push_history_for_test(report_received(100_000_000.0));
+    push_history_for_test(report_received(200_000_000.0));
+
+    let (content_type, body) = get_history_as(Some("application/x-ndjson")).await;
+    assert_eq!(content_type, "application/x-ndjson");
+    let body = String::from_utf8(body).unwrap();
+    let reports: Vec<Iperf3Report> = body.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(reports.len(), 2);
+    assert_eq!(reports[0].end.sum_received.bits_per_second, 100_000_000.0);
+
+    clear_history_for_test();
+}
+
+/// Test that `Accept: application/msgpack` yields a MessagePack array equal to the JSON form,
+/// and that quality values pick the preferred supported type.
+#[actix_web::test]
+#[serial]
+async fn history_as_msgpack() {
+    clear_history_for_test();
+    let mut report = report_received(200_000_000.0);
+    report.start.timestamp.timesecs = 1_700_000_000;
+    report.start.connected.push(Connected { remote_host: "192.0.2.10".to_string(), ..Default::default() });
+    push_history_for_test(report);
+
+    let (content_type, body) = get_history_as(Some("text/csv;q=0.5, application/msgpack")).await;
+    assert_eq!(content_type, "application/msgpack");
+    let mut pos = 0;
+    let decoded = decode_msgpack(&body, &mut pos);
+    assert_eq!(pos, body.len());
+    assert_eq!(decoded, serde_json::to_value(get_history()).unwrap());
+
+    clear_history_for_test();
+}
+
+/// Test that `/history/{index}` serves the Nth most recent report and 404s beyond the history.
+#[actix_web::test]
+#[serial]
+async fn history_entry_by_index() {
+    clear_history_for_test();
+    for i in 1..=5 {
+        push_history_for_test(report_received(i as f64));
+    }
+    let app = test::init_service(App::new().service(iperf3_history_entry)).await;
+
+    for (index, expected) in [(0, 5.0), (2, 3.0), (4, 1.0)] {
+        let req = test::TestRequest::get().uri(&format!("/history/{}", index)).to_request();
+        let body: Iperf3Report = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.end.sum_received.bits_per_second, expected, "index {}", index);
+    }
+
+    let req = test::TestRequest::get().uri("/history/5").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+    clear_history_for_test();
+}
+
+/// Test that `/iperf3/history` summarizes the bounded history, oldest first.
+#[actix_web::test]
+#[serial]
+async fn history_points_follow_ring_buffer() {
+    unsafe { std::env::set_var("HISTORY_SIZE", "2") };
+    clear_history_for_test();
+    for i in 1..=3u64 {
+        let mut report = report_received(i as f64 * 10.0);
+        report.start.timestamp.timesecs = 1_700_000_000 + i;
+        report.end.sum_sent.bits_per_second = i as f64;
+        push_history_for_test(report);
+    }
+    let app = test::init_service(App::new().service(iperf3_history_points)).await;
+
+    let req = test::TestRequest::get().uri("/iperf3/history").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(
+        body,
+        serde_json::json!([
+            {"timesecs": 1_700_000_002u64, "bits_per_second_sent": 2.0, "bits_per_second_received": 20.0},
+            {"timesecs": 1_700_000_003u64, "bits_per_second_sent": 3.0, "bits_per_second_received": 30.0},
+        ])
+    );
+
+    unsafe { std::env::remove_var("HISTORY_SIZE") };
+    clear_history_for_test();
+}
+
+/// Test that the file history backend serves `/history` and keeps its entries, evictions
+/// and all, across a simulated restart on the same file, compacting evicted lines away.
+#[actix_web::test]
+#[serial]
+async fn file_history_survives_restart() {
+    let path = std::env::temp_dir().join(format!("iperf3-history-{}.ndjson", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut history = FileHistory::open(&path, 3, None).unwrap();
+    for i in 1..=8 {
+        history.push(report_received(i as f64), 3, None);
+    }
+    assert_eq!(history.len(), 3);
+    drop(history);
+    // Evicted lines were compacted away once they outweighed the live ones
+    assert!(std::fs::read_to_string(&path).unwrap().lines().count() < 8);
+
+    let restarted = FileHistory::open(&path, 3, None).unwrap();
+    let received: Vec<f64> = restarted.snapshot().iter().map(|r| r.end.sum_received.bits_per_second).collect();
+    assert_eq!(received, vec![6.0, 7.0, 8.0]);
+    assert_eq!(restarted.entry(0).unwrap().end.sum_received.bits_per_second, 6.0);
+
+    set_history_backend(Box::new(restarted));
+    push_history_for_test(report_received(9.0));
+    let app = test::init_service(App::new().service(iperf3_history).service(iperf3_history_entry)).await;
+    let req = test::TestRequest::get().uri("/history").to_request();
+    let body: Vec<Iperf3Report> = test::call_and_read_body_json(&app, req).await;
+    let received: Vec<f64> = body.iter().map(|r| r.end.sum_received.bits_per_second).collect();
+    assert_eq!(received, vec![6.0, 7.0, 8.0, 9.0]);
+    let req = test::TestRequest::get().uri("/history/0").to_request();
+    let latest: Iperf3Report = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(latest.end.sum_received.bits_per_second, 9.0);
+
+    set_history_backend(Box::new(History::default()));
+    let reopened = FileHistory::open(&path, 3, None).unwrap();
+    let received: Vec<f64> = reopened.snapshot().iter().map(|r| r.end.sum_received.bits_per_second).collect();
+    assert_eq!(received, vec![7.0, 8.0, 9.0]);
+    drop(reopened);
+    std::fs::remove_file(&path).unwrap();
+}
+
+/// Test that a torn last line, as left by a crash mid-write, is dropped on open and later
+/// entries are appended on a fresh line.
+#[tokio::test]
+async fn file_history_truncates_torn_last_line() {
+    let path = std::env::temp_dir().join(format!("iperf3-history-torn-{}.ndjson", std::process::id()));
+    let complete = serde_json::to_string(&report_received(1.0)).unwrap();
+    std::fs::write(&path, format!("{}\n{{\"start\": {{", complete)).unwrap();
+
+    let mut history = FileHistory::open(&path, 10, None).unwrap();
+    assert_eq!(history.len(), 1);
+    history.push(report_received(2.0), 10, None);
+    drop(history);
+
+    let reopened = FileHistory::open(&path, 10, None).unwrap();
+    let received: Vec<f64> = reopened.snapshot().iter().map(|r| r.end.sum_received.bits_per_second).collect();
+    assert_eq!(received, vec![1.0, 2.0]);
+    drop(reopened);
+    std::fs::remove_file(&path).unwrap();
+}
+
+/// History backend recording, for each push, whether it ran off the test's thread and
+/// whether `LAST_RESULT` was free meanwhile.
+struct ProbeBackend {
+    test_thread: std::thread::ThreadId,
+    pushes: std::sync::Arc<std::sync::Mutex<Vec<(bool, bool)>>>,
+}
+
+impl HistoryBackend for ProbeBackend {
+    fn push(&mut self, _report: Iperf3Report, _max_len: usize, _max_bytes: Option<usize>) {
+        let off_runtime = std::thread::current().id() != self.test_thread;
+        self.pushes.lock().unwrap().push((off_runtime, LAST_RESULT.try_lock().is_ok()));
+    }
+    fn len(&self) -> usize {
+        self.pushes.lock().unwrap().len()
+    }
+    fn total_bytes(&self) -> usize {
+        0
+    }
+    fn snapshot(&self) -> Vec<Iperf3Report> {
+        Vec::new()
+    }
+    fn entry(&self, _index: usize) -> Option<Iperf3Report> {
+        None
+    }
+    fn clear(&mut self) {}
+}
+
+/// Mock runner returning an empty report.
+struct EmptyReportRunner;
+
+#[async_trait::async_trait]
+impl Iperf3Runner for EmptyReportRunner {
+    async fn run_iperf3(&self, _iperf3_ip: String, _iperf3_port: String) -> Result<String, Iperf3Error> {
+        Ok(serde_json::to_string(&Iperf3Report::default()).unwrap())
+    }
+}
+
+/// Test that a cycle appends to the history on a blocking thread, off the runtime, and
+/// after releasing `LAST_RESULT`.
+#[tokio::test]
+#[serial]
+async fn history_is_appended_off_runtime_without_holding_the_cache() {
+    clear_last_result_for_test();
+    let pushes = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    set_history_backend(Box::new(ProbeBackend { test_thread: std::thread::current().id(), pushes: pushes.clone() }));
+
+    run_iperf3_and_cache_with_runner(&EmptyReportRunner, "127.0.0.1".to_string(), "5201".to_string())
+        .await
+        .unwrap();
+    assert_eq!(*pushes.lock().unwrap(), vec![(true, true)]);
+
+    set_history_backend(Box::new(History::default()));
+    clear_last_result_for_test();
+}
+
+/// Test that `aggregate` computes min, max and mean per direction and sums retransmits,
+/// and yields no throughput for an empty slice.
+#[tokio::test]
+async fn aggregate_computes_min_max_mean() {
+    let run = |sent: f64, received: f64, retransmits: u32| {
+        let mut report = report_received(received * 1_000_000.0);
+        report.end.sum_sent.bits_per_second = sent * 1_000_000.0;
+        report.end.sum_sent.retransmits = retransmits;
+        report
+    };
+    let stats = aggregate(&[run(100.0, 90.0, 1), run(300.0, 270.0, u32::MAX), run(200.0, 180.0, 2)]);
+    assert_eq!(stats.count, 3);
+    assert_eq!(stats.sent, Some(ThroughputStats { min_mbps: 100.0, max_mbps: 300.0, mean_mbps: 200.0 }));
+    assert_eq!(stats.received, Some(ThroughputStats { min_mbps: 90.0, max_mbps: 270.0, mean_mbps: 180.0 }));
+    assert_eq!(stats.total_retransmits, u64::from(u32::MAX) + 3);
+
+    let empty = aggregate(&[]);
+    assert_eq!(empty, AggregateStats { count: 0, sent: None, received: None, total_retransmits: 0 });
+}
+
+/// Test that `/iperf3/stats` aggregates the history.
+#[actix_web::test]
+#[serial]
+async fn stats_aggregate_history() {
+    clear_history_for_test();
+    let app = test::init_service(App::new().service(iperf3_stats)).await;
+
+    let req = test::TestRequest::get().uri("/iperf3/stats").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["count"], 0);
+    assert!(body["received"].is_null());
+
+    push_history_for_test(report_received(10_000_000.0));
+    push_history_for_test(report_received(30_000_000.0));
+    let req = test::TestRequest::get().uri("/iperf3/stats").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["count"], 2);
+    assert_eq!(body["received"]["mean_mbps"], 20.0);
+    assert_eq!(body["received"]["max_mbps"], 30.0);
+
+    clear_history_for_test();
+}
@@ -0,0 +1,91 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for the in-flight request limit (`MAX_INFLIGHT_REQUESTS`).
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use actix_web::middleware::from_fn;
+use actix_web::{test, http, web, App, HttpResponse};
+use serial_test::serial;
+use tokio::sync::Notify;
+use iperf3_statuspage::*;
+
+/// Test that requests beyond the limit get 503 with `Retry-After` while the limit is held
+/// by slow requests, that `/healthz` is exempt and that freed slots are reused.
+#[actix_web::test]
+async fn overflow_beyond_limit_is_refused() {
+    let entered = Arc::new(AtomicUsize::new(0));
+    let release = Arc::new(Notify::new());
+    let limit = InflightLimit::new(Some(2));
+    let app = {
+        let (entered, release) = (entered.clone(), release.clone());
+        test::init_service(
+            App::new()
+                .wrap(from_fn(move |req, next| limit_inflight(limit.clone(), req, next)))
+                .service(healthz)
+                .route(
+                    "/slow",
+                    web::get().to(move || {
+                        let (entered, release) = (entered.clone(), release.clone());
+                        async move {
+                            entered.fetch_add(1, Ordering::SeqCst);
+                            release.notified().await;
+                            HttpResponse::Ok().finish()
+                        }
+                    }),
+                ),
+        )
+        .await
+    };
+    let slow = || test::call_service(&app, test::TestRequest::get().uri("/slow").to_request());
+
+    let held = futures::future::join(slow(), slow());
+    let overflow = async {
+        while entered.load(Ordering::SeqCst) < 2 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        let resp = slow().await;
+        assert_eq!(resp.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(resp.headers().get(http::header::RETRY_AFTER).unwrap(), "1");
+        let resp = test::call_service(&app, test::TestRequest::get().uri("/healthz").to_request()).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        release.notify_waiters();
+    };
+    let ((first, second), ()) = futures::future::join(held, overflow).await;
+    assert_eq!(first.status(), http::StatusCode::OK);
+    assert_eq!(second.status(), http::StatusCode::OK);
+    assert_eq!(entered.load(Ordering::SeqCst), 2, "the refused request must not reach its handler");
+
+    // The slots are free again once the held requests completed
+    let (resp, ()) = futures::future::join(slow(), async {
+        while entered.load(Ordering::SeqCst) < 3 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        release.notify_waiters();
+    })
+    .await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+}
+
+/// Test that `MAX_INFLIGHT_REQUESTS` disables the limit when unset or zero.
+#[tokio::test]
+#[serial]
+async fn max_inflight_requests_reads_env() {
+    unsafe { std::env::set_var("MAX_INFLIGHT_REQUESTS", "64") };
+    assert_eq!(max_inflight_requests(), Some(64));
+    unsafe { std::env::set_var("MAX_INFLIGHT_REQUESTS", "0") };
+    assert_eq!(max_inflight_requests(), None);
+    unsafe { std::env::remove_var("MAX_INFLIGHT_REQUESTS") };
+    assert_eq!(max_inflight_requests(), None);
+}
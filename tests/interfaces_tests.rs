@@ -0,0 +1,88 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for per-interface testing with `IPERF3_BIND_ADDRESSES`.
+//!
+//! These tests modify `IPERF3_BIND_ADDRESSES` and are annotated with `#[serial]`.
+
+use actix_web::{test, http, App};
+use async_trait::async_trait;
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+/// Mock runner reporting a received throughput that depends on the bound address.
+struct PerInterfaceRunner;
+
+#[async_trait]
+impl Iperf3Runner for PerInterfaceRunner {
+    async fn run_iperf3(&self, _iperf3_ip: String, _iperf3_port: String) -> Result<String, Iperf3Error> {
+        unreachable!("runs go through run_iperf3_with_options")
+    }
+
+    async fn run_iperf3_with_options(&self, opts: &Iperf3Options) -> Result<String, Iperf3Error> {
+        let mbps = match opts.bind.as_deref() {
+            Some("192.0.2.10") => 100.0,
+            Some("198.51.100.10") => 300.0,
+            other => panic!("unexpected bind address {:?}", other),
+        };
+        let mut report = Iperf3Report::default();
+        report.end.sum_sent.bits_per_second = mbps * 1_000_000.0;
+        report.end.sum_received.bits_per_second = mbps * 1_000_000.0;
+        Ok(serde_json::to_string(&report).unwrap())
+    }
+}
+
+/// Test that each bind address is run and cached separately, served with `iface`, and
+/// aggregated at `/summary/interfaces`.
+#[actix_web::test]
+#[serial]
+async fn bind_addresses_are_cached_per_interface() {
+    unsafe {
+        std::env::set_var("IPERF3_BIND_ADDRESSES", "192.0.2.10, 198.51.100.10");
+    }
+    clear_interface_results_for_test();
+
+    let targets = configured_targets("10.0.0.7".to_string(), "5201".to_string());
+    assert_eq!(targets.len(), 2);
+    assert!(build_iperf3_args(&targets[0].options).windows(2).any(|w| w == ["-B", "192.0.2.10"]));
+    for target in &targets {
+        run_iperf3_and_cache_with_options(&PerInterfaceRunner, &target.options).await.unwrap();
+    }
+
+    let (first, _) = get_interface_result("192.0.2.10").unwrap();
+    let (second, _) = get_interface_result("198.51.100.10").unwrap();
+    assert_eq!(first.end.sum_received.bits_per_second, 100_000_000.0);
+    assert_eq!(second.end.sum_received.bits_per_second, 300_000_000.0);
+
+    let app = test::init_service(App::new().service(iperf3).service(iperf3_summary_interfaces)).await;
+
+    let req = test::TestRequest::get().uri("/iperf3?iface=198.51.100.10").to_request();
+    let body: Iperf3Report = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body.end.sum_received.bits_per_second, 300_000_000.0);
+
+    let req = test::TestRequest::get().uri("/iperf3?iface=203.0.113.1").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+
+    let req = test::TestRequest::get().uri("/summary/interfaces").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let addresses: Vec<&str> =
+        body["interfaces"].as_array().unwrap().iter().map(|i| i["bind_address"].as_str().unwrap()).collect();
+    assert_eq!(addresses, ["192.0.2.10", "198.51.100.10"]);
+    assert_eq!(body["total_received_mbps"], 400.0);
+    assert_eq!(body["total_sent_mbps"], 400.0);
+
+    unsafe {
+        std::env::remove_var("IPERF3_BIND_ADDRESSES");
+    }
+    clear_interface_results_for_test();
+}
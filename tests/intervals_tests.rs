@@ -0,0 +1,277 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for the `/intervals` endpoint, interval series helpers and `/iperf3?intervals=`.
+
+use actix_web::{test, http, App};
+use serde_json::Value;
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+/// Builds an interval with a single stream spanning `start..end`.
+fn sample_interval(start: f64, end: f64, bytes: u64) -> Interval {
+    let bits_per_second = bytes as f64 * 8.0 / (end - start);
+    Interval {
+        streams: vec![Stream {
+            socket: 5,
+            start,
+            end,
+            seconds: end - start,
+            bytes,
+            bits_per_second,
+            ..Stream::default()
+        }],
+        sum: Sum {
+            start,
+            end,
+            seconds: end - start,
+            bytes,
+            bits_per_second,
+            ..Sum::default()
+        },
+    }
+}
+
+/// Builds a report with slightly jittered interval boundaries, as iperf3 emits them.
+fn sample_report() -> Iperf3Report {
+    Iperf3Report {
+        intervals: vec![
+            sample_interval(0.0, 1.001128, 8_758_493_184),
+            sample_interval(1.001128, 2.000423, 2_688_024_576),
+            sample_interval(2.000423, 3.000931, 5_000_000_000),
+        ],
+        ..Iperf3Report::default()
+    }
+}
+
+/// Test that rounding aligns every boundary to the step while keeping
+/// bytes and throughput untouched.
+#[tokio::test]
+async fn round_interval_boundaries_aligns_to_step() {
+    let report = sample_report();
+    let rounded = round_interval_boundaries(&report.intervals, 1.0);
+
+    let boundaries: Vec<(f64, f64)> = rounded.iter().map(|i| (i.sum.start, i.sum.end)).collect();
+    assert_eq!(boundaries, vec![(0.0, 1.0), (1.0, 2.0), (2.0, 3.0)]);
+
+    for (original, rounded) in report.intervals.iter().zip(&rounded) {
+        assert_eq!(rounded.streams[0].start, rounded.sum.start);
+        assert_eq!(rounded.streams[0].end, rounded.sum.end);
+        assert_eq!(rounded.sum.bytes, original.sum.bytes);
+        assert_eq!(rounded.sum.bits_per_second, original.sum.bits_per_second);
+        assert_eq!(rounded.streams[0].bits_per_second, original.streams[0].bits_per_second);
+    }
+}
+
+/// Test that `/intervals?round=0.5` returns the rounded boundaries.
+#[actix_web::test]
+#[serial]
+async fn intervals_endpoint_rounds_boundaries() {
+    set_last_result_for_test(sample_report());
+
+    let app = test::init_service(App::new().service(iperf3_intervals)).await;
+    let req = test::TestRequest::get().uri("/intervals?round=0.5").to_request();
+    let resp = test::call_service(&app, req).await;
+
+    assert_eq!(resp.status(), http::StatusCode::OK);
+
+    let body = test::read_body(resp).await;
+    let result: Vec<Interval> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(result.len(), 3);
+    assert_eq!(result[0].sum.end, 1.0);
+    assert_eq!(result[1].sum.end, 2.0);
+    assert_eq!(result[2].sum.end, 3.0);
+
+    clear_last_result_for_test();
+}
+
+/// Test that a non-positive rounding step is rejected.
+#[actix_web::test]
+#[serial]
+async fn intervals_endpoint_rejects_invalid_step() {
+    set_last_result_for_test(sample_report());
+
+    let app = test::init_service(App::new().service(iperf3_intervals)).await;
+    let req = test::TestRequest::get().uri("/intervals?round=0").to_request();
+    let resp = test::call_service(&app, req).await;
+
+    assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+    clear_last_result_for_test();
+}
+
+/// Test that `/intervals` returns 503 when there is no cached result.
+#[actix_web::test]
+#[serial]
+async fn intervals_endpoint_unavailable_without_cache() {
+    clear_last_result_for_test();
+
+    let app = test::init_service(App::new().service(iperf3_intervals)).await;
+    let req = test::TestRequest::get().uri("/intervals").to_request();
+    let resp = test::call_service(&app, req).await;
+
+    assert_eq!(resp.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+}
+
+/// Test that `/iperf3?intervals=false` omits the intervals but keeps `start` and `end`.
+#[actix_web::test]
+#[serial]
+async fn iperf3_intervals_false_omits_intervals() {
+    set_last_result_for_test(sample_report());
+
+    let app = test::init_service(App::new().service(iperf3)).await;
+    let req = test::TestRequest::get().uri("/iperf3?intervals=false").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+
+    let body: Value = test::read_body_json(resp).await;
+    assert!(body.get("intervals").is_none());
+    assert!(body.get("start").is_some());
+    assert!(body.get("end").is_some());
+
+    clear_last_result_for_test();
+}
+
+/// Test that `/iperf3?intervals=N` keeps only the first N intervals, and that
+/// the full report is served by default.
+#[actix_web::test]
+#[serial]
+async fn iperf3_intervals_count_caps_intervals() {
+    set_last_result_for_test(sample_report());
+
+    let app = test::init_service(App::new().service(iperf3)).await;
+    let req = test::TestRequest::get().uri("/iperf3?intervals=2").to_request();
+    let body: Iperf3Report = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body.intervals.len(), 2);
+    assert_eq!(body.intervals[1].sum.bytes, 2_688_024_576);
+
+    let req = test::TestRequest::get().uri("/iperf3").to_request();
+    let body: Iperf3Report = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body.intervals.len(), 3);
+
+    let req = test::TestRequest::get().uri("/iperf3?intervals=many").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+    clear_last_result_for_test();
+}
+
+/// Builds an interval with two streams carrying distinct TCP state.
+fn tcp_interval(end: f64, retransmits: u32, snd_cwnd: u64, rtt: u32) -> Interval {
+    let stream = |socket: u32, factor: u32| Stream {
+        socket,
+        end,
+        retransmits: retransmits * factor,
+        snd_cwnd: snd_cwnd * factor as u64,
+        rtt: rtt * factor,
+        ..Stream::default()
+    };
+    Interval { streams: vec![stream(5, 1), stream(7, 2)], sum: Sum::default() }
+}
+
+/// Test that `/intervals/tcp` extracts each stream's series across intervals.
+#[actix_web::test]
+#[serial]
+async fn intervals_tcp_extracts_series_per_stream() {
+    set_last_result_for_test(Iperf3Report {
+        intervals: vec![tcp_interval(1.0, 0, 1_000_000, 900), tcp_interval(2.0, 3, 1_500_000, 1100)],
+        ..Iperf3Report::default()
+    });
+
+    let app = test::init_service(App::new().service(iperf3_intervals_tcp)).await;
+    let req = test::TestRequest::get().uri("/intervals/tcp").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+    let series: Vec<TcpStreamSeries> = test::read_body_json(resp).await;
+
+    assert_eq!(series.len(), 2);
+    assert_eq!(series[0].socket, 5);
+    assert_eq!(
+        series[0].samples,
+        vec![
+            TcpSample { time: 1.0, retransmits: 0, snd_cwnd: 1_000_000, rtt: 900 },
+            TcpSample { time: 2.0, retransmits: 3, snd_cwnd: 1_500_000, rtt: 1100 },
+        ]
+    );
+    assert_eq!(series[1].socket, 7);
+    assert_eq!(series[1].samples[1], TcpSample { time: 2.0, retransmits: 6, snd_cwnd: 3_000_000, rtt: 2200 });
+
+    clear_last_result_for_test();
+}
+
+/// Test that `/intervals/tcp` returns 503 without a cached result or intervals.
+#[actix_web::test]
+#[serial]
+async fn intervals_tcp_unavailable_when_empty() {
+    let app = test::init_service(App::new().service(iperf3_intervals_tcp)).await;
+
+    clear_last_result_for_test();
+    let req = test::TestRequest::get().uri("/intervals/tcp").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+
+    set_last_result_for_test(Iperf3Report::default());
+    let req = test::TestRequest::get().uri("/intervals/tcp").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+
+    clear_last_result_for_test();
+}
+
+/// Builds an interval of one second carrying the given throughput and retransmits.
+fn rate_interval(index: usize, bits_per_second: f64, retransmits: u32) -> Interval {
+    Interval {
+        streams: vec![],
+        sum: Sum {
+            start: index as f64,
+            end: index as f64 + 1.0,
+            seconds: 1.0,
+            bytes: 100,
+            bits_per_second,
+            retransmits,
+            ..Sum::default()
+        },
+    }
+}
+
+/// Test that every aggregation mode buckets a sample series as expected.
+#[actix_web::test]
+#[serial]
+async fn intervals_downsample_with_each_aggregation() {
+    let rates = [100.0, 400.0, 250.0, 200.0, 50.0, 350.0];
+    set_last_result_for_test(Iperf3Report {
+        intervals: rates.iter().enumerate().map(|(i, &rate)| rate_interval(i, rate, i as u32)).collect(),
+        ..Iperf3Report::default()
+    });
+    let app = test::init_service(App::new().service(iperf3_intervals)).await;
+
+    for (query, expected_rates, expected_retransmits) in [
+        ("", vec![250.0, 200.0], vec![1, 4]),
+        ("&agg=avg", vec![250.0, 200.0], vec![1, 4]),
+        ("&agg=max", vec![400.0, 350.0], vec![2, 5]),
+        ("&agg=min", vec![100.0, 50.0], vec![0, 3]),
+        ("&agg=last", vec![250.0, 350.0], vec![2, 5]),
+    ] {
+        let req = test::TestRequest::get().uri(&format!("/intervals?points=2{}", query)).to_request();
+        let buckets: Vec<Interval> = test::call_and_read_body_json(&app, req).await;
+        let bucket_rates: Vec<f64> = buckets.iter().map(|i| i.sum.bits_per_second).collect();
+        let bucket_retransmits: Vec<u32> = buckets.iter().map(|i| i.sum.retransmits).collect();
+        assert_eq!(bucket_rates, expected_rates, "{}", query);
+        assert_eq!(bucket_retransmits, expected_retransmits, "{}", query);
+        assert_eq!((buckets[0].sum.start, buckets[0].sum.end, buckets[0].sum.bytes), (0.0, 3.0, 300), "{}", query);
+    }
+
+    for uri in ["/intervals?points=0", "/intervals?points=2&agg=median"] {
+        let req = test::TestRequest::get().uri(uri).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), http::StatusCode::BAD_REQUEST, "{}", uri);
+    }
+
+    clear_last_result_for_test();
+}
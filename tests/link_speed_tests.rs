@@ -0,0 +1,53 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for the sysfs link speed detection and `link_speed_mbps` in `/summary`.
+
+use std::fs;
+use iperf3_statuspage::*;
+
+/// Test that the speed is parsed from a stubbed sysfs tree and that virtual or missing
+/// interfaces are omitted.
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn link_speed_is_read_from_sysfs() {
+    let net_dir = std::env::temp_dir().join(format!("iperf3_statuspage_sysfs_{}", std::process::id()));
+    fs::create_dir_all(net_dir.join("eth0")).unwrap();
+    fs::write(net_dir.join("eth0").join("speed"), "2500\n").unwrap();
+    fs::create_dir_all(net_dir.join("veth0")).unwrap();
+    fs::write(net_dir.join("veth0").join("speed"), "-1\n").unwrap();
+
+    assert_eq!(read_link_speed_mbps(&net_dir, "eth0"), Some(2500));
+    assert_eq!(read_link_speed_mbps(&net_dir, "veth0"), None);
+    assert_eq!(read_link_speed_mbps(&net_dir, "lo"), None);
+
+    fs::remove_dir_all(&net_dir).unwrap();
+}
+
+/// Test that the link speed yields utilization figures unless `LINK_CAPACITY_MBPS` set them.
+#[tokio::test]
+async fn link_speed_derives_utilization() {
+    let mut report = Iperf3Report::default();
+    report.end.sum_received.bits_per_second = 500_000_000.0;
+    report.end.sum_sent.bits_per_second = 250_000_000.0;
+
+    let mut summary = build_summary(&report, None);
+    summary.apply_link_speed(1000);
+    assert_eq!(summary.link_speed_mbps, Some(1000));
+    assert_eq!(summary.received_utilization_percent, Some(50.0));
+    assert_eq!(summary.sent_utilization_percent, Some(25.0));
+
+    let mut summary = build_summary(&report, None);
+    summary.received_utilization_percent = Some(10.0);
+    summary.apply_link_speed(1000);
+    assert_eq!(summary.received_utilization_percent, Some(10.0));
+}
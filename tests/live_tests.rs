@@ -0,0 +1,104 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for `--json-stream` parsing and `/iperf3/live`.
+//!
+//! These tests share the live buffer and the cache and are annotated with `#[serial]`.
+
+use actix_web::{test, http, App};
+use async_trait::async_trait;
+use serial_test::serial;
+use tokio::io::{AsyncWriteExt, BufReader};
+use iperf3_statuspage::*;
+
+fn start_line() -> String {
+    let start = serde_json::to_value(Iperf3Report::default().start).unwrap();
+    serde_json::json!({"event": "start", "data": start}).to_string()
+}
+
+fn interval_line(end: f64, bits_per_second: f64) -> String {
+    let mut interval = Interval::default();
+    interval.sum.end = end;
+    interval.sum.bits_per_second = bits_per_second;
+    serde_json::json!({"event": "interval", "data": interval}).to_string()
+}
+
+fn end_line(bits_per_second: f64) -> String {
+    let mut end = Iperf3Report::default().end;
+    end.sum_received.bits_per_second = bits_per_second;
+    serde_json::json!({"event": "end", "data": end}).to_string()
+}
+
+/// Mock runner replaying streamed output through `read_json_stream`.
+struct StreamRunner {
+    lines: Vec<String>,
+}
+
+#[async_trait]
+impl Iperf3Runner for StreamRunner {
+    async fn run_iperf3(&self, _iperf3_ip: String, _iperf3_port: String) -> Result<String, Iperf3Error> {
+        let output = self.lines.join("\n");
+        read_json_stream(output.as_bytes()).await.map_err(|e| Iperf3Error::Spawn(e.to_string()))
+    }
+}
+
+/// Test that the live buffer grows with every streamed interval and that the finished
+/// stream is cached as a normal report.
+#[actix_web::test]
+#[serial]
+async fn streamed_intervals_update_live_buffer_and_cache() {
+    clear_live_run_for_test();
+    clear_last_result_for_test();
+    let app = test::init_service(App::new().service(iperf3_live)).await;
+    let req = test::TestRequest::get().uri("/iperf3/live").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+
+    let (mut writer, reader) = tokio::io::duplex(64 * 1024);
+    let reading = tokio::spawn(read_json_stream(BufReader::new(reader)));
+    for line in [start_line(), interval_line(1.0, 900e6), interval_line(2.0, 950e6)] {
+        writer.write_all(format!("{}\n", line).as_bytes()).await.unwrap();
+    }
+    while get_live_run().is_none_or(|live| live.intervals.len() < 2) {
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    }
+
+    let req = test::TestRequest::get().uri("/iperf3/live").to_request();
+    let live: LiveRun = test::call_and_read_body_json(&app, req).await;
+    assert!(live.in_progress);
+    let ends: Vec<f64> = live.intervals.iter().map(|i| i.sum.end).collect();
+    assert_eq!(ends, vec![1.0, 2.0]);
+
+    writer.write_all(format!("{}\n", end_line(925e6)).as_bytes()).await.unwrap();
+    drop(writer);
+    let assembled: Iperf3Report = serde_json::from_str(&reading.await.unwrap().unwrap()).unwrap();
+    assert_eq!(assembled.intervals.len(), 2);
+    assert!(!get_live_run().unwrap().in_progress);
+
+    let runner = StreamRunner { lines: vec![start_line(), interval_line(1.0, 900e6), end_line(925e6)] };
+    let report = run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into()).await.unwrap();
+    assert_eq!(report.intervals.len(), 1);
+    assert_eq!(get_last_result().unwrap().end.sum_received.bits_per_second, 925e6);
+
+    clear_live_run_for_test();
+    clear_last_result_for_test();
+}
+
+/// Test that a streamed `error` event becomes iperf3's `{"error": ...}` document.
+#[tokio::test]
+#[serial]
+async fn streamed_error_event_is_reported() {
+    let lines = [start_line(), r#"{"event": "error", "data": "error - the server is busy running a test. try again later"}"#.to_string()];
+    let output = read_json_stream(lines.join("\n").as_bytes()).await.unwrap();
+    let error = Iperf3Error::from_failed_run(Some(1), &output, "");
+    assert!(matches!(error, Iperf3Error::ServerBusy(_)));
+    clear_live_run_for_test();
+}
@@ -0,0 +1,73 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for the `RUST_LOG` filter and the stderr `tracing` subscriber.
+//!
+//! These tests modify the environment and are annotated with `#[serial]`.
+
+use std::time::{Duration, UNIX_EPOCH};
+use serial_test::serial;
+use tracing::Level;
+use iperf3_statuspage::*;
+
+/// Test that an unset `RUST_LOG` logs `info` and above.
+#[test]
+#[serial]
+fn log_filter_defaults_to_info() {
+    unsafe { std::env::remove_var("RUST_LOG") };
+    let filter = LogFilter::from_env();
+    assert!(filter.enabled("iperf3_statuspage", &Level::INFO));
+    assert!(filter.enabled("iperf3_statuspage", &Level::ERROR));
+    assert!(!filter.enabled("iperf3_statuspage", &Level::DEBUG));
+    assert_eq!(rust_log(), "info");
+}
+
+/// Test that the most specific target directive wins and only matches whole module paths.
+#[test]
+fn log_filter_prefers_most_specific_target() {
+    let filter = LogFilter::parse("error, iperf3_statuspage=warn,iperf3_statuspage::history=trace");
+    assert!(filter.enabled("iperf3_statuspage::history", &Level::TRACE));
+    assert!(filter.enabled("iperf3_statuspage::history_file", &Level::WARN));
+    assert!(!filter.enabled("iperf3_statuspage::history_file", &Level::INFO));
+    assert!(!filter.enabled("actix_server::worker", &Level::WARN));
+    assert!(filter.enabled("actix_server::worker", &Level::ERROR));
+}
+
+/// Test that `off` silences everything and invalid directives are skipped.
+#[test]
+fn log_filter_off_and_invalid_directives() {
+    let filter = LogFilter::parse("off");
+    assert!(!filter.enabled("iperf3_statuspage", &Level::ERROR));
+
+    let filter = LogFilter::parse("loud,iperf3_statuspage=nope,debug");
+    assert!(filter.enabled("iperf3_statuspage", &Level::DEBUG));
+    assert!(!filter.enabled("iperf3_statuspage", &Level::TRACE));
+}
+
+/// Test RFC 3339 formatting across the epoch and a leap day.
+#[test]
+fn utc_timestamps_are_rfc3339() {
+    assert_eq!(format_utc_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+    let leap_day = UNIX_EPOCH + Duration::from_secs(1_709_251_199) + Duration::from_millis(7);
+    assert_eq!(format_utc_timestamp(leap_day), "2024-02-29T23:59:59.007Z");
+}
+
+/// Test that installing the subscriber twice keeps the first one instead of panicking.
+#[test]
+#[serial]
+fn init_logging_is_idempotent() {
+    unsafe { std::env::set_var("RUST_LOG", "off") };
+    init_logging();
+    assert!(!init_logging());
+    tracing::info!("not logged");
+    unsafe { std::env::remove_var("RUST_LOG") };
+}
@@ -0,0 +1,85 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for maintenance mode.
+//!
+//! Maintenance state and the cache are process-global, so tests are annotated with `#[serial]`.
+
+use actix_web::{test, http, App};
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+/// Test that toggling maintenance makes data endpoints return 503 with `Retry-After`
+/// while `/healthz` stays up.
+#[actix_web::test]
+#[serial]
+async fn toggling_maintenance_serves_503_with_retry_after() {
+    unsafe { std::env::remove_var("MAINTENANCE_MODE") };
+    unsafe { std::env::set_var("MAINTENANCE_RETRY_AFTER_SECONDS", "120") };
+    clear_maintenance_for_test();
+    set_last_result_for_test(Iperf3Report::default());
+
+    let app = test::init_service(
+        App::new()
+            .service(iperf3)
+            .service(iperf3_summary)
+            .service(get_maintenance)
+            .service(set_maintenance_mode)
+            .service(healthz),
+    )
+    .await;
+
+    let req = test::TestRequest::get().uri("/iperf3").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), http::StatusCode::OK);
+
+    let req = test::TestRequest::post().uri("/admin/maintenance?enabled=true").to_request();
+    let state: MaintenanceState = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(state, MaintenanceState { enabled: true, retry_after_seconds: 120 });
+
+    for uri in ["/iperf3", "/summary"] {
+        let req = test::TestRequest::get().uri(uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::SERVICE_UNAVAILABLE, "{}", uri);
+        assert_eq!(resp.headers().get(http::header::RETRY_AFTER).unwrap(), "120");
+    }
+
+    let req = test::TestRequest::get().uri("/healthz").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), http::StatusCode::OK);
+
+    let req = test::TestRequest::post().uri("/admin/maintenance?enabled=false").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), http::StatusCode::OK);
+    let req = test::TestRequest::get().uri("/iperf3").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+    assert!(resp.headers().get(http::header::RETRY_AFTER).is_none());
+
+    unsafe { std::env::remove_var("MAINTENANCE_RETRY_AFTER_SECONDS") };
+    clear_maintenance_for_test();
+    clear_last_result_for_test();
+}
+
+/// Test that `MAINTENANCE_MODE` enables maintenance and a runtime toggle overrides it.
+#[tokio::test]
+#[serial]
+async fn maintenance_mode_env_and_override() {
+    clear_maintenance_for_test();
+    unsafe { std::env::set_var("MAINTENANCE_MODE", "true") };
+    assert!(maintenance_enabled());
+    assert!(matches!(ensure_not_in_maintenance(), Err(Iperf3Error::Maintenance { .. })));
+
+    set_maintenance(false);
+    assert!(!maintenance_enabled());
+    assert!(ensure_not_in_maintenance().is_ok());
+
+    unsafe { std::env::remove_var("MAINTENANCE_MODE") };
+    clear_maintenance_for_test();
+}
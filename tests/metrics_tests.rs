@@ -0,0 +1,181 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for the Prometheus rendering of `/metrics`.
+//!
+//! These tests modify `METRICS_*` environment variables and are annotated with `#[serial]`.
+
+use actix_web::{test, http, App};
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+/// Test that each `METRICS_FLOAT_FORMAT` formats a large value differently.
+#[tokio::test]
+#[serial]
+async fn float_format_differs_per_setting() {
+    let status = RunStatus { actual_interval_seconds: Some(1_250_000_000.5), ..Default::default() };
+    let mut rendered = Vec::new();
+    for (setting, expected) in [
+        (None, "iperf3_actual_interval_seconds 1250000000.5\n"),
+        (Some("fixed"), "iperf3_actual_interval_seconds 1250000000.500\n"),
+        (Some("scientific"), "iperf3_actual_interval_seconds 1.2500000005e9\n"),
+        (Some("SHORTEST"), "iperf3_actual_interval_seconds 1250000000.5\n"),
+    ] {
+        match setting {
+            Some(value) => unsafe { std::env::set_var("METRICS_FLOAT_FORMAT", value) },
+            None => unsafe { std::env::remove_var("METRICS_FLOAT_FORMAT") },
+        }
+        let body = render_status_metrics(&status);
+        assert!(body.contains(expected), "{:?} rendered {:?}", setting, body);
+        rendered.push(body);
+    }
+    assert_ne!(rendered[0], rendered[1]);
+    assert_ne!(rendered[0], rendered[2]);
+    assert_ne!(rendered[1], rendered[2]);
+
+    unsafe { std::env::remove_var("METRICS_FLOAT_FORMAT") };
+    assert_eq!(metrics_float_format(), MetricsFloatFormat::Shortest);
+}
+
+/// Test that per-stream series are gated by `METRICS_PER_STREAM` while aggregates stay.
+#[tokio::test]
+#[serial]
+async fn per_stream_series_follow_toggle() {
+    let mut report = Iperf3Report::default();
+    report.end.sum_sent.bits_per_second = 900_000_000.0;
+    report.end.sum_received.bits_per_second = 900_000_000.0;
+    for (socket, retransmits) in [(5, 3), (7, 181)] {
+        let mut stream = EndStream::default();
+        stream.sender.socket = socket;
+        stream.sender.retransmits = retransmits;
+        report.end.streams.push(stream);
+    }
+
+    unsafe { std::env::remove_var("METRICS_PER_STREAM") };
+    let body = render_prometheus(&report);
+    assert!(!body.contains("iperf3_stream_retransmits"));
+    assert!(body.contains("iperf3_asymmetry_ratio 1\n"));
+
+    unsafe { std::env::set_var("METRICS_PER_STREAM", "true") };
+    let body = render_prometheus(&report);
+    assert!(body.contains("iperf3_stream_retransmits{socket=\"5\"} 3\n"));
+    assert!(body.contains("iperf3_stream_retransmits{socket=\"7\"} 181\n"));
+    assert!(body.contains("iperf3_asymmetry_ratio 1\n"));
+
+    unsafe { std::env::remove_var("METRICS_PER_STREAM") };
+}
+
+/// Test that every run counter is followed by a numeric `_created` sample at process start,
+/// and that `METRICS_CREATED=false` drops them.
+#[tokio::test]
+#[serial]
+async fn run_counters_have_created_timestamps() {
+    unsafe { std::env::remove_var("METRICS_CREATED") };
+    record_run(true);
+    record_run(false);
+
+    let body = render_counter_metrics();
+    let start = process_start_time().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs_f64();
+    for name in ["iperf3_runs", "iperf3_run_failures"] {
+        let total_line = body.lines().find(|l| l.starts_with(&format!("{}_total ", name))).unwrap();
+        assert!(total_line.split(' ').nth(1).unwrap().parse::<u64>().unwrap() >= 1);
+
+        let created_line = body.lines().find(|l| l.starts_with(&format!("{}_created ", name))).unwrap();
+        let created: f64 = created_line.split(' ').nth(1).unwrap().parse().unwrap();
+        assert!((created - start).abs() < 1e-3, "{} != {}", created, start);
+    }
+
+    unsafe { std::env::set_var("METRICS_CREATED", "false") };
+    assert!(!render_counter_metrics().contains("_created"));
+    unsafe { std::env::remove_var("METRICS_CREATED") };
+}
+
+/// Test that the report's headline gauges are rendered from the cached result.
+#[tokio::test]
+#[serial]
+async fn report_gauges_are_rendered() {
+    let mut report = Iperf3Report::default();
+    report.start.timestamp.timesecs = 1_700_000_000;
+    report.end.sum_sent.bits_per_second = 940_000_000.0;
+    report.end.sum_sent.retransmits = 12;
+    report.end.sum_received.bits_per_second = 930_000_000.0;
+    report.end.cpu_utilization_percent.host_total = 7.5;
+
+    let body = render_prometheus(&report);
+    for line in [
+        "# TYPE iperf3_sent_bits_per_second gauge\n",
+        "iperf3_sent_bits_per_second 940000000\n",
+        "iperf3_received_bits_per_second 930000000\n",
+        "iperf3_retransmits 12\n",
+        "iperf3_cpu_host_total 7.5\n",
+        "iperf3_last_run_timestamp_seconds 1700000000\n",
+    ] {
+        assert!(body.contains(line), "missing {:?} in {:?}", line, body);
+    }
+}
+
+/// Test that `/metrics` answers 200 with `iperf3_up 0` and no result gauges before the first
+/// result, and `iperf3_up 1` once one is cached.
+#[actix_web::test]
+#[serial]
+async fn metrics_report_up_without_failing_the_scrape() {
+    clear_last_result_for_test();
+    let app = test::init_service(App::new().service(iperf3_metrics)).await;
+
+    let req = test::TestRequest::get().uri("/metrics").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert!(body.contains("iperf3_up 0\n"));
+    assert!(!body.contains("iperf3_received_bits_per_second"));
+
+    set_last_result_for_test(Iperf3Report::default());
+    let req = test::TestRequest::get().uri("/metrics").to_request();
+    let body = String::from_utf8(test::call_and_read_body(&app, req).await.to_vec()).unwrap();
+    assert!(body.contains("iperf3_up 1\n"));
+    assert!(body.contains("iperf3_received_bits_per_second 0\n"));
+
+    clear_last_result_for_test();
+}
+
+/// Test that the smoothed gauge lags the raw one after a step change and converges to it.
+#[tokio::test]
+#[serial]
+async fn smoothed_gauge_lags_then_converges() {
+    unsafe { std::env::set_var("METRICS_SMOOTHING_ALPHA", "0.5") };
+    clear_smoothed_throughput_for_test();
+    let mut report = Iperf3Report::default();
+    let gauge = |body: &str, name: &str| -> f64 {
+        let line = body.lines().find(|l| l.starts_with(&format!("{} ", name))).unwrap();
+        line.split(' ').nth(1).unwrap().parse().unwrap()
+    };
+
+    report.end.sum_received.bits_per_second = 100_000_000.0;
+    for _ in 0..3 {
+        record_smoothed_throughput(&report);
+    }
+    report.end.sum_received.bits_per_second = 500_000_000.0;
+    record_smoothed_throughput(&report);
+    let body = render_prometheus(&report);
+    assert_eq!(gauge(&body, "iperf3_received_bits_per_second"), 500_000_000.0);
+    assert_eq!(gauge(&body, "iperf3_received_bits_per_second_smoothed"), 300_000_000.0);
+
+    for _ in 0..30 {
+        record_smoothed_throughput(&report);
+    }
+    let smoothed = gauge(&render_prometheus(&report), "iperf3_received_bits_per_second_smoothed");
+    assert!((smoothed - 500_000_000.0).abs() < 1.0, "{} did not converge", smoothed);
+
+    unsafe { std::env::remove_var("METRICS_SMOOTHING_ALPHA") };
+    assert!(!render_prometheus(&report).contains("_smoothed"));
+    clear_smoothed_throughput_for_test();
+}
@@ -0,0 +1,240 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for deserializing iperf3 reports across iperf3 versions and merging them.
+
+use serde_json::{json, Value};
+use iperf3_statuspage::*;
+
+/// Returns a minimal report whose integer counters and rates are set to `bytes`,
+/// `bitrate` and `timesecs`, written either as JSON integers or floats.
+fn report_json(bytes: Value, bitrate: Value, timesecs: Value) -> Value {
+    let mut report = serde_json::to_value(Iperf3Report::default()).unwrap();
+    report["start"]["target_bitrate"] = bitrate.clone();
+    report["start"]["test_start"]["target_bitrate"] = bitrate;
+    report["start"]["timestamp"]["timesecs"] = timesecs;
+    report["end"]["sum_sent"]["bytes"] = bytes.clone();
+    report["end"]["sum_received"]["bytes"] = bytes;
+    report["end"]["sum_received"]["bits_per_second"] = json!(941000000);
+    report
+}
+
+/// Test that integer fields accept both integer and float representations.
+#[tokio::test]
+async fn integer_fields_accept_integers_and_floats() {
+    for (bytes, bitrate, timesecs) in [
+        (json!(1234567890u64), json!(1000000000u64), json!(1754995182u64)),
+        (json!(1234567890.0), json!(1e9), json!(1754995182.0)),
+    ] {
+        let report: Iperf3Report = serde_json::from_value(report_json(bytes, bitrate, timesecs)).unwrap();
+        assert_eq!(report.end.sum_sent.bytes, 1234567890);
+        assert_eq!(report.end.sum_received.bytes, 1234567890);
+        assert_eq!(report.start.target_bitrate, 1_000_000_000);
+        assert_eq!(report.start.test_start.target_bitrate, 1_000_000_000);
+        assert_eq!(report.start.timestamp.timesecs, 1754995182);
+    }
+}
+
+/// Test that float fields accept both integer and float representations.
+#[tokio::test]
+async fn float_fields_accept_integers_and_floats() {
+    let mut value = report_json(json!(0), json!(0), json!(0));
+    value["end"]["sum_sent"]["bits_per_second"] = json!(941000000);
+    value["end"]["sum_received"]["bits_per_second"] = json!(940123456.5);
+    let report: Iperf3Report = serde_json::from_value(value).unwrap();
+    assert_eq!(report.end.sum_sent.bits_per_second, 941_000_000.0);
+    assert_eq!(report.end.sum_received.bits_per_second, 940_123_456.5);
+}
+
+/// Test that floats are rounded and out-of-range values are rejected.
+#[tokio::test]
+async fn integer_fields_round_floats_and_reject_out_of_range() {
+    let report: Iperf3Report = serde_json::from_value(report_json(json!(99.6), json!(0), json!(0))).unwrap();
+    assert_eq!(report.end.sum_sent.bytes, 100);
+
+    assert!(serde_json::from_value::<Iperf3Report>(report_json(json!(-1), json!(0), json!(0))).is_err());
+    assert!(serde_json::from_value::<Iperf3Report>(report_json(json!(1e30), json!(0), json!(0))).is_err());
+}
+
+/// Test that merging takes the sent sums from the upload run, the received sums from the
+/// download run and `start` from the more recent run.
+#[tokio::test]
+async fn merge_directions_combines_sums() {
+    let mut up = Iperf3Report::default();
+    up.start.timestamp.timesecs = 1754995182;
+    up.start.test_start.reverse = 0;
+    up.end.sum_sent.bytes = 50_000_000;
+    up.end.sum_sent.bits_per_second = 40_000_000.0;
+    up.end.sum_sent.retransmits = 7;
+    up.end.sum_received.bits_per_second = 39_500_000.0;
+    up.end.sender_tcp_congestion = "cubic".to_string();
+    up.intervals.push(Interval::default());
+
+    let mut down = Iperf3Report::default();
+    down.start.timestamp.timesecs = 1754995200;
+    down.start.test_start.reverse = 1;
+    down.end.sum_sent.bits_per_second = 945_000_000.0;
+    down.end.sum_received.bytes = 1_175_000_000;
+    down.end.sum_received.bits_per_second = 940_000_000.0;
+    down.end.receiver_tcp_congestion = "bbr".to_string();
+    down.end.cpu_utilization_percent.host_total = 12.5;
+
+    let merged = Iperf3Report::merge_directions(&up, &down);
+    assert_eq!(merged.end.sum_sent.bytes, 50_000_000);
+    assert_eq!(merged.end.sum_sent.bits_per_second, 40_000_000.0);
+    assert_eq!(merged.end.sum_sent.retransmits, 7);
+    assert_eq!(merged.end.sum_received.bytes, 1_175_000_000);
+    assert_eq!(merged.end.sum_received.bits_per_second, 940_000_000.0);
+    assert_eq!(merged.end.sender_tcp_congestion, "cubic");
+    assert_eq!(merged.end.receiver_tcp_congestion, "bbr");
+
+    assert_eq!(merged.start.timestamp.timesecs, 1754995200);
+    assert_eq!(merged.end.cpu_utilization_percent.host_total, 12.5);
+    assert!(merged.intervals.is_empty());
+    assert!(merged.end.streams.is_empty());
+}
+
+/// Abridged `iperf3 -u -b 100M --json` output: no TCP-only fields, per-stream results
+/// under `udp` and jitter and loss in the sums.
+const UDP_REPORT: &str = r#"{
+    "start": {
+        "connected": [{"socket": 5, "local_host": "10.0.0.2", "local_port": 51234, "remote_host": "10.0.0.1", "remote_port": 5201}],
+        "version": "iperf 3.16",
+        "system_info": "Linux client 6.8.0 x86_64",
+        "timestamp": {"time": "Tue, 12 Aug 2025 10:39:42 GMT", "timesecs": 1754995182},
+        "connecting_to": {"host": "10.0.0.1", "port": 5201},
+        "cookie": "pv2zq6n2fvbdm4ptgo5tx7lpnchgyxbbnd2x",
+        "target_bitrate": 100000000,
+        "fq_rate": 0,
+        "sock_bufsize": 0,
+        "sndbuf_actual": 212992,
+        "rcvbuf_actual": 212992,
+        "test_start": {"protocol": "UDP", "num_streams": 1, "blksize": 1448, "omit": 0, "duration": 10, "bytes": 0, "blocks": 0, "reverse": 0, "tos": 0, "target_bitrate": 100000000, "bidir": 0, "fqrate": 0}
+    },
+    "intervals": [{
+        "streams": [{"socket": 5, "start": 0, "end": 1.000058, "seconds": 1.000058, "bytes": 12500032, "bits_per_second": 99994456.1, "packets": 8633, "omitted": false, "sender": true}],
+        "sum": {"start": 0, "end": 1.000058, "seconds": 1.000058, "bytes": 12500032, "bits_per_second": 99994456.1, "packets": 8633, "omitted": false, "sender": true}
+    }],
+    "end": {
+        "streams": [{"udp": {"socket": 5, "start": 0, "end": 10.000162, "seconds": 10.000162, "bytes": 125000544, "bits_per_second": 99999833.4, "jitter_ms": 0.021, "lost_packets": 12, "packets": 86326, "lost_percent": 0.0139, "out_of_order": 0, "sender": true}}],
+        "sum": {"start": 0, "end": 10.000162, "seconds": 10.000162, "bytes": 125000544, "bits_per_second": 99999833.4, "jitter_ms": 0.021, "lost_packets": 12, "packets": 86326, "lost_percent": 0.0139, "sender": true},
+        "sum_sent": {"start": 0, "end": 10.000162, "seconds": 10.000162, "bytes": 125000544, "bits_per_second": 99999833.4, "jitter_ms": 0, "lost_packets": 0, "packets": 86326, "lost_percent": 0, "sender": true},
+        "sum_received": {"start": 0, "end": 10.000204, "seconds": 10.000204, "bytes": 124983168, "bits_per_second": 99985936.2, "jitter_ms": 0.021, "lost_packets": 12, "packets": 86314, "lost_percent": 0.0139, "sender": false},
+        "cpu_utilization_percent": {"host_total": 12.4, "host_user": 2.1, "host_system": 10.3, "remote_total": 3.2, "remote_user": 0.4, "remote_system": 2.8}
+    }
+}"#;
+
+/// Test that UDP reports parse with their jitter and loss figures, which survive a
+/// round trip, while TCP reports keep serializing without them.
+#[tokio::test]
+async fn udp_reports_carry_jitter_and_loss() {
+    let report: Iperf3Report = serde_json::from_str(UDP_REPORT).unwrap();
+    assert_eq!(report.start.test_start.protocol, "UDP");
+    assert_eq!(report.intervals[0].sum.packets, Some(8633));
+    let received = &report.end.sum_received;
+    assert_eq!(received.jitter_ms, Some(0.021));
+    assert_eq!(received.lost_packets, Some(12));
+    assert_eq!(received.packets, Some(86314));
+    assert_eq!(received.lost_percent, Some(0.0139));
+    assert_eq!(report.end.sum_sent.retransmits, 0);
+
+    let round_tripped: Iperf3Report = serde_json::from_value(serde_json::to_value(&report).unwrap()).unwrap();
+    assert_eq!(round_tripped.end.sum_received.lost_packets, Some(12));
+
+    let tcp = serde_json::to_value(Iperf3Report::default()).unwrap();
+    assert!(tcp["end"]["sum_received"].get("jitter_ms").is_none());
+    let tcp: Iperf3Report = serde_json::from_value(tcp).unwrap();
+    assert_eq!(tcp.end.sum_received.jitter_ms, None);
+}
+
+/// Test that fields iperf3 reports beyond the model are kept, and that serializing the same
+/// report twice yields byte-identical output with the extra keys in sorted order.
+#[tokio::test]
+async fn serialization_is_byte_stable() {
+    let mut json = report_json(json!(1000), json!(1000), json!(1_700_000_000));
+    let object = json.as_object_mut().unwrap();
+    object.insert("title".to_string(), json!("nightly"));
+    object.insert("server_output_text".to_string(), json!("Accepted connection"));
+    object.insert("extra_data".to_string(), json!({"zeta": 1, "alpha": 2}));
+    let report: Iperf3Report = serde_json::from_value(json).unwrap();
+
+    let first = serde_json::to_vec(&report).unwrap();
+    let second = serde_json::to_vec(&report.clone()).unwrap();
+    assert_eq!(first, second);
+
+    let text = String::from_utf8(first).unwrap();
+    let keys: Vec<usize> = ["\"extra_data\"", "\"server_output_text\"", "\"title\""]
+        .iter()
+        .map(|key| text.find(key).unwrap())
+        .collect();
+    assert!(keys.windows(2).all(|w| w[0] < w[1]), "extra keys out of order in {}", text);
+    assert!(text.contains(r#"{"alpha":2,"zeta":1}"#));
+}
+
+/// Test that a trimmed-down report of an older iperf3, lacking congestion control, socket
+/// buffer, `sender` flags and some TCP_INFO fields and carrying fields the model does not
+/// know, parses with defaults for what is missing.
+#[tokio::test]
+async fn older_report_with_missing_and_unknown_fields_parses() {
+    let output = r#"{
+        "start": {
+            "connected": [{"socket": 5, "local_host": "10.0.0.2", "local_port": 50112,
+                           "remote_host": "10.0.0.1", "remote_port": 5201}],
+            "version": "iperf 3.1.3",
+            "system_info": "Linux host 4.4.0 x86_64",
+            "timestamp": {"time": "Mon, 13 Oct 2025 12:00:00 GMT", "timesecs": 1760356800},
+            "connecting_to": {"host": "10.0.0.1", "port": 5201},
+            "cookie": "host.1760356800.123456.0123456789abcd",
+            "tcp_mss_default": 1448,
+            "test_start": {"protocol": "TCP", "num_streams": 1, "blksize": 131072, "omit": 0,
+                           "duration": 10, "bytes": 0, "blocks": 0, "reverse": 0},
+            "unknown_start_field": true
+        },
+        "intervals": [{
+            "streams": [{"socket": 5, "start": 0, "end": 1.0, "seconds": 1.0, "bytes": 117440512,
+                         "bits_per_second": 939524096, "retransmits": 0, "snd_cwnd": 3150000,
+                         "omitted": false}],
+            "sum": {"start": 0, "end": 1.0, "seconds": 1.0, "bytes": 117440512,
+                    "bits_per_second": 939524096, "retransmits": 0, "omitted": false}
+        }],
+        "end": {
+            "streams": [{
+                "sender": {"socket": 5, "start": 0, "end": 10.0, "seconds": 10.0, "bytes": 1174405120,
+                           "bits_per_second": 939524096, "retransmits": 12},
+                "receiver": {"socket": 5, "start": 0, "end": 10.04, "seconds": 10.04, "bytes": 1172307968,
+                             "bits_per_second": 934086554}
+            }],
+            "sum_sent": {"start": 0, "end": 10.0, "seconds": 10.0, "bytes": 1174405120,
+                         "bits_per_second": 939524096, "retransmits": 12},
+            "sum_received": {"start": 0, "end": 10.04, "seconds": 10.04, "bytes": 1172307968,
+                             "bits_per_second": 934086554},
+            "cpu_utilization_percent": {"host_total": 5.1, "host_user": 0.4, "host_system": 4.7,
+                                        "remote_total": 20.3, "remote_user": 1.2, "remote_system": 19.1}
+        }
+    }"#;
+
+    let report: Iperf3Report = serde_json::from_str(output).unwrap();
+    assert_eq!(report.start.timestamp.timesecs, 1_760_356_800);
+    assert_eq!(report.start.fq_rate, 0);
+    assert_eq!(report.start.sock_bufsize, 0);
+    assert_eq!(report.start.test_start.bidir, 0);
+    assert_eq!(report.end.sender_tcp_congestion, "");
+    assert_eq!(report.end.receiver_tcp_congestion, "");
+    assert_eq!(report.end.streams[0].sender.retransmits, 12);
+    assert_eq!(report.end.streams[0].sender.max_snd_cwnd, 0);
+    assert_eq!(report.end.streams[0].sender.mean_rtt, 0);
+    assert_eq!(report.intervals[0].streams[0].rtt, 0);
+    assert_eq!(report.end.sum_sent.retransmits, 12);
+    assert_eq!(report.end.sum_received.bits_per_second, 934_086_554.0);
+    assert!(report.end.sum_sent.jitter_ms.is_none());
+    assert!(!report.end.sum_sent.sender);
+}
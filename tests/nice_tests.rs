@@ -0,0 +1,45 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for `IPERF3_NICE`.
+//!
+//! These tests modify `IPERF3_NICE` and are annotated with `#[serial]`.
+
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+/// Test that the requested niceness is applied to the spawned process.
+#[cfg(unix)]
+#[tokio::test]
+async fn nice_is_applied_to_child() {
+    // SAFETY: getpriority has no preconditions.
+    let current = unsafe { libc::getpriority(libc::PRIO_PROCESS as _, 0) };
+    let requested = (current + 5).min(19);
+
+    let mut command = tokio::process::Command::new("nice");
+    apply_nice(&mut command, requested);
+    let output = command.output().await.unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap().trim(), requested.to_string());
+}
+
+/// Test that `IPERF3_NICE` is read from the environment and out-of-range values ignored.
+#[tokio::test]
+#[serial]
+async fn nice_reads_env() {
+    unsafe { std::env::set_var("IPERF3_NICE", "10") };
+    assert_eq!(iperf3_nice(), Some(10));
+    unsafe { std::env::set_var("IPERF3_NICE", "42") };
+    assert_eq!(iperf3_nice(), None);
+    unsafe { std::env::remove_var("IPERF3_NICE") };
+    assert_eq!(iperf3_nice(), None);
+}
@@ -0,0 +1,101 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for on-demand runs with `POST /iperf3/run`.
+//!
+//! These tests modify `IPERF3_SERVER_IP` and `IPERF3_SERVER_PORT` and are annotated with
+//! `#[serial]`.
+
+use std::sync::Arc;
+use actix_web::{test, http, App};
+use async_trait::async_trait;
+use serial_test::serial;
+use tokio::sync::Notify;
+use iperf3_statuspage::*;
+
+/// Mock runner that waits for `release` before returning a report.
+struct GatedRunner {
+    release: Arc<Notify>,
+}
+
+#[async_trait]
+impl Iperf3Runner for GatedRunner {
+    async fn run_iperf3(&self, _iperf3_ip: String, _iperf3_port: String) -> Result<String, Iperf3Error> {
+        self.release.notified().await;
+        let mut report = Iperf3Report::default();
+        report.end.sum_received.bytes = 42;
+        Ok(serde_json::to_string(&report).unwrap())
+    }
+}
+
+/// Mock runner whose every run fails.
+struct FailingRunner;
+
+#[async_trait]
+impl Iperf3Runner for FailingRunner {
+    async fn run_iperf3(&self, _iperf3_ip: String, _iperf3_port: String) -> Result<String, Iperf3Error> {
+        Err(Iperf3Error::NonZeroExit { code: Some(1), stderr: "unable to connect to server".to_string() })
+    }
+}
+
+/// Test that an on-demand run caches its result, and that a second trigger while it is in
+/// flight is refused with a conflict instead of starting another run.
+#[tokio::test]
+async fn concurrent_on_demand_run_is_refused() {
+    let opts = Iperf3Options::from_env("10.0.0.7", "5201");
+    let release = Arc::new(Notify::new());
+    let runner = GatedRunner { release: release.clone() };
+
+    let first = run_on_demand_with_runner(&runner, &opts);
+    tokio::pin!(first);
+    assert!(futures::poll!(first.as_mut()).is_pending());
+
+    let err = run_on_demand_with_runner(&runner, &opts).await.unwrap_err();
+    assert!(matches!(err, Iperf3Error::Conflict(_)));
+
+    release.notify_one();
+    assert_eq!(first.await.unwrap().end.sum_received.bytes, 42);
+    clear_last_result_for_test();
+}
+
+/// Test that a failed on-demand run surfaces its error message as an internal error.
+#[tokio::test]
+async fn failed_on_demand_run_is_an_internal_error() {
+    let opts = Iperf3Options::from_env("10.0.0.8", "5201");
+    let err = run_on_demand_with_runner(&FailingRunner, &opts).await.unwrap_err();
+    assert!(matches!(err, Iperf3Error::Internal(ref message) if message.contains("unable to connect to server")));
+}
+
+/// Test that `POST /iperf3/run` answers 409 Conflict while a run against the configured
+/// server holds its lock, without starting iperf3.
+#[actix_web::test]
+#[serial]
+async fn run_endpoint_conflicts_with_in_flight_run() {
+    unsafe {
+        std::env::set_var("IPERF3_SERVER_IP", "10.0.0.9");
+        std::env::set_var("IPERF3_SERVER_PORT", "5201");
+    }
+    let lock = target_lock(&Target::with_options(Iperf3Options::from_env("10.0.0.9", "5201")));
+    let _guard = lock.lock().await;
+
+    let app = test::init_service(App::new().service(iperf3_run)).await;
+    let req = test::TestRequest::post().uri("/iperf3/run").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::CONFLICT);
+    let body = test::read_body(resp).await;
+    assert!(String::from_utf8_lossy(&body).contains("already in progress"));
+
+    unsafe {
+        std::env::remove_var("IPERF3_SERVER_IP");
+        std::env::remove_var("IPERF3_SERVER_PORT");
+    }
+}
@@ -0,0 +1,179 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for one-shot mode.
+
+use async_trait::async_trait;
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+/// Mock runner returning a fixed output.
+struct MockRunner {
+    output: Result<String, Iperf3Error>,
+}
+
+#[async_trait]
+impl Iperf3Runner for MockRunner {
+    async fn run_iperf3(&self, _iperf3_ip: String, _iperf3_port: String) -> Result<String, Iperf3Error> {
+        self.output.clone()
+    }
+}
+
+fn sample_report() -> Iperf3Report {
+    let mut report = Iperf3Report::default();
+    report.start.timestamp.timesecs = 1754995182;
+    report.end.sum_received.bits_per_second = 941_000_000.0;
+    report
+}
+
+/// Test that a successful one-shot run exits 0, prints the JSON and writes the state file.
+#[tokio::test]
+#[serial]
+async fn one_shot_success_prints_and_writes_state_file() {
+    let dir = std::env::temp_dir().join(format!("iperf3-oneshot-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let state_file = dir.join("state.json");
+
+    let runner = MockRunner { output: Ok(serde_json::to_string(&sample_report()).unwrap()) };
+    let mut out = Vec::new();
+    let code = run_one_shot_with_runner(&runner, "127.0.0.1".into(), "5201".into(), Some(&state_file), &mut out).await;
+
+    assert_eq!(code, 0);
+    let printed: Iperf3Report = serde_json::from_slice(&out).unwrap();
+    assert_eq!(printed.start.timestamp.timesecs, 1754995182);
+
+    let written: Iperf3Report = serde_json::from_str(&std::fs::read_to_string(&state_file).unwrap()).unwrap();
+    assert_eq!(written.end.sum_received.bits_per_second, 941_000_000.0);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+    clear_last_result_for_test();
+}
+
+/// Test that a failed one-shot run exits 1 and produces no output.
+#[tokio::test]
+#[serial]
+async fn one_shot_failure_exits_non_zero() {
+    let runner = MockRunner { output: Err(Iperf3Error::NonZeroExit { code: Some(1), stderr: "unable to connect".into() }) };
+    let mut out = Vec::new();
+    let code = run_one_shot_with_runner(&runner, "127.0.0.1".into(), "5201".into(), None, &mut out).await;
+
+    assert_eq!(code, 1);
+    assert!(out.is_empty());
+}
+
+/// Test that an unwritable state file makes the run fail.
+#[tokio::test]
+#[serial]
+async fn one_shot_unwritable_state_file_exits_non_zero() {
+    let state_file = std::env::temp_dir().join("iperf3-oneshot-missing-dir").join("state.json");
+    let runner = MockRunner { output: Ok(serde_json::to_string(&sample_report()).unwrap()) };
+    let mut out = Vec::new();
+    let code = run_one_shot_with_runner(&runner, "127.0.0.1".into(), "5201".into(), Some(&state_file), &mut out).await;
+
+    assert_eq!(code, 1);
+    clear_last_result_for_test();
+}
+
+/// Test that `ONE_SHOT` is parsed from the environment.
+#[tokio::test]
+#[serial]
+async fn one_shot_enabled_reads_env() {
+    unsafe { std::env::set_var("ONE_SHOT", "true") };
+    assert!(one_shot_enabled());
+    unsafe { std::env::set_var("ONE_SHOT", "false") };
+    assert!(!one_shot_enabled());
+    unsafe { std::env::remove_var("ONE_SHOT") };
+    assert!(!one_shot_enabled());
+}
+
+/// Mock runner whose probe returns a fixed result and which counts test runs.
+struct ProbeRunner {
+    probe: Result<(), Iperf3Error>,
+    runs: std::sync::atomic::AtomicUsize,
+}
+
+#[async_trait]
+impl Iperf3Runner for ProbeRunner {
+    async fn run_iperf3(&self, _iperf3_ip: String, _iperf3_port: String) -> Result<String, Iperf3Error> {
+        self.runs.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        Ok(serde_json::to_string(&sample_report()).unwrap())
+    }
+
+    async fn probe(&self, _iperf3_ip: String, _iperf3_port: String) -> Result<(), Iperf3Error> {
+        self.probe.clone()
+    }
+}
+
+/// Test that a failing probe aborts the one-shot run before iperf3 is started,
+/// and that a successful probe lets it proceed.
+#[tokio::test]
+#[serial]
+async fn one_shot_propagates_probe_result() {
+    let runner = ProbeRunner {
+        probe: Err(Iperf3Error::Unreachable("127.0.0.1:5201: connection refused".into())),
+        runs: Default::default(),
+    };
+    let mut out = Vec::new();
+    let code = run_one_shot_with_runner(&runner, "127.0.0.1".into(), "5201".into(), None, &mut out).await;
+    assert_eq!(code, 1);
+    assert!(out.is_empty());
+    assert_eq!(runner.runs.load(std::sync::atomic::Ordering::SeqCst), 0);
+
+    let runner = ProbeRunner { probe: Ok(()), runs: Default::default() };
+    let code = run_one_shot_with_runner(&runner, "127.0.0.1".into(), "5201".into(), None, &mut out).await;
+    assert_eq!(code, 0);
+    assert_eq!(runner.runs.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+    clear_last_result_for_test();
+}
+
+/// Test that the real probe reports a closed port as unreachable and succeeds against a listener.
+#[tokio::test]
+async fn real_probe_connects_to_server() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port().to_string();
+    assert!(RealIperf3Runner.probe("127.0.0.1".into(), port.clone()).await.is_ok());
+
+    drop(listener);
+    let err = RealIperf3Runner.probe("127.0.0.1".into(), port).await.unwrap_err();
+    assert_eq!(err.code(), "unreachable");
+}
+
+/// Test that a state file round-trips and that corrupting it is detected on load, leaving
+/// the cache empty.
+#[tokio::test]
+#[serial]
+async fn corrupt_state_file_is_rejected() {
+    let dir = std::env::temp_dir().join(format!("iperf3-state-checksum-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let state_file = dir.join("state.json");
+    write_state_file(&state_file, &sample_report()).unwrap();
+    assert!(checksum_path(&state_file).exists());
+    assert_eq!(read_state_file(&state_file).unwrap().start.timestamp.timesecs, sample_report().start.timestamp.timesecs);
+
+    unsafe { std::env::set_var("STATE_FILE", &state_file) };
+    clear_last_result_for_test();
+    load_state_from_env();
+    assert!(get_last_result().is_some());
+
+    // Flip a digit inside the JSON: still valid, but no longer what was written
+    let contents = std::fs::read_to_string(&state_file).unwrap();
+    std::fs::write(&state_file, contents.replacen("941", "142", 1)).unwrap();
+    assert!(read_state_file(&state_file).unwrap_err().contains("Checksum mismatch"));
+
+    clear_last_result_for_test();
+    load_state_from_env();
+    assert!(get_last_result().is_none());
+
+    unsafe { std::env::remove_var("STATE_FILE") };
+    std::fs::remove_dir_all(&dir).unwrap();
+}
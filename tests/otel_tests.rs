@@ -0,0 +1,135 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for the OpenTelemetry span export (`otel` feature).
+//!
+//! Run with `cargo test --features otel`.
+
+#![cfg(feature = "otel")]
+
+use std::sync::Arc;
+use async_trait::async_trait;
+use serde_json::json;
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+/// Mock runner returning a fixed output after "starting" its child.
+struct MockRunner {
+    output: Result<String, Iperf3Error>,
+}
+
+#[async_trait]
+impl Iperf3Runner for MockRunner {
+    async fn run_iperf3(&self, _iperf3_ip: String, _iperf3_port: String) -> Result<String, Iperf3Error> {
+        mark_child_spawned();
+        self.output.clone()
+    }
+}
+
+/// Test that a successful cycle produces a root span with child phase spans.
+#[tokio::test]
+#[serial]
+async fn successful_cycle_produces_spans() {
+    let exporter = Arc::new(InMemoryExporter::default());
+    set_span_exporter(Some(exporter.clone()));
+
+    let mut report = Iperf3Report::default();
+    report.end.sum_received.bits_per_second = 941_000_000.0;
+    let runner = MockRunner { output: Ok(serde_json::to_string(&report).unwrap()) };
+    run_iperf3_and_cache_with_runner(&runner, "192.0.2.10".into(), "5201".into()).await.unwrap();
+
+    let spans = exporter.finished_spans();
+    let root = spans.iter().find(|s| s.name == "iperf3_cycle").unwrap();
+    assert_eq!(root.parent_span_id, None);
+    assert_eq!(root.trace_id.len(), 32);
+    assert_eq!(root.attribute("iperf3.target.host"), Some(&json!("192.0.2.10")));
+    assert_eq!(root.attribute("iperf3.received_bps"), Some(&json!(941_000_000.0)));
+    assert_eq!(root.attribute("iperf3.outcome"), Some(&json!("success")));
+    assert_eq!(root.error, None);
+
+    let phases: Vec<&str> = spans
+        .iter()
+        .filter(|s| s.parent_span_id.as_deref() == Some(root.span_id.as_str()))
+        .map(|s| s.name.as_str())
+        .collect();
+    assert_eq!(phases, vec!["spawn", "test_run", "parse", "cache"]);
+    assert!(spans.iter().all(|s| s.trace_id == root.trace_id));
+
+    set_span_exporter(None);
+    clear_last_result_for_test();
+}
+
+/// Test that a failed cycle is exported with an error outcome.
+#[tokio::test]
+#[serial]
+async fn failed_cycle_records_error_outcome() {
+    let exporter = Arc::new(InMemoryExporter::default());
+    set_span_exporter(Some(exporter.clone()));
+
+    let runner = MockRunner {
+        output: Err(Iperf3Error::NonZeroExit { code: Some(1), stderr: "unable to connect".into() }),
+    };
+    run_iperf3_and_cache_with_runner(&runner, "192.0.2.10".into(), "5201".into()).await.unwrap_err();
+
+    let spans = exporter.finished_spans();
+    let root = spans.iter().find(|s| s.name == "iperf3_cycle").unwrap();
+    assert_eq!(root.attribute("iperf3.outcome"), Some(&json!("error")));
+    assert!(root.error.as_deref().unwrap().contains("unable to connect"));
+    assert!(root.attribute("iperf3.received_bps").is_none());
+
+    set_span_exporter(None);
+}
+
+/// Test that the OTLP exporter posts JSON-encoded spans to `/v1/traces`.
+#[tokio::test]
+async fn otlp_exporter_posts_json() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    let server = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            let n = stream.read(&mut buf).await.unwrap();
+            request.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&request);
+            if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                let len: usize = head
+                    .lines()
+                    .find_map(|l| l.strip_prefix("Content-Length: "))
+                    .unwrap()
+                    .parse()
+                    .unwrap();
+                if body.len() >= len {
+                    break;
+                }
+            }
+        }
+        stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n").await.unwrap();
+        String::from_utf8(request).unwrap()
+    });
+
+    let timing = CycleTiming { phases: vec![], total_millis: 5.0 };
+    let result: Result<Iperf3Report, Iperf3Error> = Ok(Iperf3Report::default());
+    let spans = cycle_spans(&Iperf3Options::new("192.0.2.10", "5201"), std::time::SystemTime::now(), &timing, &result);
+    OtlpHttpExporter::new(&endpoint).unwrap().export(&spans).await.unwrap();
+
+    let request = server.await.unwrap();
+    assert!(request.starts_with("POST /v1/traces HTTP/1.1\r\n"));
+    let body: serde_json::Value = serde_json::from_str(request.split_once("\r\n\r\n").unwrap().1).unwrap();
+    let span = &body["resourceSpans"][0]["scopeSpans"][0]["spans"][0];
+    assert_eq!(span["name"], "iperf3_cycle");
+    assert_eq!(span["status"]["code"], 1);
+}
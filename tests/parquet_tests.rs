@@ -0,0 +1,210 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for the Parquet history export (`parquet` feature).
+//!
+//! Run with `cargo test --features parquet`. The file is read back with a minimal
+//! Thrift compact protocol decoder.
+
+#![cfg(feature = "parquet")]
+
+use std::collections::BTreeMap;
+use actix_web::{test, http, App};
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+/// Decoded Thrift value.
+#[derive(Debug, Clone)]
+enum Thrift {
+    Int(i64),
+    Bytes(Vec<u8>),
+    List(Vec<Thrift>),
+    Struct(BTreeMap<i16, Thrift>),
+}
+
+impl Thrift {
+    fn field(&self, id: i16) -> &Thrift {
+        match self {
+            Thrift::Struct(fields) => &fields[&id],
+            other => panic!("not a struct: {:?}", other),
+        }
+    }
+
+    fn int(&self) -> i64 {
+        match self {
+            Thrift::Int(v) => *v,
+            other => panic!("not an int: {:?}", other),
+        }
+    }
+
+    fn list(&self) -> &[Thrift] {
+        match self {
+            Thrift::List(items) => items,
+            other => panic!("not a list: {:?}", other),
+        }
+    }
+
+    fn string(&self) -> String {
+        match self {
+            Thrift::Bytes(b) => String::from_utf8(b.clone()).unwrap(),
+            other => panic!("not bytes: {:?}", other),
+        }
+    }
+}
+
+/// Minimal Thrift compact protocol decoder over a byte slice.
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn byte(&mut self) -> u8 {
+        self.pos += 1;
+        self.buf[self.pos - 1]
+    }
+
+    fn varint(&mut self) -> u64 {
+        let (mut value, mut shift) = (0u64, 0);
+        loop {
+            let b = self.byte();
+            value |= ((b & 0x7f) as u64) << shift;
+            if b & 0x80 == 0 {
+                return value;
+            }
+            shift += 7;
+        }
+    }
+
+    fn zigzag(&mut self) -> i64 {
+        let v = self.varint();
+        ((v >> 1) as i64) ^ -((v & 1) as i64)
+    }
+
+    fn value(&mut self, kind: u8) -> Thrift {
+        match kind {
+            1 => Thrift::Int(1),
+            2 => Thrift::Int(0),
+            3 => Thrift::Int(self.byte() as i8 as i64),
+            4..=6 => Thrift::Int(self.zigzag()),
+            7 => {
+                self.pos += 8;
+                Thrift::Int(0)
+            }
+            8 => {
+                let len = self.varint() as usize;
+                self.pos += len;
+                Thrift::Bytes(self.buf[self.pos - len..self.pos].to_vec())
+            }
+            9 => {
+                let header = self.byte();
+                let len = if header >> 4 == 15 { self.varint() as usize } else { (header >> 4) as usize };
+                Thrift::List((0..len).map(|_| self.value(header & 0x0f)).collect())
+            }
+            12 => self.structure(),
+            other => panic!("unsupported thrift type {}", other),
+        }
+    }
+
+    fn structure(&mut self) -> Thrift {
+        let mut fields = BTreeMap::new();
+        let mut last = 0i16;
+        loop {
+            let header = self.byte();
+            if header == 0 {
+                return Thrift::Struct(fields);
+            }
+            let id = if header >> 4 == 0 { self.zigzag() as i16 } else { last + (header >> 4) as i16 };
+            fields.insert(id, self.value(header & 0x0f));
+            last = id;
+        }
+    }
+}
+
+/// Decodes the footer `FileMetaData` of a Parquet file.
+fn file_metadata(bytes: &[u8]) -> Thrift {
+    assert_eq!(&bytes[..4], b"PAR1");
+    assert_eq!(&bytes[bytes.len() - 4..], b"PAR1");
+    let len = u32::from_le_bytes(bytes[bytes.len() - 8..bytes.len() - 4].try_into().unwrap()) as usize;
+    let start = bytes.len() - 8 - len;
+    Reader { buf: &bytes[start..bytes.len() - 8], pos: 0 }.structure()
+}
+
+/// Reads the PLAIN-encoded DOUBLE values of the named column.
+fn double_column(bytes: &[u8], name: &str) -> Vec<f64> {
+    let meta = file_metadata(bytes);
+    let row_group = &meta.field(4).list()[0];
+    let chunk = row_group
+        .field(1)
+        .list()
+        .iter()
+        .map(|c| c.field(3))
+        .find(|m| m.field(3).list()[0].string() == name)
+        .unwrap();
+    let offset = chunk.field(9).int() as usize;
+
+    let mut reader = Reader { buf: bytes, pos: offset };
+    let header = reader.structure();
+    let size = header.field(3).int() as usize;
+    bytes[reader.pos..reader.pos + size]
+        .chunks(8)
+        .map(|c| f64::from_le_bytes(c.try_into().unwrap()))
+        .collect()
+}
+
+fn report(timesecs: u64, received_bps: f64) -> Iperf3Report {
+    let mut report = Iperf3Report::default();
+    report.start.timestamp.timesecs = timesecs;
+    report.end.sum_received.bits_per_second = received_bps;
+    report
+}
+
+/// Test that the Parquet bytes parse back into one row per history entry.
+#[tokio::test]
+async fn parquet_parses_back_into_rows() {
+    let reports = vec![report(1, 900e6), report(2, 940e6), report(3, 120e6)];
+    let bytes = history_to_parquet(&reports);
+
+    let meta = file_metadata(&bytes);
+    assert_eq!(meta.field(3).int(), 3);
+    let schema: Vec<String> = meta.field(2).list()[1..].iter().map(|e| e.field(4).string()).collect();
+    assert_eq!(schema, vec!["timestamp", "sent_bps", "received_bps", "retransmits", "host_cpu_percent"]);
+    assert_eq!(meta.field(4).list()[0].field(3).int(), 3);
+    assert_eq!(double_column(&bytes, "received_bps"), vec![900e6, 940e6, 120e6]);
+}
+
+/// Test that an empty history yields a valid file without rows.
+#[tokio::test]
+async fn empty_history_has_no_rows() {
+    let meta = file_metadata(&history_to_parquet(&[]));
+    assert_eq!(meta.field(3).int(), 0);
+    assert!(meta.field(4).list().is_empty());
+}
+
+/// Test that `/history.parquet` serves the history with the Parquet content type.
+#[actix_web::test]
+#[serial]
+async fn history_parquet_endpoint() {
+    clear_history_for_test();
+    push_history_for_test(report(1, 900e6));
+    push_history_for_test(report(2, 940e6));
+
+    let app = test::init_service(App::new().service(history_parquet)).await;
+    let req = test::TestRequest::get().uri("/history.parquet").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+    assert_eq!(resp.headers().get(http::header::CONTENT_TYPE).unwrap(), PARQUET_CONTENT_TYPE);
+
+    let bytes = test::read_body(resp).await;
+    assert_eq!(file_metadata(&bytes).field(3).int(), 2);
+    clear_history_for_test();
+}
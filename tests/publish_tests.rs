@@ -0,0 +1,134 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for publishing successful results.
+//!
+//! The publisher and cache are process-global, so tests are annotated with `#[serial]`.
+
+use std::sync::{Arc, Mutex};
+use async_trait::async_trait;
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+/// Mock runner returning a fixed output.
+struct MockRunner {
+    output: Result<String, Iperf3Error>,
+}
+
+#[async_trait]
+impl Iperf3Runner for MockRunner {
+    async fn run_iperf3(&self, _iperf3_ip: String, _iperf3_port: String) -> Result<String, Iperf3Error> {
+        self.output.clone()
+    }
+}
+
+/// Publisher recording every payload, optionally failing each publish.
+#[derive(Default)]
+struct RecordingPublisher {
+    published: Mutex<Vec<String>>,
+    fail: bool,
+}
+
+#[async_trait]
+impl ResultPublisher for RecordingPublisher {
+    async fn publish(&self, report_json: &str) -> Result<(), String> {
+        self.published.lock().unwrap().push(report_json.to_string());
+        if self.fail { Err("broker unavailable".to_string()) } else { Ok(()) }
+    }
+}
+
+fn report_json(bytes: u64) -> String {
+    let mut report = Iperf3Report::default();
+    report.end.sum_received.bytes = bytes;
+    serde_json::to_string(&report).unwrap()
+}
+
+/// Test that the report is published after a successful run only.
+#[tokio::test]
+#[serial]
+async fn publishes_on_success_only() {
+    let publisher = Arc::new(RecordingPublisher::default());
+    set_publisher(Some(publisher.clone()));
+
+    let failing = MockRunner {
+        output: Err(Iperf3Error::NonZeroExit { code: Some(1), stderr: "unable to connect".into() }),
+    };
+    run_iperf3_and_cache_with_runner(&failing, "127.0.0.1".into(), "5201".into()).await.unwrap_err();
+    assert!(publisher.published.lock().unwrap().is_empty());
+
+    let ok = MockRunner { output: Ok(report_json(42)) };
+    run_iperf3_and_cache_with_runner(&ok, "127.0.0.1".into(), "5201".into()).await.unwrap();
+    let published = publisher.published.lock().unwrap().clone();
+    assert_eq!(published.len(), 1);
+    let report: Iperf3Report = serde_json::from_str(&published[0]).unwrap();
+    assert_eq!(report.end.sum_received.bytes, 42);
+
+    set_publisher(None);
+    clear_last_result_for_test();
+}
+
+/// Test that a failing publisher does not affect caching.
+#[tokio::test]
+#[serial]
+async fn publish_failure_does_not_affect_caching() {
+    let publisher = Arc::new(RecordingPublisher { fail: true, ..Default::default() });
+    set_publisher(Some(publisher.clone()));
+
+    let ok = MockRunner { output: Ok(report_json(7)) };
+    let report = run_iperf3_and_cache_with_runner(&ok, "127.0.0.1".into(), "5201".into()).await.unwrap();
+    assert_eq!(report.end.sum_received.bytes, 7);
+    assert_eq!(get_last_result().unwrap().end.sum_received.bytes, 7);
+    assert_eq!(publisher.published.lock().unwrap().len(), 1);
+
+    set_publisher(None);
+    clear_last_result_for_test();
+}
+
+/// Test that the NATS publisher speaks the text protocol to a fake server.
+#[cfg(feature = "nats")]
+#[tokio::test]
+async fn nats_publisher_sends_pub_frame() {
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("nats://{}", listener.local_addr().unwrap());
+
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (read, mut write) = stream.into_split();
+        write.write_all(b"INFO {\"server_id\":\"test\"}\r\n").await.unwrap();
+
+        let mut reader = BufReader::new(read);
+        let mut connect = String::new();
+        reader.read_line(&mut connect).await.unwrap();
+        let mut header = String::new();
+        reader.read_line(&mut header).await.unwrap();
+        let len: usize = header.trim_end().rsplit(' ').next().unwrap().parse().unwrap();
+        let mut payload = vec![0; len + 2];
+        reader.read_exact(&mut payload).await.unwrap();
+        let mut ping = String::new();
+        reader.read_line(&mut ping).await.unwrap();
+        write.write_all(b"PONG\r\n").await.unwrap();
+
+        (connect, header, String::from_utf8(payload[..len].to_vec()).unwrap(), ping)
+    });
+
+    let publisher = NatsPublisher::new(&url, "iperf3.results");
+    publisher.publish("{\"ok\":true}").await.unwrap();
+
+    let (connect, header, payload, ping) = server.await.unwrap();
+    assert!(connect.starts_with("CONNECT {"));
+    assert_eq!(header, "PUB iperf3.results 11\r\n");
+    assert_eq!(payload, "{\"ok\":true}");
+    assert_eq!(ping, "PING\r\n");
+}
@@ -0,0 +1,203 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for `REDACT_TARGET`.
+//!
+//! These tests modify `REDACT_TARGET`, `API_TOKEN` and the cache, and are annotated with `#[serial]`.
+
+use std::time::Duration;
+use actix_web::body::MessageBody;
+use actix_web::{test, http, App};
+use async_trait::async_trait;
+use serde_json::Value;
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+fn sample_report() -> Iperf3Report {
+    let mut report = Iperf3Report::default();
+    report.start.connecting_to.host = "10.20.30.40".to_string();
+    report.start.connected.push(Connected {
+        socket: 5,
+        local_host: "10.20.30.1".to_string(),
+        local_port: 53412,
+        remote_host: "10.20.30.40".to_string(),
+        remote_port: 5201,
+    });
+    report.start.system_info = "Linux iperf-internal-01 6.1.0 x86_64".to_string();
+    report.end.sum_received.bits_per_second = 941_000_000.0;
+    report
+}
+
+/// Fetches `uri` as JSON, with the bearer token if given.
+async fn get_json(uri: &str, token: Option<&str>) -> Value {
+    let app = test::init_service(App::new().service(iperf3).service(iperf3_summary)).await;
+    let mut req = test::TestRequest::get().uri(uri);
+    if let Some(token) = token {
+        req = req.insert_header((http::header::AUTHORIZATION, format!("Bearer {}", token)));
+    }
+    test::call_and_read_body_json(&app, req.to_request()).await
+}
+
+/// Test that anonymous requests see redacted hosts while authenticated ones see the real values.
+#[actix_web::test]
+#[serial]
+async fn target_is_redacted_for_anonymous_requests() {
+    unsafe {
+        std::env::set_var("REDACT_TARGET", "true");
+        std::env::set_var("API_TOKEN", "s3cret");
+    }
+    set_last_result_for_test(sample_report());
+
+    let body = get_json("/iperf3", None).await;
+    assert_eq!(body["start"]["connecting_to"]["host"], REDACTED);
+    assert_eq!(body["start"]["connected"][0]["remote_host"], REDACTED);
+    assert_eq!(body["start"]["system_info"], REDACTED);
+    assert_eq!(body["end"]["sum_received"]["bits_per_second"], 941_000_000.0);
+    let summary = get_json("/summary", None).await;
+    assert_eq!(summary["remote_host"], REDACTED);
+    assert_eq!(summary["received_mbps"], 941.0);
+
+    let body = get_json("/iperf3", Some("s3cret")).await;
+    assert_eq!(body["start"]["connecting_to"]["host"], "10.20.30.40");
+    assert_eq!(body["start"]["connected"][0]["remote_host"], "10.20.30.40");
+    assert_eq!(body["start"]["system_info"], "Linux iperf-internal-01 6.1.0 x86_64");
+    let summary = get_json("/summary", Some("s3cret")).await;
+    assert_eq!(summary["remote_host"], "10.20.30.40");
+
+    unsafe {
+        std::env::remove_var("REDACT_TARGET");
+        std::env::remove_var("API_TOKEN");
+    }
+    let body = get_json("/iperf3", None).await;
+    assert_eq!(body["start"]["connecting_to"]["host"], "10.20.30.40");
+    clear_last_result_for_test();
+}
+
+/// Address of the server in [`every_endpoint_hides_the_configured_server`].
+const SERVER_IP: &str = "10.9.8.7";
+
+/// Runner returning a report naming [`SERVER_IP`] everywhere, passthrough fields included.
+struct LeakyRunner;
+
+#[async_trait]
+impl Iperf3Runner for LeakyRunner {
+    async fn run_iperf3(&self, _iperf3_ip: String, _iperf3_port: String) -> Result<String, Iperf3Error> {
+        let mut report = sample_report();
+        report.start.connecting_to.host = SERVER_IP.to_string();
+        report.start.connected[0].remote_host = SERVER_IP.to_string();
+        report.extra.insert("title".to_string(), format!("probe to {}", SERVER_IP).into());
+        report.extra.insert("server_output_text".to_string(), format!("Accepted connection from {}", SERVER_IP).into());
+        report.extra.insert(
+            "server_output_json".to_string(),
+            serde_json::json!({"start": {"connected": [{"local_host": SERVER_IP}]}}),
+        );
+        Ok(serde_json::to_string(&report).unwrap())
+    }
+}
+
+/// Diagnostics runner echoing the host it was asked about.
+struct EchoDiagnostics;
+
+#[async_trait]
+impl DiagnosticsRunner for EchoDiagnostics {
+    async fn ping(&self, host: &str) -> Result<String, String> {
+        Ok(format!("PING {} 56 data bytes", host))
+    }
+
+    async fn traceroute(&self, host: &str) -> Result<String, String> {
+        Ok(format!("traceroute to {}", host))
+    }
+}
+
+/// Returns the body of `resp`, or just its first chunk for endless event streams.
+async fn read_body_start<B: MessageBody>(resp: actix_web::dev::ServiceResponse<B>) -> Vec<u8> {
+    let body = resp.into_body();
+    let mut body = std::pin::pin!(body);
+    let mut out = Vec::new();
+    while let Ok(Some(Ok(chunk))) =
+        tokio::time::timeout(Duration::from_millis(200), std::future::poll_fn(|cx| body.as_mut().poll_next(cx))).await
+    {
+        out.extend_from_slice(&chunk);
+    }
+    out
+}
+
+/// Test that with `REDACT_TARGET` enabled no endpoint reveals the server's address to an
+/// anonymous client, while an authenticated one still sees it.
+#[actix_web::test]
+#[serial]
+async fn every_endpoint_hides_the_configured_server() {
+    unsafe {
+        std::env::set_var("REDACT_TARGET", "true");
+        std::env::set_var("API_TOKEN", "s3cret");
+        std::env::set_var("IPERF3_SERVER_IP", SERVER_IP);
+        std::env::set_var("IPERF3_SERVER_PORT", "5201");
+    }
+    clear_last_result_for_test();
+    clear_history_for_test();
+    let opts = Iperf3Options::from_env(SERVER_IP, "5201");
+    run_iperf3_and_cache_with_options(&LeakyRunner, &opts).await.unwrap();
+    run_deep_with_runner(&LeakyRunner, &opts).await.unwrap();
+    set_primary_target(Some(format!("{}:5201", SERVER_IP)));
+    set_baseline(get_last_result());
+    capture_bad_output(&format!("{{\"host\": \"{}\"", SERVER_IP));
+    capture_diagnostics(&EchoDiagnostics, SERVER_IP, &Iperf3Error::Unreachable(format!("no route to {}", SERVER_IP))).await;
+    set_last_error_for_test(format!("unable to connect to server {}", SERVER_IP));
+
+    let app = test::init_service(App::new().configure(configure_services)).await;
+    let paths = [
+        "/", "/iperf3", "/iperf3?fields=start&units=mbps", "/iperf3/download", "/intervals", "/intervals/tcp",
+        "/status", "/iperf3/status", "/sparkline", "/summary", "/summary/interfaces", "/metrics", "/debug/timing",
+        "/debug/config", "/debug/last-bad-output", "/debug/last-diagnostics", "/baseline", "/admin/maintenance",
+        "/badge", "/iperf3/deep", "/version", "/history", "/history/0", "/iperf3/history", "/iperf3/stats",
+        "/dashboard", "/iperf3/live", "/events", "/iperf3/events", "/whoami", "/iperf3/10.9.8.7/5201", "/healthz",
+    ];
+    let accepts = ["application/json", "text/csv", "application/x-ndjson", "application/msgpack"];
+    let requests = paths
+        .iter()
+        .map(|path| (*path, "application/json"))
+        .chain(accepts.iter().map(|accept| ("/history", *accept)));
+    for (path, accept) in requests {
+        let req = test::TestRequest::get().uri(path).insert_header((http::header::ACCEPT, accept)).to_request();
+        let resp = test::call_service(&app, req).await;
+        let status = resp.status();
+        let body = read_body_start(resp).await;
+        assert!(
+            !String::from_utf8_lossy(&body).contains(SERVER_IP),
+            "{} ({}) leaked the server with {}: {}",
+            path,
+            accept,
+            status,
+            String::from_utf8_lossy(&body)
+        );
+    }
+
+    let req = test::TestRequest::get()
+        .uri("/iperf3/download")
+        .insert_header((http::header::AUTHORIZATION, "Bearer s3cret"))
+        .to_request();
+    let body = test::call_and_read_body(&app, req).await;
+    assert!(String::from_utf8_lossy(&body).contains(SERVER_IP));
+
+    unsafe {
+        for name in ["REDACT_TARGET", "API_TOKEN", "IPERF3_SERVER_IP", "IPERF3_SERVER_PORT"] {
+            std::env::remove_var(name);
+        }
+    }
+    clear_last_result_for_test();
+    clear_history_for_test();
+    clear_deep_result_for_test();
+    clear_last_bad_output_for_test();
+    clear_last_diagnostics_for_test();
+    clear_last_error_for_test();
+    set_baseline(None);
+}
@@ -0,0 +1,78 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for the response serialization timeout.
+//!
+//! These tests modify `RESPONSE_TIMEOUT_MS` and the cache, and are annotated with `#[serial]`.
+
+use std::time::Duration;
+use actix_web::{test, http, App, ResponseError};
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+/// Test that a serializer overrunning the limit yields a 503 timeout error.
+#[tokio::test]
+async fn slow_serialization_times_out() {
+    let result = serialize_with_timeout(Duration::from_millis(20), || {
+        std::thread::sleep(Duration::from_millis(300));
+        Ok(b"{}".to_vec())
+    })
+    .await;
+
+    let err = result.unwrap_err();
+    assert_eq!(err, Iperf3Error::ResponseTimeout { timeout_ms: 20 });
+    assert_eq!(err.status_code(), http::StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(err.code(), "response_timeout");
+}
+
+/// Test that a serializer finishing in time returns its bytes, and its own errors pass through.
+#[tokio::test]
+async fn fast_serialization_passes_through() {
+    let body = serialize_with_timeout(Duration::from_secs(5), || Ok(b"{}".to_vec())).await;
+    assert_eq!(body.unwrap(), b"{}".to_vec());
+
+    let err = serialize_with_timeout(Duration::from_secs(5), || Err(Iperf3Error::UnknownFields(vec!["x".into()])))
+        .await
+        .unwrap_err();
+    assert_eq!(err, Iperf3Error::UnknownFields(vec!["x".into()]));
+}
+
+/// Test that `RESPONSE_TIMEOUT_MS` is read, ignoring invalid values.
+#[tokio::test]
+#[serial]
+async fn response_timeout_reads_env() {
+    unsafe { std::env::set_var("RESPONSE_TIMEOUT_MS", "250") };
+    assert_eq!(response_timeout(), Duration::from_millis(250));
+    unsafe { std::env::set_var("RESPONSE_TIMEOUT_MS", "0") };
+    assert_eq!(response_timeout(), Duration::from_millis(5000));
+    unsafe { std::env::remove_var("RESPONSE_TIMEOUT_MS") };
+    assert_eq!(response_timeout(), Duration::from_millis(5000));
+}
+
+/// Test that `/iperf3` still serves the cached result as JSON through the guarded path.
+#[actix_web::test]
+#[serial]
+async fn iperf3_serves_json_within_timeout() {
+    unsafe { std::env::set_var("RESPONSE_TIMEOUT_MS", "1000") };
+    set_last_result_for_test(Iperf3Report::default());
+    let app = test::init_service(App::new().service(iperf3)).await;
+
+    let req = test::TestRequest::get().uri("/iperf3").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+    assert_eq!(resp.headers().get(http::header::CONTENT_TYPE).unwrap(), "application/json");
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body, serde_json::to_value(Iperf3Report::default()).unwrap());
+
+    unsafe { std::env::remove_var("RESPONSE_TIMEOUT_MS") };
+    clear_last_result_for_test();
+}
@@ -0,0 +1,146 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for the graceful shutdown path.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use async_trait::async_trait;
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+/// Sets its flag when dropped, i.e. when the run holding it is cancelled.
+struct DropFlag(Arc<AtomicBool>);
+
+impl Drop for DropFlag {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Mock runner whose runs never finish, recording when one starts and when it is cancelled.
+#[derive(Default)]
+struct HangingRunner {
+    started: Arc<AtomicBool>,
+    cancelled: Arc<AtomicBool>,
+}
+
+#[async_trait]
+impl Iperf3Runner for HangingRunner {
+    async fn run_iperf3(&self, _iperf3_ip: String, _iperf3_port: String) -> Result<String, Iperf3Error> {
+        let _flag = DropFlag(self.cancelled.clone());
+        self.started.store(true, Ordering::SeqCst);
+        std::future::pending().await
+    }
+}
+
+/// Test that triggering shutdown completes the scheduler future and cancels the in-flight run.
+#[tokio::test]
+#[serial]
+async fn shutdown_completes_scheduler_and_cancels_run() {
+    let runner = HangingRunner::default();
+    let (started, cancelled) = (runner.started.clone(), runner.cancelled.clone());
+    let (trigger, shutdown) = tokio::sync::oneshot::channel::<()>();
+
+    let scheduler = tokio::spawn(run_until_shutdown(
+        async move {
+            let targets = vec![Target::new("127.0.0.1", "5201")];
+            run_scheduler_with_runner(&runner, &targets, 1, Duration::ZERO, Duration::from_secs(600)).await
+        },
+        shutdown,
+    ));
+
+    while !started.load(Ordering::SeqCst) {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    assert!(!cancelled.load(Ordering::SeqCst));
+
+    trigger.send(()).unwrap();
+    tokio::time::timeout(Duration::from_secs(5), scheduler).await.unwrap().unwrap();
+    assert!(cancelled.load(Ordering::SeqCst));
+
+    clear_last_result_for_test();
+    clear_run_status_for_test();
+}
+
+/// Mock runner whose runs take `delay` before returning a default report.
+struct SlowRunner {
+    delay: Duration,
+}
+
+#[async_trait]
+impl Iperf3Runner for SlowRunner {
+    async fn run_iperf3(&self, _iperf3_ip: String, _iperf3_port: String) -> Result<String, Iperf3Error> {
+        tokio::time::sleep(self.delay).await;
+        Ok(serde_json::to_string(&Iperf3Report::default()).unwrap())
+    }
+}
+
+/// Starts a scheduler for `runner` draining for `timeout` on shutdown, and waits for its
+/// first run to be in flight.
+async fn start_draining_scheduler(
+    runner: impl Iperf3Runner + 'static,
+    timeout: Duration,
+) -> (tokio::sync::oneshot::Sender<()>, tokio::task::JoinHandle<()>) {
+    let (trigger, shutdown) = tokio::sync::oneshot::channel::<()>();
+    let scheduler = tokio::spawn(drain_until_shutdown(
+        async move {
+            let targets = vec![Target::new("127.0.0.1", "5201")];
+            run_scheduler_with_runner(&runner, &targets, 1, Duration::ZERO, Duration::from_secs(600)).await
+        },
+        shutdown,
+        timeout,
+    ));
+    while runs_in_flight() == 0 {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    (trigger, scheduler)
+}
+
+/// Test that shutdown gives up on a stuck run once the timeout elapses.
+#[tokio::test(start_paused = true)]
+#[serial]
+async fn drain_gives_up_on_stuck_run_after_timeout() {
+    let runner = HangingRunner::default();
+    let cancelled = runner.cancelled.clone();
+    let (trigger, scheduler) = start_draining_scheduler(runner, Duration::from_secs(5)).await;
+
+    let started = tokio::time::Instant::now();
+    trigger.send(()).unwrap();
+    scheduler.await.unwrap();
+    assert!(started.elapsed() >= Duration::from_secs(5));
+    assert!(started.elapsed() < Duration::from_secs(6));
+    assert!(cancelled.load(Ordering::SeqCst));
+    assert_eq!(runs_in_flight(), 0);
+
+    clear_last_result_for_test();
+    clear_run_status_for_test();
+}
+
+/// Test that shutdown lets an in-flight run finish within the timeout.
+#[tokio::test(start_paused = true)]
+#[serial]
+async fn drain_lets_in_flight_run_finish() {
+    clear_last_result_for_test();
+    let runner = SlowRunner { delay: Duration::from_secs(2) };
+    let (trigger, scheduler) = start_draining_scheduler(runner, Duration::from_secs(30)).await;
+
+    let started = tokio::time::Instant::now();
+    trigger.send(()).unwrap();
+    scheduler.await.unwrap();
+    assert!(started.elapsed() < Duration::from_secs(3));
+    assert!(get_last_result().is_some());
+
+    clear_last_result_for_test();
+    clear_run_status_for_test();
+}
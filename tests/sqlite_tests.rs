@@ -0,0 +1,101 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for the SQLite result export (`sqlite` feature).
+//!
+//! Run with `cargo test --features sqlite`; requires the `sqlite3` shell on `PATH`.
+
+#![cfg(feature = "sqlite")]
+
+use std::path::PathBuf;
+use actix_web::{test, http, App};
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+fn db_file(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("iperf3-sqlite-{}-{}.db", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn report(timesecs: u64, received_mbps: f64) -> Iperf3Report {
+    let mut report = Iperf3Report::default();
+    report.start.timestamp.timesecs = timesecs;
+    report.end.sum_sent.bits_per_second = received_mbps * 1_100_000.0;
+    report.end.sum_sent.retransmits = 3;
+    report.end.sum_received.bits_per_second = received_mbps * 1_000_000.0;
+    report.end.cpu_utilization_percent.host_total = 12.5;
+    report
+}
+
+#[tokio::test]
+async fn inserted_results_are_queried_back_since_a_timestamp() {
+    let path = db_file("query");
+    insert_result(&path, &report(1_700_000_000, 900.0)).await.unwrap();
+    insert_result(&path, &report(1_700_000_600, 850.0)).await.unwrap();
+
+    let all = query_results_since(&path, 0).await.unwrap();
+    assert_eq!(all.len(), 2);
+    assert_eq!(all[0], StoredResult::from(&report(1_700_000_000, 900.0)));
+    assert_eq!(all[1].received_bps, Some(850_000_000.0));
+    assert_eq!(all[1].retransmits, 3);
+    assert_eq!(all[1].host_cpu_percent, Some(12.5));
+
+    let recent = query_results_since(&path, 1_700_000_001).await.unwrap();
+    assert_eq!(recent, vec![StoredResult::from(&report(1_700_000_600, 850.0))]);
+    assert!(query_results_since(&path, 1_800_000_000).await.unwrap().is_empty());
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn non_finite_values_are_stored_as_null() {
+    let path = db_file("nan");
+    let mut nan = report(1_700_000_000, 900.0);
+    nan.end.cpu_utilization_percent.host_total = f64::NAN;
+    insert_result(&path, &nan).await.unwrap();
+
+    let stored = query_results_since(&path, 0).await.unwrap();
+    assert_eq!(stored[0].host_cpu_percent, None);
+    assert_eq!(stored[0].received_bps, Some(900_000_000.0));
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn unusable_database_is_an_error() {
+    let path = std::env::temp_dir().join("iperf3-sqlite-missing-dir").join("results.db");
+    assert!(insert_result(&path, &report(1_700_000_000, 900.0)).await.is_err());
+}
+
+#[actix_web::test]
+#[serial]
+async fn query_endpoint_serves_stored_results() {
+    let path = db_file("endpoint");
+    insert_result(&path, &report(1_700_000_000, 900.0)).await.unwrap();
+    insert_result(&path, &report(1_700_000_600, 850.0)).await.unwrap();
+    unsafe { std::env::set_var("DB_PATH", &path) };
+
+    let app = test::init_service(App::new().service(query_results)).await;
+    let req = test::TestRequest::get().uri("/query?since=1700000300").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+    let body: Vec<StoredResult> = test::read_body_json(resp).await;
+    assert_eq!(body, vec![StoredResult::from(&report(1_700_000_600, 850.0))]);
+
+    unsafe { std::env::remove_var("DB_PATH") };
+    let req = test::TestRequest::get().uri("/query").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+
+    let _ = std::fs::remove_file(&path);
+}
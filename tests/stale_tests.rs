@@ -0,0 +1,100 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for the auth-aware staleness policy.
+//!
+//! These tests modify `STALE_AFTER_SECONDS`, `API_TOKEN` and the cache, and are annotated with `#[serial]`.
+
+use std::time::{Duration, Instant};
+use actix_web::{test, http, App};
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+/// Caches a default report as if it had been cached `age` ago.
+fn cache_result_aged(age: Duration) {
+    *LAST_RESULT.lock().unwrap() = Some((Iperf3Report::default(), Instant::now() - age));
+}
+
+fn configure(stale_after: &str, token: &str) {
+    unsafe {
+        std::env::set_var("STALE_AFTER_SECONDS", stale_after);
+        std::env::set_var("API_TOKEN", token);
+    }
+}
+
+fn reset() {
+    unsafe {
+        std::env::remove_var("STALE_AFTER_SECONDS");
+        std::env::remove_var("API_TOKEN");
+    }
+    clear_last_result_for_test();
+}
+
+/// Test that anonymous clients get a 503 for stale data.
+#[actix_web::test]
+#[serial]
+async fn anonymous_stale_request_gets_503() {
+    configure("300", "s3cret");
+    cache_result_aged(Duration::from_secs(600));
+
+    let app = test::init_service(App::new().service(iperf3).service(iperf3_summary)).await;
+    for uri in ["/iperf3", "/summary"] {
+        let req = test::TestRequest::get().uri(uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::SERVICE_UNAVAILABLE, "{}", uri);
+    }
+
+    let req = test::TestRequest::get()
+        .uri("/iperf3")
+        .insert_header((http::header::AUTHORIZATION, "Bearer wrong"))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+
+    reset();
+}
+
+/// Test that authenticated clients get stale data flagged with `X-Stale: true`.
+#[actix_web::test]
+#[serial]
+async fn authenticated_stale_request_gets_flagged_data() {
+    configure("300", "s3cret");
+    cache_result_aged(Duration::from_secs(600));
+
+    let app = test::init_service(App::new().service(iperf3).service(iperf3_summary)).await;
+    for uri in ["/iperf3", "/summary"] {
+        let req = test::TestRequest::get()
+            .uri(uri)
+            .insert_header((http::header::AUTHORIZATION, "Bearer s3cret"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK, "{}", uri);
+        assert_eq!(resp.headers().get(X_STALE).unwrap(), "true");
+    }
+
+    reset();
+}
+
+/// Test that fresh data is served to anyone without the stale flag.
+#[actix_web::test]
+#[serial]
+async fn fresh_data_is_not_flagged() {
+    configure("300", "s3cret");
+    cache_result_aged(Duration::from_secs(10));
+
+    let app = test::init_service(App::new().service(iperf3)).await;
+    let req = test::TestRequest::get().uri("/iperf3").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+    assert!(resp.headers().get(X_STALE).is_none());
+
+    reset();
+}
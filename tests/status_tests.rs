@@ -0,0 +1,464 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for the `/status` endpoint and the sanity checks feeding it.
+//!
+//! Tests which modify environment variables are annotated with
+//! `#[serial]` since the environment is process-global.
+
+use actix_web::{test, http, App};
+use async_trait::async_trait;
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+/// Mock runner returning a fixed output.
+struct MockRunner {
+    output: Result<String, Iperf3Error>,
+}
+
+#[async_trait]
+impl Iperf3Runner for MockRunner {
+    async fn run_iperf3(&self, _iperf3_ip: String, _iperf3_port: String) -> Result<String, Iperf3Error> {
+        self.output.clone()
+    }
+}
+
+/// Builds a report in which iperf3 only established `streams` streams.
+fn report_with_streams(streams: usize) -> Iperf3Report {
+    let mut report = Iperf3Report::default();
+    report.start.test_start.num_streams = streams as u32;
+    report.end.streams = vec![EndStream::default(); streams];
+    report
+}
+
+/// Test that fewer established streams than configured produces a warning.
+#[tokio::test]
+async fn check_expected_streams_warns_on_mismatch() {
+    let warning = check_expected_streams(&report_with_streams(2), 4);
+    assert!(warning.unwrap().contains("Expected 4 parallel streams"));
+
+    assert!(check_expected_streams(&report_with_streams(4), 4).is_none());
+}
+
+/// Test that a `--bidir` run, which ends with one stream per direction, does not warn.
+#[tokio::test]
+async fn check_expected_streams_counts_both_directions_of_bidir_runs() {
+    let mut report = report_with_streams(4);
+    report.start.test_start.num_streams = 2;
+    report.start.test_start.bidir = 1;
+    assert!(check_expected_streams(&report, 2).is_none());
+
+    report.end.streams.truncate(2);
+    assert!(check_expected_streams(&report, 2).unwrap().contains("2 (test_start) and 2 (end)"));
+}
+
+/// Test that a run with a stream shortfall sets a warning in `/status`.
+#[actix_web::test]
+#[serial]
+async fn status_reports_stream_mismatch_warning() {
+    clear_run_status_for_test();
+    unsafe { std::env::set_var("IPERF3_PARALLEL", "4") };
+
+    let runner = MockRunner {
+        output: Ok(serde_json::to_string(&report_with_streams(2)).unwrap()),
+    };
+    run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into()).await.unwrap();
+
+    let app = test::init_service(App::new().service(iperf3_status)).await;
+    let req = test::TestRequest::get().uri("/status").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+
+    let body: RunStatus = test::read_body_json(resp).await;
+    assert_eq!(body.warnings.len(), 1);
+    assert!(body.warnings[0].contains("2 (test_start) and 2 (end)"));
+
+    unsafe { std::env::remove_var("IPERF3_PARALLEL") };
+    clear_last_result_for_test();
+}
+
+/// Test that a parallel count passed through `IPERF3_EXTRA_ARGS` is checked too.
+#[tokio::test]
+#[serial]
+async fn stream_mismatch_honours_extra_args_parallel_count() {
+    clear_run_status_for_test();
+    unsafe { std::env::remove_var("IPERF3_PARALLEL") };
+    unsafe { std::env::set_var("IPERF3_EXTRA_ARGS", "-P 4") };
+
+    let runner = MockRunner {
+        output: Ok(serde_json::to_string(&report_with_streams(2)).unwrap()),
+    };
+    run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into()).await.unwrap();
+
+    let warnings = get_run_status().warnings;
+    unsafe { std::env::remove_var("IPERF3_EXTRA_ARGS") };
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("Expected 4 parallel streams"));
+    clear_last_result_for_test();
+}
+
+/// Test that no warning is raised when no parallel count is configured.
+#[tokio::test]
+#[serial]
+async fn no_warning_without_configured_parallel_count() {
+    clear_run_status_for_test();
+    unsafe { std::env::remove_var("IPERF3_PARALLEL") };
+
+    let runner = MockRunner {
+        output: Ok(serde_json::to_string(&report_with_streams(2)).unwrap()),
+    };
+    run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into()).await.unwrap();
+
+    assert!(get_run_status().warnings.is_empty());
+    clear_last_result_for_test();
+}
+
+/// Test that the start-to-start interval is computed from a mock clock over two runs
+/// and published in `/status`.
+#[actix_web::test]
+#[serial]
+async fn status_reports_actual_interval_between_cycles() {
+    use std::time::{Duration, Instant};
+
+    clear_run_status_for_test();
+    let t0 = Instant::now();
+
+    assert_eq!(record_cycle_start(t0), None);
+    assert_eq!(get_run_status().actual_interval_seconds, None);
+
+    // Nominal interval of 10 minutes, but the first run took 12.5s longer than expected.
+    let drift = record_cycle_start(t0 + Duration::from_millis(612_500));
+    assert_eq!(drift, Some(Duration::from_millis(612_500)));
+
+    let app = test::init_service(App::new().service(iperf3_status)).await;
+    let req = test::TestRequest::get().uri("/status").to_request();
+    let body: RunStatus = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body.actual_interval_seconds, Some(612.5));
+}
+
+/// Test that a non-zero iperf3 exit code is captured in the error and `/status`.
+#[actix_web::test]
+#[serial]
+async fn status_reports_last_exit_code() {
+    clear_run_status_for_test();
+    let runner = MockRunner {
+        output: Err(Iperf3Error::NonZeroExit {
+            code: Some(2),
+            stderr: "iperf3: error - unable to connect to server".into(),
+        }),
+    };
+
+    let err = run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into())
+        .await
+        .unwrap_err();
+    assert_eq!(err.exit_code(), Some(2));
+    assert!(err.to_string().contains("exit code 2"));
+
+    let app = test::init_service(App::new().service(iperf3_status)).await;
+    let req = test::TestRequest::get().uri("/status").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["last_exit_code"], 2);
+
+    let runner = MockRunner {
+        output: Ok(serde_json::to_string(&Iperf3Report::default()).unwrap()),
+    };
+    run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into()).await.unwrap();
+    assert_eq!(get_run_status().last_exit_code, Some(0));
+
+    clear_last_result_for_test();
+    clear_run_status_for_test();
+}
+
+/// Test that a near-zero-bytes report is rejected and does not overwrite the cache.
+#[tokio::test]
+#[serial]
+async fn near_zero_bytes_report_is_rejected() {
+    unsafe { std::env::set_var("MIN_VALID_BYTES", "1000000") };
+    let mut good = Iperf3Report::default();
+    good.end.sum_received.bytes = 117_000_000;
+    set_last_result_for_test(good);
+
+    let mut tiny = Iperf3Report::default();
+    tiny.end.sum_received.bytes = 128;
+    let runner = MockRunner { output: Ok(serde_json::to_string(&tiny).unwrap()) };
+
+    let err = run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Iperf3Error::Rejected(_)));
+    assert_eq!(get_last_result().unwrap().end.sum_received.bytes, 117_000_000);
+
+    unsafe { std::env::remove_var("MIN_VALID_BYTES") };
+    run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into()).await.unwrap();
+    assert_eq!(get_last_result().unwrap().end.sum_received.bytes, 128);
+
+    clear_last_result_for_test();
+    clear_run_status_for_test();
+}
+
+/// Test that the measured cycle spacing is exported as `iperf3_actual_interval_seconds`.
+#[tokio::test]
+async fn actual_interval_is_rendered_as_metric() {
+    let status = RunStatus { actual_interval_seconds: Some(605.5), ..Default::default() };
+    assert!(render_status_metrics(&status).contains("iperf3_actual_interval_seconds 605.5\n"));
+    assert!(render_status_metrics(&RunStatus::default()).is_empty());
+}
+
+/// Test that a report skewed beyond `MAX_CLOCK_SKEW_SECONDS` is cached with a warning by
+/// default, and rejected without overwriting the cache when `REJECT_CLOCK_SKEW` is set.
+#[tokio::test]
+#[serial]
+async fn clock_skewed_report_warns_or_is_rejected() {
+    unsafe { std::env::set_var("MAX_CLOCK_SKEW_SECONDS", "300") };
+    clear_run_status_for_test();
+    let mut skewed = Iperf3Report::default();
+    skewed.start.timestamp.timesecs = 946_684_800; // 2000-01-01, years behind
+    skewed.end.sum_received.bytes = 42;
+    let runner = MockRunner { output: Ok(serde_json::to_string(&skewed).unwrap()) };
+
+    run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into()).await.unwrap();
+    assert_eq!(get_last_result().unwrap().end.sum_received.bytes, 42);
+    let warnings = get_run_status().warnings;
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("behind the local clock, beyond MAX_CLOCK_SKEW_SECONDS of 300"));
+
+    unsafe { std::env::set_var("REJECT_CLOCK_SKEW", "true") };
+    set_last_result_for_test(Iperf3Report::default());
+    let err = run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Iperf3Error::Rejected(ref reason) if reason.contains("MAX_CLOCK_SKEW_SECONDS")));
+    assert_eq!(get_last_result().unwrap().end.sum_received.bytes, 0);
+
+    unsafe {
+        std::env::remove_var("MAX_CLOCK_SKEW_SECONDS");
+        std::env::remove_var("REJECT_CLOCK_SKEW");
+    }
+    clear_last_result_for_test();
+    clear_run_status_for_test();
+}
+
+/// Test that the `RUN_ANNOTATION` in effect when a result is cached appears in `/status`
+/// and `/summary`, and that a reload only affects results cached afterwards.
+#[actix_web::test]
+#[serial]
+async fn run_annotation_is_attached_to_cached_results() {
+    unsafe { std::env::set_var("RUN_ANNOTATION", "deploy-3f2a9c1") };
+    assert_eq!(reload_run_annotation().as_deref(), Some("deploy-3f2a9c1"));
+    let runner = MockRunner { output: Ok(serde_json::to_string(&Iperf3Report::default()).unwrap()) };
+    run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into()).await.unwrap();
+    assert_eq!(last_cache_metadata().unwrap().annotation.as_deref(), Some("deploy-3f2a9c1"));
+
+    unsafe { std::env::set_var("RUN_ANNOTATION", "deploy-7b01e44") };
+    reload_run_annotation();
+    let app = test::init_service(App::new().service(iperf3_status).service(iperf3_summary)).await;
+    let req = test::TestRequest::get().uri("/status").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["annotation"], "deploy-3f2a9c1");
+
+    run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into()).await.unwrap();
+    let req = test::TestRequest::get().uri("/summary").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["annotation"], "deploy-7b01e44");
+
+    unsafe { std::env::remove_var("RUN_ANNOTATION") };
+    assert_eq!(reload_run_annotation(), None);
+    clear_last_result_for_test();
+    clear_run_status_for_test();
+}
+
+/// Test that a report whose protocol differs from `EXPECTED_PROTOCOL` raises a warning and
+/// sets `protocol_mismatch` in `/status`, while a matching one clears it.
+#[actix_web::test]
+#[serial]
+async fn expected_protocol_mismatch_is_flagged() {
+    unsafe { std::env::set_var("EXPECTED_PROTOCOL", "tcp") };
+    assert_eq!(expected_protocol().as_deref(), Some("TCP"));
+    clear_run_status_for_test();
+
+    let mut udp = Iperf3Report::default();
+    udp.start.test_start.protocol = "UDP".to_string();
+    let runner = MockRunner { output: Ok(serde_json::to_string(&udp).unwrap()) };
+    run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into()).await.unwrap();
+
+    let app = test::init_service(App::new().service(iperf3_status)).await;
+    let req = test::TestRequest::get().uri("/status").to_request();
+    let body: RunStatus = test::call_and_read_body_json(&app, req).await;
+    assert!(body.protocol_mismatch);
+    assert_eq!(body.warnings, vec!["Expected protocol TCP but iperf3 reported \"UDP\"".to_string()]);
+
+    let mut tcp = Iperf3Report::default();
+    tcp.start.test_start.protocol = "TCP".to_string();
+    let runner = MockRunner { output: Ok(serde_json::to_string(&tcp).unwrap()) };
+    run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into()).await.unwrap();
+    assert!(!get_run_status().protocol_mismatch);
+    assert!(get_run_status().warnings.is_empty());
+
+    unsafe { std::env::remove_var("EXPECTED_PROTOCOL") };
+    clear_last_result_for_test();
+    clear_run_status_for_test();
+}
+
+/// Test that a report beyond `MAX_HOST_CPU_PERCENT` is cached and flagged `cpu_saturated`
+/// in `/summary` by default, and rejected when `REJECT_CPU_SATURATED` is set.
+#[actix_web::test]
+#[serial]
+async fn cpu_saturated_report_is_flagged_or_rejected() {
+    unsafe { std::env::set_var("MAX_HOST_CPU_PERCENT", "90") };
+    clear_run_status_for_test();
+    let mut saturated = Iperf3Report::default();
+    saturated.end.cpu_utilization_percent.host_total = 99.2;
+    saturated.end.sum_received.bytes = 42;
+    let runner = MockRunner { output: Ok(serde_json::to_string(&saturated).unwrap()) };
+
+    run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into()).await.unwrap();
+    assert_eq!(get_last_result().unwrap().end.sum_received.bytes, 42);
+    assert!(get_run_status().warnings[0].contains("exceeds MAX_HOST_CPU_PERCENT of 90"));
+    let app = test::init_service(App::new().service(iperf3_summary)).await;
+    let req = test::TestRequest::get().uri("/summary").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["cpu_saturated"], true);
+
+    unsafe { std::env::set_var("REJECT_CPU_SATURATED", "true") };
+    set_last_result_for_test(Iperf3Report::default());
+    let err = run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Iperf3Error::Rejected(ref reason) if reason.contains("MAX_HOST_CPU_PERCENT")));
+    assert_eq!(get_last_result().unwrap().end.sum_received.bytes, 0);
+    let req = test::TestRequest::get().uri("/summary").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["cpu_saturated"], false);
+
+    unsafe {
+        std::env::remove_var("MAX_HOST_CPU_PERCENT");
+        std::env::remove_var("REJECT_CPU_SATURATED");
+    }
+    clear_last_result_for_test();
+    clear_run_status_for_test();
+}
+
+/// Builds a report started `age` before now with the given received throughput in Mbps.
+fn report_aged(age: std::time::Duration, mbps: f64) -> Iperf3Report {
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap();
+    let mut report = Iperf3Report::default();
+    report.start.timestamp.timesecs = (now - age).as_secs();
+    report.end.sum_received.bits_per_second = mbps * 1_000_000.0;
+    report
+}
+
+/// Test that `slo_compliance_percent` in `/status` and `/metrics` is the share of runs
+/// within `SLO_WINDOW_HOURS` meeting `SLA_MIN_MBPS`, ignoring older runs.
+#[actix_web::test]
+#[serial]
+async fn slo_compliance_counts_runs_within_window() {
+    use std::time::Duration;
+
+    unsafe {
+        std::env::set_var("SLA_MIN_MBPS", "500");
+        std::env::set_var("SLO_WINDOW_HOURS", "2");
+    }
+    clear_run_status_for_test();
+    clear_history_for_test();
+    // Outside the window, so this failure does not count
+    push_history_for_test(report_aged(Duration::from_secs(3 * 3600), 100.0));
+    for (minutes_ago, mbps) in [(110, 940.0), (90, 320.0), (60, 510.0), (30, 900.0)] {
+        push_history_for_test(report_aged(Duration::from_secs(minutes_ago * 60), mbps));
+    }
+    set_last_result_for_test(report_aged(Duration::from_secs(30 * 60), 900.0));
+
+    let app = test::init_service(App::new().service(iperf3_status).service(iperf3_metrics)).await;
+    let req = test::TestRequest::get().uri("/status").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["slo_compliance_percent"], 75.0);
+    let req = test::TestRequest::get().uri("/metrics").to_request();
+    let metrics = String::from_utf8(test::call_and_read_body(&app, req).await.to_vec()).unwrap();
+    assert!(metrics.contains("iperf3_slo_compliance_percent 75\n"));
+
+    unsafe { std::env::remove_var("SLA_MIN_MBPS") };
+    let req = test::TestRequest::get().uri("/status").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert!(body.get("slo_compliance_percent").is_none());
+
+    unsafe { std::env::remove_var("SLO_WINDOW_HOURS") };
+    clear_history_for_test();
+    clear_last_result_for_test();
+    clear_run_status_for_test();
+}
+
+/// Test that a spawn failing with `PermissionDenied` is classified as such, with a hint,
+/// and flagged as `permission_denied` in `/status` until a run succeeds.
+#[actix_web::test]
+#[serial]
+async fn permission_denied_spawn_is_flagged() {
+    clear_run_status_for_test();
+    let error = Iperf3Error::from_spawn_error(&std::io::Error::from(std::io::ErrorKind::PermissionDenied));
+    assert!(matches!(error, Iperf3Error::PermissionDenied(_)));
+    assert!(error.to_string().contains("AppArmor"));
+    assert!(matches!(
+        Iperf3Error::from_spawn_error(&std::io::Error::from(std::io::ErrorKind::NotFound)),
+        Iperf3Error::Spawn(_)
+    ));
+
+    let runner = MockRunner { output: Err(error) };
+    let err = run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into()).await.unwrap_err();
+    assert_eq!(err.code(), "permission_denied");
+    let app = test::init_service(App::new().service(iperf3_status)).await;
+    let req = test::TestRequest::get().uri("/status").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["permission_denied"], true);
+
+    let runner = MockRunner { output: Ok(serde_json::to_string(&Iperf3Report::default()).unwrap()) };
+    run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into()).await.unwrap();
+    assert!(!get_run_status().permission_denied);
+
+    clear_last_result_for_test();
+    clear_run_status_for_test();
+}
+
+/// Test that `/iperf3/status` tells never having run apart from failing after a success,
+/// and that a successful run clears the last error.
+#[actix_web::test]
+#[serial]
+async fn health_distinguishes_never_ran_from_failing() {
+    clear_run_status_for_test();
+    clear_last_result_for_test();
+    let app = test::init_service(App::new().service(iperf3_health)).await;
+    let health = || async {
+        let req = test::TestRequest::get().uri("/iperf3/status").to_request();
+        test::call_and_read_body_json::<_, _, serde_json::Value>(&app, req).await
+    };
+
+    assert_eq!(
+        health().await,
+        serde_json::json!({"healthy": false, "last_success_secs_ago": null, "last_error": null})
+    );
+
+    set_last_result_for_test(Iperf3Report::default());
+    let failing = MockRunner { output: Ok("not json".to_string()) };
+    run_iperf3_and_cache_with_runner(&failing, "127.0.0.1".into(), "5201".into()).await.unwrap_err();
+    let body = health().await;
+    assert_eq!(body["healthy"], false);
+    assert_eq!(body["last_success_secs_ago"], 0);
+    assert!(body["last_error"].as_str().unwrap().starts_with("Failed to parse iperf3 JSON"));
+
+    let succeeding = MockRunner { output: Ok(serde_json::to_string(&Iperf3Report::default()).unwrap()) };
+    run_iperf3_and_cache_with_runner(&succeeding, "127.0.0.1".into(), "5201".into()).await.unwrap();
+    assert_eq!(get_last_error(), None);
+    assert_eq!(health().await, serde_json::json!({"healthy": true, "last_success_secs_ago": 0, "last_error": null}));
+
+    set_last_error_for_test("iperf3 timed out after 60s");
+    assert_eq!(health().await["last_error"], "iperf3 timed out after 60s");
+
+    clear_last_result_for_test();
+    clear_run_status_for_test();
+}
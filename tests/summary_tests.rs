@@ -0,0 +1,475 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for the `/summary` endpoint and remote host resolution.
+//!
+//! The cache and hostname cache are process-global, so tests are annotated with `#[serial]`.
+
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use actix_web::{test, http, App};
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+/// Resolver returning a fixed name for one IP and counting lookups.
+struct MockResolver {
+    ip: IpAddr,
+    name: &'static str,
+    lookups: AtomicUsize,
+}
+
+impl HostResolver for MockResolver {
+    fn reverse_lookup(&self, ip: IpAddr) -> Option<String> {
+        self.lookups.fetch_add(1, Ordering::SeqCst);
+        (ip == self.ip).then(|| self.name.to_string())
+    }
+}
+
+/// Builds a report connected to `remote_host`.
+fn report_to(remote_host: &str) -> Iperf3Report {
+    let mut report = Iperf3Report::default();
+    report.start.connected.push(Connected {
+        remote_host: remote_host.to_string(),
+        ..Default::default()
+    });
+    report.start.timestamp.timesecs = 1_700_000_000;
+    report.end.sum_sent.bits_per_second = 950_000_000.0;
+    report.end.sum_received.bits_per_second = 941_000_000.0;
+    report.end.sum_sent.retransmits = 12;
+    report
+}
+
+/// Test that an injected resolver's name appears in the summary and is cached.
+#[tokio::test]
+#[serial]
+async fn resolved_hostname_appears_in_summary() {
+    clear_hostname_cache_for_test();
+    let resolver = MockResolver {
+        ip: "192.0.2.10".parse().unwrap(),
+        name: "iperf.example.net",
+        lookups: AtomicUsize::new(0),
+    };
+    let report = report_to("192.0.2.10");
+
+    let summary = build_summary(&report, Some(&resolver));
+    assert_eq!(summary.remote_host, "192.0.2.10");
+    assert_eq!(summary.remote_hostname.as_deref(), Some("iperf.example.net"));
+
+    build_summary(&report, Some(&resolver));
+    assert_eq!(resolver.lookups.load(Ordering::SeqCst), 1);
+    clear_hostname_cache_for_test();
+}
+
+/// Test that an unresolvable address falls back to the IP.
+#[tokio::test]
+#[serial]
+async fn unresolved_hostname_falls_back_to_ip() {
+    clear_hostname_cache_for_test();
+    let resolver = MockResolver {
+        ip: "192.0.2.10".parse().unwrap(),
+        name: "iperf.example.net",
+        lookups: AtomicUsize::new(0),
+    };
+
+    let summary = build_summary(&report_to("198.51.100.7"), Some(&resolver));
+    assert_eq!(summary.remote_hostname.as_deref(), Some("198.51.100.7"));
+    clear_hostname_cache_for_test();
+}
+
+/// Test that `/summary` reports headline figures and omits the hostname by default.
+#[actix_web::test]
+#[serial]
+async fn summary_endpoint_without_resolution() {
+    unsafe { std::env::remove_var("RESOLVE_REMOTE_HOST") };
+    set_last_result_for_test(report_to("192.0.2.10"));
+
+    let app = test::init_service(App::new().service(iperf3_summary)).await;
+    let req = test::TestRequest::get().uri("/summary").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["received_mbps"], 941.0);
+    assert_eq!(body["sent_mbps"], 950.0);
+    assert_eq!(body["retransmits"], 12);
+    assert_eq!(body["remote_host"], "192.0.2.10");
+    assert!(body.get("remote_hostname").is_none());
+
+    clear_last_result_for_test();
+}
+
+/// Test that `/summary` returns 503 before any result is cached.
+#[actix_web::test]
+#[serial]
+async fn summary_endpoint_unavailable_without_result() {
+    clear_last_result_for_test();
+
+    let app = test::init_service(App::new().service(iperf3_summary)).await;
+    let req = test::TestRequest::get().uri("/summary").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+}
+
+/// Test that `/summary` of a bidirectional test reports all four directional throughputs,
+/// parsed from the `_bidir_reverse` sums, and omits them for a normal test.
+#[actix_web::test]
+#[serial]
+async fn summary_reports_bidir_directions() {
+    let mut json = serde_json::to_value(Iperf3Report::default()).unwrap();
+    json["start"]["test_start"]["bidir"] = serde_json::json!(1);
+    json["end"]["sum_sent"]["bits_per_second"] = serde_json::json!(900_000_000.0);
+    json["end"]["sum_received"]["bits_per_second"] = serde_json::json!(890_000_000.0);
+    let mut reverse_sent = json["end"]["sum_sent"].clone();
+    reverse_sent["bits_per_second"] = serde_json::json!(400_000_000.0);
+    reverse_sent["sender"] = serde_json::json!(false);
+    let mut reverse_received = json["end"]["sum_received"].clone();
+    reverse_received["bits_per_second"] = serde_json::json!(390_000_000.0);
+    json["end"]["sum_sent_bidir_reverse"] = reverse_sent;
+    json["end"]["sum_received_bidir_reverse"] = reverse_received;
+    let report: Iperf3Report = serde_json::from_value(json).unwrap();
+    set_last_result_for_test(report);
+
+    let app = test::init_service(App::new().service(iperf3_summary)).await;
+    let req = test::TestRequest::get().uri("/summary").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(
+        body["bidir"],
+        serde_json::json!({
+            "forward_sent_mbps": 900.0,
+            "forward_received_mbps": 890.0,
+            "reverse_sent_mbps": 400.0,
+            "reverse_received_mbps": 390.0,
+        })
+    );
+
+    set_last_result_for_test(report_to("192.0.2.10"));
+    let req = test::TestRequest::get().uri("/summary").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert!(body.get("bidir").is_none());
+
+    clear_last_result_for_test();
+}
+
+/// Builds a report whose streams have the given `(socket, retransmits)`.
+fn report_with_streams(streams: &[(u32, u32)]) -> Iperf3Report {
+    let mut report = report_to("192.0.2.10");
+    for &(socket, retransmits) in streams {
+        let mut stream = EndStream::default();
+        stream.sender.socket = socket;
+        stream.sender.retransmits = retransmits;
+        report.end.streams.push(stream);
+    }
+    report
+}
+
+/// Test that the stream with the most retransmits is reported as `worst_stream`.
+#[actix_web::test]
+#[serial]
+async fn summary_identifies_worst_stream() {
+    unsafe { std::env::remove_var("RESOLVE_REMOTE_HOST") };
+    set_last_result_for_test(report_with_streams(&[(5, 3), (7, 181), (9, 0), (11, 4)]));
+
+    let app = test::init_service(App::new().service(iperf3_summary)).await;
+    let req = test::TestRequest::get().uri("/summary").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["worst_stream"], serde_json::json!({"socket": 7, "retransmits": 181}));
+
+    let clean = build_summary(&report_with_streams(&[(5, 0), (7, 0)]), None);
+    assert_eq!(clean.worst_stream, None);
+
+    clear_last_result_for_test();
+}
+
+/// Test that `/metrics` emits one retransmit series per stream when `METRICS_PER_STREAM` is enabled.
+#[actix_web::test]
+#[serial]
+async fn metrics_emit_per_stream_retransmits() {
+    unsafe { std::env::set_var("METRICS_PER_STREAM", "true") };
+    set_last_result_for_test(report_with_streams(&[(5, 3), (7, 181)]));
+
+    let app = test::init_service(App::new().service(iperf3_metrics)).await;
+    let req = test::TestRequest::get().uri("/metrics").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert!(body.contains("# TYPE iperf3_stream_retransmits gauge\n"));
+    assert!(body.contains("iperf3_stream_retransmits{socket=\"5\"} 3\n"));
+    assert!(body.contains("iperf3_stream_retransmits{socket=\"7\"} 181\n"));
+
+    unsafe { std::env::remove_var("METRICS_PER_STREAM") };
+    clear_last_result_for_test();
+}
+
+/// Test that an asymmetric link reports `received / sent` in `/summary` and `/metrics`,
+/// and `null` when nothing was sent.
+#[actix_web::test]
+#[serial]
+async fn summary_reports_asymmetry_ratio() {
+    let mut report = report_to("192.0.2.10");
+    report.end.sum_sent.bits_per_second = 800_000_000.0;
+    report.end.sum_received.bits_per_second = 200_000_000.0;
+    assert_eq!(build_summary(&report, None).asymmetry_ratio, Some(0.25));
+    set_last_result_for_test(report);
+
+    let app = test::init_service(App::new().service(iperf3_summary).service(iperf3_metrics)).await;
+    let req = test::TestRequest::get().uri("/summary").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["asymmetry_ratio"], 0.25);
+
+    let req = test::TestRequest::get().uri("/metrics").to_request();
+    let body = String::from_utf8(test::call_and_read_body(&app, req).await.to_vec()).unwrap();
+    assert!(body.contains("iperf3_asymmetry_ratio 0.25\n"));
+
+    set_last_result_for_test(Iperf3Report::default());
+    let req = test::TestRequest::get().uri("/summary").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert!(body["asymmetry_ratio"].is_null());
+
+    clear_last_result_for_test();
+}
+
+/// Test that `/summary` sends `Last-Modified` and answers a current `If-Modified-Since`
+/// with 304, while an older one gets the full body.
+#[actix_web::test]
+#[serial]
+async fn summary_honours_if_modified_since() {
+    set_last_result_for_test(report_to("192.0.2.10"));
+    let app = test::init_service(App::new().service(iperf3_summary)).await;
+
+    let req = test::TestRequest::get().uri("/summary").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+    let last_modified = resp.headers().get(http::header::LAST_MODIFIED).unwrap().clone();
+    let expected = http::header::HttpDate::from(last_cached_at().unwrap()).to_string();
+    assert_eq!(last_modified.to_str().unwrap(), expected);
+
+    let req = test::TestRequest::get()
+        .uri("/summary")
+        .insert_header((http::header::IF_MODIFIED_SINCE, last_modified.clone()))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::NOT_MODIFIED);
+    assert_eq!(resp.headers().get(http::header::LAST_MODIFIED).unwrap(), &last_modified);
+    assert!(test::read_body(resp).await.is_empty());
+
+    let req = test::TestRequest::get()
+        .uri("/summary")
+        .insert_header((http::header::IF_MODIFIED_SINCE, "Thu, 01 Jan 1970 00:00:00 GMT"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+
+    clear_last_result_for_test();
+}
+
+/// Test that `/summary` reports the local address and port of the first connection,
+/// and nulls when nothing connected.
+#[actix_web::test]
+#[serial]
+async fn summary_reports_local_connection_details() {
+    let mut report = Iperf3Report::default();
+    report.start.connected.push(Connected {
+        local_host: "192.0.2.1".to_string(),
+        local_port: 54012,
+        remote_host: "192.0.2.10".to_string(),
+        remote_port: 5201,
+        ..Default::default()
+    });
+    set_last_result_for_test(report);
+    let app = test::init_service(App::new().service(iperf3_summary)).await;
+
+    let req = test::TestRequest::get().uri("/summary").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["local_host"], "192.0.2.1");
+    assert_eq!(body["local_port"], 54012);
+
+    set_last_result_for_test(Iperf3Report::default());
+    let req = test::TestRequest::get().uri("/summary").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert!(body["local_host"].is_null());
+    assert!(body["local_port"].is_null());
+
+    clear_last_result_for_test();
+}
+
+/// Test the stability score of a known series and its null single-sample case.
+#[actix_web::test]
+#[serial]
+async fn summary_reports_stability_score() {
+    // Mean 500, population standard deviation 100: CV = 0.2, score = 100 / 1.2
+    let series = [400.0, 600.0, 400.0, 600.0];
+    let score = stability_score(&series).unwrap();
+    assert!((score - 83.333_333).abs() < 1e-5, "score was {}", score);
+    assert_eq!(stability_score(&[0.0, 0.0]), None);
+
+    clear_history_for_test();
+    set_last_result_for_test(report_to("192.0.2.10"));
+    let app = test::init_service(App::new().service(iperf3_summary)).await;
+
+    let mut report = Iperf3Report::default();
+    report.end.sum_received.bits_per_second = 400_000_000.0;
+    push_history_for_test(report.clone());
+    let req = test::TestRequest::get().uri("/summary").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert!(body["stability_score"].is_null());
+
+    for bps in [600_000_000.0, 400_000_000.0, 600_000_000.0] {
+        report.end.sum_received.bits_per_second = bps;
+        push_history_for_test(report.clone());
+    }
+    let req = test::TestRequest::get().uri("/summary").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert!((body["stability_score"].as_f64().unwrap() - 83.333_333).abs() < 1e-5);
+
+    clear_history_for_test();
+    clear_last_result_for_test();
+}
+
+/// Test that utilization is `measured / LINK_CAPACITY_MBPS * 100` in `/summary` and
+/// `/metrics`, and omitted when the capacity is unset.
+#[actix_web::test]
+#[serial]
+async fn summary_reports_link_utilization() {
+    let mut report = report_to("192.0.2.10");
+    report.end.sum_sent.bits_per_second = 500_000_000.0;
+    report.end.sum_received.bits_per_second = 940_000_000.0;
+    set_last_result_for_test(report);
+    let app = test::init_service(App::new().service(iperf3_summary).service(iperf3_metrics)).await;
+
+    unsafe { std::env::set_var("LINK_CAPACITY_MBPS", "1000") };
+    let req = test::TestRequest::get().uri("/summary").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["received_utilization_percent"], 940.0 / 1000.0 * 100.0);
+    assert_eq!(body["sent_utilization_percent"], 500.0 / 1000.0 * 100.0);
+
+    let req = test::TestRequest::get().uri("/metrics").to_request();
+    let body = String::from_utf8(test::call_and_read_body(&app, req).await.to_vec()).unwrap();
+    assert!(body.contains("iperf3_received_utilization_percent 94\n"));
+    assert!(body.contains("iperf3_sent_utilization_percent 50\n"));
+
+    unsafe { std::env::remove_var("LINK_CAPACITY_MBPS") };
+    let req = test::TestRequest::get().uri("/summary").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert!(body.get("received_utilization_percent").is_none());
+    assert!(body.get("sent_utilization_percent").is_none());
+
+    let req = test::TestRequest::get().uri("/metrics").to_request();
+    let body = String::from_utf8(test::call_and_read_body(&app, req).await.to_vec()).unwrap();
+    assert!(!body.contains("utilization_percent"));
+
+    clear_last_result_for_test();
+}
+
+/// Test that the session cookie appears in `/summary` and `/status`, and is omitted when
+/// iperf3 reported none.
+#[actix_web::test]
+#[serial]
+async fn session_cookie_appears_in_summary_and_status() {
+    let mut report = report_to("192.0.2.10");
+    report.start.cookie = "p2dkpiylp4ajcm7gtqetxuruoebwgyv6hyvz".to_string();
+    set_last_result_for_test(report);
+
+    let app = test::init_service(App::new().service(iperf3_summary).service(iperf3_status)).await;
+    for uri in ["/summary", "/status"] {
+        let req = test::TestRequest::get().uri(uri).to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["cookie"], "p2dkpiylp4ajcm7gtqetxuruoebwgyv6hyvz", "{}", uri);
+    }
+
+    set_last_result_for_test(report_to("192.0.2.10"));
+    let req = test::TestRequest::get().uri("/summary").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert!(body.get("cookie").is_none());
+
+    clear_last_result_for_test();
+}
+
+/// Test that the sent and received SLA thresholds are evaluated independently, that
+/// `sla_met` requires both, and that `SLA_MIN_MBPS` still sets the received threshold.
+#[tokio::test]
+#[serial]
+async fn per_direction_sla_is_evaluated_independently() {
+    let mut report = Iperf3Report::default();
+    report.end.sum_sent.bits_per_second = 40_000_000.0;
+    report.end.sum_received.bits_per_second = 900_000_000.0;
+    let sla = |report: &Iperf3Report| {
+        let summary = build_summary(report, None);
+        (summary.sla_sent_met, summary.sla_received_met, summary.sla_met)
+    };
+
+    for name in ["SLA_MIN_MBPS", "SLA_MIN_SENT_MBPS", "SLA_MIN_RECEIVED_MBPS"] {
+        unsafe { std::env::remove_var(name) };
+    }
+    assert_eq!(sla(&report), (None, None, None));
+
+    unsafe {
+        std::env::set_var("SLA_MIN_SENT_MBPS", "50");
+        std::env::set_var("SLA_MIN_RECEIVED_MBPS", "500");
+    }
+    // Upload fails, download passes
+    assert_eq!(sla(&report), (Some(false), Some(true), Some(false)));
+
+    // Upload passes, download fails
+    report.end.sum_sent.bits_per_second = 60_000_000.0;
+    report.end.sum_received.bits_per_second = 400_000_000.0;
+    assert_eq!(sla(&report), (Some(true), Some(false), Some(false)));
+
+    // Both pass
+    report.end.sum_received.bits_per_second = 600_000_000.0;
+    assert_eq!(sla(&report), (Some(true), Some(true), Some(true)));
+
+    // The single legacy threshold still applies to the received direction alone
+    unsafe {
+        std::env::remove_var("SLA_MIN_SENT_MBPS");
+        std::env::remove_var("SLA_MIN_RECEIVED_MBPS");
+        std::env::set_var("SLA_MIN_MBPS", "700");
+    }
+    assert_eq!(sla(&report), (None, Some(false), Some(false)));
+    let body = serde_json::to_value(build_summary(&report, None)).unwrap();
+    assert!(body.get("sla_sent_met").is_none());
+    assert_eq!(body["sla_received_met"], false);
+
+    unsafe { std::env::remove_var("SLA_MIN_MBPS") };
+}
+
+/// Test that goodput discounts the estimated retransmitted bytes from the received
+/// throughput of a retransmit-heavy run.
+#[tokio::test]
+async fn goodput_is_below_throughput_by_retransmitted_share() {
+    let mut report = Iperf3Report::default();
+    report.start.tcp_mss_default = 1448;
+    report.end.sum_sent.bytes = 1_000_000_000;
+    report.end.sum_sent.bits_per_second = 800_000_000.0;
+    // 69,061 segments of 1448 bytes: about 10% of the data sent
+    report.end.sum_sent.retransmits = 69_061;
+    report.end.sum_received.bits_per_second = 790_000_000.0;
+
+    let summary = build_summary(&report, None);
+    let goodput = summary.goodput_mbps.unwrap();
+    let expected = 790.0 * (1.0 - 69_061.0 * 1448.0 / 1_000_000_000.0);
+    assert!((goodput - expected).abs() < 1e-9, "{} != {}", goodput, expected);
+    assert!((summary.received_mbps - goodput - 79.0).abs() < 0.01, "{}", goodput);
+
+    // Without an MSS in the report a 1448-byte segment is assumed
+    report.start.tcp_mss_default = 0;
+    assert_eq!(goodput_mbps(&report), Some(goodput));
+
+    // Retransmits beyond the data sent cannot push goodput below zero
+    report.end.sum_sent.retransmits = u32::MAX;
+    assert_eq!(goodput_mbps(&report), Some(0.0));
+
+    // Without retransmits goodput equals throughput; without data it is unknown
+    report.end.sum_sent.retransmits = 0;
+    assert_eq!(goodput_mbps(&report), Some(790.0));
+    report.end.sum_sent.bytes = 0;
+    assert_eq!(build_summary(&report, None).goodput_mbps, None);
+}
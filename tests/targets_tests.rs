@@ -0,0 +1,547 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for running several targets with bounded concurrency.
+
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use async_trait::async_trait;
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+/// Mock runner which records how many runs overlap, overall and per target.
+#[derive(Default)]
+struct ConcurrencyTrackingRunner {
+    active: AtomicUsize,
+    max_active: AtomicUsize,
+    completed: AtomicUsize,
+    active_targets: Mutex<HashSet<String>>,
+    self_overlaps: AtomicUsize,
+}
+
+#[async_trait]
+impl Iperf3Runner for ConcurrencyTrackingRunner {
+    async fn run_iperf3(&self, iperf3_ip: String, iperf3_port: String) -> Result<String, Iperf3Error> {
+        let key = format!("{}:{}", iperf3_ip, iperf3_port);
+        if !self.active_targets.lock().unwrap().insert(key.clone()) {
+            self.self_overlaps.fetch_add(1, Ordering::SeqCst);
+        }
+        let now_active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_active.fetch_max(now_active, Ordering::SeqCst);
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+
+        self.active.fetch_sub(1, Ordering::SeqCst);
+        self.active_targets.lock().unwrap().remove(&key);
+        self.completed.fetch_add(1, Ordering::SeqCst);
+
+        if iperf3_ip == "unreachable" {
+            Err(Iperf3Error::NonZeroExit {
+                code: Some(1),
+                stderr: "unable to connect to server".to_string(),
+            })
+        } else {
+            Ok(serde_json::to_string(&Iperf3Report::default()).unwrap())
+        }
+    }
+}
+
+/// Test that concurrency is bounded, that it is actually used, and that a
+/// repeated target never overlaps itself.
+#[tokio::test]
+#[serial]
+async fn run_targets_bounds_concurrency_without_self_overlap() {
+    let runner = ConcurrencyTrackingRunner::default();
+    let targets = vec![
+        Target::new("10.0.0.1", "5201"),
+        Target::new("10.0.0.2", "5201"),
+        Target::new("10.0.0.1", "5201"),
+        Target::new("10.0.0.3", "5201"),
+        Target::new("10.0.0.1", "5201"),
+        Target::new("10.0.0.4", "5201"),
+    ];
+
+    let results = run_targets_with_runner(&runner, &targets, 2).await;
+
+    assert_eq!(results.len(), targets.len());
+    assert_eq!(runner.completed.load(Ordering::SeqCst), targets.len());
+    assert_eq!(runner.max_active.load(Ordering::SeqCst), 2);
+    assert_eq!(runner.self_overlaps.load(Ordering::SeqCst), 0);
+
+    clear_last_result_for_test();
+}
+
+/// Test that one failing target does not prevent the others from running.
+#[tokio::test]
+#[serial]
+async fn run_targets_isolates_failures() {
+    let runner = ConcurrencyTrackingRunner::default();
+    let targets = vec![
+        Target::new("unreachable", "5201"),
+        Target::new("10.0.0.2", "5201"),
+    ];
+
+    let results = run_targets_with_runner(&runner, &targets, 4).await;
+
+    assert!(results[0].is_err());
+    assert!(results[1].is_ok());
+
+    clear_last_result_for_test();
+}
+
+/// Test that `MAX_CONCURRENT_RUNS` falls back to its default when unset or invalid.
+#[tokio::test]
+#[serial]
+async fn max_concurrent_runs_reads_env() {
+    unsafe { std::env::set_var("MAX_CONCURRENT_RUNS", "5") };
+    assert_eq!(max_concurrent_runs(), 5);
+    unsafe { std::env::set_var("MAX_CONCURRENT_RUNS", "0") };
+    assert_eq!(max_concurrent_runs(), 2);
+    unsafe { std::env::remove_var("MAX_CONCURRENT_RUNS") };
+    assert_eq!(max_concurrent_runs(), 2);
+}
+
+/// Mock runner recording the iperf3 arguments each run would be invoked with.
+#[derive(Default)]
+struct ArgsRecordingRunner {
+    invocations: Mutex<Vec<Vec<String>>>,
+}
+
+#[async_trait]
+impl Iperf3Runner for ArgsRecordingRunner {
+    async fn run_iperf3(&self, iperf3_ip: String, iperf3_port: String) -> Result<String, Iperf3Error> {
+        self.run_iperf3_with_options(&Iperf3Options::new(iperf3_ip, iperf3_port)).await
+    }
+
+    async fn run_iperf3_with_options(&self, opts: &Iperf3Options) -> Result<String, Iperf3Error> {
+        self.invocations.lock().unwrap().push(build_iperf3_args(opts));
+        Ok(serde_json::to_string(&Iperf3Report::default()).unwrap())
+    }
+}
+
+/// Test that targets loaded from `TARGETS_FILE` each run with their own options.
+#[tokio::test]
+#[serial]
+async fn targets_file_passes_per_target_options() {
+    let path = std::env::temp_dir().join(format!("iperf3-targets-{}.json", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"[
+            {"host": "10.0.0.1", "port": 5201, "udp": true},
+            {"host": "10.0.0.2", "port": "5202", "reverse": true, "parallel": 4}
+        ]"#,
+    )
+    .unwrap();
+    unsafe { std::env::set_var("TARGETS_FILE", &path) };
+
+    let targets = configured_targets("127.0.0.1".into(), "5201".into());
+    assert_eq!(targets.len(), 2);
+    assert_eq!(targets[1].key(), "10.0.0.2:5202");
+
+    let runner = ArgsRecordingRunner::default();
+    run_targets_with_runner(&runner, &targets, 1).await;
+
+    let invocations = runner.invocations.lock().unwrap().clone();
+    assert_eq!(invocations, vec![
+        vec!["-c", "10.0.0.1", "-p", "5201", "-u", "--json"],
+        vec!["-c", "10.0.0.2", "-p", "5202", "-P", "4", "-R", "--json"],
+    ]);
+
+    unsafe { std::env::remove_var("TARGETS_FILE") };
+    std::fs::remove_file(&path).unwrap();
+    clear_last_result_for_test();
+}
+
+/// Test that a missing `TARGETS_FILE` falls back to the single configured server.
+#[tokio::test]
+#[serial]
+async fn broken_targets_file_falls_back_to_primary_server() {
+    unsafe { std::env::set_var("TARGETS_FILE", "/nonexistent/targets.json") };
+
+    let targets = configured_targets("127.0.0.1".into(), "5201".into());
+    assert_eq!(targets, vec![Target::new("127.0.0.1", "5201")]);
+
+    unsafe { std::env::remove_var("TARGETS_FILE") };
+}
+
+/// Test that comma-separated servers become one target each, sharing a single port.
+#[tokio::test]
+#[serial]
+async fn comma_separated_servers_become_targets() {
+    let targets = configured_targets("10.0.0.1, 10.0.0.2,10.0.0.3".into(), "5201".into());
+    assert_eq!(
+        targets,
+        vec![Target::new("10.0.0.1", "5201"), Target::new("10.0.0.2", "5201"), Target::new("10.0.0.3", "5201")]
+    );
+
+    let targets = configured_targets("10.0.0.1,10.0.0.2".into(), "5201,5202".into());
+    assert_eq!(targets[1].key(), "10.0.0.2:5202");
+
+    assert!(configured_targets("10.0.0.1,10.0.0.2".into(), "5201,5202,5203".into()).is_empty());
+}
+
+/// Mock runner whose reports carry the last octet of the target's address as timestamp
+/// and which cannot reach `unreachable`.
+struct PerTargetRunner;
+
+#[async_trait]
+impl Iperf3Runner for PerTargetRunner {
+    async fn run_iperf3(&self, iperf3_ip: String, _iperf3_port: String) -> Result<String, Iperf3Error> {
+        let Some(octet) = iperf3_ip.rsplit('.').next().and_then(|o| o.parse::<u64>().ok()) else {
+            return Err(Iperf3Error::Unreachable(format!("{} is unreachable", iperf3_ip)));
+        };
+        let mut report = Iperf3Report::default();
+        report.start.timestamp.timesecs = octet;
+        Ok(serde_json::to_string(&report).unwrap())
+    }
+}
+
+/// Test that each target's result is kept under its `host:port`, that `/iperf3`,
+/// `/summary` and the history serve the primary target and that an unreachable target
+/// does not hold back the others.
+#[actix_web::test]
+#[serial]
+async fn results_are_keyed_by_target() {
+    use actix_web::{test, http, App};
+
+    clear_last_result_for_test();
+    clear_history_for_test();
+    unsafe { std::env::set_var("IPERF3_MAX_RETRIES", "0") };
+    let targets = vec![
+        Target::new("10.0.0.1", "5201"),
+        Target::new("unreachable", "5201"),
+        Target::new("10.0.0.2", "5202"),
+    ];
+    set_primary_target(Some(targets[0].key()));
+
+    let results = run_targets_with_runner(&PerTargetRunner, &targets, 1).await;
+    assert!(results[1].is_err());
+    assert_eq!(get_target_result("10.0.0.1:5201").unwrap().0.start.timestamp.timesecs, 1);
+    assert_eq!(get_target_result("10.0.0.2:5202").unwrap().0.start.timestamp.timesecs, 2);
+    assert!(get_target_result("unreachable:5201").is_none());
+
+    let app = test::init_service(App::new().service(iperf3).service(iperf3_target).service(iperf3_summary)).await;
+    let req = test::TestRequest::get().uri("/iperf3/10.0.0.2/5202").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+    assert!(resp.headers().contains_key("X-Cache-Age-Seconds"));
+    let report: Iperf3Report = test::read_body_json(resp).await;
+    assert_eq!(report.start.timestamp.timesecs, 2);
+
+    // The last run cached was the second target's, but /iperf3 keeps serving the primary
+    let req = test::TestRequest::get().uri("/iperf3").to_request();
+    let report: Iperf3Report = test::read_body_json(test::call_service(&app, req).await).await;
+    assert_eq!(report.start.timestamp.timesecs, 1);
+    let req = test::TestRequest::get().uri("/summary").to_request();
+    let summary: serde_json::Value = test::read_body_json(test::call_service(&app, req).await).await;
+    assert_eq!(summary["timestamp"], 1);
+    assert_eq!(get_last_result().unwrap().start.timestamp.timesecs, 1);
+    let history: Vec<u64> = get_history().iter().map(|r| r.start.timestamp.timesecs).collect();
+    assert_eq!(history, vec![1]);
+
+    let req = test::TestRequest::get().uri("/iperf3/unreachable/5201").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+
+    unsafe { std::env::remove_var("IPERF3_MAX_RETRIES") };
+    clear_last_result_for_test();
+    clear_history_for_test();
+}
+
+/// Test that a secondary target's run leaves the primary target's cache metadata,
+/// `/status` warnings and direction result untouched.
+#[tokio::test]
+#[serial]
+async fn secondary_target_leaves_primary_state_alone() {
+    clear_last_result_for_test();
+    clear_run_status_for_test();
+    let primary = Target::new("10.0.0.1", "5201");
+    // Expects four streams where the mock reports none, so its run warns
+    let secondary =
+        Target::with_options(Iperf3Options { parallel: Some(4), ..Iperf3Options::new("10.0.0.2", "5201") });
+    set_primary_target(Some(primary.key()));
+
+    run_targets_with_runner(&PerTargetRunner, std::slice::from_ref(&primary), 1).await;
+    let cached_at = last_cached_at().unwrap();
+    tokio::time::sleep(Duration::from_millis(5)).await;
+    run_targets_with_runner(&PerTargetRunner, std::slice::from_ref(&secondary), 1).await;
+
+    assert_eq!(get_target_result("10.0.0.2:5201").unwrap().0.start.timestamp.timesecs, 2);
+    assert_eq!(last_cached_at(), Some(cached_at));
+    assert!(get_run_status().warnings.is_empty());
+    assert_eq!(get_direction_result(Direction::Up).unwrap().0.start.timestamp.timesecs, 1);
+
+    clear_last_result_for_test();
+    clear_run_status_for_test();
+}
+
+/// Mock runner numbering its runs through `end.sum_received.bytes`.
+#[derive(Default)]
+struct SequenceRunner {
+    runs: AtomicUsize,
+}
+
+#[async_trait]
+impl Iperf3Runner for SequenceRunner {
+    async fn run_iperf3(&self, _iperf3_ip: String, _iperf3_port: String) -> Result<String, Iperf3Error> {
+        let mut report = Iperf3Report::default();
+        report.end.sum_received.bytes = self.runs.fetch_add(1, Ordering::SeqCst) as u64 + 1;
+        Ok(serde_json::to_string(&report).unwrap())
+    }
+}
+
+/// Test that with `DISCARD_FIRST_RUN` the startup run is not cached while the next one is.
+#[tokio::test]
+#[serial]
+async fn discard_first_run_skips_caching_startup_run() {
+    unsafe { std::env::set_var("DISCARD_FIRST_RUN", "true") };
+    clear_last_result_for_test();
+    clear_run_status_for_test();
+    let runner = SequenceRunner::default();
+    let targets = vec![Target::new("127.0.0.1", "5201")];
+
+    run_startup_cycle_with_runner(&runner, &targets, 1).await;
+    assert_eq!(runner.runs.load(Ordering::SeqCst), 1);
+    assert!(get_last_result().is_none());
+    assert_eq!(record_cycle_start(std::time::Instant::now()), None);
+
+    run_targets_with_runner(&runner, &targets, 1).await;
+    assert_eq!(get_last_result().unwrap().end.sum_received.bytes, 2);
+
+    unsafe { std::env::remove_var("DISCARD_FIRST_RUN") };
+    clear_last_result_for_test();
+    clear_run_status_for_test();
+}
+
+/// Test that without `DISCARD_FIRST_RUN` the startup run is cached.
+#[tokio::test]
+#[serial]
+async fn startup_run_is_cached_by_default() {
+    unsafe { std::env::remove_var("DISCARD_FIRST_RUN") };
+    clear_last_result_for_test();
+    let runner = SequenceRunner::default();
+
+    run_startup_cycle_with_runner(&runner, &[Target::new("127.0.0.1", "5201")], 1).await;
+    assert_eq!(get_last_result().unwrap().end.sum_received.bytes, 1);
+
+    clear_last_result_for_test();
+    clear_run_status_for_test();
+}
+
+/// Test that a target projected over `MAX_TEST_BYTES` is refused without running iperf3.
+#[tokio::test]
+#[serial]
+async fn max_test_bytes_refuses_run_without_invoking_iperf3() {
+    unsafe { std::env::set_var("MAX_TEST_BYTES", "1000000") };
+    let runner = ArgsRecordingRunner::default();
+    let opts = Iperf3Options {
+        bitrate: Some(100_000_000),
+        duration: Some(10),
+        ..Iperf3Options::new("10.0.0.1", "5201")
+    };
+
+    let result = run_iperf3_and_cache_with_options(&runner, &opts).await;
+    assert_eq!(
+        result.unwrap_err(),
+        Iperf3Error::OverByteBudget { projected_bytes: 125_000_000, max_bytes: 1_000_000 }
+    );
+    assert!(runner.invocations.lock().unwrap().is_empty());
+
+    unsafe { std::env::remove_var("MAX_TEST_BYTES") };
+}
+
+/// Test that no run happens before `INITIAL_DELAY_SECONDS` has elapsed on a paused clock.
+#[tokio::test(start_paused = true)]
+#[serial]
+async fn initial_delay_postpones_first_run() {
+    unsafe { std::env::set_var("INITIAL_DELAY_SECONDS", "30") };
+    assert_eq!(initial_delay(), Duration::from_secs(30));
+    unsafe { std::env::remove_var("INITIAL_DELAY_SECONDS") };
+    assert_eq!(initial_delay(), Duration::ZERO);
+
+    clear_last_result_for_test();
+    let runner = std::sync::Arc::new(SequenceRunner::default());
+    let scheduler = tokio::spawn({
+        let runner = runner.clone();
+        async move {
+            let targets = vec![Target::new("127.0.0.1", "5201")];
+            run_scheduler_with_runner(&*runner, &targets, 1, Duration::from_secs(30), Duration::from_secs(600)).await
+        }
+    });
+
+    tokio::time::sleep(Duration::from_secs(29)).await;
+    assert_eq!(runner.runs.load(Ordering::SeqCst), 0);
+    assert!(get_last_result().is_none());
+
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    assert!(runner.runs.load(Ordering::SeqCst) >= 1);
+
+    scheduler.abort();
+    clear_last_result_for_test();
+    clear_run_status_for_test();
+}
+
+/// Test that the scheduler runs once at startup and next after `interval`, not twice at
+/// startup, and that after a discarded warm-up the first measured run follows at once.
+#[tokio::test(start_paused = true)]
+#[serial]
+async fn scheduler_runs_at_startup_then_each_interval() {
+    clear_last_result_for_test();
+    let interval = Duration::from_secs(600);
+    let spawn = |runner: std::sync::Arc<StartRecordingRunner>| {
+        tokio::spawn(async move {
+            let targets = vec![Target::new("127.0.0.1", "5201")];
+            run_scheduler_with_runner(&*runner, &targets, 1, Duration::ZERO, interval).await
+        })
+    };
+
+    let began = tokio::time::Instant::now();
+    let runner = std::sync::Arc::new(StartRecordingRunner::default());
+    let scheduler = spawn(runner.clone());
+    tokio::time::sleep(interval - Duration::from_secs(1)).await;
+    assert_eq!(runner.starts.lock().unwrap().len(), 1);
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    let starts: Vec<_> = runner.starts.lock().unwrap().iter().map(|(_, at)| *at - began).collect();
+    assert_eq!(starts, vec![Duration::ZERO, interval]);
+    scheduler.abort();
+
+    unsafe { std::env::set_var("DISCARD_FIRST_RUN", "true") };
+    let began = tokio::time::Instant::now();
+    let runner = std::sync::Arc::new(StartRecordingRunner::default());
+    let scheduler = spawn(runner.clone());
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    let starts: Vec<_> = runner.starts.lock().unwrap().iter().map(|(_, at)| *at - began).collect();
+    assert_eq!(starts, vec![Duration::ZERO, Duration::ZERO]);
+    scheduler.abort();
+    unsafe { std::env::remove_var("DISCARD_FIRST_RUN") };
+
+    clear_last_result_for_test();
+    clear_run_status_for_test();
+}
+
+/// stderr iperf3 prints when another client holds the server.
+const BUSY_STDERR: &str = "iperf3: error - the server is busy running a test. try again later\n";
+
+/// Mock runner failing with the busy stderr for the first `busy_runs` runs.
+struct BusyRunner {
+    busy_runs: usize,
+    runs: AtomicUsize,
+}
+
+#[async_trait]
+impl Iperf3Runner for BusyRunner {
+    async fn run_iperf3(&self, _iperf3_ip: String, _iperf3_port: String) -> Result<String, Iperf3Error> {
+        if self.runs.fetch_add(1, Ordering::SeqCst) < self.busy_runs {
+            Err(Iperf3Error::from_failed_run(Some(1), "", BUSY_STDERR))
+        } else {
+            Ok(serde_json::to_string(&Iperf3Report::default()).unwrap())
+        }
+    }
+}
+
+/// Test that the busy message is classified as `ServerBusy` rather than a hard failure,
+/// from stderr or from iperf3's JSON error output.
+#[tokio::test]
+async fn busy_server_is_classified() {
+    let err = Iperf3Error::from_failed_run(Some(1), "", BUSY_STDERR);
+    assert!(matches!(err, Iperf3Error::ServerBusy(ref m) if m.ends_with("try again later")));
+    assert_eq!(err.code(), "server_busy");
+
+    let json = r#"{"start": {}, "error": "error - the server is busy running a test. try again later"}"#;
+    assert!(matches!(Iperf3Error::from_failed_run(Some(1), json, ""), Iperf3Error::ServerBusy(_)));
+
+    let other = Iperf3Error::from_failed_run(Some(1), "", "iperf3: error - unable to connect to server");
+    assert_eq!(
+        other,
+        Iperf3Error::NonZeroExit { code: Some(1), stderr: "iperf3: error - unable to connect to server".into() }
+    );
+}
+
+/// Test that a busy target is retried after the short delay instead of the full interval,
+/// and that `/status` surfaces the busy state until a run succeeds.
+#[tokio::test(start_paused = true)]
+#[serial]
+async fn busy_server_is_retried_after_short_delay() {
+    clear_last_result_for_test();
+    clear_run_status_for_test();
+    let runner = BusyRunner { busy_runs: 2, runs: AtomicUsize::new(0) };
+    let targets = vec![Target::new("127.0.0.1", "5201")];
+
+    let started = tokio::time::Instant::now();
+    let results = run_targets_retrying_busy(&runner, &targets, 1, Duration::from_secs(15)).await;
+    assert!(results[0].is_ok());
+    assert_eq!(runner.runs.load(Ordering::SeqCst), 3);
+    assert_eq!(started.elapsed(), Duration::from_secs(30));
+    assert!(!get_run_status().server_busy);
+
+    // A server that stays busy is given up on after the retry limit and reported busy
+    let runner = BusyRunner { busy_runs: usize::MAX, runs: AtomicUsize::new(0) };
+    let results = run_targets_retrying_busy(&runner, &targets, 1, Duration::from_secs(15)).await;
+    assert!(matches!(results[0], Err(Iperf3Error::ServerBusy(_))));
+    assert_eq!(runner.runs.load(Ordering::SeqCst), 1 + MAX_SERVER_BUSY_RETRIES);
+    assert!(get_run_status().server_busy);
+
+    unsafe { std::env::set_var("SERVER_BUSY_RETRY_SECONDS", "5") };
+    assert_eq!(server_busy_retry_delay(), Duration::from_secs(5));
+    unsafe { std::env::remove_var("SERVER_BUSY_RETRY_SECONDS") };
+
+    clear_last_result_for_test();
+    clear_run_status_for_test();
+}
+
+/// Mock runner recording when each target's run starts on the (paused) clock.
+#[derive(Default)]
+struct StartRecordingRunner {
+    starts: Mutex<Vec<(String, tokio::time::Instant)>>,
+}
+
+#[async_trait]
+impl Iperf3Runner for StartRecordingRunner {
+    async fn run_iperf3(&self, iperf3_ip: String, _iperf3_port: String) -> Result<String, Iperf3Error> {
+        self.starts.lock().unwrap().push((iperf3_ip, tokio::time::Instant::now()));
+        Ok(serde_json::to_string(&Iperf3Report::default()).unwrap())
+    }
+}
+
+/// Test that start offsets grow by `MULTI_SERVER_STAGGER_MS` per target and runs start accordingly.
+#[tokio::test(start_paused = true)]
+#[serial]
+async fn targets_start_staggered() {
+    unsafe { std::env::set_var("MULTI_SERVER_STAGGER_MS", "250") };
+    let stagger = multi_server_stagger();
+    assert_eq!(stagger, Duration::from_millis(250));
+    unsafe { std::env::remove_var("MULTI_SERVER_STAGGER_MS") };
+    assert_eq!(multi_server_stagger(), Duration::ZERO);
+
+    let offsets = stagger_offsets(4, stagger);
+    for pair in offsets.windows(2) {
+        assert_eq!(pair[1] - pair[0], stagger);
+    }
+    assert_eq!(offsets[0], Duration::ZERO);
+
+    let runner = StartRecordingRunner::default();
+    let targets: Vec<Target> = (1..=4).map(|i| Target::new(format!("192.0.2.{}", i), "5201")).collect();
+    let began = tokio::time::Instant::now();
+    run_targets_staggered(&runner, &targets, 4, stagger).await;
+
+    let starts = runner.starts.lock().unwrap().clone();
+    assert_eq!(starts.len(), 4);
+    for (i, target) in targets.iter().enumerate() {
+        let (_, started) = starts.iter().find(|(host, _)| *host == target.host).unwrap();
+        assert_eq!(*started - began, offsets[i]);
+    }
+
+    clear_last_result_for_test();
+    clear_run_status_for_test();
+}
@@ -0,0 +1,182 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for per-phase cycle timing and the `/debug/timing` endpoint.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use actix_web::{test, http, App};
+use async_trait::async_trait;
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+/// Mock runner returning a fixed output after "starting" its child.
+struct MockRunner {
+    output: Result<String, Iperf3Error>,
+}
+
+#[async_trait]
+impl Iperf3Runner for MockRunner {
+    async fn run_iperf3(&self, _iperf3_ip: String, _iperf3_port: String) -> Result<String, Iperf3Error> {
+        mark_child_spawned();
+        self.output.clone()
+    }
+}
+
+/// Mock runner whose first run cannot reach the server, before any child is started.
+struct FlakyRunner {
+    runs: AtomicUsize,
+}
+
+#[async_trait]
+impl Iperf3Runner for FlakyRunner {
+    async fn run_iperf3(&self, _iperf3_ip: String, _iperf3_port: String) -> Result<String, Iperf3Error> {
+        if self.runs.fetch_add(1, Ordering::SeqCst) == 0 {
+            return Err(Iperf3Error::Unreachable("connection refused".into()));
+        }
+        mark_child_spawned();
+        Ok(serde_json::to_string(&Iperf3Report::default()).unwrap())
+    }
+}
+
+fn phase_names(timing: &CycleTiming) -> Vec<&str> {
+    timing.phases.iter().map(|p| p.phase.as_str()).collect()
+}
+
+/// Test that a successful mock run records the spawn, test run, parse and cache phases.
+#[tokio::test]
+#[serial]
+async fn successful_run_records_all_phases() {
+    clear_last_timing_for_test();
+    unsafe { std::env::set_var("PHASE_TIMING", "true") };
+    let runner = MockRunner {
+        output: Ok(serde_json::to_string(&Iperf3Report::default()).unwrap()),
+    };
+
+    run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into()).await.unwrap();
+
+    let timing = get_last_timing().expect("timing should be recorded");
+    assert_eq!(phase_names(&timing), vec!["spawn", "test_run", "parse", "cache"]);
+    assert!(timing.phases.iter().all(|p| p.millis >= 0.0));
+    let phase_sum: f64 = timing.phases.iter().map(|p| p.millis).sum();
+    assert!(timing.total_millis >= phase_sum);
+    assert!(timing.phases.iter().all(|p| p.attempt == 1));
+
+    unsafe { std::env::remove_var("PHASE_TIMING") };
+    clear_last_result_for_test();
+}
+
+/// Test that a failed run still records the phases that did run.
+#[tokio::test]
+#[serial]
+async fn failed_run_records_run_phases_only() {
+    clear_last_timing_for_test();
+    unsafe { std::env::set_var("PHASE_TIMING", "true") };
+    let runner = MockRunner { output: Err(Iperf3Error::NonZeroExit { code: Some(1), stderr: "boom".into() }) };
+
+    run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into()).await.unwrap_err();
+
+    let timing = get_last_timing().expect("timing should be recorded");
+    assert_eq!(phase_names(&timing), vec!["spawn", "test_run"]);
+    unsafe { std::env::remove_var("PHASE_TIMING") };
+}
+
+/// Test that a retry after a parse failure records its phases under the second attempt
+/// instead of repeating the first attempt's names.
+#[tokio::test]
+#[serial]
+async fn parse_retry_records_phases_per_attempt() {
+    clear_last_timing_for_test();
+    unsafe {
+        std::env::set_var("PHASE_TIMING", "true");
+        std::env::set_var("RETRY_ON_PARSE_FAILURE", "true");
+    }
+    let runner = MockRunner { output: Ok("not json".to_string()) };
+
+    run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into()).await.unwrap_err();
+
+    let timing = get_last_timing().expect("timing should be recorded");
+    let phases: Vec<(&str, u32)> = timing.phases.iter().map(|p| (p.phase.as_str(), p.attempt)).collect();
+    assert_eq!(phases, vec![("spawn", 1), ("test_run", 1), ("parse", 1), ("spawn", 2), ("test_run", 2), ("parse", 2)]);
+    unsafe {
+        std::env::remove_var("PHASE_TIMING");
+        std::env::remove_var("RETRY_ON_PARSE_FAILURE");
+    }
+}
+
+/// Test that a transient failure records its spawn, the backoff and the retried run.
+#[tokio::test(start_paused = true)]
+#[serial]
+async fn transient_retry_records_backoff_phase() {
+    clear_last_timing_for_test();
+    unsafe { std::env::set_var("PHASE_TIMING", "true") };
+    let runner = FlakyRunner { runs: AtomicUsize::new(0) };
+
+    run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into()).await.unwrap();
+
+    let timing = get_last_timing().expect("timing should be recorded");
+    assert_eq!(
+        phase_names(&timing),
+        vec!["spawn", "retry_backoff", "spawn", "test_run", "parse", "cache"]
+    );
+    assert!(timing.phases.iter().all(|p| p.attempt == 1));
+    unsafe { std::env::remove_var("PHASE_TIMING") };
+    clear_last_result_for_test();
+}
+
+/// Test that without `PHASE_TIMING` no timing is recorded and `/debug/timing` says so.
+#[actix_web::test]
+#[serial]
+async fn phase_timing_is_off_by_default() {
+    clear_last_timing_for_test();
+    unsafe { std::env::remove_var("PHASE_TIMING") };
+    let runner = MockRunner {
+        output: Ok(serde_json::to_string(&Iperf3Report::default()).unwrap()),
+    };
+    run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into()).await.unwrap();
+    assert!(get_last_timing().is_none());
+
+    let app = test::init_service(App::new().service(debug_timing)).await;
+    let req = test::TestRequest::get().uri("/debug/timing").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+    let body = test::read_body(resp).await;
+    assert!(String::from_utf8_lossy(&body).contains("PHASE_TIMING"));
+
+    clear_last_result_for_test();
+}
+
+/// Test that `/debug/timing` serves the last breakdown and 503 before any cycle.
+#[actix_web::test]
+#[serial]
+async fn debug_timing_endpoint_serves_last_cycle() {
+    clear_last_timing_for_test();
+    unsafe { std::env::set_var("PHASE_TIMING", "true") };
+    let app = test::init_service(App::new().service(debug_timing)).await;
+
+    let req = test::TestRequest::get().uri("/debug/timing").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+
+    let runner = MockRunner {
+        output: Ok(serde_json::to_string(&Iperf3Report::default()).unwrap()),
+    };
+    run_iperf3_and_cache_with_runner(&runner, "127.0.0.1".into(), "5201".into()).await.unwrap();
+
+    let req = test::TestRequest::get().uri("/debug/timing").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+    let timing: CycleTiming = test::read_body_json(resp).await;
+    assert_eq!(phase_names(&timing), vec!["spawn", "test_run", "parse", "cache"]);
+
+    unsafe { std::env::remove_var("PHASE_TIMING") };
+    clear_last_result_for_test();
+}
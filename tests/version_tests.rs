@@ -0,0 +1,33 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for the `/version` endpoint.
+
+use actix_web::{test, http, App};
+use iperf3_statuspage::*;
+
+/// Test that `/version` reports the crate version, a build timestamp and a non-empty build id.
+#[actix_web::test]
+async fn version_includes_build_identifier() {
+    let app = test::init_service(App::new().service(iperf3_version)).await;
+    let req = test::TestRequest::get().uri("/version").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+
+    let info: BuildInfo = test::read_body_json(resp).await;
+    assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+    assert!(!info.build_id.is_empty());
+    assert!(info.build_timestamp.unwrap() > 0);
+    if let Some(sha) = &info.git_sha {
+        assert_eq!(info.build_id, format!("{}+{}", info.version, sha));
+    }
+}
@@ -0,0 +1,94 @@
+//! # iperf3-statuspage
+//!
+//! A utility application to serve iperf3 results over an HTTP endpoint.
+
+// Copyright (c) 2025 Jak Bracegirdle
+//
+// This file is part of the iperf3_statuspage crate.
+//
+// Licensed under the Apache License, Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>
+// or the MIT license <http://opensource.org/licenses/MIT>, at your option.
+// This file may not be copied, modified, or distributed except according to those terms.
+
+//! Tests for `/whoami`.
+//!
+//! These tests modify the environment and the cache, and are annotated with `#[serial]`.
+
+use actix_web::{test, http, App};
+use async_trait::async_trait;
+use serial_test::serial;
+use iperf3_statuspage::*;
+
+/// Mock runner whose report connected from `192.168.1.20:40000`.
+struct ConnectedRunner;
+
+#[async_trait]
+impl Iperf3Runner for ConnectedRunner {
+    async fn run_iperf3(&self, iperf3_ip: String, iperf3_port: String) -> Result<String, Iperf3Error> {
+        let mut report = Iperf3Report::default();
+        report.start.connected.push(Connected {
+            socket: 5,
+            local_host: "192.168.1.20".to_string(),
+            local_port: 40000,
+            remote_host: iperf3_ip,
+            remote_port: iperf3_port.parse().unwrap(),
+        });
+        Ok(serde_json::to_string(&report).unwrap())
+    }
+}
+
+/// Fetches `/whoami`, with the bearer token if given.
+async fn get_whoami(token: Option<&str>) -> Whoami {
+    let app = test::init_service(App::new().service(iperf3_whoami)).await;
+    let mut req = test::TestRequest::get().uri("/whoami");
+    if let Some(token) = token {
+        req = req.insert_header((http::header::AUTHORIZATION, format!("Bearer {}", token)));
+    }
+    test::call_and_read_body_json(&app, req.to_request()).await
+}
+
+/// Test that `/whoami` reports the target, the source of the last run, the instance label
+/// and the version, redacting the target for anonymous clients under `REDACT_TARGET`.
+#[actix_web::test]
+#[serial]
+async fn whoami_reports_target_and_source_after_a_run() {
+    clear_last_result_for_test();
+    unsafe {
+        std::env::set_var("IPERF3_SERVER_IP", "10.0.0.5");
+        std::env::set_var("IPERF3_SERVER_PORT", "5201");
+        std::env::set_var("INSTANCE_LABEL", "edge-ams-1");
+    }
+
+    let before = get_whoami(None).await;
+    assert_eq!(before.target.as_deref(), Some("10.0.0.5:5201"));
+    assert_eq!(before.source_host, None);
+
+    run_iperf3_and_cache_with_runner(&ConnectedRunner, "10.0.0.5".to_string(), "5201".to_string())
+        .await
+        .unwrap();
+    let whoami = get_whoami(None).await;
+    assert_eq!(
+        whoami,
+        Whoami {
+            target: Some("10.0.0.5:5201".to_string()),
+            source_host: Some("192.168.1.20".to_string()),
+            source_port: Some(40000),
+            instance: Some("edge-ams-1".to_string()),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    );
+
+    unsafe {
+        std::env::set_var("REDACT_TARGET", "true");
+        std::env::set_var("API_TOKEN", "s3cret");
+    }
+    assert_eq!(get_whoami(None).await.target.as_deref(), Some(REDACTED));
+    assert_eq!(get_whoami(Some("s3cret")).await.target.as_deref(), Some("10.0.0.5:5201"));
+
+    unsafe {
+        for var in ["IPERF3_SERVER_IP", "IPERF3_SERVER_PORT", "INSTANCE_LABEL", "REDACT_TARGET", "API_TOKEN"] {
+            std::env::remove_var(var);
+        }
+    }
+    clear_last_result_for_test();
+}